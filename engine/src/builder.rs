@@ -11,7 +11,10 @@ use miette::Diagnostic;
 use thiserror::Error;
 
 use crate::generate_rs_single;
-use crate::{strip_system_headers, CppCodegenOptions, ParseError, RebuildDependencyRecorder};
+use crate::{
+    strip_system_headers, ConstructorDetector, CppCodegenOptions, ForeignFnHook, ParseError,
+    RebuildDependencyRecorder,
+};
 use std::ffi::OsStr;
 use std::ffi::OsString;
 use std::fs::File;
@@ -169,6 +172,27 @@ impl<CTX: BuilderContext> Builder<'_, CTX> {
         self
     }
 
+    /// A function to decide whether and how to convert each C++ function.
+    /// See [`ForeignFnHook`] for details.
+    pub fn foreign_fn_hook(mut self, foreign_fn_hook: ForeignFnHook) -> Self {
+        self.cpp_codegen_options.foreign_fn_hook = Some(foreign_fn_hook);
+        self
+    }
+
+    /// A function to decide which C++ methods are constructors.
+    /// See [`ConstructorDetector`] for details.
+    pub fn constructor_detector(mut self, constructor_detector: ConstructorDetector) -> Self {
+        self.cpp_codegen_options.constructor_detector = Some(constructor_detector);
+        self
+    }
+
+    /// A directory in which to cache conversion results between builds.
+    /// See [`CppCodegenOptions::conversion_cache_dir`].
+    pub fn conversion_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cpp_codegen_options.conversion_cache_dir = Some(dir.into());
+        self
+    }
+
     /// Build autocxx C++ files and return a [`cc::Build`] you can use to build
     /// more from a build.rs file.
     ///
@@ -82,6 +82,7 @@ fn create_alloc_and_free(ty_name: QualifiedName) -> impl Iterator<Item = Api<Pod
                     unused_template_param: false,
                     references: References::default(),
                     original_name: None,
+                    rust_name: None,
                     self_ty: None,
                     synthesized_this_type: None,
                     synthetic_cpp: Some((cpp_function_body, CppFunctionKind::Function)),
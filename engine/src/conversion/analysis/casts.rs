@@ -105,6 +105,7 @@ fn create_cast(from: &QualifiedName, to: &QualifiedName, mutable: CastMutability
             unused_template_param: false,
             references: References::new_with_this_and_return_as_reference(),
             original_name: None,
+            rust_name: None,
             self_ty: Some(from.clone()),
             synthesized_this_type: None,
             add_to_trait: Some(TraitSynthesis::Cast {
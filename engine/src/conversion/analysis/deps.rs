@@ -53,6 +53,13 @@ impl HasDependencies for Api<FnPrePhase1> {
             } => Box::new(std::iter::once(superclass)),
             Api::RustSubclassFn { details, .. } => Box::new(details.dependencies.iter()),
             Api::RustFn { receiver, .. } => Box::new(receiver.iter()),
+            Api::IntrusivePtrTarget { target, .. } => Box::new(std::iter::once(target)),
+            Api::ComparisonOperators { target, .. } => Box::new(std::iter::once(target)),
+            Api::BoolConversion { target, .. } => Box::new(std::iter::once(target)),
+            Api::IndexOperators {
+                target, element, ..
+            } => Box::new([target, element].into_iter()),
+            Api::RustSharedStruct { deps, .. } => Box::new(deps.iter()),
             _ => Box::new(std::iter::empty()),
         }
     }
@@ -106,6 +113,13 @@ impl HasDependencies for Api<FnPhase> {
             } => Box::new(std::iter::once(superclass)),
             Api::RustSubclassFn { details, .. } => Box::new(details.dependencies.iter()),
             Api::RustFn { receiver, .. } => Box::new(receiver.iter()),
+            Api::IntrusivePtrTarget { target, .. } => Box::new(std::iter::once(target)),
+            Api::ComparisonOperators { target, .. } => Box::new(std::iter::once(target)),
+            Api::BoolConversion { target, .. } => Box::new(std::iter::once(target)),
+            Api::IndexOperators {
+                target, element, ..
+            } => Box::new([target, element].into_iter()),
+            Api::RustSharedStruct { deps, .. } => Box::new(deps.iter()),
             _ => Box::new(std::iter::empty()),
         }
     }
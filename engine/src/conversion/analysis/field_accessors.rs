@@ -0,0 +1,114 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use autocxx_parser::IncludeCppConfig;
+use syn::{parse_quote, FnArg, Ident, ReturnType, Type, Visibility};
+
+use crate::{
+    conversion::{
+        api::{Api, ApiName, CppVisibility, FuncToConvert, Provenance, References, Virtualness},
+        apivec::ApiVec,
+    },
+    types::{make_ident, QualifiedName},
+};
+
+use super::{
+    fun::function_wrapper::{CppFunctionBody, CppFunctionKind},
+    pod::PodPhase,
+};
+
+/// For any types listed in `field_accessors!`, synthesize a `get_` and
+/// `set_` method for each public field, backed by C++ which reads or
+/// writes that field. The type itself remains opaque in Rust.
+pub(crate) fn add_field_accessors(
+    apis: ApiVec<PodPhase>,
+    config: &IncludeCppConfig,
+) -> ApiVec<PodPhase> {
+    apis.into_iter()
+        .flat_map(|api| {
+            let mut resultant_apis = match api {
+                Api::Struct {
+                    ref name,
+                    ref details,
+                    ..
+                } if config.has_field_accessors(&name.name.to_cpp_name()) => details
+                    .item
+                    .fields
+                    .iter()
+                    .filter(|field| matches!(field.vis, Visibility::Public(_)))
+                    .filter_map(|field| match (&field.ident, &field.ty) {
+                        (Some(id), ty @ Type::Path(_)) if !id.to_string().starts_with('_') => {
+                            Some(create_accessors(&name.name, id, ty))
+                        }
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect(),
+                _ => Vec::new(),
+            };
+            resultant_apis.push(api);
+            resultant_apis.into_iter()
+        })
+        .collect()
+}
+
+fn create_accessors(self_ty: &QualifiedName, field: &Ident, ty: &Type) -> [Api<PodPhase>; 2] {
+    let typ = self_ty.to_type_path();
+    [
+        create_accessor(
+            self_ty,
+            format!("get_{}", field),
+            parse_quote! { this: *const #typ },
+            parse_quote! { -> #ty },
+            CppFunctionBody::FieldGetter(field.clone()),
+        ),
+        create_accessor(
+            self_ty,
+            format!("set_{}", field),
+            parse_quote! { this: *mut #typ, value: #ty },
+            ReturnType::Default,
+            CppFunctionBody::FieldSetter(field.clone()),
+        ),
+    ]
+}
+
+fn create_accessor(
+    self_ty: &QualifiedName,
+    rust_name: String,
+    inputs: syn::punctuated::Punctuated<FnArg, syn::token::Comma>,
+    output: ReturnType,
+    cpp_function_body: CppFunctionBody,
+) -> Api<PodPhase> {
+    let ident = make_ident(format!("{}_{}", self_ty.get_final_item(), rust_name));
+    let name = QualifiedName::new(self_ty.get_namespace(), ident.clone());
+    Api::Function {
+        name: ApiName::new_from_qualified_name(name),
+        fun: Box::new(FuncToConvert {
+            ident,
+            doc_attrs: Vec::new(),
+            inputs,
+            output,
+            vis: parse_quote! { pub },
+            virtualness: Virtualness::None,
+            cpp_vis: CppVisibility::Public,
+            special_member: None,
+            unused_template_param: false,
+            references: References::new_with_this_as_reference(),
+            original_name: None,
+            rust_name: Some(rust_name),
+            self_ty: Some(self_ty.clone()),
+            synthesized_this_type: None,
+            add_to_trait: None,
+            synthetic_cpp: Some((cpp_function_body, CppFunctionKind::Method)),
+            is_deleted: false,
+            provenance: Provenance::SynthesizedOther,
+            variadic: false,
+        }),
+        analysis: (),
+    }
+}
@@ -7,11 +7,12 @@
 // except according to those terms.
 
 use crate::{
-    conversion::api::SubclassName,
+    conversion::{api::SubclassName, ConvertError},
     types::{Namespace, QualifiedName},
 };
+use autocxx_parser::IntrusivePtr;
 use quote::ToTokens;
-use syn::{parse_quote, Ident, Type, TypeReference};
+use syn::{parse_quote, Expr, Ident, Type, TypeReference};
 
 #[derive(Clone, Debug)]
 pub(crate) enum CppConversionType {
@@ -25,7 +26,73 @@ pub(crate) enum CppConversionType {
     IgnoredPlacementPtrParameter,
     FromReturnValueToPlacementPtr,
     FromPointerToReference, // unwrapped_type is always Type::Ptr
-    FromReferenceToPointer, // unwrapped_type is always Type::Ptr
+    FromReferenceToPointer, // unwrapped_type is Type::Ptr, or a reference for params
+    FromStrToStringView,    // unwrapped_type is always &str
+    FromStringViewToStr,    // unwrapped_type is always &str
+    /// A returned `std::string_view`, which may not be UTF-8, so Rust
+    /// gets its bytes. unwrapped_type is always &[u8].
+    FromStringViewToBytes,
+    FromBoxToStdFunction, // unwrapped_type is always Box<adapter>
+    FromSliceToSpan,      // unwrapped_type is always &mut [T]
+    FromSliceToRustSlice, // unwrapped_type is always &mut [T]
+    /// A slice which C++ wants as a pointer to its first element and its
+    /// length, in that order unless `len_first`. unwrapped_type is always
+    /// &[T] or &mut [T].
+    FromSliceToPtrAndLen {
+        len_first: bool,
+    },
+    /// An array of pointers to C strings, which C++ wants as a pointer to
+    /// its first element and its length, in that order unless `len_first`.
+    /// unwrapped_type is always `&[*mut c_char]`, and `ptr_type` is the
+    /// original pointer type, such as `char**` or `const char* const*`.
+    FromCStrSliceToPtrArray {
+        ptr_type: Box<Type>,
+        len_first: bool,
+    },
+    /// A slice which we copy into a temporary `std::vector`.
+    /// unwrapped_type is always &[T].
+    FromSliceToVector,
+    /// A `&str` which we copy into a temporary `std::string`.
+    /// unwrapped_type is always &str.
+    FromStrToString,
+    /// A pointer to the first element of an array of the given length,
+    /// which C++ wants as a reference to the array. unwrapped_type is
+    /// always Type::Ptr.
+    FromPointerToArrayReference(Box<Expr>),
+    /// unwrapped_type is always Type::Ptr
+    FromPtrToIntrusivePtr(IntrusivePtr),
+    /// unwrapped_type is always Type::Ptr
+    FromIntrusivePtrToPtr(IntrusivePtr),
+    /// A reference returned from C++ which we copy into an owned value.
+    /// unwrapped_type is the referent type.
+    FromReferenceToValue,
+    /// A pointer returned from C++ whose object the caller now owns, and
+    /// which we therefore wrap in a `std::unique_ptr`. unwrapped_type is
+    /// the pointee type.
+    FromOwningPtrToUniquePtr,
+    /// A pointer returned from C++, which we combine with the result of
+    /// calling the named companion function to find its length, so as to
+    /// return a slice. unwrapped_type is always &[T].
+    FromPtrAndLenToSlice(String),
+    /// A `std::tuple` returned from C++ whose elements we copy into a
+    /// bridge struct. unwrapped_type is always the bridge struct.
+    FromStdTupleToBridge,
+    /// A `std::variant` returned from C++ whose active alternative we copy
+    /// into a bridge struct. unwrapped_type is always the bridge struct.
+    FromStdVariantToBridge,
+    /// A `std::optional` returned from C++ whose value, if any, we copy
+    /// into a tuple bridge struct along with whether there was one.
+    /// unwrapped_type is always the bridge struct.
+    FromStdOptionalToBridge,
+    /// A `std::error_code` returned from C++, of which we pass the value
+    /// in the lower 32 bits and whether it's in `std::system_category`,
+    /// `std::generic_category` or some other category in the upper 32 bits.
+    /// unwrapped_type is always `i64`.
+    FromErrorCodeToInt,
+    /// A `std::unique_ptr<void, D>` returned from C++, which we move into
+    /// an `OpaqueHandle`. unwrapped_type is always
+    /// `cxx::UniquePtr<OpaqueHandle>`.
+    FromVoidUniquePtrToOpaqueHandle,
 }
 
 impl CppConversionType {
@@ -41,6 +108,14 @@ impl CppConversionType {
             CppConversionType::FromValueToUniquePtr => CppConversionType::FromUniquePtrToValue,
             CppConversionType::FromPointerToReference => CppConversionType::FromReferenceToPointer,
             CppConversionType::FromReferenceToPointer => CppConversionType::FromPointerToReference,
+            CppConversionType::FromStrToStringView => CppConversionType::FromStringViewToStr,
+            CppConversionType::FromStringViewToStr => CppConversionType::FromStrToStringView,
+            CppConversionType::FromPtrToIntrusivePtr(ptr) => {
+                CppConversionType::FromIntrusivePtrToPtr(ptr.clone())
+            }
+            CppConversionType::FromIntrusivePtrToPtr(ptr) => {
+                CppConversionType::FromPtrToIntrusivePtr(ptr.clone())
+            }
             _ => panic!("Did not expect to have to invert this conversion"),
         }
     }
@@ -59,6 +134,71 @@ pub(crate) enum RustConversionType {
     FromRValueParamToPtr,
     FromReferenceWrapperToPointer, // unwrapped_type is always Type::Ptr
     FromPointerToReferenceWrapper, // unwrapped_type is always Type::Ptr
+    /// This parameter doesn't appear in the Rust wrapper function; instead
+    /// we allocate space for it and return it. unwrapped_type is always
+    /// Type::Ptr.
+    FromOutParamToReturn,
+    /// Like [`RustConversionType::FromOutParamToReturn`], but the Rust
+    /// wrapper function also takes the parameter by value, to pass in its
+    /// initial value. unwrapped_type is always Type::Ptr.
+    FromInOutParamToReturn,
+    /// This parameter is a closure which we box up into the given
+    /// `std::function` adapter. `fn_trait` is the `dyn Fn` trait
+    /// object which the closure must implement.
+    FromClosureToStdFunctionAdapter {
+        fn_trait: Box<Type>,
+        adapter: Ident,
+    },
+    /// This parameter is a C++ array of the given length, which Rust
+    /// passes as a reference to an array. unwrapped_type is always
+    /// Type::Ptr.
+    FromArrayRefToPtr(Box<Expr>),
+    /// This parameter is an `autocxx::IntrusivePtr`, whose reference we
+    /// hand over to C++ as a raw pointer. unwrapped_type is always
+    /// Type::Ptr.
+    FromIntrusivePtrToPtr,
+    /// This return value is a raw pointer carrying a reference, which we
+    /// wrap up in an `autocxx::IntrusivePtr`. unwrapped_type is always
+    /// Type::Ptr.
+    FromPtrToIntrusivePtr,
+    /// This parameter is a pointer into which C++ stores a pointer to a
+    /// new object, the ownership of which passes to us. It doesn't appear
+    /// in the Rust wrapper function, which instead returns a
+    /// `cxx::UniquePtr`. unwrapped_type is always a pointer to a pointer.
+    FromOutPtrToUniquePtr,
+    /// This parameter is a `void*` through which C++ is given ownership
+    /// of an `autocxx::Context`. unwrapped_type is always Type::Ptr.
+    FromContextToPtr,
+    /// This return value is a pointer which C++ got from a reference that
+    /// may nevertheless be null, which we turn into an `Option<&T>` or
+    /// `Option<Pin<&mut T>>`. unwrapped_type is always Type::Ptr.
+    FromPtrToOptionalReference,
+    /// This parameter is a slice of C strings, of which we make a
+    /// temporary array of pointers. unwrapped_type is always
+    /// `&[*mut c_char]`.
+    FromCStrSliceToPtrSlice,
+    /// This return value is a slice which we copy into a `Vec`, because
+    /// there's no reference parameter from which it could borrow.
+    /// unwrapped_type is always `&'static [T]`.
+    FromSliceToVec,
+    /// This return value is a bridge struct holding the elements of a
+    /// `std::tuple`, which we unpack into a Rust tuple of these types.
+    FromBridgeToTuple(Vec<Type>),
+    /// This return value is a bridge struct holding the active alternative
+    /// of a `std::variant`, which we turn into this generated enum type.
+    FromBridgeToVariant(Box<Type>),
+    /// This return value is a tuple bridge struct holding whether a
+    /// `std::optional` had a value and, if so, the value of this type,
+    /// which we turn into an `Option`.
+    FromBridgeToOption(Box<Type>),
+    /// This return value is the value and category of a `std::error_code`,
+    /// which we turn into a `Result<(), std::io::Error>`. unwrapped_type is
+    /// always `i64`.
+    FromIntToIoResult,
+    /// This return value is an enum marked with `status_result!`, which we
+    /// turn into `Ok(())` if it's the given success value, or an error
+    /// holding the value otherwise. unwrapped_type is always the enum.
+    FromStatusToResult(Ident),
 }
 
 impl RustConversionType {
@@ -156,7 +296,8 @@ impl TypeConversionPolicy {
 
     pub(crate) fn unconverted_rust_type(&self) -> Type {
         match self.cpp_conversion {
-            CppConversionType::FromValueToUniquePtr => self.make_unique_ptr_type(),
+            CppConversionType::FromValueToUniquePtr
+            | CppConversionType::FromOwningPtrToUniquePtr => self.make_unique_ptr_type(),
             _ => self.unwrapped_type.clone(),
         }
     }
@@ -181,6 +322,14 @@ impl TypeConversionPolicy {
         }
     }
 
+    /// The name of the adapter type for a `std::function` parameter.
+    pub(crate) fn std_function_adapter(&self) -> Result<&Ident, ConvertError> {
+        match &self.rust_conversion {
+            RustConversionType::FromClosureToStdFunctionAdapter { adapter, .. } => Ok(adapter),
+            _ => Err(ConvertError::UnsupportedStdFunction),
+        }
+    }
+
     pub(crate) fn rust_work_needed(&self) -> bool {
         !matches!(self.rust_conversion, RustConversionType::None)
     }
@@ -202,11 +351,26 @@ impl TypeConversionPolicy {
             RustConversionType::FromValueParamToPtr
                 | RustConversionType::FromRValueParamToPtr
                 | RustConversionType::FromPlacementParamToNewReturn
+                | RustConversionType::FromOutParamToReturn
+                | RustConversionType::FromInOutParamToReturn
                 | RustConversionType::FromPointerToReferenceWrapper { .. }
                 | RustConversionType::FromReferenceWrapperToPointer { .. }
+                | RustConversionType::FromArrayRefToPtr(_)
+                | RustConversionType::FromIntrusivePtrToPtr
+                | RustConversionType::FromOutPtrToUniquePtr
+                | RustConversionType::FromContextToPtr
         )
     }
 
+    /// The C++ function which returns the length of a returned array.
+    /// Only present for [`CppConversionType::FromPtrAndLenToSlice`].
+    pub(crate) fn slice_len_function(&self) -> Option<&str> {
+        match &self.cpp_conversion {
+            CppConversionType::FromPtrAndLenToSlice(len_function) => Some(len_function),
+            _ => None,
+        }
+    }
+
     pub(crate) fn is_placement_parameter(&self) -> bool {
         matches!(
             self.cpp_conversion,
@@ -232,6 +396,10 @@ pub(crate) enum CppFunctionBody {
     Destructor(Namespace, Ident),
     AllocUninitialized(QualifiedName),
     FreeUninitialized(QualifiedName),
+    FieldGetter(Ident),
+    FieldSetter(Ident),
+    /// Move-assigns the single argument into the receiver.
+    MoveAssignment,
 }
 
 #[derive(Clone)]
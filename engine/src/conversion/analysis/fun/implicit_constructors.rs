@@ -221,7 +221,13 @@ pub(super) fn find_constructors_present(
             let fields_items_found: Vec<_> = field_info
                 .iter()
                 .filter_map(|field_info| match field_info.type_kind {
-                    TypeKind::Regular | TypeKind::SubclassHolder(_) => match field_info.ty {
+                    TypeKind::Regular
+                    | TypeKind::SubclassHolder(_)
+                    | TypeKind::StdFunction { .. }
+                    | TypeKind::StdTuple(_)
+                    | TypeKind::StdVariant(_)
+                    | TypeKind::StdOptional(_)
+                    | TypeKind::OpaqueHandle => match field_info.ty {
                         Type::Path(ref qn) => get_items_found(&QualifiedName::from_type_path(qn)),
                         Type::Array(TypeArray { ref elem, .. }) => match elem.as_ref() {
                             Type::Path(ref qn) => {
@@ -235,9 +241,16 @@ pub(super) fn find_constructors_present(
                     // differentiate between pointers and references coming from C++. Pointers
                     // have a default constructor.
                     TypeKind::Pointer
+                    | TypeKind::SizedArrayPointer(_)
+                    | TypeKind::SizedArrayReference(_)
+                    | TypeKind::IntrusivePtr(_)
+                    | TypeKind::ReferenceToPointer
                     | TypeKind::Reference
                     | TypeKind::MutableReference
-                    | TypeKind::RValueReference => Some(ItemsFound {
+                    | TypeKind::RValueReference
+                    | TypeKind::StringView
+                    | TypeKind::Span
+                    | TypeKind::RustSlice => Some(ItemsFound {
                         default_constructor: SpecialMemberFound::NotPresent,
                         destructor: SpecialMemberFound::Implicit,
                         const_copy_constructor: SpecialMemberFound::Implicit,
@@ -569,6 +582,19 @@ fn find_explicit_items(
     let mut unknown_types = HashSet::new();
     for api in apis.iter() {
         match api {
+            // We bind move assignment operators, so they may have been
+            // ignored for any reason. That doesn't stop them existing.
+            Api::Function {
+                analysis:
+                    FnAnalysis {
+                        kind: FnKind::Method { impl_for, .. },
+                        ..
+                    },
+                fun,
+                ..
+            } if fun.is_move_assignment_operator() => {
+                merge_fun(impl_for.clone(), ExplicitKind::MoveAssignmentOperator, fun)
+            }
             Api::Function {
                 analysis:
                     FnAnalysis {
@@ -585,26 +611,19 @@ fn find_explicit_items(
                 Some(SpecialMemberKind::AssignmentOperator)
             ) =>
             {
-                let is_move_assignment_operator = !fun.references.rvalue_ref_params.is_empty();
+                let receiver_mutability = &param_details
+                    .iter()
+                    .next()
+                    .unwrap()
+                    .self_type
+                    .as_ref()
+                    .unwrap()
+                    .1;
                 merge_fun(
                     impl_for.clone(),
-                    if is_move_assignment_operator {
-                        ExplicitKind::MoveAssignmentOperator
-                    } else {
-                        let receiver_mutability = &param_details
-                            .iter()
-                            .next()
-                            .unwrap()
-                            .self_type
-                            .as_ref()
-                            .unwrap()
-                            .1;
-                        match receiver_mutability {
-                            ReceiverMutability::Const => ExplicitKind::ConstCopyAssignmentOperator,
-                            ReceiverMutability::Mutable => {
-                                ExplicitKind::NonConstCopyAssignmentOperator
-                            }
-                        }
+                    match receiver_mutability {
+                        ReceiverMutability::Const => ExplicitKind::ConstCopyAssignmentOperator,
+                        ReceiverMutability::Mutable => ExplicitKind::NonConstCopyAssignmentOperator,
                     },
                     fun,
                 )
@@ -28,8 +28,9 @@ use crate::{
         convert_error::{ConvertErrorWithContext, ErrorContextType},
         error_reporter::{convert_apis, report_any_error},
     },
-    known_types::known_types,
+    known_types::{is_c_string_ptr, known_types, CxxGenericType},
     types::validate_ident_ok_for_rust,
+    ConstructorDetector,
 };
 use indexmap::map::IndexMap as HashMap;
 use indexmap::set::IndexSet as HashSet;
@@ -38,16 +39,16 @@ use autocxx_parser::{ExternCppType, IncludeCppConfig, UnsafePolicy};
 use function_wrapper::{CppFunction, CppFunctionBody, TypeConversionPolicy};
 use itertools::Itertools;
 use proc_macro2::Span;
-use quote::quote;
+use quote::{quote, ToTokens};
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, FnArg, Ident, Pat, ReturnType, Type,
-    TypePath, TypePtr, TypeReference, Visibility,
+    parse_quote, punctuated::Punctuated, token::Comma, FnArg, GenericArgument, Ident, Pat,
+    PathArguments, ReturnType, Type, TypePath, TypePtr, TypeReference, Visibility,
 };
 
 use crate::{
     conversion::{
         api::{AnalysisPhase, Api, TypeKind},
-        ConvertError,
+        trace_decision, ConvertError,
     },
     types::{make_ident, validate_ident_ok_for_cxx, Namespace, QualifiedName},
 };
@@ -59,7 +60,7 @@ use self::{
     overload_tracker::OverloadTracker,
     subclass::{
         create_subclass_constructor, create_subclass_fn_wrapper, create_subclass_function,
-        create_subclass_trait_item,
+        create_subclass_protected_method_access, create_subclass_trait_item,
     },
 };
 
@@ -170,6 +171,8 @@ pub(crate) struct FnAnalysis {
     pub(crate) externally_callable: bool,
     /// Whether we need to generate a Rust-side calling function
     pub(crate) rust_wrapper_needed: bool,
+    /// Whether this has been marked using `thread_unsafe!`.
+    pub(crate) thread_unsafe: bool,
 }
 
 #[derive(Clone)]
@@ -243,6 +246,7 @@ pub(crate) struct FnPhase;
 pub(crate) struct PublicConstructors {
     pub(crate) move_constructor: bool,
     pub(crate) destructor: bool,
+    pub(crate) copy_constructor: bool,
 }
 
 impl PublicConstructors {
@@ -250,6 +254,7 @@ impl PublicConstructors {
         Self {
             move_constructor: items_found.move_constructor.callable_any(),
             destructor: items_found.destructor.callable_any(),
+            copy_constructor: items_found.const_copy_constructor.callable_any(),
         }
     }
 }
@@ -283,6 +288,8 @@ pub(crate) struct FnAnalyzer<'a> {
     generic_types: HashSet<QualifiedName>,
     types_in_anonymous_namespace: HashSet<QualifiedName>,
     existing_superclass_trait_api_names: HashSet<QualifiedName>,
+    mutable_overloads: HashSet<QualifiedName>,
+    constructor_detector: ConstructorDetector,
 }
 
 impl<'a> FnAnalyzer<'a> {
@@ -290,6 +297,7 @@ impl<'a> FnAnalyzer<'a> {
         apis: ApiVec<PodPhase>,
         unsafe_policy: &'a UnsafePolicy,
         config: &'a IncludeCppConfig,
+        constructor_detector: ConstructorDetector,
     ) -> ApiVec<FnPrePhase2> {
         let mut me = Self {
             unsafe_policy,
@@ -305,6 +313,8 @@ impl<'a> FnAnalyzer<'a> {
             generic_types: Self::build_generic_type_set(&apis),
             existing_superclass_trait_api_names: HashSet::new(),
             types_in_anonymous_namespace: Self::build_types_in_anonymous_namespace(&apis),
+            mutable_overloads: Self::build_mutable_overload_set(&apis),
+            constructor_detector,
         };
         let mut results = ApiVec::new();
         convert_apis(
@@ -334,6 +344,7 @@ impl<'a> FnAnalyzer<'a> {
                 } => Some(api.name().clone()),
                 Api::Enum { .. } => Some(api.name().clone()),
                 Api::ExternCppType { pod: true, .. } => Some(api.name().clone()),
+                Api::RustSharedStruct { .. } => Some(api.name().clone()),
                 _ => None,
             })
             .chain(
@@ -403,6 +414,55 @@ impl<'a> FnAnalyzer<'a> {
             .collect()
     }
 
+    /// Finds the non-const half of each pair of methods which differ only
+    /// in the constness of their receiver, such as `const T& at() const`
+    /// and `T& at()`. We call these `at` and `at_mut` rather than `at` and
+    /// `at1`.
+    fn build_mutable_overload_set(apis: &ApiVec<PodPhase>) -> HashSet<QualifiedName> {
+        let mut receivers_by_signature: HashMap<String, Vec<(QualifiedName, bool)>> =
+            HashMap::new();
+        for api in apis.iter() {
+            let (name, fun) = match api {
+                Api::Function { name, fun, .. } => (name, fun),
+                _ => continue,
+            };
+            let mut inputs = fun.inputs.iter();
+            let receiver = match inputs.next() {
+                Some(FnArg::Typed(pt)) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                    (Pat::Ident(pp), Type::Ptr(receiver)) if pp.ident == "this" => receiver,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let cpp_name = name
+                .cpp_name_if_present()
+                .cloned()
+                .unwrap_or_else(|| fun.ident.to_string());
+            let signature = format!(
+                "{} {} {}({})",
+                name.name.get_namespace(),
+                receiver.elem.to_token_stream(),
+                cpp_name,
+                inputs
+                    .map(|arg| arg.to_token_stream().to_string())
+                    .join(", ")
+            );
+            receivers_by_signature
+                .entry(signature)
+                .or_default()
+                .push((name.name.clone(), receiver.mutability.is_some()));
+        }
+        receivers_by_signature
+            .into_values()
+            .filter_map(|overloads| match overloads.as_slice() {
+                [(_, false), (mutable, true)] | [(mutable, true), (_, false)] => {
+                    Some(mutable.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Builds a mapping from a qualified type name to the last 'nest'
     /// of its name, if it has multiple elements.
     fn build_nested_type_map(apis: &ApiVec<PodPhase>) -> HashMap<QualifiedName, String> {
@@ -448,6 +508,28 @@ impl<'a> FnAnalyzer<'a> {
         self.config.is_on_allowlist(&type_name.to_cpp_name())
     }
 
+    fn is_pod_safe_type(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(p) => self
+                .pod_safe_types
+                .contains(&QualifiedName::from_type_path(p)),
+            _ => false,
+        }
+    }
+
+    /// Whether this is a `std::error_code`, in whatever inline namespace
+    /// the standard library puts it.
+    fn is_std_error_code(ty: &Type) -> bool {
+        match ty {
+            Type::Path(p) => {
+                let tn = QualifiedName::from_type_path(p);
+                tn.get_final_item() == "error_code"
+                    && tn.ns_segment_iter().next().map(String::as_str) == Some("std")
+            }
+            _ => false,
+        }
+    }
+
     fn is_generic_type(&self, type_name: &QualifiedName) -> bool {
         self.generic_types.contains(type_name)
     }
@@ -458,6 +540,8 @@ impl<'a> FnAnalyzer<'a> {
         &self,
         param_details: &[ArgumentAnalysis],
         kind: &FnKind,
+        ret_type: &ReturnType,
+        ret_was_reference: bool,
     ) -> UnsafetyNeeded {
         let unsafest_non_placement_param = UnsafetyNeeded::from_param_details(param_details, true);
         let unsafest_param = UnsafetyNeeded::from_param_details(param_details, false);
@@ -476,7 +560,9 @@ impl<'a> FnAnalyzer<'a> {
                 UnsafetyNeeded::Always => UnsafetyNeeded::JustBridge,
                 _ => unsafest_param,
             },
-            _ if matches!(self.unsafe_policy, UnsafePolicy::AllFunctionsUnsafe) => {
+            _ if matches!(self.unsafe_policy, UnsafePolicy::AllFunctionsUnsafe)
+                && !self.is_safe_getter(param_details, kind, ret_type, ret_was_reference) =>
+            {
                 UnsafetyNeeded::Always
             }
             _ => match unsafest_non_placement_param {
@@ -493,6 +579,35 @@ impl<'a> FnAnalyzer<'a> {
         }
     }
 
+    /// A `const` method which takes nothing but its receiver and returns
+    /// a POD or primitive type by value can't cause memory unsafety: there's
+    /// nothing to pass in, and nothing returned which borrows from C++.
+    fn is_safe_getter(
+        &self,
+        param_details: &[ArgumentAnalysis],
+        kind: &FnKind,
+        ret_type: &ReturnType,
+        ret_was_reference: bool,
+    ) -> bool {
+        if !self.config.safe_getters() || param_details.len() != 1 || ret_was_reference {
+            return false;
+        }
+        if !matches!(
+            kind,
+            FnKind::Method {
+                method_kind: MethodKind::Normal(ReceiverMutability::Const)
+                    | MethodKind::Virtual(ReceiverMutability::Const),
+                ..
+            }
+        ) {
+            return false;
+        }
+        match ret_type {
+            ReturnType::Type(_, ty) => self.is_pod_safe_type(ty),
+            ReturnType::Default => false,
+        }
+    }
+
     fn add_subclass_constructors(&mut self, apis: &mut ApiVec<FnPrePhase2>) {
         let mut results = ApiVec::new();
 
@@ -664,6 +779,32 @@ impl<'a> FnAnalyzer<'a> {
             }
         }
 
+        // Protected methods can't be called by anyone outside the class
+        // hierarchy, but Rust subclasses may well want to call them. Make
+        // them accessible through each subclass's C++ peer.
+        if let FnKind::Method {
+            impl_for: sup,
+            method_kind: MethodKind::Normal(..),
+            ..
+        } = &analysis.kind
+        {
+            if matches!(fun.cpp_vis, CppVisibility::Protected) && !fun.is_deleted {
+                for sub in self.subclasses_by_superclass(sup) {
+                    let access_name = ApiName::new_in_root_namespace(make_ident(format!(
+                        "{}_{}",
+                        sub.cpp().get_final_item(),
+                        name.name.get_final_item()
+                    )));
+                    self.analyze_and_add(
+                        access_name,
+                        create_subclass_protected_method_access(&sub, &fun),
+                        &mut results,
+                        TypeConversionSophistication::Regular,
+                    );
+                }
+            }
+        }
+
         results.push(Api::Function {
             fun,
             analysis,
@@ -722,6 +863,25 @@ impl<'a> FnAnalyzer<'a> {
         // for diagnostics whilst we do that.
         let initial_rust_name = fun.ident.to_string();
         let diagnostic_display_name = cpp_name.as_ref().unwrap_or(&initial_rust_name);
+        // Per-function directives name methods as `Class::method`, so that
+        // they don't apply to every method of the same name.
+        let config_name = Self::config_name(fun, diagnostic_display_name);
+        let copy_return = self.config.is_copy_return(&config_name);
+        let error_code_result = self.config.is_error_code_result(&config_name);
+        let nullable_return = self.config.is_nullable_return(&config_name);
+        let factory_for = self
+            .config
+            .get_factory_type(&config_name)
+            .map(QualifiedName::new_from_cpp_name);
+        let factory_name = diagnostic_display_name.clone();
+        let owning_return = self.config.is_owning_return(&config_name) || factory_for.is_some();
+        let slice_return_len_function = self
+            .config
+            .get_slice_return_len_function(&config_name)
+            .map(str::to_string);
+        let force_mut_self = self.config.is_force_mut_self(&config_name);
+        let array_params = self.config.is_array_params(&config_name);
+        let thread_unsafe = self.config.is_thread_unsafe(&config_name);
 
         // Now let's analyze all the parameters.
         // See if any have annotations which our fork of bindgen has craftily inserted...
@@ -729,6 +889,17 @@ impl<'a> FnAnalyzer<'a> {
             .inputs
             .iter()
             .map(|i| {
+                let force_rust_conversion = if self.is_out_param(&config_name, i) {
+                    Some(RustConversionType::FromOutParamToReturn)
+                } else if self.is_inout_param(&config_name, i) {
+                    Some(RustConversionType::FromInOutParamToReturn)
+                } else if self.is_owning_out_param(&config_name, i) {
+                    Some(RustConversionType::FromOutPtrToUniquePtr)
+                } else if self.is_context_param(&config_name, i) {
+                    Some(RustConversionType::FromContextToPtr)
+                } else {
+                    None
+                };
                 self.convert_fn_arg(
                     i,
                     ns,
@@ -737,14 +908,31 @@ impl<'a> FnAnalyzer<'a> {
                     &fun.references,
                     true,
                     false,
-                    None,
+                    force_rust_conversion,
                     sophistication,
                     false,
+                    force_mut_self,
+                    array_params,
                 )
             })
             .partition(Result::is_ok);
         let (mut params, mut param_details): (Punctuated<_, Comma>, Vec<_>) =
             param_details.into_iter().map(Result::unwrap).unzip();
+        let slice_param_problem = if bads.is_empty() {
+            self.pair_slice_params(&config_name, &mut params, &mut param_details)
+                .and_then(|_| {
+                    self.adapt_container_params(&config_name, &mut params, &mut param_details)
+                })
+                .and_then(|_| {
+                    self.adapt_reference_params(&config_name, &mut params, &mut param_details)
+                })
+                .and_then(|_| {
+                    self.adapt_const_slice_params(&config_name, &mut params, &mut param_details)
+                })
+                .err()
+        } else {
+            None
+        };
 
         let params_deps: HashSet<_> = param_details
             .iter()
@@ -769,9 +957,11 @@ impl<'a> FnAnalyzer<'a> {
         //   method,   IRN=A_foo,  CN=foo                       output: foo    case 4
         //   method,   IRN=A_move, CN=move   (keyword problem)  output: move_  case 5
         //   method,   IRN=A_foo1, CN=foo    (overload)         output: foo    case 6
-        let ideal_rust_name = match &cpp_name {
-            None => initial_rust_name, // case 1
-            Some(cpp_name) => {
+        // A name requested by a foreign_fn_hook trumps all of these.
+        let ideal_rust_name = match (&fun.rust_name, &cpp_name) {
+            (Some(rust_name), _) => rust_name.clone(),
+            (None, None) => initial_rust_name, // case 1
+            (None, Some(cpp_name)) => {
                 if initial_rust_name.ends_with('_') {
                     initial_rust_name // case 2
                 } else if validate_ident_ok_for_rust(cpp_name).is_err() {
@@ -805,8 +995,35 @@ impl<'a> FnAnalyzer<'a> {
             &ideal_rust_name,
             &self_ty,
         );
+        // A factory may be a free function or a static method, but in C++
+        // a static method must still be called via its own class.
+        let is_factory = factory_for.is_some() && receiver_mutability.is_none();
+        let factory_class = if is_factory && is_static_method {
+            self_ty.clone()
+        } else {
+            None
+        };
         let (kind, error_context, rust_name) = if let Some(trait_details) = trait_details {
             trait_details
+        } else if let (true, Some(factory_for)) = (is_factory, factory_for.clone()) {
+            // A factory registered using `factory!` becomes a static
+            // `make_unique` method of the type it constructs, but the C++
+            // wrapper must still call it by its own name.
+            cpp_name = Some(factory_name.clone());
+            let rust_name = self.get_overload_name(
+                factory_for.get_namespace(),
+                factory_for.get_final_item(),
+                "make_unique".into(),
+            );
+            let error_context = self.error_context_for_method(&factory_for, &rust_name);
+            (
+                FnKind::Method {
+                    impl_for: factory_for,
+                    method_kind: MethodKind::Static,
+                },
+                error_context,
+                rust_name,
+            )
         } else if let Some(self_ty) = self_ty {
             // Some kind of method or static method.
             let type_ident = self_ty.get_final_item();
@@ -828,7 +1045,9 @@ impl<'a> FnAnalyzer<'a> {
             ) {
                 let is_move =
                     matches!(fun.special_member, Some(SpecialMemberKind::MoveConstructor));
-                if let Some(constructor_suffix) = rust_name.strip_prefix(nested_type_ident) {
+                if let Some(constructor_suffix) =
+                    (self.constructor_detector)(&rust_name, nested_type_ident)
+                {
                     rust_name = format!("new{}", constructor_suffix);
                 }
                 rust_name = predetermined_rust_name
@@ -921,7 +1140,7 @@ impl<'a> FnAnalyzer<'a> {
                 )
             } else {
                 let method_kind = if let Some(constructor_suffix) =
-                    constructor_with_suffix(&rust_name, nested_type_ident)
+                    (self.constructor_detector)(&rust_name, nested_type_ident)
                 {
                     // It's a constructor. bindgen generates
                     // fn Type(this: *mut Type, ...args)
@@ -952,6 +1171,11 @@ impl<'a> FnAnalyzer<'a> {
                         Virtualness::PureVirtual => MethodKind::PureVirtual(receiver_mutability),
                     }
                 };
+                if fun.is_move_assignment_operator() {
+                    rust_name = "move_assign".to_string();
+                } else if self.mutable_overloads.contains(&name.name) {
+                    rust_name = format!("{}_mut", rust_name);
+                }
                 // Disambiguate overloads.
                 let rust_name = predetermined_rust_name
                     .unwrap_or_else(|| self.get_overload_name(ns, type_ident, rust_name));
@@ -1108,6 +1332,9 @@ impl<'a> FnAnalyzer<'a> {
         if fun.variadic {
             set_ignore_reason(ConvertError::Variadic);
         }
+        if let Some(problem) = slice_param_problem {
+            set_ignore_reason(problem);
+        }
         if let Some(problem) = bads.into_iter().next() {
             match problem {
                 Ok(_) => panic!("No error in the error"),
@@ -1120,7 +1347,8 @@ impl<'a> FnAnalyzer<'a> {
         } else if matches!(
             fun.special_member,
             Some(SpecialMemberKind::AssignmentOperator)
-        ) {
+        ) && !fun.is_move_assignment_operator()
+        {
             // Be careful with the order of this if-else tree. Anything above here means we won't
             // treat it as an assignment operator, but anything below we still consider when
             // deciding which other C++ special member functions are implicitly defined.
@@ -1157,6 +1385,12 @@ impl<'a> FnAnalyzer<'a> {
                         set_ignore_reason(ConvertError::MethodInAnonymousNamespace);
                     }
                 }
+                FnKind::Function if ns.iter().any(|seg| seg.starts_with("_bindgen_mod")) => {
+                    // bindgen names anonymous namespaces _bindgen_mod_N.
+                    // Anything within has internal linkage so there's no
+                    // symbol for us (or our C++ wrappers) to link against.
+                    set_ignore_reason(ConvertError::FunctionInAnonymousNamespace);
+                }
                 _ => {}
             }
         };
@@ -1181,7 +1415,34 @@ impl<'a> FnAnalyzer<'a> {
         // Analyze the return type, just as we previously did for the
         // parameters.
         let mut return_analysis = self
-            .convert_return_type(&fun.output, ns, &fun.references, sophistication)
+            .convert_return_type(
+                &fun.output,
+                ns,
+                &fun.references,
+                sophistication,
+                copy_return,
+                error_code_result,
+                nullable_return,
+            )
+            .and_then(|return_analysis| {
+                self.adapt_slice_return(
+                    &rust_name,
+                    slice_return_len_function,
+                    return_analysis,
+                    param_details.iter().any(|pd| pd.self_type.is_some()),
+                )
+            })
+            .and_then(|return_analysis| {
+                Self::check_factory_return(
+                    &factory_name,
+                    factory_for.as_ref(),
+                    is_factory,
+                    return_analysis,
+                )
+            })
+            .and_then(|return_analysis| {
+                self.adapt_owning_return(&rust_name, owning_return, return_analysis)
+            })
             .unwrap_or_else(|err| {
                 set_ignore_reason(err);
                 ReturnTypeAnalysis::default()
@@ -1197,7 +1458,12 @@ impl<'a> FnAnalyzer<'a> {
             params.push(extra_param);
         }
 
-        let requires_unsafe = self.should_be_unsafe(&param_details, &kind);
+        let requires_unsafe = self.should_be_unsafe(
+            &param_details,
+            &kind,
+            &return_analysis.rt,
+            return_analysis.was_reference,
+        );
 
         let num_input_references = param_details.iter().filter(|pd| pd.has_lifetime).count();
         if num_input_references != 1 && return_analysis.was_reference {
@@ -1247,7 +1513,12 @@ impl<'a> FnAnalyzer<'a> {
             _ if param_conversion_needed => true,
             _ if ret_type_conversion_needed => true,
             _ if cpp_name_incompatible_with_cxx => true,
+            // cxx would otherwise insist on a non-const C++ method.
+            FnKind::Method { .. } if force_mut_self => true,
             _ if fun.synthetic_cpp.is_some() => true,
+            // Gives the cxx::bridge method a distinct name from the Rust
+            // wrapper which will hold the tracing span.
+            FnKind::Method { .. } if self.config.trace_calls() => true,
             _ => false,
         };
 
@@ -1285,6 +1556,25 @@ impl<'a> FnAnalyzer<'a> {
                         CppFunctionBody::Destructor(ns.clone(), impl_for.get_final_ident()),
                         CppFunctionKind::Function,
                     ),
+                    // A factory is a static method in Rust, but in C++ it's
+                    // either a free function or a static method of some
+                    // other class.
+                    FnKind::Method {
+                        method_kind: MethodKind::Static,
+                        ..
+                    } if is_factory => (
+                        match &factory_class {
+                            Some(factory_class) => CppFunctionBody::StaticMethodCall(
+                                ns.clone(),
+                                factory_class.get_final_ident(),
+                                cpp_construction_ident,
+                            ),
+                            None => {
+                                CppFunctionBody::FunctionCall(ns.clone(), cpp_construction_ident)
+                            }
+                        },
+                        CppFunctionKind::Function,
+                    ),
                     FnKind::Method {
                         ref impl_for,
                         method_kind: MethodKind::Static,
@@ -1297,6 +1587,9 @@ impl<'a> FnAnalyzer<'a> {
                         ),
                         CppFunctionKind::Function,
                     ),
+                    FnKind::Method { .. } if fun.is_move_assignment_operator() => {
+                        (CppFunctionBody::MoveAssignment, CppFunctionKind::Method)
+                    }
                     FnKind::Method { .. } => (
                         CppFunctionBody::FunctionCall(ns.clone(), cpp_construction_ident),
                         CppFunctionKind::Method,
@@ -1357,6 +1650,7 @@ impl<'a> FnAnalyzer<'a> {
         let rust_wrapper_needed = match kind {
             _ if any_param_needs_rust_conversion || return_needs_rust_conversion => true,
             FnKind::TraitMethod { .. } => true,
+            _ if self.config.trace_calls() => true,
             FnKind::Method { .. } => cxxbridge_name != rust_name,
             _ => false,
         };
@@ -1372,6 +1666,12 @@ impl<'a> FnAnalyzer<'a> {
             }
             _ => RustRenameStrategy::None,
         };
+        if let Some(cpp_name) = cpp_name.as_ref().filter(|cpp_name| **cpp_name != rust_name) {
+            trace_decision(
+                &name.name,
+                format_args!("{} is called {} in Rust", cpp_name, rust_name),
+            );
+        }
 
         let analysis = FnAnalysis {
             cxxbridge_name: cxxbridge_name.clone(),
@@ -1389,6 +1689,7 @@ impl<'a> FnAnalyzer<'a> {
             ignore_reason,
             externally_callable,
             rust_wrapper_needed,
+            thread_unsafe,
         };
         let name = ApiName::new_with_cpp_name(ns, cxxbridge_name, cpp_name);
         (analysis, name)
@@ -1434,6 +1735,8 @@ impl<'a> FnAnalyzer<'a> {
             force_rust_conversion,
             sophistication,
             construct_into_self,
+            false,
+            false,
         )
         .map(|(new_arg, new_analysis)| {
             param_details[param_idx] = new_analysis;
@@ -1451,6 +1754,496 @@ impl<'a> FnAnalyzer<'a> {
         })
     }
 
+    /// The name by which per-function directives refer to this function:
+    /// `Class::method` for methods, or just the function name otherwise.
+    fn config_name(fun: &FuncToConvert, cpp_name: &str) -> String {
+        let receiver = fun.self_ty.clone().or_else(|| match fun.inputs.first() {
+            Some(FnArg::Typed(pt)) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                (Pat::Ident(pp), Type::Ptr(receiver)) if pp.ident == "this" => {
+                    match receiver.elem.as_ref() {
+                        Type::Path(typ) => Some(QualifiedName::from_type_path(typ)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        });
+        match receiver {
+            Some(receiver) => format!("{}::{}", receiver.get_final_item(), cpp_name),
+            None => cpp_name.to_string(),
+        }
+    }
+
+    /// Whether the user has asked for this pointer parameter to be
+    /// turned into part of the return value instead.
+    fn is_out_param(&self, fn_name: &str, arg: &FnArg) -> bool {
+        match arg {
+            FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                (syn::Pat::Ident(pp), Type::Ptr(_)) => {
+                    self.config.is_out_param(fn_name, &pp.ident.to_string())
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the user has asked for this pointer parameter to be passed
+    /// by value, and its updated value returned.
+    fn is_inout_param(&self, fn_name: &str, arg: &FnArg) -> bool {
+        match arg {
+            FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                (syn::Pat::Ident(pp), Type::Ptr(_)) => {
+                    self.config.is_inout_param(fn_name, &pp.ident.to_string())
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the user has asked for this reference-to-pointer parameter
+    /// to be returned as a `UniquePtr` owning the object C++ stores in it.
+    fn is_owning_out_param(&self, fn_name: &str, arg: &FnArg) -> bool {
+        match arg {
+            FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                (syn::Pat::Ident(pp), Type::Ptr(_)) => self
+                    .config
+                    .is_owning_out_param(fn_name, &pp.ident.to_string()),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Replace each pointer parameter marked with `slice_param!`, along
+    /// with the length parameter which follows it, with a single slice
+    /// parameter. The C++ wrapper splits the slice back up.
+    fn pair_slice_params(
+        &self,
+        fn_name: &str,
+        params: &mut Punctuated<FnArg, Comma>,
+        param_details: &mut Vec<ArgumentAnalysis>,
+    ) -> Result<(), ConvertError> {
+        for slice_param in self.config.get_slice_params(fn_name) {
+            let find_param = |name: &str| {
+                param_details
+                    .iter()
+                    .position(|pd| matches!(&pd.name, Pat::Ident(pp) if pp.ident == name))
+            };
+            let (idx, len_idx) = match (
+                find_param(&slice_param.param),
+                find_param(&slice_param.len_param),
+            ) {
+                (Some(idx), Some(len_idx)) if len_idx == idx + 1 || len_idx + 1 == idx => {
+                    (idx, len_idx)
+                }
+                _ => return Err(ConvertError::InvalidSliceParam(slice_param.param.clone())),
+            };
+            let len_first = len_idx < idx;
+            let ptr_type = param_details[idx].conversion.cxxbridge_type().clone();
+            // A `char**` would let C++ write to strings we've only borrowed.
+            let requires_unsafe = match &ptr_type {
+                Type::Ptr(ptr) => match ptr.elem.as_ref() {
+                    Type::Ptr(inner)
+                        if is_c_string_ptr(&ptr.elem) && inner.mutability.is_some() =>
+                    {
+                        UnsafetyNeeded::Always
+                    }
+                    _ => UnsafetyNeeded::None,
+                },
+                _ => UnsafetyNeeded::None,
+            };
+            let conversion = match &ptr_type {
+                Type::Ptr(ptr) if is_c_string_ptr(&ptr.elem) => TypeConversionPolicy::new(
+                    parse_quote! { &[*mut ::std::os::raw::c_char] },
+                    CppConversionType::FromCStrSliceToPtrArray {
+                        ptr_type: Box::new(ptr_type.clone()),
+                        len_first,
+                    },
+                    RustConversionType::FromCStrSliceToPtrSlice,
+                ),
+                Type::Ptr(ptr) if self.is_pod_safe_type(&ptr.elem) => {
+                    let elem = &ptr.elem;
+                    TypeConversionPolicy::new(
+                        if ptr.mutability.is_some() {
+                            parse_quote! { &mut [#elem] }
+                        } else {
+                            parse_quote! { &[#elem] }
+                        },
+                        CppConversionType::FromSliceToPtrAndLen { len_first },
+                        RustConversionType::None,
+                    )
+                }
+                _ => return Err(ConvertError::InvalidSliceParam(slice_param.param.clone())),
+            };
+            let slice_ty = conversion.cxxbridge_type().clone();
+            let len_details = param_details.remove(len_idx);
+            let details = &mut param_details[if len_first { idx - 1 } else { idx }];
+            details.conversion = conversion;
+            details.has_lifetime = true;
+            details.requires_unsafe = requires_unsafe;
+            details.deps.extend(len_details.deps);
+            *params = std::mem::take(params)
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| *i != len_idx)
+                .map(|(i, mut param)| {
+                    if i == idx {
+                        if let FnArg::Typed(pt) = &mut param {
+                            *pt.ty = slice_ty.clone();
+                        }
+                    }
+                    param
+                })
+                .collect();
+        }
+        Ok(())
+    }
+
+    /// If this function is marked with `slice_return!`, turn the pointer
+    /// it returns into a slice whose length comes from the companion
+    /// function. A method's slice borrows from `self`; any other function's
+    /// slice is copied into a `Vec` by the Rust wrapper.
+    fn adapt_slice_return(
+        &self,
+        fn_name: &str,
+        len_function: Option<String>,
+        return_analysis: ReturnTypeAnalysis,
+        has_receiver: bool,
+    ) -> Result<ReturnTypeAnalysis, ConvertError> {
+        let len_function = match len_function {
+            None => return Ok(return_analysis),
+            Some(len_function) => len_function,
+        };
+        let (rarrow, elem) = match &return_analysis.rt {
+            ReturnType::Type(rarrow, ty) => match ty.as_ref() {
+                Type::Ptr(ptr) if self.is_pod_safe_type(&ptr.elem) => (*rarrow, ptr.elem.clone()),
+                _ => return Err(ConvertError::InvalidSliceReturn(fn_name.to_string())),
+            },
+            ReturnType::Default => {
+                return Err(ConvertError::InvalidSliceReturn(fn_name.to_string()))
+            }
+        };
+        let (slice_ty, rust_conversion): (Type, _) = if has_receiver {
+            (parse_quote! { &[#elem] }, RustConversionType::None)
+        } else {
+            (
+                parse_quote! { &'static [#elem] },
+                RustConversionType::FromSliceToVec,
+            )
+        };
+        Ok(ReturnTypeAnalysis {
+            conversion: Some(TypeConversionPolicy::new(
+                slice_ty.clone(),
+                CppConversionType::FromPtrAndLenToSlice(len_function),
+                rust_conversion,
+            )),
+            rt: ReturnType::Type(rarrow, Box::new(slice_ty)),
+            was_reference: has_receiver,
+            ..return_analysis
+        })
+    }
+
+    /// If this function is marked with `owning_return!`, the caller owns
+    /// the object at the pointer it returns, so have the C++ wrapper put it
+    /// in a `std::unique_ptr` which will delete it.
+    fn adapt_owning_return(
+        &self,
+        fn_name: &str,
+        owning_return: bool,
+        return_analysis: ReturnTypeAnalysis,
+    ) -> Result<ReturnTypeAnalysis, ConvertError> {
+        if !owning_return {
+            return Ok(return_analysis);
+        }
+        let (rarrow, elem) = match &return_analysis.rt {
+            ReturnType::Type(rarrow, ty) => match ty.as_ref() {
+                Type::Ptr(TypePtr {
+                    mutability: Some(_),
+                    elem,
+                    ..
+                }) => match elem.as_ref() {
+                    Type::Path(p)
+                        if known_types()
+                            .permissible_within_unique_ptr(&QualifiedName::from_type_path(p)) =>
+                    {
+                        (*rarrow, elem.clone())
+                    }
+                    _ => return Err(ConvertError::InvalidOwningReturn(fn_name.to_string())),
+                },
+                _ => return Err(ConvertError::InvalidOwningReturn(fn_name.to_string())),
+            },
+            ReturnType::Default => {
+                return Err(ConvertError::InvalidOwningReturn(fn_name.to_string()))
+            }
+        };
+        Ok(ReturnTypeAnalysis {
+            conversion: Some(TypeConversionPolicy::new(
+                elem.as_ref().clone(),
+                CppConversionType::FromOwningPtrToUniquePtr,
+                RustConversionType::None,
+            )),
+            rt: ReturnType::Type(rarrow, elem),
+            was_reference: false,
+            ..return_analysis
+        })
+    }
+
+    /// A factory registered using `factory!` must be a free function or a
+    /// static method, and must return a pointer to a new object of the type
+    /// it's registered for.
+    fn check_factory_return(
+        fn_name: &str,
+        factory_for: Option<&QualifiedName>,
+        is_factory: bool,
+        return_analysis: ReturnTypeAnalysis,
+    ) -> Result<ReturnTypeAnalysis, ConvertError> {
+        let factory_for = match factory_for {
+            None => return Ok(return_analysis),
+            Some(factory_for) => factory_for,
+        };
+        if !is_factory {
+            return Err(ConvertError::FactoryNotStatic(fn_name.to_string()));
+        }
+        if let ReturnType::Type(_, ty) = &return_analysis.rt {
+            if let Type::Ptr(TypePtr {
+                mutability: Some(_),
+                elem,
+                ..
+            }) = ty.as_ref()
+            {
+                if let Type::Path(p) = elem.as_ref() {
+                    if &QualifiedName::from_type_path(p) == factory_for {
+                        return Ok(return_analysis);
+                    }
+                }
+            }
+        }
+        Err(ConvertError::InvalidFactory(
+            fn_name.to_string(),
+            factory_for.to_cpp_name(),
+        ))
+    }
+
+    /// Replace each `std::vector<T>` or `std::string` parameter marked with
+    /// `container_param!` with a `&[T]` or `&str` parameter respectively.
+    /// The C++ wrapper copies it into a temporary container.
+    fn adapt_container_params(
+        &self,
+        fn_name: &str,
+        params: &mut Punctuated<FnArg, Comma>,
+        param_details: &mut [ArgumentAnalysis],
+    ) -> Result<(), ConvertError> {
+        for (param, details) in params.iter_mut().zip(param_details.iter_mut()) {
+            let pt = match (param, &details.name) {
+                (FnArg::Typed(pt), Pat::Ident(pp))
+                    if self
+                        .config
+                        .is_container_param(fn_name, &pp.ident.to_string()) =>
+                {
+                    pt
+                }
+                _ => continue,
+            };
+            let invalid =
+                || ConvertError::InvalidContainerParam(details.name.to_token_stream().to_string());
+            let container = match details.conversion.cxxbridge_type() {
+                Type::Reference(TypeReference {
+                    elem,
+                    mutability: None,
+                    ..
+                })
+                | Type::Ptr(TypePtr {
+                    elem,
+                    mutability: None,
+                    ..
+                }) => elem.as_ref(),
+                ty => ty,
+            };
+            let container = match container {
+                Type::Path(tp) => tp,
+                _ => return Err(invalid()),
+            };
+            let tn = QualifiedName::from_type_path(container);
+            let (ty, cpp_conversion): (Type, _) = if known_types().convertible_from_strs(&tn) {
+                (parse_quote! { &str }, CppConversionType::FromStrToString)
+            } else if matches!(
+                known_types().cxx_generic_behavior(&tn),
+                CxxGenericType::CppVector
+            ) {
+                let elem = match container.path.segments.last().map(|seg| &seg.arguments) {
+                    Some(PathArguments::AngleBracketed(ab)) => match ab.args.first() {
+                        Some(GenericArgument::Type(elem)) if self.is_pod_safe_type(elem) => elem,
+                        _ => return Err(invalid()),
+                    },
+                    _ => return Err(invalid()),
+                };
+                (
+                    parse_quote! { &[#elem] },
+                    CppConversionType::FromSliceToVector,
+                )
+            } else {
+                return Err(invalid());
+            };
+            *pt.ty = ty.clone();
+            details.conversion =
+                TypeConversionPolicy::new(ty, cpp_conversion, RustConversionType::None);
+            details.has_lifetime = true;
+            details.requires_unsafe = UnsafetyNeeded::None;
+        }
+        Ok(())
+    }
+
+    /// Replace each pointer parameter marked with `reference_param!` with a
+    /// `Pin<&mut T>` or `&T`. The C++ wrapper passes on the address of the
+    /// referenced object.
+    fn adapt_reference_params(
+        &self,
+        fn_name: &str,
+        params: &mut Punctuated<FnArg, Comma>,
+        param_details: &mut [ArgumentAnalysis],
+    ) -> Result<(), ConvertError> {
+        for (param, details) in params.iter_mut().zip(param_details.iter_mut()) {
+            let pt = match (param, &details.name) {
+                (FnArg::Typed(pt), Pat::Ident(pp))
+                    if self
+                        .config
+                        .is_reference_param(fn_name, &pp.ident.to_string()) =>
+                {
+                    pt
+                }
+                _ => continue,
+            };
+            let (mutability, elem) = match details.conversion.cxxbridge_type() {
+                Type::Ptr(TypePtr {
+                    mutability, elem, ..
+                }) => match elem.as_ref() {
+                    Type::Path(tp)
+                        if !self
+                            .pod_safe_types
+                            .contains(&QualifiedName::from_type_path(tp)) =>
+                    {
+                        (*mutability, elem.clone())
+                    }
+                    _ => {
+                        return Err(ConvertError::InvalidReferenceParam(
+                            details.name.to_token_stream().to_string(),
+                        ))
+                    }
+                },
+                _ => {
+                    return Err(ConvertError::InvalidReferenceParam(
+                        details.name.to_token_stream().to_string(),
+                    ))
+                }
+            };
+            let ty: Type = match mutability {
+                Some(_) => parse_quote! { ::std::pin::Pin<&mut #elem> },
+                None => parse_quote! { &#elem },
+            };
+            *pt.ty = ty.clone();
+            details.conversion = TypeConversionPolicy::new(
+                ty,
+                CppConversionType::FromReferenceToPointer,
+                RustConversionType::None,
+            );
+            details.has_lifetime = true;
+            details.requires_unsafe = UnsafetyNeeded::None;
+        }
+        Ok(())
+    }
+
+    /// Take each `std::span` or `rust::Slice` parameter marked with
+    /// `const_slice_param!` as `&[T]` rather than `&mut [T]`. cxx passes a
+    /// `&[T]` as a `rust::Slice<const T>`, so the latter needs no wrapper.
+    fn adapt_const_slice_params(
+        &self,
+        fn_name: &str,
+        params: &mut Punctuated<FnArg, Comma>,
+        param_details: &mut [ArgumentAnalysis],
+    ) -> Result<(), ConvertError> {
+        for (param, details) in params.iter_mut().zip(param_details.iter_mut()) {
+            let pt = match (param, &details.name) {
+                (FnArg::Typed(pt), Pat::Ident(pp))
+                    if self
+                        .config
+                        .is_const_slice_param(fn_name, &pp.ident.to_string()) =>
+                {
+                    pt
+                }
+                _ => continue,
+            };
+            let (elem, cpp_conversion) = match (
+                &details.conversion.cpp_conversion,
+                details.conversion.cxxbridge_type(),
+            ) {
+                (
+                    CppConversionType::FromSliceToSpan,
+                    Type::Reference(TypeReference { elem, .. }),
+                ) => (elem.clone(), CppConversionType::FromSliceToSpan),
+                (
+                    CppConversionType::FromSliceToRustSlice,
+                    Type::Reference(TypeReference { elem, .. }),
+                ) => (elem.clone(), CppConversionType::None),
+                _ => {
+                    return Err(ConvertError::InvalidConstSliceParam(
+                        details.name.to_token_stream().to_string(),
+                    ))
+                }
+            };
+            let ty: Type = parse_quote! { &#elem };
+            *pt.ty = ty.clone();
+            details.conversion =
+                TypeConversionPolicy::new(ty, cpp_conversion, RustConversionType::None);
+        }
+        Ok(())
+    }
+
+    /// Whether this is a `Pin<&mut T>` where `T` is a primitive or POD type.
+    fn is_pinned_pod(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(p) => match extract_type_from_pinned_mut_ref(p) {
+                Type::Path(elem) => self.is_primitive_or_pod(&QualifiedName::from_type_path(&elem)),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether this is a `*mut T` where `T` is a primitive or POD type.
+    fn is_pointer_to_pod(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Ptr(ptr) => match ptr.elem.as_ref() {
+                Type::Path(elem) => self.is_primitive_or_pod(&QualifiedName::from_type_path(elem)),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether values of this type are plain old data which C++ can
+    /// assign over without looking at what was there before. Known types
+    /// such as `rust::String` may be held by value in Rust, but don't
+    /// qualify.
+    fn is_primitive_or_pod(&self, tn: &QualifiedName) -> bool {
+        self.pod_safe_types.contains(tn)
+            && (!known_types().is_known_type(tn) || known_types().is_simple_by_value(tn))
+    }
+
+    fn is_context_param(&self, fn_name: &str, arg: &FnArg) -> bool {
+        match arg {
+            FnArg::Typed(pt) => match (pt.pat.as_ref(), pt.ty.as_ref()) {
+                (syn::Pat::Ident(pp), Type::Ptr(_)) => {
+                    self.config.is_context_param(fn_name, &pp.ident.to_string())
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     fn get_overload_name(&mut self, ns: &Namespace, type_ident: &str, rust_name: String) -> String {
         let overload_tracker = self.overload_trackers_by_mod.entry(ns.clone()).or_default();
         overload_tracker.get_method_real_name(type_ident, rust_name)
@@ -1590,6 +2383,8 @@ impl<'a> FnAnalyzer<'a> {
         force_rust_conversion: Option<RustConversionType>,
         sophistication: TypeConversionSophistication,
         construct_into_self: bool,
+        force_mut_self: bool,
+        array_params: bool,
     ) -> Result<(FnArg, ArgumentAnalysis), ConvertError> {
         Ok(match arg {
             FnArg::Typed(pt) => {
@@ -1605,6 +2400,14 @@ impl<'a> FnAnalyzer<'a> {
                                 elem, mutability, ..
                             }) => match elem.as_ref() {
                                 Type::Path(typ) => {
+                                    // A logically-const method may be told to
+                                    // take `self` mutably, e.g. because it returns
+                                    // a mutable reference to a `mutable` field.
+                                    let mutability = if force_mut_self {
+                                        Some(syn::Token![mut](Span::call_site()))
+                                    } else {
+                                        *mutability
+                                    };
                                     let receiver_mutability = if mutability.is_some() {
                                         ReceiverMutability::Mutable
                                     } else {
@@ -1623,7 +2426,12 @@ impl<'a> FnAnalyzer<'a> {
                                         });
                                         virtual_this.clone()
                                     } else {
-                                        QualifiedName::from_type_path(typ)
+                                        let this_type = QualifiedName::from_type_path(typ);
+                                        if force_mut_self {
+                                            let typ = typ.clone();
+                                            pt.ty = Box::new(parse_quote! { *mut #typ });
+                                        }
+                                        this_type
                                     };
                                     Ok((this_type, receiver_mutability))
                                 }
@@ -1647,6 +2455,11 @@ impl<'a> FnAnalyzer<'a> {
                     }
                     syn::Pat::Ident(pp) => {
                         validate_ident_ok_for_cxx(&pp.ident.to_string())?;
+                        // bindgen has dropped the volatile qualifier, so we'd
+                        // otherwise give Rust non-volatile access.
+                        if references.volatile_params.contains(&pp.ident) {
+                            return Err(ConvertError::VolatileParam(pp.ident.to_string()));
+                        }
                         pointer_treatment = references.param_treatment(&pp.ident);
                         syn::Pat::Ident(pp)
                     }
@@ -1658,7 +2471,73 @@ impl<'a> FnAnalyzer<'a> {
                         force_rust_conversion,
                         Some(RustConversionType::FromPlacementParamToNewReturn)
                     );
-                let annotated_type = self.convert_boxed_type(pt.ty, ns, pointer_treatment)?;
+                let is_out_param = matches!(
+                    force_rust_conversion,
+                    Some(
+                        RustConversionType::FromOutParamToReturn
+                            | RustConversionType::FromInOutParamToReturn
+                            | RustConversionType::FromOutPtrToUniquePtr
+                    )
+                );
+                let is_context_param = matches!(
+                    force_rust_conversion,
+                    Some(RustConversionType::FromContextToPtr)
+                );
+                let mut annotated_type = self.convert_boxed_type(pt.ty, ns, pointer_treatment)?;
+                // Other functions' array parameters stay as plain pointers.
+                if matches!(
+                    annotated_type.kind,
+                    type_converter::TypeKind::SizedArrayPointer(_)
+                ) && !array_params
+                {
+                    annotated_type.kind = type_converter::TypeKind::Pointer;
+                }
+                if let type_converter::TypeKind::StdTuple(_) = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedStdTuple);
+                }
+                if let type_converter::TypeKind::StdVariant(_) = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedStdVariant);
+                }
+                if let type_converter::TypeKind::StdOptional(_) = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedStdOptional);
+                }
+                if let type_converter::TypeKind::OpaqueHandle = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedVoidUniquePtr);
+                }
+                // We'll hand C++ a pointer to a value which Rust owns, and
+                // which Rust then treats as initialized, so Rust has to be
+                // able to hold that value and C++ must be unable to leave it
+                // in any state Rust can't cope with.
+                if is_out_param
+                    && !matches!(
+                        force_rust_conversion,
+                        Some(RustConversionType::FromOutPtrToUniquePtr)
+                    )
+                    && !match annotated_type.kind {
+                        type_converter::TypeKind::MutableReference => {
+                            self.is_pinned_pod(&annotated_type.ty)
+                        }
+                        type_converter::TypeKind::Pointer => {
+                            self.is_pointer_to_pod(&annotated_type.ty)
+                        }
+                        _ => false,
+                    }
+                {
+                    return Err(ConvertError::InvalidOutParam(
+                        new_pat.to_token_stream().to_string(),
+                    ));
+                }
+                if matches!(
+                    force_rust_conversion,
+                    Some(RustConversionType::FromOutPtrToUniquePtr)
+                ) && !matches!(
+                    annotated_type.kind,
+                    type_converter::TypeKind::ReferenceToPointer
+                ) {
+                    return Err(ConvertError::InvalidOwningOutParam(
+                        new_pat.to_token_stream().to_string(),
+                    ));
+                }
                 let conversion = self.argument_conversion_details(
                     &annotated_type,
                     is_move_constructor,
@@ -1670,16 +2549,20 @@ impl<'a> FnAnalyzer<'a> {
                 let new_ty = annotated_type.ty;
                 pt.pat = Box::new(new_pat.clone());
                 pt.ty = new_ty;
-                let requires_unsafe =
-                    if matches!(annotated_type.kind, type_converter::TypeKind::Pointer)
-                        && !is_placement_return_destination
-                    {
-                        UnsafetyNeeded::Always
-                    } else if conversion.bridge_unsafe_needed() || is_placement_return_destination {
-                        UnsafetyNeeded::JustBridge
-                    } else {
-                        UnsafetyNeeded::None
-                    };
+                let requires_unsafe = if matches!(
+                    annotated_type.kind,
+                    type_converter::TypeKind::Pointer
+                        | type_converter::TypeKind::ReferenceToPointer
+                ) && !is_placement_return_destination
+                    && !is_out_param
+                    && !is_context_param
+                {
+                    UnsafetyNeeded::Always
+                } else if conversion.bridge_unsafe_needed() || is_placement_return_destination {
+                    UnsafetyNeeded::JustBridge
+                } else {
+                    UnsafetyNeeded::None
+                };
                 (
                     FnArg::Typed(pt),
                     ArgumentAnalysis {
@@ -1690,6 +2573,9 @@ impl<'a> FnAnalyzer<'a> {
                             annotated_type.kind,
                             type_converter::TypeKind::Reference
                                 | type_converter::TypeKind::MutableReference
+                                | type_converter::TypeKind::StringView
+                                | type_converter::TypeKind::Span
+                                | type_converter::TypeKind::RustSlice
                         ),
                         deps: annotated_type.types_encountered,
                         requires_unsafe,
@@ -1722,6 +2608,93 @@ impl<'a> FnAnalyzer<'a> {
             matches!(annotated_type.kind, type_converter::TypeKind::Reference) || is_self;
         let rust_conversion_forced = force_rust_conversion.is_some();
         let ty = &*annotated_type.ty;
+        if let (
+            Some(
+                rust_conversion @ (RustConversionType::FromOutParamToReturn
+                | RustConversionType::FromInOutParamToReturn),
+            ),
+            type_converter::TypeKind::MutableReference,
+            Type::Path(p),
+        ) = (&force_rust_conversion, &annotated_type.kind, ty)
+        {
+            let unwrapped_type = extract_type_from_pinned_mut_ref(p);
+            return TypeConversionPolicy::new(
+                parse_quote! { *mut #unwrapped_type },
+                CppConversionType::FromPointerToReference,
+                rust_conversion.clone(),
+            );
+        }
+        if matches!(annotated_type.kind, type_converter::TypeKind::StringView) {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromStrToStringView,
+                RustConversionType::None,
+            );
+        }
+        if matches!(annotated_type.kind, type_converter::TypeKind::Span) {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromSliceToSpan,
+                RustConversionType::None,
+            );
+        }
+        if matches!(annotated_type.kind, type_converter::TypeKind::RustSlice) {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromSliceToRustSlice,
+                RustConversionType::None,
+            );
+        }
+        if let type_converter::TypeKind::StdFunction { fn_trait, adapter } = &annotated_type.kind {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromBoxToStdFunction,
+                RustConversionType::FromClosureToStdFunctionAdapter {
+                    fn_trait: fn_trait.clone(),
+                    adapter: adapter.clone(),
+                },
+            );
+        }
+        if let type_converter::TypeKind::SizedArrayPointer(len) = &annotated_type.kind {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::None,
+                RustConversionType::FromArrayRefToPtr(Box::new(len.clone())),
+            );
+        }
+        if let type_converter::TypeKind::SizedArrayReference(len) = &annotated_type.kind {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromPointerToArrayReference(Box::new(len.clone())),
+                RustConversionType::FromArrayRefToPtr(Box::new(len.clone())),
+            );
+        }
+        if matches!(
+            annotated_type.kind,
+            type_converter::TypeKind::ReferenceToPointer
+        ) {
+            // Unless the user has told us otherwise, we can't know whether
+            // C++ hands over ownership of whatever it stores in the pointer,
+            // so leave it as a raw pointer to the pointer.
+            let rust_conversion = match force_rust_conversion {
+                Some(RustConversionType::FromOutPtrToUniquePtr) => {
+                    RustConversionType::FromOutPtrToUniquePtr
+                }
+                _ => RustConversionType::None,
+            };
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromPointerToReference,
+                rust_conversion,
+            );
+        }
+        if let type_converter::TypeKind::IntrusivePtr(ptr) = &annotated_type.kind {
+            return TypeConversionPolicy::new(
+                ty.clone(),
+                CppConversionType::FromPtrToIntrusivePtr(ptr.clone()),
+                RustConversionType::FromIntrusivePtrToPtr,
+            );
+        }
         if let Some(holder_id) = is_subclass_holder {
             let subclass = SubclassName::from_holder_name(holder_id);
             return {
@@ -1790,6 +2763,9 @@ impl<'a> FnAnalyzer<'a> {
                         RustConversionType::None,
                     )
                 } else {
+                    // This may become `FromRValueParamToPtr` once we know
+                    // whether the type can be copied; see
+                    // `pass_move_only_params_by_rvalue`.
                     TypeConversionPolicy::new(
                         ty,
                         CppConversionType::FromPtrToValue,
@@ -1853,21 +2829,198 @@ impl<'a> FnAnalyzer<'a> {
         }
     }
 
+    #[allow(clippy::too_many_arguments)] // it's true, but sticking with it for now
     fn convert_return_type(
         &mut self,
         rt: &ReturnType,
         ns: &Namespace,
         references: &References,
         sophistication: TypeConversionSophistication,
+        copy_return: bool,
+        error_code_result: bool,
+        nullable_return: bool,
     ) -> Result<ReturnTypeAnalysis, ConvertError> {
+        // Otherwise we'd return whatever it is unchecked.
+        if nullable_return && !references.ref_return {
+            return Err(ConvertError::InvalidNullableReturn);
+        }
         Ok(match rt {
             ReturnType::Default => ReturnTypeAnalysis::default(),
+            // The user asked for this std::error_code to become a Rust
+            // Result, so the C++ wrapper returns just its value and
+            // category, which the Rust wrapper turns into an error if the
+            // value is nonzero.
+            ReturnType::Type(rarrow, boxed_type)
+                if error_code_result && Self::is_std_error_code(boxed_type) =>
+            {
+                let ty: Type = parse_quote! { i64 };
+                ReturnTypeAnalysis {
+                    conversion: Some(TypeConversionPolicy::new(
+                        ty.clone(),
+                        CppConversionType::FromErrorCodeToInt,
+                        RustConversionType::FromIntToIoResult,
+                    )),
+                    rt: ReturnType::Type(*rarrow, Box::new(ty)),
+                    was_reference: false,
+                    deps: HashSet::new(),
+                    placement_param_needed: None,
+                }
+            }
             ReturnType::Type(rarrow, boxed_type) => {
                 let annotated_type =
                     self.convert_boxed_type(boxed_type.clone(), ns, references.return_treatment())?;
+                if let type_converter::TypeKind::StdFunction { .. } = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedStdFunction);
+                }
+                if let type_converter::TypeKind::Span = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedStdSpan);
+                }
+                if let type_converter::TypeKind::RustSlice = annotated_type.kind {
+                    return Err(ConvertError::UnsupportedRustSlice);
+                }
                 let boxed_type = annotated_type.ty;
                 let ty: &Type = boxed_type.as_ref();
+                let status_success_value = match ty {
+                    Type::Path(p) => self
+                        .config
+                        .get_status_result_success_value(
+                            &QualifiedName::from_type_path(p).to_cpp_name(),
+                        )
+                        .map(make_ident),
+                    _ => None,
+                };
                 match ty {
+                    // We can't return a std::tuple through cxx, so the C++
+                    // wrapper copies its elements into a bridge struct, which
+                    // the Rust wrapper then unpacks into a tuple.
+                    _ if matches!(annotated_type.kind, type_converter::TypeKind::StdTuple(_)) => {
+                        let elems = match annotated_type.kind {
+                            type_converter::TypeKind::StdTuple(elems) => elems,
+                            _ => unreachable!(),
+                        };
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                ty.clone(),
+                                CppConversionType::FromStdTupleToBridge,
+                                RustConversionType::FromBridgeToTuple(elems),
+                            )),
+                            rt: ReturnType::Type(*rarrow, boxed_type),
+                            was_reference: false,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    // Likewise a std::variant, for which the bridge struct holds
+                    // the index and value of the active alternative, and the
+                    // Rust wrapper turns it into the corresponding enum variant.
+                    _ if matches!(annotated_type.kind, type_converter::TypeKind::StdVariant(_)) => {
+                        let enum_type = match annotated_type.kind {
+                            type_converter::TypeKind::StdVariant(enum_type) => enum_type,
+                            _ => unreachable!(),
+                        };
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                ty.clone(),
+                                CppConversionType::FromStdVariantToBridge,
+                                RustConversionType::FromBridgeToVariant(enum_type),
+                            )),
+                            rt: ReturnType::Type(*rarrow, boxed_type),
+                            was_reference: false,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    // Likewise a std::optional, for which the bridge struct holds
+                    // whether there's a value and the value itself, from which
+                    // the Rust wrapper makes an Option.
+                    _ if matches!(
+                        annotated_type.kind,
+                        type_converter::TypeKind::StdOptional(_)
+                    ) =>
+                    {
+                        let value_type = match annotated_type.kind {
+                            type_converter::TypeKind::StdOptional(value_type) => value_type,
+                            _ => unreachable!(),
+                        };
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                ty.clone(),
+                                CppConversionType::FromStdOptionalToBridge,
+                                RustConversionType::FromBridgeToOption(value_type),
+                            )),
+                            rt: ReturnType::Type(*rarrow, boxed_type),
+                            was_reference: false,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    // We can't return a std::unique_ptr<void, D> through cxx, so
+                    // the C++ wrapper moves it into a type-erased handle which
+                    // will call the deleter in due course.
+                    _ if matches!(annotated_type.kind, type_converter::TypeKind::OpaqueHandle) => {
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                ty.clone(),
+                                CppConversionType::FromVoidUniquePtrToOpaqueHandle,
+                                RustConversionType::None,
+                            )),
+                            rt: ReturnType::Type(*rarrow, boxed_type),
+                            was_reference: false,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    // The user told us this reference may in practice be null,
+                    // so the C++ wrapper returns a pointer which the Rust wrapper
+                    // checks before turning it into an Option.
+                    Type::Reference(TypeReference {
+                        mutability, elem, ..
+                    }) if nullable_return => ReturnTypeAnalysis {
+                        conversion: Some(TypeConversionPolicy::new(
+                            if mutability.is_some() {
+                                parse_quote! { *mut #elem }
+                            } else {
+                                parse_quote! { *const #elem }
+                            },
+                            CppConversionType::FromReferenceToPointer,
+                            RustConversionType::FromPtrToOptionalReference,
+                        )),
+                        rt: ReturnType::Type(*rarrow, boxed_type.clone()),
+                        was_reference: true,
+                        deps: annotated_type.types_encountered,
+                        placement_param_needed: None,
+                    },
+                    Type::Path(p)
+                        if nullable_return
+                            && references.ref_return
+                            && p.path.segments.last().unwrap().ident == "Pin" =>
+                    {
+                        let elem = extract_type_from_pinned_mut_ref(p);
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                parse_quote! { *mut #elem },
+                                CppConversionType::FromReferenceToPointer,
+                                RustConversionType::FromPtrToOptionalReference,
+                            )),
+                            rt: ReturnType::Type(*rarrow, boxed_type.clone()),
+                            was_reference: true,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    // The user told us which value of this enum means success,
+                    // so the Rust wrapper turns any other value into an error.
+                    Type::Path(_) if status_success_value.is_some() => ReturnTypeAnalysis {
+                        conversion: Some(TypeConversionPolicy::new(
+                            ty.clone(),
+                            CppConversionType::None,
+                            RustConversionType::FromStatusToResult(status_success_value.unwrap()),
+                        )),
+                        rt: ReturnType::Type(*rarrow, boxed_type.clone()),
+                        was_reference: false,
+                        deps: annotated_type.types_encountered,
+                        placement_param_needed: None,
+                    },
                     Type::Path(p)
                         if !self
                             .pod_safe_types
@@ -1896,6 +3049,8 @@ impl<'a> FnAnalyzer<'a> {
                                 Some(RustConversionType::FromPlacementParamToNewReturn),
                                 TypeConversionSophistication::Regular,
                                 false,
+                                false,
+                                false,
                             )?;
                             ReturnTypeAnalysis {
                                 rt: ReturnType::Default,
@@ -1923,6 +3078,66 @@ impl<'a> FnAnalyzer<'a> {
                             }
                         }
                     }
+                    _ if matches!(
+                        annotated_type.kind,
+                        type_converter::TypeKind::IntrusivePtr(_)
+                    ) =>
+                    {
+                        let ptr = match annotated_type.kind {
+                            type_converter::TypeKind::IntrusivePtr(ptr) => ptr,
+                            _ => unreachable!(),
+                        };
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                ty.clone(),
+                                CppConversionType::FromIntrusivePtrToPtr(ptr),
+                                RustConversionType::FromPtrToIntrusivePtr,
+                            )),
+                            rt: ReturnType::Type(*rarrow, boxed_type),
+                            was_reference: false,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    // The user asked for a copy of this POD type rather than a
+                    // reference to it, so the C++ wrapper returns it by value.
+                    Type::Reference(TypeReference {
+                        mutability: None,
+                        elem,
+                        ..
+                    }) if copy_return
+                        && matches!(sophistication, TypeConversionSophistication::Regular)
+                        && self.is_pod_safe_type(elem) =>
+                    {
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                elem.as_ref().clone(),
+                                CppConversionType::FromReferenceToValue,
+                                RustConversionType::None,
+                            )),
+                            rt: ReturnType::Type(*rarrow, elem.clone()),
+                            was_reference: false,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
+                    _ if matches!(annotated_type.kind, type_converter::TypeKind::StringView) => {
+                        // A view needn't contain UTF-8, so we return its bytes.
+                        // They borrow from whatever the view refers to, so treat
+                        // them just like a returned reference for lifetime purposes.
+                        let bytes: Type = parse_quote! { &[u8] };
+                        ReturnTypeAnalysis {
+                            conversion: Some(TypeConversionPolicy::new(
+                                bytes.clone(),
+                                CppConversionType::FromStringViewToBytes,
+                                RustConversionType::None,
+                            )),
+                            rt: ReturnType::Type(*rarrow, Box::new(bytes)),
+                            was_reference: true,
+                            deps: annotated_type.types_encountered,
+                            placement_param_needed: None,
+                        }
+                    }
                     _ => {
                         let was_reference = references.ref_return;
                         let conversion = Some(
@@ -1964,6 +3179,14 @@ impl<'a> FnAnalyzer<'a> {
     /// for further analysis phases.
     fn add_constructors_present(&mut self, mut apis: ApiVec<FnPrePhase1>) -> ApiVec<FnPrePhase2> {
         let all_items_found = find_constructors_present(&apis);
+        // Only now do we know which types can't be copied, including those
+        // whose copy constructor is implicitly deleted, e.g. because
+        // they have a move-only field.
+        let move_only_types: HashSet<QualifiedName> = all_items_found
+            .iter()
+            .filter(|(_, items_found)| !items_found.const_copy_constructor.callable_any())
+            .map(|(ty, _)| ty.clone())
+            .collect();
         for (self_ty, items_found) in all_items_found.iter() {
             if self.config.exclude_impls {
                 // Remember that `find_constructors_present` mutates `apis`, so we always have to
@@ -1975,6 +3198,7 @@ impl<'a> FnAnalyzer<'a> {
                 .config
                 .is_on_constructor_blocklist(&self_ty.to_cpp_name())
             {
+                trace_decision(self_ty, "implicit special members skipped: blocklisted");
                 continue;
             }
             let path = self_ty.to_type_path();
@@ -2026,6 +3250,16 @@ impl<'a> FnAnalyzer<'a> {
             }
         }
 
+        let apis: ApiVec<FnPrePhase1> = apis
+            .into_iter()
+            .map(|mut api| {
+                if let Api::Function { analysis, .. } = &mut api {
+                    Self::pass_move_only_params_by_rvalue(analysis, &move_only_types);
+                }
+                api
+            })
+            .collect();
+
         // Also, annotate each type with the constructors we found.
         let mut results = ApiVec::new();
         convert_apis(
@@ -2053,6 +3287,28 @@ impl<'a> FnAnalyzer<'a> {
         results
     }
 
+    /// We can't copy a move-only type into a by-value parameter, so only
+    /// accept arguments which we can move from.
+    fn pass_move_only_params_by_rvalue(
+        analysis: &mut FnAnalysis,
+        move_only_types: &HashSet<QualifiedName>,
+    ) {
+        for param in analysis.param_details.iter_mut() {
+            let conversion = &mut param.conversion;
+            if !matches!(
+                conversion.rust_conversion,
+                RustConversionType::FromValueParamToPtr
+            ) {
+                continue;
+            }
+            if let Type::Path(typ) = conversion.cxxbridge_type() {
+                if move_only_types.contains(&QualifiedName::from_type_path(typ)) {
+                    conversion.rust_conversion = RustConversionType::FromRValueParamToPtr;
+                }
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)] // it's true, but sticking with it for now
     fn synthesize_special_member(
         &mut self,
@@ -2101,6 +3357,7 @@ impl<'a> FnAnalyzer<'a> {
                         unused_template_param: false,
                         references,
                         original_name: None,
+                        rust_name: None,
                         synthesized_this_type: None,
                         is_deleted: false,
                         add_to_trait: None,
@@ -2117,19 +3374,6 @@ impl<'a> FnAnalyzer<'a> {
     }
 }
 
-/// Attempts to determine whether this function name is a constructor, and if so,
-/// returns the suffix.
-fn constructor_with_suffix<'a>(rust_name: &'a str, nested_type_ident: &str) -> Option<&'a str> {
-    let suffix = rust_name.strip_prefix(nested_type_ident);
-    suffix.and_then(|suffix| {
-        if suffix.is_empty() || suffix.parse::<u32>().is_ok() {
-            Some(suffix)
-        } else {
-            None
-        }
-    })
-}
-
 impl Api<FnPhase> {
     pub(crate) fn name_for_allowlist(&self) -> QualifiedName {
         match &self {
@@ -2141,6 +3385,9 @@ impl Api<FnPhase> {
                 }
             },
             Api::RustSubclassFn { subclass, .. } => subclass.0.name.clone(),
+            Api::ComparisonOperators { target, .. } => target.clone(),
+            Api::IndexOperators { target, .. } => target.clone(),
+            Api::BoolConversion { target, .. } => target.clone(),
             Api::IgnoredItem {
                 name,
                 ctx: Some(ctx),
@@ -2175,6 +3422,15 @@ impl Api<FnPhase> {
                 },
                 ..
             } | Api::StringConstructor { .. }
+                | Api::StringTable { .. }
+                | Api::StdFunctionAdapter { .. }
+                | Api::StdTupleBridge { .. }
+                | Api::StdVariantBridge { .. }
+                | Api::OpaqueHandle { .. }
+                | Api::IntrusivePtrTarget { .. }
+                | Api::ComparisonOperators { .. }
+                | Api::IndexOperators { .. }
+                | Api::BoolConversion { .. }
                 | Api::ConcreteType { .. }
                 | Api::CType { .. }
                 | Api::RustSubclassFn { .. }
@@ -2197,6 +3453,11 @@ impl Api<FnPhase> {
             Api::Function { ref analysis, .. } => Some(analysis.cxxbridge_name.clone()),
             Api::StringConstructor { .. }
             | Api::Const { .. }
+            | Api::StringTable { .. }
+            | Api::IntrusivePtrTarget { .. }
+            | Api::ComparisonOperators { .. }
+            | Api::IndexOperators { .. }
+            | Api::BoolConversion { .. }
             | Api::IgnoredItem { .. }
             | Api::RustSubclassFn { .. } => None,
             _ => Some(self.name().get_final_ident()),
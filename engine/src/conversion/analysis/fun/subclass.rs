@@ -64,6 +64,7 @@ pub(super) fn create_subclass_fn_wrapper(
         special_member: None,
         unused_template_param: fun.unused_template_param,
         original_name: None,
+        rust_name: None,
         references: fun.references.clone(),
         add_to_trait: fun.add_to_trait.clone(),
         is_deleted: fun.is_deleted,
@@ -73,6 +74,38 @@ pub(super) fn create_subclass_fn_wrapper(
     })
 }
 
+/// Protected superclass methods can't be called from outside the class
+/// hierarchy, so re-expose them as public methods of the C++ peer class
+/// (by means of a `using` declaration) such that Rust subclasses can call
+/// them via their peer.
+pub(super) fn create_subclass_protected_method_access(
+    sub: &SubclassName,
+    fun: &FuncToConvert,
+) -> Box<FuncToConvert> {
+    let self_ty = Some(sub.cpp());
+    Box::new(FuncToConvert {
+        synthesized_this_type: self_ty.clone(),
+        self_ty,
+        ident: fun.ident.clone(),
+        doc_attrs: fun.doc_attrs.clone(),
+        inputs: fun.inputs.clone(),
+        output: fun.output.clone(),
+        vis: fun.vis.clone(),
+        virtualness: Virtualness::None,
+        cpp_vis: CppVisibility::Public,
+        special_member: None,
+        unused_template_param: fun.unused_template_param,
+        original_name: fun.original_name.clone(),
+        rust_name: fun.rust_name.clone(),
+        references: fun.references.clone(),
+        add_to_trait: None,
+        is_deleted: fun.is_deleted,
+        synthetic_cpp: None,
+        provenance: Provenance::SynthesizedProtectedMethodAccess(sub.clone()),
+        variadic: fun.variadic,
+    })
+}
+
 pub(super) fn create_subclass_trait_item(
     name: ApiName,
     analysis: &FnAnalysis,
@@ -242,6 +275,7 @@ pub(super) fn create_subclass_constructor(
         cpp_vis: CppVisibility::Public,
         special_member: fun.special_member.clone(),
         original_name: None,
+        rust_name: None,
         unused_template_param: fun.unused_template_param,
         references: fun.references.clone(),
         synthesized_this_type: Some(cpp.clone()),
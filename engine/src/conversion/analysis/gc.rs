@@ -12,7 +12,7 @@ use indexmap::set::IndexSet as HashSet;
 use autocxx_parser::IncludeCppConfig;
 
 use crate::{
-    conversion::{api::Api, apivec::ApiVec},
+    conversion::{api::Api, apivec::ApiVec, ConvertError},
     types::QualifiedName,
 };
 
@@ -39,7 +39,7 @@ pub(crate) fn filter_apis_by_following_edges_from_allowlist(
     apis: ApiVec<FnPhase>,
     config: &IncludeCppConfig,
 ) -> ApiVec<FnPhase> {
-    let mut todos: Vec<QualifiedName> = apis
+    let roots = apis
         .iter()
         .filter(|api| {
             let tnforal = api.name_for_allowlist();
@@ -48,6 +48,34 @@ pub(crate) fn filter_apis_by_following_edges_from_allowlist(
         .map(Api::name)
         .cloned()
         .collect();
+    filter_apis_by_following_edges_from_roots(apis, roots)
+}
+
+/// As [filter_apis_by_following_edges_from_allowlist], but retains only
+/// the APIs for a single requested symbol and the things it depends upon,
+/// irrespective of the allowlist. A type brings its methods along, just as
+/// it would if it were on the allowlist. It's an error if there's no such
+/// symbol.
+pub(crate) fn filter_apis_by_following_edges_from_symbol(
+    apis: ApiVec<FnPhase>,
+    symbol: &QualifiedName,
+) -> Result<ApiVec<FnPhase>, ConvertError> {
+    let roots: Vec<_> = apis
+        .iter()
+        .filter(|api| &api.name_for_allowlist() == symbol)
+        .map(Api::name)
+        .cloned()
+        .collect();
+    if roots.is_empty() {
+        return Err(ConvertError::SymbolNotFound(symbol.clone()));
+    }
+    Ok(filter_apis_by_following_edges_from_roots(apis, roots))
+}
+
+fn filter_apis_by_following_edges_from_roots(
+    apis: ApiVec<FnPhase>,
+    mut todos: Vec<QualifiedName>,
+) -> ApiVec<FnPhase> {
     let mut by_typename: HashMap<QualifiedName, ApiVec<FnPhase>> = HashMap::new();
     for api in apis.into_iter() {
         let tn = api.name().clone();
@@ -0,0 +1,80 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use autocxx_parser::IncludeCppConfig;
+use indexmap::map::IndexMap as HashMap;
+use syn::{Ident, Type};
+
+use crate::{
+    conversion::{api::Api, apivec::ApiVec, error_reporter::convert_item_apis, ConvertError},
+    types::{make_ident, QualifiedName},
+};
+
+use super::fun::{
+    function_wrapper::{RustConversionType, TypeConversionPolicy},
+    FnAnalysis, FnKind, FnPhase,
+};
+
+/// For each type marked with `generator!`, the Rust name of its `next()`
+/// method, which takes no parameters and returns a `std::optional`, and
+/// the type of its items.
+pub(crate) fn find_generator_items(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> HashMap<QualifiedName, (Ident, Type)> {
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function {
+                analysis:
+                    FnAnalysis {
+                        kind: FnKind::Method { impl_for, .. },
+                        rust_name,
+                        params,
+                        ret_conversion:
+                            Some(TypeConversionPolicy {
+                                rust_conversion: RustConversionType::FromBridgeToOption(item_type),
+                                ..
+                            }),
+                        ignore_reason: Ok(()),
+                        ..
+                    },
+                ..
+            } if api.effective_cpp_name() == "next"
+                && params.len() == 1
+                && config.is_generator(&impl_for.to_cpp_name()) =>
+            {
+                Some((
+                    impl_for.clone(),
+                    (make_ident(rust_name), item_type.as_ref().clone()),
+                ))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Refuse to generate any type marked with `generator!` which doesn't
+/// have a suitable `next()` method, rather than silently generating it
+/// without `autocxx::CppGenerator`.
+pub(crate) fn check_generators(
+    apis: ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> ApiVec<FnPhase> {
+    let generator_items = find_generator_items(&apis, config);
+    let mut results = ApiVec::new();
+    convert_item_apis(apis, &mut results, |api| match api {
+        Api::Struct { ref name, .. }
+            if config.is_generator(&name.name.to_cpp_name())
+                && !generator_items.contains_key(&name.name) =>
+        {
+            Err(ConvertError::GeneratorWithoutNext)
+        }
+        _ => Ok(Box::new(std::iter::once(api))),
+    });
+    results
+}
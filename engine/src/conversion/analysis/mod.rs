@@ -14,8 +14,10 @@ pub(crate) mod ctypes;
 pub(crate) mod deps;
 mod depth_first;
 mod doc_label;
+pub(crate) mod field_accessors;
 pub(crate) mod fun;
 pub(crate) mod gc;
+pub(crate) mod generators;
 mod name_check;
 pub(crate) mod pod; // hey, that rhymes
 pub(crate) mod remove_ignored;
@@ -25,4 +27,5 @@ mod type_converter;
 
 pub(crate) use name_check::check_names;
 pub(crate) use replace_hopeless_typedef_targets::replace_hopeless_typedef_targets;
+pub(crate) use type_converter::function_pointer_type;
 pub(crate) use type_converter::PointerTreatment;
@@ -37,6 +37,7 @@ pub(crate) fn check_names(apis: ApiVec<FnPhase>) -> ApiVec<FnPhase> {
         | Api::ForwardDeclaration { ref name, .. }
         | Api::OpaqueTypedef { ref name, .. }
         | Api::Const { ref name, .. }
+        | Api::StringTable { ref name, .. }
         | Api::Enum { ref name, .. }
         | Api::Struct { ref name, .. } => {
             validate_all_segments_ok_for_cxx(name.name.segment_iter())?;
@@ -66,9 +67,18 @@ pub(crate) fn check_names(apis: ApiVec<FnPhase>) -> ApiVec<FnPhase> {
             Ok(Box::new(std::iter::once(api)))
         }
         Api::ConcreteType { .. }
+        | Api::StdFunctionAdapter { .. }
+        | Api::StdTupleBridge { .. }
+        | Api::StdVariantBridge { .. }
+        | Api::OpaqueHandle { .. }
+        | Api::IntrusivePtrTarget { .. }
+        | Api::ComparisonOperators { .. }
+        | Api::IndexOperators { .. }
+        | Api::BoolConversion { .. }
         | Api::CType { .. }
         | Api::StringConstructor { .. }
         | Api::RustType { .. }
+        | Api::RustSharedStruct { .. }
         | Api::RustSubclassFn { .. }
         | Api::RustFn { .. }
         | Api::SubclassTraitItem { .. }
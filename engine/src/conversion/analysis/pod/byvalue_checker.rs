@@ -6,18 +6,20 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::conversion::analysis::function_pointer_type;
 use crate::conversion::apivec::ApiVec;
+use crate::conversion::parse::BindgenSemanticAttributes;
 use crate::{conversion::ConvertError, known_types::known_types};
 use crate::{
     conversion::{
         analysis::tdef::TypedefPhase,
-        api::{Api, TypedefKind},
+        api::{Api, SpecialMemberKind, TypedefKind},
     },
     types::{Namespace, QualifiedName},
 };
 use autocxx_parser::IncludeCppConfig;
 use std::collections::HashMap;
-use syn::{ItemStruct, Type};
+use syn::{ItemStruct, ReturnType, Type};
 
 #[derive(Clone)]
 enum PodState {
@@ -121,7 +123,7 @@ impl ByValueChecker {
                         .results
                         .insert(api.name().clone(), StructDetails::new(PodState::IsPod));
                 }
-                Api::ExternCppType { pod: true, .. } => {
+                Api::ExternCppType { pod: true, .. } | Api::RustSharedStruct { .. } => {
                     byvalue_checker
                         .results
                         .insert(api.name().clone(), StructDetails::new(PodState::IsPod));
@@ -137,6 +139,9 @@ impl ByValueChecker {
         byvalue_checker
             .satisfy_requests(pod_requests)
             .map_err(ConvertError::UnsafePodType)?;
+        if config.pod_by_default() {
+            byvalue_checker.make_safe_types_pod(apis, config);
+        }
         Ok(byvalue_checker)
     }
 
@@ -163,6 +168,34 @@ impl ByValueChecker {
                 }
             }
         }
+        if Self::has_self_reference(def, &tyname) {
+            // Whatever the other fields, such types are likely to rely on
+            // their address remaining stable (e.g. intrusive lists) so we
+            // must never let Rust move them around.
+            let reason = format!(
+                "Type {} could not be POD because it contains a pointer to its own type.",
+                tyname
+            );
+            field_safety_problem = PodState::UnsafeToBePod(reason);
+        }
+        if Self::has_reference_member(def) {
+            // bindgen gives us a pointer, but a reference can't be reseated,
+            // so the C++ struct isn't assignable and Rust mustn't treat it
+            // as a plain value either.
+            let reason = format!(
+                "Type {} could not be POD because it has a reference member.",
+                tyname
+            );
+            field_safety_problem = PodState::UnsafeToBePod(reason);
+        }
+        if Self::has_volatile_member(def) {
+            // Rust would read and write the field as ordinary memory.
+            let reason = format!(
+                "Type {} could not be POD because it has a volatile member.",
+                tyname
+            );
+            field_safety_problem = PodState::UnsafeToBePod(reason);
+        }
         if Self::has_vtable(def) {
             let reason = format!(
                 "Type {} could not be POD because it has virtual functions.",
@@ -175,6 +208,108 @@ impl ByValueChecker {
         self.results.insert(tyname, my_details);
     }
 
+    /// Make POD every struct which could be. Unlike explicit requests,
+    /// it's not an error if some can't be; they just remain non-POD.
+    fn make_safe_types_pod(&mut self, apis: &ApiVec<TypedefPhase>, config: &IncludeCppConfig) {
+        // Someone who explicitly asks for a type to be POD can be trusted to
+        // know that Rust will copy it around bytewise. Here, nobody has
+        // asked, so don't do that to types whose authors have said how they
+        // should be copied, moved or destroyed. This also stops any type
+        // containing one of them from becoming POD.
+        for api in apis.iter() {
+            if let Api::Function { fun, .. } = api {
+                if let (
+                    Some(
+                        kind @ (SpecialMemberKind::Destructor
+                        | SpecialMemberKind::CopyConstructor
+                        | SpecialMemberKind::MoveConstructor),
+                    ),
+                    Some(self_ty),
+                ) = (&fun.special_member, &fun.self_ty)
+                {
+                    if let Some(deets) = self.results.get_mut(self_ty) {
+                        if matches!(deets.state, PodState::SafeToBePod) {
+                            deets.state = PodState::UnsafeToBePod(format!(
+                                "Type {} wasn't made POD because it has a user-declared {}.",
+                                self_ty, kind
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        // Nor to types compared by identity, which only makes sense for
+        // types which stay put.
+        for (ty, deets) in self.results.iter_mut() {
+            if matches!(deets.state, PodState::SafeToBePod)
+                && config.has_identity_eq(&ty.to_cpp_name())
+            {
+                deets.state = PodState::UnsafeToBePod(format!(
+                    "Type {} wasn't made POD because it's marked identity_eq!.",
+                    ty
+                ));
+            }
+        }
+        // A type containing one which can't be POD can't be POD either, so
+        // rule such types out until nothing changes. Then every type which
+        // is still safe can be made POD without hitting anything unsafe.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for api in apis.iter() {
+                if let Api::Struct { name, .. } = api {
+                    let blocker = match self.results.get(&name.name) {
+                        Some(StructDetails {
+                            state: PodState::SafeToBePod,
+                            dependent_structs,
+                        }) => dependent_structs
+                            .iter()
+                            .find(|dep| !self.could_be_pod(dep))
+                            .cloned(),
+                        _ => None,
+                    };
+                    if let Some(blocker) = blocker {
+                        let reason = self.explain(&blocker);
+                        self.results.get_mut(&name.name).unwrap().state =
+                            PodState::UnsafeToBePod(reason);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        for api in apis.iter() {
+            if let Api::Struct { name, .. } = api {
+                if matches!(
+                    self.results.get(&name.name),
+                    Some(StructDetails {
+                        state: PodState::SafeToBePod,
+                        ..
+                    })
+                ) {
+                    if let Err(reason) = self.satisfy_requests(vec![name.name.clone()]) {
+                        self.results.get_mut(&name.name).unwrap().state =
+                            PodState::UnsafeToBePod(reason);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether this type is, or could yet be made, POD.
+    fn could_be_pod(&self, ty_id: &QualifiedName) -> bool {
+        match self.results.get(ty_id) {
+            Some(StructDetails {
+                state: PodState::SafeToBePod | PodState::IsPod,
+                ..
+            }) => true,
+            Some(StructDetails {
+                state: PodState::IsAlias(target),
+                ..
+            }) => target != ty_id && self.could_be_pod(target),
+            _ => false,
+        }
+    }
+
     fn ingest_nonpod_type(&mut self, tyname: QualifiedName) {
         let new_reason = format!("Type {} is a typedef to a complex type", tyname);
         self.results.insert(
@@ -189,14 +324,9 @@ impl ByValueChecker {
             let deets = self.results.get_mut(&ty_id);
             let mut alias_to_consider = None;
             match deets {
-                None => {
-                    return Err(format!(
-                        "Unable to make {} POD because we never saw a struct definition",
-                        ty_id
-                    ))
-                }
+                None => return Err(self.explain(&ty_id)),
                 Some(deets) => match &deets.state {
-                    PodState::UnsafeToBePod(error_msg) => return Err(error_msg.clone()),
+                    PodState::UnsafeToBePod(_) => return Err(self.explain(&ty_id)),
                     PodState::IsPod => {}
                     PodState::SafeToBePod => {
                         deets.state = PodState::IsPod;
@@ -236,11 +366,50 @@ impl ByValueChecker {
         )
     }
 
+    /// Describe why a given type is or isn't POD, for diagnostic purposes.
+    pub fn explain(&self, ty_id: &QualifiedName) -> String {
+        match self.results.get(ty_id) {
+            None => format!(
+                "Type {} is not POD because we never saw a struct definition",
+                ty_id
+            ),
+            Some(deets) => match &deets.state {
+                PodState::UnsafeToBePod(reason) => reason.clone(),
+                PodState::SafeToBePod => format!(
+                    "Type {} could be POD, but nothing requested it using generate_pod!",
+                    ty_id
+                ),
+                PodState::IsPod => format!("Type {} is POD", ty_id),
+                PodState::IsAlias(target) => {
+                    format!("Type {} is an alias to {}", ty_id, target)
+                }
+            },
+        }
+    }
+
     fn get_field_types(def: &ItemStruct) -> Vec<QualifiedName> {
         let mut results = Vec::new();
         for f in &def.fields {
             let fty = &f.ty;
-            if let Type::Path(p) = fty {
+            if let Some(bare_fn) = function_pointer_type(fty) {
+                // The pointer itself is fine to copy around; only its
+                // parameter and return types need to be known.
+                let output = match &bare_fn.output {
+                    ReturnType::Type(_, ty) => Some(ty.as_ref()),
+                    ReturnType::Default => None,
+                };
+                results.extend(
+                    bare_fn
+                        .inputs
+                        .iter()
+                        .map(|arg| &arg.ty)
+                        .chain(output)
+                        .filter_map(|ty| match ty {
+                            Type::Path(p) => Some(QualifiedName::from_type_path(p)),
+                            _ => None,
+                        }),
+                );
+            } else if let Type::Path(p) = fty {
                 results.push(QualifiedName::from_type_path(p));
             }
             // TODO handle anything else which bindgen might spit out, e.g. arrays?
@@ -248,6 +417,36 @@ impl ByValueChecker {
         results
     }
 
+    fn has_self_reference(def: &ItemStruct, tyname: &QualifiedName) -> bool {
+        def.fields.iter().any(|f| Self::points_to(&f.ty, tyname))
+    }
+
+    fn points_to(ty: &Type, tyname: &QualifiedName) -> bool {
+        match ty {
+            Type::Ptr(ptr) => match ptr.elem.as_ref() {
+                Type::Path(p) => QualifiedName::from_type_path(p) == *tyname,
+                other => Self::points_to(other, tyname),
+            },
+            Type::Reference(r) => match r.elem.as_ref() {
+                Type::Path(p) => QualifiedName::from_type_path(p) == *tyname,
+                other => Self::points_to(other, tyname),
+            },
+            _ => false,
+        }
+    }
+
+    fn has_reference_member(def: &ItemStruct) -> bool {
+        def.fields
+            .iter()
+            .any(|f| BindgenSemanticAttributes::new(&f.attrs).is_reference_field())
+    }
+
+    fn has_volatile_member(def: &ItemStruct) -> bool {
+        def.fields
+            .iter()
+            .any(|f| BindgenSemanticAttributes::new(&f.attrs).is_volatile_field())
+    }
+
     fn has_vtable(def: &ItemStruct) -> bool {
         for f in &def.fields {
             if f.ident.as_ref().map(|id| id == "vtable_").unwrap_or(false) {
@@ -340,4 +539,90 @@ mod tests {
         bvc.ingest_struct(&t, &Namespace::new());
         assert!(bvc.satisfy_requests(vec![t_id]).is_err());
     }
+
+    #[test]
+    fn test_self_referential() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Node {
+                next: *mut Node,
+                val: i64,
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        assert!(bvc.satisfy_requests(vec![t_id.clone()]).is_err());
+        assert!(!bvc.is_pod(&t_id));
+        assert!(bvc.explain(&t_id).contains("pointer to its own type"));
+    }
+
+    #[test]
+    fn test_reference_member() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Holder {
+                #[cpp_semantics(reference)]
+                foo: *mut Foo,
+                val: i64,
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        assert!(bvc.satisfy_requests(vec![t_id.clone()]).is_err());
+        assert!(!bvc.is_pod(&t_id));
+        assert!(bvc.explain(&t_id).contains("reference member"));
+    }
+
+    #[test]
+    fn test_volatile_member() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Register {
+                #[cpp_semantics(volatile)]
+                status: u32,
+                val: i64,
+            }
+        };
+        let t_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        assert!(bvc.satisfy_requests(vec![t_id.clone()]).is_err());
+        assert!(!bvc.is_pod(&t_id));
+        assert!(bvc.explain(&t_id).contains("volatile member"));
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut bvc = ByValueChecker::new();
+        let t: ItemStruct = parse_quote! {
+            struct Foo {
+                a: CxxString,
+            }
+        };
+        let foo_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        let t: ItemStruct = parse_quote! {
+            struct Bar {
+                a: Foo,
+                b: i64,
+            }
+        };
+        let bar_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        let t: ItemStruct = parse_quote! {
+            struct Baz {
+                a: i64,
+            }
+        };
+        let baz_id = ty_from_ident(&t.ident);
+        bvc.ingest_struct(&t, &Namespace::new());
+        bvc.satisfy_requests(vec![baz_id.clone()]).unwrap();
+        let explanation = bvc.explain(&bar_id);
+        assert!(explanation.contains("dependent type Foo"));
+        assert_eq!(bvc.satisfy_requests(vec![bar_id.clone()]), Err(explanation));
+        assert!(!bvc.is_pod(&foo_id));
+        assert_eq!(bvc.explain(&baz_id), "Type Baz is POD");
+        assert!(bvc
+            .explain(&QualifiedName::new_from_cpp_name("Unknown"))
+            .contains("never saw"));
+    }
 }
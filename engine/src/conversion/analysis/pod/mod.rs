@@ -13,18 +13,23 @@ use indexmap::set::IndexSet as HashSet;
 
 use autocxx_parser::IncludeCppConfig;
 use byvalue_checker::ByValueChecker;
-use syn::{ItemEnum, ItemStruct, Type, Visibility};
+use quote::ToTokens;
+use syn::{parse_quote, ItemEnum, ItemStruct, ReturnType, Type, Visibility};
 
 use crate::{
     conversion::{
-        analysis::type_converter::{self, add_analysis, TypeConversionContext, TypeConverter},
+        analysis::{
+            function_pointer_type,
+            type_converter::{self, add_analysis, TypeConversionContext, TypeConverter},
+        },
         api::{AnalysisPhase, Api, ApiName, NullPhase, StructDetails, TypeKind},
         apivec::ApiVec,
         convert_error::{ConvertErrorWithContext, ErrorContext},
         error_reporter::convert_apis,
         parse::BindgenSemanticAttributes,
-        ConvertError,
+        trace_decision, ConvertError,
     },
+    known_types::known_types,
     types::{Namespace, QualifiedName},
 };
 
@@ -73,6 +78,15 @@ pub(crate) fn analyze_pod_apis(
     let byvalue_checker = ByValueChecker::new_from_apis(&apis, config)?;
     let mut extra_apis = ApiVec::new();
     let mut type_converter = TypeConverter::new(config, &apis);
+    let apis = apis
+        .into_iter()
+        .map(|api| match api {
+            Api::RustSharedStruct { name, item, .. } => {
+                analyze_rust_shared_struct(&mut type_converter, &mut extra_apis, name, item)
+            }
+            _ => api,
+        })
+        .collect();
     let mut results = ApiVec::new();
     convert_apis(
         apis,
@@ -162,10 +176,43 @@ fn analyze_struct(
                 Some(ErrorContext::new_for_item(id)),
             ));
         }
+        trace_decision(&name.name, "POD");
         TypeKind::Pod
     } else {
+        let explanation = byvalue_checker.explain(&name.name);
+        log::info!("Treating {} as non-POD: {}", name.name, explanation);
+        trace_decision(&name.name, format_args!("non-POD: {}", explanation));
         TypeKind::NonPod
     };
+    if config.is_transparent_type(&name.name.to_cpp_name()) {
+        if !matches!(type_kind, TypeKind::Pod) || details.item.fields.len() != 1 {
+            return Err(ConvertErrorWithContext(
+                ConvertError::TransparentTypeNotSingleFieldPod,
+                Some(ErrorContext::new_for_item(id)),
+            ));
+        }
+        // bindgen gives us repr(C), which is layout-compatible but doesn't
+        // guarantee the same ABI as the field when passed by value.
+        details
+            .item
+            .attrs
+            .retain(|attr| !attr.path.is_ident("repr"));
+        details
+            .item
+            .attrs
+            .push(parse_quote! { #[repr(transparent)] });
+    }
+    // A POD type is a value, so comparing addresses means nothing.
+    if matches!(type_kind, TypeKind::Pod) && config.has_identity_eq(&name.name.to_cpp_name()) {
+        return Err(ConvertErrorWithContext(
+            ConvertError::IdentityEqPod,
+            Some(ErrorContext::new_for_item(id)),
+        ));
+    }
+    if config.is_serde_pod(&name.name.to_cpp_name()) {
+        check_serde_pod(config, type_kind, &field_info)
+            .map_err(|err| ConvertErrorWithContext(err, Some(ErrorContext::new_for_item(id))))?;
+    }
     let castable_bases = bases
         .iter()
         .filter(|(_, is_public)| **is_public)
@@ -193,6 +240,74 @@ fn analyze_struct(
     })))
 }
 
+/// A struct can only derive serde's traits if it's POD (otherwise Rust
+/// can't create one) and if each of its fields can itself be serialized.
+fn check_serde_pod(
+    config: &IncludeCppConfig,
+    type_kind: TypeKind,
+    field_info: &[FieldInfo],
+) -> Result<(), ConvertError> {
+    if !matches!(type_kind, TypeKind::Pod) {
+        return Err(ConvertError::SerdePodNotPod);
+    }
+    for field in field_info {
+        let serializable = match &field.ty {
+            Type::Path(typ) => {
+                let qn = QualifiedName::from_type_path(typ);
+                known_types().is_simple_by_value(&qn) || config.is_serde_pod(&qn.to_cpp_name())
+            }
+            _ => false,
+        };
+        if !serializable {
+            return Err(ConvertError::SerdePodFieldNotSerializable(
+                field.ty.to_token_stream().to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Structs defined in Rust may nevertheless contain C++ types, so convert
+/// their fields just like those of C++ structs.
+fn analyze_rust_shared_struct(
+    type_converter: &mut TypeConverter,
+    extra_apis: &mut ApiVec<NullPhase>,
+    name: ApiName,
+    mut item: ItemStruct,
+) -> Api<TypedefPhase> {
+    let type_conversion_context = TypeConversionContext::WithinStructField {
+        struct_type_params: HashSet::new(),
+    };
+    let mut deps = HashSet::new();
+    for f in item.fields.iter_mut() {
+        match type_converter.convert_type(
+            f.ty.clone(),
+            name.name.get_namespace(),
+            &type_conversion_context,
+        ) {
+            Ok(mut annotated) => {
+                f.ty = annotated.ty;
+                deps.extend(annotated.types_encountered);
+                extra_apis.append(&mut annotated.extra_apis);
+            }
+            Err(err) => {
+                let id = name.name.get_final_ident();
+                return Api::IgnoredItem {
+                    name,
+                    err,
+                    ctx: Some(ErrorContext::new_for_item(id)),
+                };
+            }
+        }
+    }
+    deps.remove(&name.name);
+    Api::RustSharedStruct {
+        name,
+        item,
+        deps: deps.into_iter().collect(),
+    }
+}
+
 fn get_struct_field_types(
     type_converter: &mut TypeConverter,
     ns: &Namespace,
@@ -209,6 +324,28 @@ fn get_struct_field_types(
         .collect();
     let type_conversion_context = TypeConversionContext::WithinStructField { struct_type_params };
     for f in &s.fields {
+        if let Some(bare_fn) = function_pointer_type(&f.ty) {
+            // A C function pointer stays just as bindgen gave it, but the
+            // types it takes and returns must still be generated.
+            let output = match &bare_fn.output {
+                ReturnType::Type(_, ty) => Some(ty.as_ref()),
+                ReturnType::Default => None,
+            };
+            for ty in bare_fn.inputs.iter().map(|arg| &arg.ty).chain(output) {
+                match type_converter.convert_type(ty.clone(), ns, &type_conversion_context) {
+                    Ok(mut r) => {
+                        extra_apis.append(&mut r.extra_apis);
+                        field_deps.extend(r.types_encountered);
+                    }
+                    Err(e) => convert_errors.push(e),
+                }
+            }
+            field_info.push(FieldInfo {
+                ty: f.ty.clone(),
+                type_kind: type_converter::TypeKind::Regular,
+            });
+            continue;
+        }
         let annotated = type_converter.convert_type(f.ty.clone(), ns, &type_conversion_context);
         match annotated {
             Ok(mut r) => {
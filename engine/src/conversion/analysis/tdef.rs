@@ -86,10 +86,24 @@ fn get_replacement_typedef(
     extra_apis: &mut ApiVec<NullPhase>,
 ) -> Result<Api<TypedefPhase>, ConvertErrorWithContext> {
     if !ity.generics.params.is_empty() {
-        return Err(ConvertErrorWithContext(
-            ConvertError::TypedefTakesGenericParameters,
-            Some(ErrorContext::new_for_item(name.name.get_final_ident())),
-        ));
+        // This is an alias template. We can't convert its target until
+        // we know the type arguments, so the type converter instead
+        // resolves each use of it.
+        if ity.generics.params.len() != ity.generics.type_params().count() {
+            return Err(ConvertErrorWithContext(
+                ConvertError::TypedefTakesGenericParameters,
+                Some(ErrorContext::new_for_item(name.name.get_final_ident())),
+            ));
+        }
+        return Ok(Api::Typedef {
+            name,
+            item: TypedefKind::Type(ity.clone()),
+            old_tyname,
+            analysis: TypedefAnalysis {
+                kind: TypedefKind::Type(ity),
+                deps: HashSet::new(),
+            },
+        });
     }
     let mut converted_type = ity.clone();
     let metadata = BindgenSemanticAttributes::new_retaining_others(&mut converted_type.attrs);
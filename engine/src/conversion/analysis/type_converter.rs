@@ -11,20 +11,21 @@ use crate::{
         api::{AnalysisPhase, Api, ApiName, NullPhase, TypedefKind, UnanalyzedApi},
         apivec::ApiVec,
         codegen_cpp::type_to_cpp::type_to_cpp,
+        utilities::opaque_handle_name,
         ConvertError,
     },
     known_types::{known_types, CxxGenericType},
     types::{make_ident, Namespace, QualifiedName},
 };
-use autocxx_parser::IncludeCppConfig;
+use autocxx_parser::{IncludeCppConfig, IntrusivePtr};
 use indexmap::map::IndexMap as HashMap;
 use indexmap::set::IndexSet as HashSet;
 use itertools::Itertools;
 use proc_macro2::Ident;
 use quote::ToTokens;
 use syn::{
-    parse_quote, punctuated::Punctuated, token::Comma, GenericArgument, PathArguments, PathSegment,
-    Type, TypePath, TypePtr,
+    parse_quote, punctuated::Punctuated, token::Comma, Expr, GenericArgument, PathArguments,
+    PathSegment, ReturnType, Type, TypeArray, TypeBareFn, TypePath, TypePtr,
 };
 
 use super::tdef::TypedefAnalysis;
@@ -38,6 +39,47 @@ pub(crate) enum TypeKind {
     Reference,
     RValueReference,
     MutableReference,
+    /// A `std::string_view`, represented in Rust as `&str`, or as `&[u8]`
+    /// when returned.
+    StringView,
+    /// A `std::function`, which Rust provides as a closure implementing
+    /// the given `dyn Fn(...)` trait, boxed up in the given adapter type.
+    StdFunction {
+        fn_trait: Box<Type>,
+        adapter: Ident,
+    },
+    /// A C++ array parameter such as `int buf[4]`, which decays to a
+    /// pointer to its first element. Holds the array length.
+    SizedArrayPointer(Expr),
+    /// A C++ reference to an array such as `int (&buf)[4]`, which we pass
+    /// as a pointer to its first element. Holds the array length.
+    SizedArrayReference(Expr),
+    /// A smart pointer registered using `intrusive_ptr!`, represented as
+    /// a raw pointer to its pointee across which one reference is handed
+    /// over.
+    IntrusivePtr(IntrusivePtr),
+    /// A `std::span` passed by value, represented in Rust as a mutable
+    /// slice unless marked with `const_slice_param!`.
+    Span,
+    /// A `rust::Slice` passed by value, also represented in Rust as a
+    /// mutable slice unless marked with `const_slice_param!`.
+    RustSlice,
+    /// A `std::tuple` returned by value, represented by a bridge struct
+    /// which we unpack into a Rust tuple of the given element types.
+    StdTuple(Vec<Type>),
+    /// A `std::optional` returned by value, represented by a tuple bridge
+    /// struct holding whether it has a value and, if so, the value of the
+    /// given type, which we turn into a Rust `Option`.
+    StdOptional(Box<Type>),
+    /// A `std::variant` returned by value, represented by a bridge struct
+    /// which we turn into the generated Rust enum of the given type.
+    StdVariant(Box<Type>),
+    /// A mutable C++ reference to a pointer to one of our types, such as
+    /// `Foo*&`, which we pass as a pointer to the pointer.
+    ReferenceToPointer,
+    /// A `std::unique_ptr<void, D>` passed by value, represented by a
+    /// `cxx::UniquePtr` to a type-erased handle which calls the deleter.
+    OpaqueHandle,
 }
 
 /// Results of some type conversion, annotated with a list of every type encountered,
@@ -126,7 +168,13 @@ impl TypeConversionContext {
 pub(crate) struct TypeConverter<'a> {
     types_found: HashSet<QualifiedName>,
     typedefs: HashMap<QualifiedName, Type>,
+    alias_templates: HashMap<QualifiedName, (Vec<Ident>, Type)>,
     concrete_templates: HashMap<String, QualifiedName>,
+    std_function_adapters: HashSet<QualifiedName>,
+    std_tuple_bridges: HashSet<QualifiedName>,
+    std_variant_bridges: HashSet<QualifiedName>,
+    opaque_handle_exists: bool,
+    intrusive_ptr_targets: HashSet<QualifiedName>,
     forward_declarations: HashSet<QualifiedName>,
     ignored_types: HashSet<QualifiedName>,
     config: &'a IncludeCppConfig,
@@ -140,7 +188,15 @@ impl<'a> TypeConverter<'a> {
         Self {
             types_found: find_types(apis),
             typedefs: Self::find_typedefs(apis),
+            alias_templates: Self::find_alias_templates(apis),
             concrete_templates: Self::find_concrete_templates(apis),
+            std_function_adapters: Self::find_std_function_adapters(apis),
+            std_tuple_bridges: Self::find_std_tuple_bridges(apis),
+            std_variant_bridges: Self::find_std_variant_bridges(apis),
+            opaque_handle_exists: apis
+                .iter()
+                .any(|api| matches!(api, Api::OpaqueHandle { .. })),
+            intrusive_ptr_targets: Self::find_intrusive_ptr_targets(apis),
             forward_declarations: Self::find_incomplete_types(apis),
             ignored_types: Self::find_ignored_types(apis),
             config,
@@ -176,7 +232,21 @@ impl<'a> TypeConverter<'a> {
                     // doesn't simply get renamed to a different type _identifier_.
                     // This plain type-by-value (as far as bindgen is concerned)
                     // is actually a &str.
-                    if known_types().should_dereference_in_cpp(&qn) {
+                    if known_types().is_string_view(&qn) {
+                        // Views don't own their data, so we can only represent
+                        // them as &str where Rust's lifetime rules can apply.
+                        if !matches!(ctx, TypeConversionContext::OuterType { .. }) {
+                            return Err(ConvertError::StringViewNotByValue);
+                        }
+                        Annotated::new(
+                            Type::Reference(parse_quote! {
+                                &str
+                            }),
+                            newp.types_encountered,
+                            newp.extra_apis,
+                            TypeKind::StringView,
+                        )
+                    } else if known_types().should_dereference_in_cpp(&qn) {
                         Annotated::new(
                             Type::Reference(parse_quote! {
                                 &str
@@ -256,6 +326,13 @@ impl<'a> TypeConverter<'a> {
         if self.config.is_on_blocklist(&original_tn.to_cpp_name()) {
             return Err(ConvertError::Blocked(original_tn));
         }
+        // Alias templates are replaced by their target, with our type
+        // arguments substituted in, which is then converted as normal.
+        if let Some(resolved) = self.resolve_alias_template(&original_tn, &typ) {
+            let mut annotated = self.convert_type(resolved, ns, ctx)?;
+            annotated.types_encountered.insert(original_tn);
+            return Ok(annotated);
+        }
         let mut deps = HashSet::new();
 
         // Now convert this type itself.
@@ -286,6 +363,89 @@ impl<'a> TypeConverter<'a> {
             }
         };
 
+        // Users may supply their own mappings, which take precedence over
+        // the types we know about. The replacement is then treated exactly
+        // as if bindgen had given it to us, so if it's a type we know
+        // (e.g. cxx::UniquePtr) it'll get all the usual handling below.
+        let (typ, tn) = match self.config.get_extra_type_mapping(&tn.to_cpp_name()) {
+            Some(mapped) => {
+                let mut mapped = mapped.clone();
+                if let Some(last_seg_args) =
+                    typ.path.segments.into_iter().last().map(|ps| ps.arguments)
+                {
+                    let last_seg = mapped.path.segments.last_mut().unwrap();
+                    last_seg.arguments = last_seg_args;
+                }
+                let mapped_tn = QualifiedName::from_type_path(&mapped);
+                (mapped, mapped_tn)
+            }
+            None => (typ, tn),
+        };
+
+        if let Some(required) = known_types().required_cpp_std(&tn) {
+            let cpp_std = self.config.cpp_std();
+            if cpp_std < required {
+                return Err(ConvertError::RequiresNewerCppStd(tn, required, cpp_std));
+            }
+        }
+
+        if known_types().is_std_function(&tn) {
+            return self.convert_std_function(typ, ns, ctx, deps);
+        }
+
+        if known_types().is_std_tuple(&tn) {
+            return self.convert_std_tuple(typ, ns, ctx, deps);
+        }
+
+        if known_types().is_std_variant(&tn) {
+            return self.convert_std_variant(typ, ns, ctx, deps);
+        }
+
+        if known_types().is_std_optional(&tn) {
+            return self.convert_std_optional(typ, ns, ctx, deps);
+        }
+
+        if known_types().is_std_span(&tn) {
+            return self.convert_to_mut_slice(
+                typ,
+                ns,
+                ctx,
+                deps,
+                TypeKind::Span,
+                ConvertError::UnsupportedStdSpan,
+            );
+        }
+
+        if known_types().is_rust_slice(&tn) {
+            return self.convert_to_mut_slice(
+                typ,
+                ns,
+                ctx,
+                deps,
+                TypeKind::RustSlice,
+                ConvertError::UnsupportedRustSlice,
+            );
+        }
+
+        // Intrusive smart pointers passed or returned by value are handed
+        // over as raw pointers. Anywhere else, they're just like any other
+        // template.
+        if let Some(ptr) = self.config.get_intrusive_ptr(&tn.to_cpp_name()) {
+            if matches!(ctx, TypeConversionContext::OuterType { .. }) {
+                let ptr = ptr.clone();
+                return self.convert_intrusive_ptr(typ, ptr, ns, deps);
+            }
+        }
+
+        // cxx can't represent a `std::unique_ptr<void, D>`, so if one is
+        // passed or returned by value we instead hand over a type-erased
+        // handle which remembers the deleter.
+        if matches!(ctx, TypeConversionContext::OuterType { .. })
+            && Self::is_void_unique_ptr(&tn, &typ)
+        {
+            return Ok(self.convert_void_unique_ptr(&tn, deps));
+        }
+
         // Now let's see if it's a known type.
         // (We may entirely reject some types at this point too.)
         let mut typ = match known_types().consider_substitution(&tn) {
@@ -312,12 +472,21 @@ impl<'a> TypeConverter<'a> {
                 // this is a type of generic understood by cxx (e.g. CxxVector)
                 // so let's convert any generic type arguments. This recurses.
                 if let PathArguments::AngleBracketed(ref mut ab) = last_seg.arguments {
+                    Self::strip_defaulted_generic_args(&mut ab.args);
                     let mut innerty = self.convert_punctuated(
                         ab.args.clone(),
                         ns,
                         &TypeConversionContext::WithinContainer,
                     )?;
                     ab.args = innerty.ty;
+                    if generic_behavior == CxxGenericType::CppVector {
+                        Self::use_fixed_width_ints_within_vector(&mut ab.args);
+                        self.make_nested_vectors_concrete(
+                            &mut ab.args,
+                            &mut innerty.types_encountered,
+                            &mut innerty.extra_apis,
+                        )?;
+                    }
                     kind = self.confirm_inner_type_is_acceptable_generic_payload(
                         &ab.args,
                         &tn,
@@ -325,6 +494,7 @@ impl<'a> TypeConverter<'a> {
                         forward_declarations_ok,
                     )?;
                     deps.extend(innerty.types_encountered.drain(..));
+                    extra_apis.append(&mut innerty.extra_apis);
                 } else {
                     return Err(ConvertError::TemplatedTypeContainingNonPathArg(tn.clone()));
                 }
@@ -364,10 +534,455 @@ impl<'a> TypeConverter<'a> {
                 typ = new_tn.to_type_path();
                 deps.insert(new_tn);
             }
+        } else if ctx.allow_instantiation_of_forward_declaration() {
+            extra_apis.extend(self.stub_undefined_type(&typ, &tn).into_iter());
         }
         Ok(Annotated::new(Type::Path(typ), deps, extra_apis, kind))
     }
 
+    /// A C++ type which we only see behind a pointer or reference, but
+    /// which bindgen never gave us, was presumably declared but never
+    /// defined. Make an opaque forward declaration for it so that it can
+    /// still be referred to.
+    fn stub_undefined_type(&mut self, typ: &TypePath, tn: &QualifiedName) -> Option<UnanalyzedApi> {
+        let is_cpp_type = typ
+            .path
+            .segments
+            .first()
+            .is_some_and(|seg| seg.ident == "root");
+        if !is_cpp_type
+            || self.types_found.contains(tn)
+            || self.ignored_types.contains(tn)
+            || known_types().is_known_type(tn)
+        {
+            return None;
+        }
+        self.types_found.insert(tn.clone());
+        self.forward_declarations.insert(tn.clone());
+        Some(UnanalyzedApi::ForwardDeclaration {
+            name: ApiName::new_from_qualified_name(tn.clone()),
+            err: None,
+        })
+    }
+
+    /// Convert a `std::function<R(A...)>` into a boxed adapter type which
+    /// the Rust side can construct from a closure, creating the adapter API
+    /// if we haven't already done so for this signature.
+    fn convert_std_function(
+        &mut self,
+        typ: TypePath,
+        ns: &Namespace,
+        ctx: &TypeConversionContext,
+        mut deps: HashSet<QualifiedName>,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        if !matches!(
+            ctx,
+            TypeConversionContext::OuterType { .. } | TypeConversionContext::WithinReference
+        ) {
+            return Err(ConvertError::UnsupportedStdFunction);
+        }
+        // bindgen represents the function type as a function pointer,
+        // possibly wrapped in an Option.
+        let mut bare_fn = match typ.path.segments.last().map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) if ab.args.len() == 1 => {
+                match ab.args.first() {
+                    Some(GenericArgument::Type(ty)) => ty.clone(),
+                    _ => return Err(ConvertError::UnsupportedStdFunction),
+                }
+            }
+            _ => return Err(ConvertError::UnsupportedStdFunction),
+        };
+        if let Type::Path(tp) = &bare_fn {
+            if let Some(PathArguments::AngleBracketed(ab)) =
+                tp.path.segments.last().map(|seg| &seg.arguments)
+            {
+                if let Some(GenericArgument::Type(inner)) = ab.args.first() {
+                    bare_fn = inner.clone();
+                }
+            }
+        }
+        let bare_fn = match bare_fn {
+            Type::BareFn(bare_fn) => bare_fn,
+            _ => return Err(ConvertError::UnsupportedStdFunction),
+        };
+        let mut convert_payload = |ty: Type| -> Result<Type, ConvertError> {
+            let annotated = self.convert_type(ty, ns, &TypeConversionContext::WithinContainer)?;
+            match &annotated.ty {
+                Type::Path(tp)
+                    if known_types().is_simple_by_value(&QualifiedName::from_type_path(tp)) =>
+                {
+                    deps.extend(annotated.types_encountered);
+                    Ok(annotated.ty)
+                }
+                _ => Err(ConvertError::UnsupportedStdFunction),
+            }
+        };
+        let params = bare_fn
+            .inputs
+            .into_iter()
+            .map(|arg| convert_payload(arg.ty))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ret = match bare_fn.output {
+            ReturnType::Default => ReturnType::Default,
+            ReturnType::Type(rarrow, ty) => {
+                ReturnType::Type(rarrow, Box::new(convert_payload(*ty)?))
+            }
+        };
+        let name_part = |ty: &Type| match ty {
+            Type::Path(tp) => QualifiedName::from_type_path(tp)
+                .get_final_item()
+                .to_string(),
+            _ => unreachable!(),
+        };
+        let adapter_name = format!(
+            "AutocxxStdFunction_{}_{}",
+            if params.is_empty() {
+                "void".to_string()
+            } else {
+                params.iter().map(name_part).join("_")
+            },
+            match &ret {
+                ReturnType::Default => "void".to_string(),
+                ReturnType::Type(_, ty) => name_part(ty),
+            }
+        );
+        let adapter_name = ApiName::new_in_root_namespace(make_ident(adapter_name));
+        let mut extra_apis = ApiVec::new();
+        if self.std_function_adapters.insert(adapter_name.name.clone()) {
+            extra_apis.push(UnanalyzedApi::StdFunctionAdapter {
+                name: adapter_name.clone(),
+                params: params.clone(),
+                ret: ret.clone(),
+            });
+        }
+        deps.remove(&QualifiedName::from_type_path(&typ));
+        deps.insert(adapter_name.name.clone());
+        let adapter_id = adapter_name.name.get_final_ident();
+        let fn_trait = parse_quote! {
+            dyn Fn(#(#params),*) #ret + Send + Sync
+        };
+        Ok(Annotated::new(
+            Type::Path(parse_quote! {
+                std::boxed::Box< #adapter_id >
+            }),
+            deps,
+            extra_apis,
+            TypeKind::StdFunction {
+                fn_trait: Box::new(fn_trait),
+                adapter: adapter_id,
+            },
+        ))
+    }
+
+    /// Convert a `std::span<T>` or `rust::Slice<T>` passed by value into a
+    /// `&mut [T]`, which cxx gives to C++ as a `rust::Slice<T>`. bindgen
+    /// doesn't tell us whether either was of `const T`, so we have to assume
+    /// the C++ might write through it, unless the parameter is marked with
+    /// `const_slice_param!`. Any extent is ignored.
+    fn convert_to_mut_slice(
+        &mut self,
+        typ: TypePath,
+        ns: &Namespace,
+        ctx: &TypeConversionContext,
+        mut deps: HashSet<QualifiedName>,
+        kind: TypeKind,
+        unsupported: ConvertError,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        if !matches!(ctx, TypeConversionContext::OuterType { .. }) {
+            return Err(unsupported);
+        }
+        let elem = match typ.path.segments.last().map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) => match ab.args.first() {
+                Some(GenericArgument::Type(ty)) => ty.clone(),
+                _ => return Err(unsupported),
+            },
+            _ => return Err(unsupported),
+        };
+        let annotated = self.convert_type(elem, ns, &TypeConversionContext::WithinContainer)?;
+        let elem = match &annotated.ty {
+            Type::Path(tp)
+                if known_types().is_simple_by_value(&QualifiedName::from_type_path(tp)) =>
+            {
+                annotated.ty
+            }
+            _ => return Err(unsupported),
+        };
+        deps.extend(annotated.types_encountered);
+        deps.remove(&QualifiedName::from_type_path(&typ));
+        Ok(Annotated::new(
+            Type::Reference(parse_quote! {
+                &mut [#elem]
+            }),
+            deps,
+            ApiVec::new(),
+            kind,
+        ))
+    }
+
+    /// Convert a `std::tuple<A...>` returned by value into a bridge struct
+    /// with one field per element, creating the bridge API if we haven't
+    /// already done so for these element types.
+    fn convert_std_tuple(
+        &mut self,
+        typ: TypePath,
+        ns: &Namespace,
+        ctx: &TypeConversionContext,
+        mut deps: HashSet<QualifiedName>,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        if !matches!(ctx, TypeConversionContext::OuterType { .. }) {
+            return Err(ConvertError::UnsupportedStdTuple);
+        }
+        let elems = self
+            .convert_simple_type_args(&typ, ns, &mut deps)
+            .ok_or(ConvertError::UnsupportedStdTuple)??;
+        let bridge_name = format!("AutocxxStdTuple_{}", Self::simple_types_suffix(&elems));
+        let bridge_name = ApiName::new_in_root_namespace(make_ident(bridge_name));
+        let mut extra_apis = ApiVec::new();
+        if self.std_tuple_bridges.insert(bridge_name.name.clone()) {
+            extra_apis.push(UnanalyzedApi::StdTupleBridge {
+                name: bridge_name.clone(),
+                elems: elems.clone(),
+            });
+        }
+        deps.remove(&QualifiedName::from_type_path(&typ));
+        deps.insert(bridge_name.name.clone());
+        let bridge_id = bridge_name.name.get_final_ident();
+        Ok(Annotated::new(
+            Type::Path(parse_quote! { #bridge_id }),
+            deps,
+            extra_apis,
+            TypeKind::StdTuple(elems),
+        ))
+    }
+
+    /// Convert a `std::optional<T>` returned by value into a tuple bridge
+    /// struct holding a `bool` and a `T`, creating the bridge API if we
+    /// haven't already done so for this value type.
+    fn convert_std_optional(
+        &mut self,
+        typ: TypePath,
+        ns: &Namespace,
+        ctx: &TypeConversionContext,
+        mut deps: HashSet<QualifiedName>,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        if !matches!(ctx, TypeConversionContext::OuterType { .. }) {
+            return Err(ConvertError::UnsupportedStdOptional);
+        }
+        let value_type = match self.convert_simple_type_args(&typ, ns, &mut deps) {
+            Some(Ok(mut tys)) if tys.len() == 1 => tys.remove(0),
+            Some(Err(err)) => return Err(err),
+            _ => return Err(ConvertError::UnsupportedStdOptional),
+        };
+        let bridge_name = format!(
+            "AutocxxStdOptional_{}",
+            Self::simple_types_suffix(std::slice::from_ref(&value_type))
+        );
+        let bridge_name = ApiName::new_in_root_namespace(make_ident(bridge_name));
+        let mut extra_apis = ApiVec::new();
+        if self.std_tuple_bridges.insert(bridge_name.name.clone()) {
+            extra_apis.push(UnanalyzedApi::StdTupleBridge {
+                name: bridge_name.clone(),
+                elems: vec![parse_quote! { bool }, value_type.clone()],
+            });
+        }
+        deps.remove(&QualifiedName::from_type_path(&typ));
+        deps.insert(bridge_name.name.clone());
+        let bridge_id = bridge_name.name.get_final_ident();
+        Ok(Annotated::new(
+            Type::Path(parse_quote! { #bridge_id }),
+            deps,
+            extra_apis,
+            TypeKind::StdOptional(Box::new(value_type)),
+        ))
+    }
+
+    /// Convert a `std::variant<A...>` returned by value into a bridge
+    /// struct holding the index and value of its active alternative,
+    /// creating the bridge API and its Rust enum if we haven't already
+    /// done so for these alternatives.
+    fn convert_std_variant(
+        &mut self,
+        typ: TypePath,
+        ns: &Namespace,
+        ctx: &TypeConversionContext,
+        mut deps: HashSet<QualifiedName>,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        if !matches!(ctx, TypeConversionContext::OuterType { .. }) {
+            return Err(ConvertError::UnsupportedStdVariant);
+        }
+        let alternatives = self
+            .convert_simple_type_args(&typ, ns, &mut deps)
+            .ok_or(ConvertError::UnsupportedStdVariant)??;
+        let suffix = Self::simple_types_suffix(&alternatives);
+        let bridge_name = ApiName::new_in_root_namespace(make_ident(format!(
+            "AutocxxStdVariantBridge_{}",
+            suffix
+        )));
+        let enum_name = QualifiedName::new(
+            &Namespace::new(),
+            make_ident(format!("StdVariant_{}", suffix)),
+        );
+        let mut extra_apis = ApiVec::new();
+        if self.std_variant_bridges.insert(bridge_name.name.clone()) {
+            extra_apis.push(UnanalyzedApi::StdVariantBridge {
+                name: bridge_name.clone(),
+                enum_name: enum_name.clone(),
+                alternatives,
+            });
+        }
+        deps.remove(&QualifiedName::from_type_path(&typ));
+        deps.insert(bridge_name.name.clone());
+        let bridge_id = bridge_name.name.get_final_ident();
+        let enum_path = enum_name.to_type_path();
+        Ok(Annotated::new(
+            Type::Path(parse_quote! { #bridge_id }),
+            deps,
+            extra_apis,
+            TypeKind::StdVariant(Box::new(Type::Path(enum_path))),
+        ))
+    }
+
+    /// Convert the type arguments of a template such as `std::tuple`,
+    /// provided they're all simple primitive types. Returns `None` if there
+    /// are no type arguments or any of them isn't a simple primitive type.
+    fn convert_simple_type_args(
+        &mut self,
+        typ: &TypePath,
+        ns: &Namespace,
+        deps: &mut HashSet<QualifiedName>,
+    ) -> Option<Result<Vec<Type>, ConvertError>> {
+        let args = match typ.path.segments.last().map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) if !ab.args.is_empty() => ab.args.clone(),
+            _ => return None,
+        };
+        args.into_iter()
+            .map(|arg| {
+                let ty = match arg {
+                    GenericArgument::Type(ty) => ty,
+                    _ => return Ok(None),
+                };
+                let annotated =
+                    self.convert_type(ty, ns, &TypeConversionContext::WithinContainer)?;
+                match &annotated.ty {
+                    Type::Path(tp)
+                        if known_types().is_simple_by_value(&QualifiedName::from_type_path(tp)) =>
+                    {
+                        deps.extend(annotated.types_encountered);
+                        Ok(Some(annotated.ty))
+                    }
+                    _ => Ok(None),
+                }
+            })
+            .collect::<Result<Option<Vec<_>>, _>>()
+            .transpose()
+    }
+
+    /// A name suffix made from a list of simple primitive types, used to
+    /// give each distinct bridge struct its own name.
+    fn simple_types_suffix(tys: &[Type]) -> String {
+        tys.iter()
+            .map(|ty| match ty {
+                Type::Path(tp) => QualifiedName::from_type_path(tp)
+                    .get_final_item()
+                    .to_string(),
+                _ => unreachable!(),
+            })
+            .join("_")
+    }
+
+    fn is_void_unique_ptr(tn: &QualifiedName, typ: &TypePath) -> bool {
+        if !known_types().is_unique_ptr(tn) {
+            return false;
+        }
+        match typ.path.segments.last().map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) => matches!(
+                ab.args.first(),
+                Some(GenericArgument::Type(Type::Path(inner)))
+                    if known_types().is_c_void(&QualifiedName::from_type_path(inner))
+            ),
+            _ => false,
+        }
+    }
+
+    /// Convert a `std::unique_ptr<void, D>` into a `cxx::UniquePtr` to
+    /// an `OpaqueHandle`, creating that type if we haven't already done so.
+    fn convert_void_unique_ptr(
+        &mut self,
+        tn: &QualifiedName,
+        mut deps: HashSet<QualifiedName>,
+    ) -> Annotated<Type> {
+        let handle_name = opaque_handle_name(self.config);
+        let mut extra_apis = ApiVec::new();
+        if !self.opaque_handle_exists {
+            self.opaque_handle_exists = true;
+            extra_apis.push(UnanalyzedApi::OpaqueHandle {
+                name: handle_name.clone(),
+            });
+        }
+        deps.remove(tn);
+        deps.insert(handle_name.name.clone());
+        // The handle gives out the raw pointer as a `*mut c_void`.
+        deps.insert(QualifiedName::new_from_cpp_name("autocxx::c_void"));
+        let handle_id = handle_name.name.get_final_ident();
+        Annotated::new(
+            parse_quote! { cxx::UniquePtr<#handle_id> },
+            deps,
+            extra_apis,
+            TypeKind::OpaqueHandle,
+        )
+    }
+
+    /// Convert an intrusive smart pointer such as `RefPtr<T>` into a
+    /// `*mut T`, creating the reference counting API for `T` if we haven't
+    /// already done so.
+    fn convert_intrusive_ptr(
+        &mut self,
+        typ: TypePath,
+        ptr: IntrusivePtr,
+        ns: &Namespace,
+        mut deps: HashSet<QualifiedName>,
+    ) -> Result<Annotated<Type>, ConvertError> {
+        let pointee = match typ.path.segments.last().map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) if ab.args.len() == 1 => {
+                match ab.args.first() {
+                    Some(GenericArgument::Type(ty)) => ty.clone(),
+                    _ => return Err(ConvertError::UnsupportedIntrusivePtr(ptr.cpp_name)),
+                }
+            }
+            _ => return Err(ConvertError::UnsupportedIntrusivePtr(ptr.cpp_name)),
+        };
+        let pointee = self.convert_type(pointee, ns, &TypeConversionContext::WithinReference)?;
+        let target = match &pointee.ty {
+            Type::Path(tp) => QualifiedName::from_type_path(tp),
+            _ => return Err(ConvertError::UnsupportedIntrusivePtr(ptr.cpp_name)),
+        };
+        let api_name = ApiName::new_in_root_namespace(make_ident(format!(
+            "{}_{}",
+            ptr.cpp_name.replace("::", "_"),
+            target.to_cpp_name().replace("::", "_")
+        )));
+        let mut extra_apis = ApiVec::new();
+        if self.intrusive_ptr_targets.insert(api_name.name.clone()) {
+            extra_apis.push(UnanalyzedApi::IntrusivePtrTarget {
+                name: api_name.clone(),
+                target,
+                ptr: ptr.clone(),
+            });
+        }
+        deps.remove(&QualifiedName::from_type_path(&typ));
+        deps.extend(pointee.types_encountered);
+        deps.insert(api_name.name);
+        let pointee = pointee.ty;
+        Ok(Annotated::new(
+            Type::Ptr(parse_quote! {
+                *mut #pointee
+            }),
+            deps,
+            extra_apis,
+            TypeKind::IntrusivePtr(ptr),
+        ))
+    }
+
     fn get_generic_args(typ: &mut TypePath) -> Option<&mut PathSegment> {
         match typ.path.segments.last_mut() {
             Some(s) if !s.arguments.is_empty() => Some(s),
@@ -375,6 +990,76 @@ impl<'a> TypeConverter<'a> {
         }
     }
 
+    /// Where bindgen has resolved a type via its canonical form - for
+    /// instance, the deduced return type of a function declared `auto` -
+    /// it spells out template arguments which are normally left to their
+    /// defaults, e.g. `std::vector<int, std::allocator<int>>`. cxx knows
+    /// nothing of these, so drop them.
+    fn strip_defaulted_generic_args<P: Default>(args: &mut Punctuated<GenericArgument, P>) {
+        while args.len() > 1 {
+            let is_default = match args.last() {
+                Some(GenericArgument::Type(Type::Path(typ))) => {
+                    let qn = QualifiedName::from_type_path(typ);
+                    qn.get_namespace().iter().next().map(String::as_str) == Some("std")
+                        && matches!(qn.get_final_item(), "allocator" | "default_delete")
+                }
+                _ => false,
+            };
+            if !is_default {
+                break;
+            }
+            let len = args.len();
+            *args = std::mem::take(args).into_iter().take(len - 1).collect();
+        }
+    }
+
+    /// cxx can't put our `c_int` newtypes in a `CxxVector`, but `int` is
+    /// 32 bits on every platform cxx supports, so `std::vector<int>` is the
+    /// very same type as `std::vector<int32_t>`, which cxx does understand.
+    /// This applies wherever the vector is found, so `std::vector<int>` is
+    /// always a `CxxVector<i32>`.
+    fn use_fixed_width_ints_within_vector(args: &mut Punctuated<GenericArgument, Comma>) {
+        for arg in args.iter_mut() {
+            if let GenericArgument::Type(Type::Path(typ)) = arg {
+                let replacement: Option<Type> =
+                    match QualifiedName::from_type_path(typ).to_cpp_name().as_str() {
+                        "int" => Some(parse_quote! { i32 }),
+                        "unsigned int" => Some(parse_quote! { u32 }),
+                        _ => None,
+                    };
+                if let Some(replacement) = replacement {
+                    *arg = GenericArgument::Type(replacement);
+                }
+            }
+        }
+    }
+
+    /// cxx can't put a `CxxVector` within a `CxxVector`, so instead we make
+    /// a concrete type for the inner vector, which cxx can. This applies
+    /// at each level of nesting, since the inner vector has already been
+    /// converted.
+    fn make_nested_vectors_concrete(
+        &mut self,
+        args: &mut Punctuated<GenericArgument, Comma>,
+        deps: &mut HashSet<QualifiedName>,
+        extra_apis: &mut ApiVec<NullPhase>,
+    ) -> Result<(), ConvertError> {
+        for arg in args.iter_mut() {
+            if let GenericArgument::Type(Type::Path(typ)) = arg {
+                let inner_qn = QualifiedName::from_type_path(typ);
+                if known_types().cxx_generic_behavior(&inner_qn) != CxxGenericType::CppVector {
+                    continue;
+                }
+                let (new_tn, api) = self.get_templated_typename(&Type::Path(typ.clone()))?;
+                extra_apis.extend(api.into_iter());
+                deps.remove(&inner_qn);
+                *arg = GenericArgument::Type(Type::Path(new_tn.to_type_path()));
+                deps.insert(new_tn);
+            }
+        }
+        Ok(())
+    }
+
     fn convert_punctuated<P>(
         &mut self,
         pun: Punctuated<GenericArgument, P>,
@@ -436,6 +1121,34 @@ impl<'a> TypeConverter<'a> {
         }
     }
 
+    fn is_pointer_to_our_type(&self, ptr: &TypePtr) -> bool {
+        match ptr.elem.as_ref() {
+            Type::Path(typ) => self
+                .types_found
+                .contains(&QualifiedName::from_type_path(typ)),
+            _ => false,
+        }
+    }
+
+    fn resolve_alias_template(&self, tn: &QualifiedName, typ: &TypePath) -> Option<Type> {
+        let (params, target) = self.alias_templates.get(tn)?;
+        let args: Vec<Type> = match typ.path.segments.last().map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(ab)) => ab
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let substitutions: HashMap<Ident, Type> = params.iter().cloned().zip(args).collect();
+        let mut resolved = target.clone();
+        substitute_type_params(&mut resolved, &substitutions);
+        Some(resolved)
+    }
+
     fn convert_ptr(
         &mut self,
         mut ptr: TypePtr,
@@ -444,21 +1157,100 @@ impl<'a> TypeConverter<'a> {
     ) -> Result<Annotated<Type>, ConvertError> {
         match pointer_treatment {
             PointerTreatment::Pointer => {
+                // Array parameters decay to a pointer to their first element,
+                // but if `array_params!` is in use, bindgen tells us their
+                // size, which we keep for later.
+                let (elem, array_len) = match *ptr.elem {
+                    Type::Array(TypeArray { elem, len, .. }) if self.config.has_array_params() => {
+                        (elem, Some(len))
+                    }
+                    elem => (Box::new(elem), None),
+                };
+                ptr.elem = elem;
+                // An array of C strings, such as `argv`, which
+                // `slice_param!` can turn into a slice of `CStr`s.
+                if crate::known_types::is_c_string_ptr(&ptr.elem) {
+                    let innerty = self.convert_boxed_type(
+                        ptr.elem,
+                        ns,
+                        &TypeConversionContext::WithinReference,
+                    )?;
+                    ptr.elem = innerty.ty;
+                    return Ok(Annotated::new(
+                        Type::Ptr(ptr),
+                        innerty.types_encountered,
+                        innerty.extra_apis,
+                        TypeKind::Pointer,
+                    ));
+                }
                 crate::known_types::ensure_pointee_is_valid(&ptr)?;
                 let innerty =
                     self.convert_boxed_type(ptr.elem, ns, &TypeConversionContext::WithinReference)?;
+                if let TypeKind::StdFunction { .. } = innerty.kind {
+                    return Err(ConvertError::UnsupportedStdFunction);
+                }
                 ptr.elem = innerty.ty;
                 Ok(Annotated::new(
                     Type::Ptr(ptr),
                     innerty.types_encountered,
                     innerty.extra_apis,
-                    TypeKind::Pointer,
+                    match array_len {
+                        Some(len) => TypeKind::SizedArrayPointer(len),
+                        None => TypeKind::Pointer,
+                    },
                 ))
             }
             PointerTreatment::Reference => {
                 let mutability = ptr.mutability;
+                // References to arrays are passed just like array parameters:
+                // as a pointer to their first element.
+                let elem = match *ptr.elem {
+                    Type::Array(TypeArray { elem, len, .. }) => {
+                        ptr.elem = elem;
+                        crate::known_types::ensure_pointee_is_valid(&ptr)?;
+                        let innerty = self.convert_boxed_type(
+                            ptr.elem,
+                            ns,
+                            &TypeConversionContext::WithinReference,
+                        )?;
+                        ptr.elem = innerty.ty;
+                        return Ok(Annotated::new(
+                            Type::Ptr(ptr),
+                            innerty.types_encountered,
+                            innerty.extra_apis,
+                            TypeKind::SizedArrayReference(len),
+                        ));
+                    }
+                    // C++ commonly hands over ownership of a newly created
+                    // object by assigning it into a reference to a pointer.
+                    Type::Ptr(inner)
+                        if mutability.is_some() && self.is_pointer_to_our_type(&inner) =>
+                    {
+                        let innerty = self.convert_type(
+                            Type::Ptr(inner),
+                            ns,
+                            &TypeConversionContext::WithinReference,
+                        )?;
+                        ptr.elem = Box::new(innerty.ty);
+                        return Ok(Annotated::new(
+                            Type::Ptr(ptr),
+                            innerty.types_encountered,
+                            innerty.extra_apis,
+                            TypeKind::ReferenceToPointer,
+                        ));
+                    }
+                    elem => Box::new(elem),
+                };
                 let elem =
-                    self.convert_boxed_type(ptr.elem, ns, &TypeConversionContext::WithinReference)?;
+                    self.convert_boxed_type(elem, ns, &TypeConversionContext::WithinReference)?;
+                if let TypeKind::StdFunction { .. } = elem.kind {
+                    // We construct a new std::function for each call, which
+                    // can be bound to a const reference but nothing else.
+                    if mutability.is_some() {
+                        return Err(ConvertError::UnsupportedStdFunction);
+                    }
+                    return Ok(elem.map(|elem| *elem));
+                }
                 // TODO - in the future, we should check if this is a rust::Str and throw
                 // a wobbler if not. rust::Str should only be seen _by value_ in C++
                 // headers; it manifests as &str in Rust but on the C++ side it must
@@ -482,6 +1274,9 @@ impl<'a> TypeConverter<'a> {
                 crate::known_types::ensure_pointee_is_valid(&ptr)?;
                 let innerty =
                     self.convert_boxed_type(ptr.elem, ns, &TypeConversionContext::WithinReference)?;
+                if let TypeKind::StdFunction { .. } = innerty.kind {
+                    return Err(ConvertError::UnsupportedStdFunction);
+                }
                 ptr.elem = innerty.ty;
                 Ok(Annotated::new(
                     Type::Ptr(ptr),
@@ -617,6 +1412,29 @@ impl<'a> TypeConverter<'a> {
             .collect()
     }
 
+    fn find_alias_templates<A: AnalysisPhase>(
+        apis: &ApiVec<A>,
+    ) -> HashMap<QualifiedName, (Vec<Ident>, Type)> {
+        apis.iter()
+            .filter_map(|api| match &api {
+                Api::Typedef {
+                    item: TypedefKind::Type(ity),
+                    ..
+                } if !ity.generics.params.is_empty() => Some((
+                    api.name().clone(),
+                    (
+                        ity.generics
+                            .type_params()
+                            .map(|tp| tp.ident.clone())
+                            .collect(),
+                        ity.ty.as_ref().clone(),
+                    ),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
     fn find_concrete_templates<A: AnalysisPhase>(
         apis: &ApiVec<A>,
     ) -> HashMap<String, QualifiedName> {
@@ -630,6 +1448,46 @@ impl<'a> TypeConverter<'a> {
             .collect()
     }
 
+    fn find_std_function_adapters<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<QualifiedName> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::StdFunctionAdapter { .. } => Some(api.name()),
+                _ => None,
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn find_std_tuple_bridges<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<QualifiedName> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::StdTupleBridge { .. } => Some(api.name()),
+                _ => None,
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn find_std_variant_bridges<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<QualifiedName> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::StdVariantBridge { .. } => Some(api.name()),
+                _ => None,
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn find_intrusive_ptr_targets<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<QualifiedName> {
+        apis.iter()
+            .filter_map(|api| match api {
+                Api::IntrusivePtrTarget { .. } => Some(api.name()),
+                _ => None,
+            })
+            .cloned()
+            .collect()
+    }
+
     fn find_incomplete_types<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<QualifiedName> {
         apis.iter()
             .filter_map(|api| match api {
@@ -671,10 +1529,86 @@ pub(crate) fn add_analysis<A: AnalysisPhase>(api: UnanalyzedApi) -> Api<A> {
             rs_definition,
             cpp_definition,
         },
+        Api::StdFunctionAdapter { name, params, ret } => {
+            Api::StdFunctionAdapter { name, params, ret }
+        }
+        Api::StdTupleBridge { name, elems } => Api::StdTupleBridge { name, elems },
+        Api::StdVariantBridge {
+            name,
+            enum_name,
+            alternatives,
+        } => Api::StdVariantBridge {
+            name,
+            enum_name,
+            alternatives,
+        },
+        Api::OpaqueHandle { name } => Api::OpaqueHandle { name },
+        Api::IntrusivePtrTarget { name, target, ptr } => {
+            Api::IntrusivePtrTarget { name, target, ptr }
+        }
+        Api::ForwardDeclaration { name, err } => Api::ForwardDeclaration { name, err },
         Api::IgnoredItem { name, err, ctx } => Api::IgnoredItem { name, err, ctx },
         _ => panic!("Function analysis created an unexpected type of extra API"),
     }
 }
+
+/// Replace any of the given type parameters found within `ty` by the
+/// corresponding type arguments.
+fn substitute_type_params(ty: &mut Type, substitutions: &HashMap<Ident, Type>) {
+    match ty {
+        Type::Path(typ) => {
+            if typ.qself.is_none() && typ.path.segments.len() == 1 {
+                if let Some(replacement) = substitutions.get(&typ.path.segments[0].ident) {
+                    *ty = replacement.clone();
+                    return;
+                }
+            }
+            for seg in typ.path.segments.iter_mut() {
+                if let PathArguments::AngleBracketed(ab) = &mut seg.arguments {
+                    for arg in ab.args.iter_mut() {
+                        if let GenericArgument::Type(ty) = arg {
+                            substitute_type_params(ty, substitutions);
+                        }
+                    }
+                }
+            }
+        }
+        Type::Ptr(ptr) => substitute_type_params(&mut ptr.elem, substitutions),
+        Type::Reference(r) => substitute_type_params(&mut r.elem, substitutions),
+        Type::Array(arr) => substitute_type_params(&mut arr.elem, substitutions),
+        Type::Slice(slice) => substitute_type_params(&mut slice.elem, substitutions),
+        Type::Tuple(tuple) => {
+            for elem in tuple.elems.iter_mut() {
+                substitute_type_params(elem, substitutions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// If `ty` is a nullable C function pointer, as bindgen represents a field
+/// such as `int (*read)(void*, int)`, returns the function type.
+pub(crate) fn function_pointer_type(ty: &Type) -> Option<&TypeBareFn> {
+    let seg = match ty {
+        Type::Path(typ) if typ.qself.is_none() => typ.path.segments.last()?,
+        _ => return None,
+    };
+    if seg.ident != "Option" {
+        return None;
+    }
+    match &seg.arguments {
+        PathArguments::AngleBracketed(ab) if ab.args.len() == 1 => match ab.args.first() {
+            Some(GenericArgument::Type(Type::BareFn(bare_fn)))
+                if bare_fn.abi.is_some() && bare_fn.variadic.is_none() =>
+            {
+                Some(bare_fn)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub(crate) trait TypedefTarget {
     fn get_target(&self) -> Option<&Type>;
 }
@@ -705,10 +1639,20 @@ pub(crate) fn find_types<A: AnalysisPhase>(apis: &ApiVec<A>) -> HashSet<Qualifie
             | Api::Struct { .. }
             | Api::Subclass { .. }
             | Api::ExternCppType { .. }
-            | Api::RustType { .. } => Some(api.name()),
+            | Api::StdFunctionAdapter { .. }
+            | Api::StdTupleBridge { .. }
+            | Api::StdVariantBridge { .. }
+            | Api::OpaqueHandle { .. }
+            | Api::RustType { .. }
+            | Api::RustSharedStruct { .. } => Some(api.name()),
             Api::StringConstructor { .. }
             | Api::Function { .. }
             | Api::Const { .. }
+            | Api::StringTable { .. }
+            | Api::IntrusivePtrTarget { .. }
+            | Api::ComparisonOperators { .. }
+            | Api::IndexOperators { .. }
+            | Api::BoolConversion { .. }
             | Api::CType { .. }
             | Api::RustSubclassFn { .. }
             | Api::IgnoredItem { .. }
@@ -10,7 +10,7 @@ use indexmap::set::IndexSet as HashSet;
 use std::fmt::Display;
 
 use crate::types::{make_ident, Namespace, QualifiedName};
-use autocxx_parser::{ExternCppType, RustFun, RustPath};
+use autocxx_parser::{ExternCppType, IntrusivePtr, RustFun, RustPath};
 use itertools::Itertools;
 use quote::ToTokens;
 use syn::{
@@ -145,9 +145,16 @@ pub(crate) struct References {
     pub(crate) ref_params: HashSet<Ident>,
     pub(crate) ref_return: bool,
     pub(crate) rvalue_ref_return: bool,
+    pub(crate) volatile_params: HashSet<Ident>,
 }
 
 impl References {
+    pub(crate) fn new_with_this_as_reference() -> Self {
+        Self {
+            ref_params: [make_ident("this")].into_iter().collect(),
+            ..Default::default()
+        }
+    }
     pub(crate) fn new_with_this_and_return_as_reference() -> Self {
         Self {
             ref_return: true,
@@ -244,6 +251,9 @@ pub(crate) enum Provenance {
     Bindgen,
     SynthesizedOther,
     SynthesizedSubclassConstructor(Box<SubclassConstructorDetails>),
+    /// A protected superclass method, re-exposed on the C++ peer class
+    /// of this subclass.
+    SynthesizedProtectedMethodAccess(SubclassName),
 }
 
 /// A C++ function for which we need to generate bindings, but haven't
@@ -271,6 +281,9 @@ pub(crate) struct FuncToConvert {
     pub(crate) unused_template_param: bool,
     pub(crate) references: References,
     pub(crate) original_name: Option<String>,
+    /// A name requested for this function in Rust, overriding the one
+    /// we'd otherwise choose.
+    pub(crate) rust_name: Option<String>,
     /// Used for static functions only. For all other functons,
     /// this is figured out from the receiver type in the inputs.
     pub(crate) self_ty: Option<QualifiedName>,
@@ -286,6 +299,16 @@ pub(crate) struct FuncToConvert {
     pub(crate) is_deleted: bool,
 }
 
+impl FuncToConvert {
+    /// Whether this is an `operator=` taking an rvalue reference.
+    pub(crate) fn is_move_assignment_operator(&self) -> bool {
+        matches!(
+            self.special_member,
+            Some(SpecialMemberKind::AssignmentOperator)
+        ) && !self.references.rvalue_ref_params.is_empty()
+    }
+}
+
 /// Layers of analysis which may be applied to decorate each API.
 /// See description of the purpose of this trait within `Api`.
 pub(crate) trait AnalysisPhase {
@@ -493,6 +516,58 @@ pub(crate) enum Api<T: AnalysisPhase> {
         name: ApiName,
         const_item: ItemConst,
     },
+    /// A global array of C string literals (`const char* const[]`),
+    /// exposed via a synthesized accessor.
+    StringTable { name: ApiName, len: usize },
+    /// A synthetic Rust type holding a closure, which C++ can turn
+    /// into a `std::function` with the given signature.
+    StdFunctionAdapter {
+        name: ApiName,
+        params: Vec<Type>,
+        ret: ReturnType,
+    },
+    /// A synthetic struct with one field per element of a `std::tuple`,
+    /// which C++ fills in so that Rust can unpack it into a tuple.
+    StdTupleBridge { name: ApiName, elems: Vec<Type> },
+    /// A synthetic struct holding the index and value of the active
+    /// alternative of a `std::variant`, which Rust turns into the
+    /// generated enum with the given name.
+    StdVariantBridge {
+        name: ApiName,
+        enum_name: QualifiedName,
+        alternatives: Vec<Type>,
+    },
+    /// A synthetic C++ type owning a `void*` along with the deleter of the
+    /// `std::unique_ptr<void, D>` it came from, which cxx can't represent.
+    OpaqueHandle { name: ApiName },
+    /// Reference counting functions for a type found within an
+    /// `intrusive_ptr!` smart pointer, which allow the Rust
+    /// `autocxx::IntrusivePtr` to manage its lifetime.
+    IntrusivePtrTarget {
+        name: ApiName,
+        target: QualifiedName,
+        ptr: IntrusivePtr,
+    },
+    /// Comparison functions for a type marked with `ordered!`, which
+    /// allow Rust's ordering traits to be implemented using C++'s
+    /// comparison operators.
+    ComparisonOperators {
+        name: ApiName,
+        target: QualifiedName,
+    },
+    /// Subscript functions for a type marked with `indexed!`, which allow
+    /// Rust's indexing traits to be implemented using C++'s `operator[]`.
+    IndexOperators {
+        name: ApiName,
+        target: QualifiedName,
+        element: QualifiedName,
+    },
+    /// A conversion function for a type marked with `bool_conversion!`,
+    /// which calls C++'s `operator bool`.
+    BoolConversion {
+        name: ApiName,
+        target: QualifiedName,
+    },
     /// A typedef found in the bindgen output which we wish
     /// to pass on in our output
     Typedef {
@@ -527,6 +602,14 @@ pub(crate) enum Api<T: AnalysisPhase> {
     },
     /// A Rust type which is not a C++ type.
     RustType { name: ApiName, path: RustPath },
+    /// A struct defined in Rust which is shared with C++ by value.
+    /// Its field types are converted during POD analysis, and `deps`
+    /// records any C++ types which they use.
+    RustSharedStruct {
+        name: ApiName,
+        item: ItemStruct,
+        deps: Vec<QualifiedName>,
+    },
     /// A function for the 'extern Rust' block which is not a C++ type.
     RustFn {
         name: ApiName,
@@ -587,12 +670,22 @@ impl<T: AnalysisPhase> Api<T> {
             Api::StringConstructor { name } => name,
             Api::Function { name, .. } => name,
             Api::Const { name, .. } => name,
+            Api::StringTable { name, .. } => name,
+            Api::StdFunctionAdapter { name, .. } => name,
+            Api::StdTupleBridge { name, .. } => name,
+            Api::StdVariantBridge { name, .. } => name,
+            Api::OpaqueHandle { name } => name,
+            Api::IntrusivePtrTarget { name, .. } => name,
+            Api::ComparisonOperators { name, .. } => name,
+            Api::IndexOperators { name, .. } => name,
+            Api::BoolConversion { name, .. } => name,
             Api::Typedef { name, .. } => name,
             Api::Enum { name, .. } => name,
             Api::Struct { name, .. } => name,
             Api::CType { name, .. } => name,
             Api::IgnoredItem { name, .. } => name,
             Api::RustType { name, .. } => name,
+            Api::RustSharedStruct { name, .. } => name,
             Api::RustFn { name, .. } => name,
             Api::RustSubclassFn { name, .. } => name,
             Api::Subclass { name, .. } => &name.0,
@@ -0,0 +1,223 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{fmt::Display, hash::Hasher, path::PathBuf};
+
+use indexmap::map::IndexMap as HashMap;
+use quote::quote;
+use sha2::{Digest, Sha256};
+use syn::Item;
+
+use crate::CppFilePair;
+
+use super::CodegenResults;
+
+const MAGIC: &[u8] = b"autocxx-conversion-cache-3";
+
+/// A SHA-256 digest of all the inputs to a conversion. Unlike the hashers
+/// in the standard library, this is the same from one build of autocxx to
+/// the next, and long enough that we needn't worry about collisions.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct CacheKey([u8; 32]);
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Feeds anything implementing [`std::hash::Hash`] into a SHA-256 digest.
+pub(crate) struct CacheKeyHasher(Sha256);
+
+impl CacheKeyHasher {
+    pub(crate) fn new() -> Self {
+        Self(Sha256::new())
+    }
+
+    pub(crate) fn key(self) -> CacheKey {
+        CacheKey(self.0.finalize().into())
+    }
+}
+
+impl Hasher for CacheKeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+}
+
+/// A directory in which we store the results of previous conversions,
+/// keyed by a hash of all their inputs, so that build scripts needn't
+/// repeat a conversion when nothing has changed.
+///
+/// Entries are only ever written whole and are never trusted beyond
+/// their own contents, so a corrupt or truncated entry is simply treated
+/// as absent. Each entry records its full key, so an entry which has
+/// somehow ended up under the wrong name is treated as absent too.
+pub(crate) struct ConversionCache {
+    dir: PathBuf,
+}
+
+impl ConversionCache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path_for(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.autocxx", key))
+    }
+
+    /// Returns the results previously stored under this key, if any.
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<CodegenResults> {
+        let contents = std::fs::read(self.path_for(key)).ok()?;
+        decode(&contents, key)
+    }
+
+    /// Stores these results under the given key.
+    pub(crate) fn put(&self, key: &CacheKey, results: &CodegenResults) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        // Write to a temporary file and then rename it into place, so that
+        // concurrent builds never see a partially written entry.
+        let mut temp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        std::io::Write::write_all(&mut temp, &encode(key, results))?;
+        temp.persist(self.path_for(key)).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+// Entries are a series of records, each a little-endian u64 length
+// followed by that many bytes. Rust items are stored as token strings.
+
+fn encode(key: &CacheKey, results: &CodegenResults) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_record(&mut out, MAGIC);
+    write_record(&mut out, &key.0);
+    write_record(&mut out, items_to_string(&results.rs).as_bytes());
+    write_record(&mut out, items_to_string(&results.bridge_items).as_bytes());
+    write_record(
+        &mut out,
+        results
+            .bridge_items_by_namespace
+            .len()
+            .to_string()
+            .as_bytes(),
+    );
+    for (ns, items) in &results.bridge_items_by_namespace {
+        write_record(&mut out, ns.as_bytes());
+        write_record(&mut out, items_to_string(items).as_bytes());
+    }
+    write_record(
+        &mut out,
+        items_to_string(&results.merged_bridge_items).as_bytes(),
+    );
+    match &results.cpp {
+        None => write_record(&mut out, b"0"),
+        Some(cpp) => {
+            write_record(&mut out, b"1");
+            write_record(&mut out, &cpp.header);
+            match &cpp.implementation {
+                None => write_record(&mut out, b"0"),
+                Some(implementation) => {
+                    write_record(&mut out, b"1");
+                    write_record(&mut out, implementation);
+                }
+            }
+            write_record(&mut out, cpp.header_name.as_bytes());
+        }
+    }
+    write_record(&mut out, results.cxxgen_header_name.as_bytes());
+    out
+}
+
+fn decode(contents: &[u8], key: &CacheKey) -> Option<CodegenResults> {
+    let mut reader = RecordReader(contents);
+    if reader.next()? != MAGIC || reader.next()? != key.0 {
+        return None;
+    }
+    let rs = string_to_items(reader.next_str()?)?;
+    let bridge_items = string_to_items(reader.next_str()?)?;
+    let namespace_count: usize = reader.next_str()?.parse().ok()?;
+    let mut bridge_items_by_namespace = HashMap::new();
+    for _ in 0..namespace_count {
+        let ns = reader.next_str()?.to_string();
+        bridge_items_by_namespace.insert(ns, string_to_items(reader.next_str()?)?);
+    }
+    let merged_bridge_items = string_to_items(reader.next_str()?)?;
+    let cpp = match reader.next()? {
+        b"0" => None,
+        b"1" => {
+            let header = reader.next()?.to_vec();
+            let implementation = match reader.next()? {
+                b"0" => None,
+                b"1" => Some(reader.next()?.to_vec()),
+                _ => return None,
+            };
+            let header_name = reader.next_str()?.to_string();
+            Some(CppFilePair {
+                header,
+                implementation,
+                header_name,
+            })
+        }
+        _ => return None,
+    };
+    let cxxgen_header_name = reader.next_str()?.to_string();
+    if !reader.0.is_empty() {
+        return None;
+    }
+    Some(CodegenResults {
+        rs,
+        bridge_items,
+        bridge_items_by_namespace,
+        merged_bridge_items,
+        cpp,
+        cxxgen_header_name,
+    })
+}
+
+fn write_record(out: &mut Vec<u8>, record: &[u8]) {
+    out.extend_from_slice(&(record.len() as u64).to_le_bytes());
+    out.extend_from_slice(record);
+}
+
+struct RecordReader<'a>(&'a [u8]);
+
+impl<'a> RecordReader<'a> {
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.0.len() < 8 {
+            return None;
+        }
+        let (len, rest) = self.0.split_at(8);
+        let len = usize::try_from(u64::from_le_bytes(len.try_into().ok()?)).ok()?;
+        if rest.len() < len {
+            return None;
+        }
+        let (record, rest) = rest.split_at(len);
+        self.0 = rest;
+        Some(record)
+    }
+
+    fn next_str(&mut self) -> Option<&'a str> {
+        std::str::from_utf8(self.next()?).ok()
+    }
+}
+
+fn items_to_string(items: &[Item]) -> String {
+    quote! { #(#items)* }.to_string()
+}
+
+fn string_to_items(s: &str) -> Option<Vec<Item>> {
+    syn::parse_str::<syn::File>(s).ok().map(|file| file.items)
+}
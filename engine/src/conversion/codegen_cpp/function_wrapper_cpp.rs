@@ -6,11 +6,15 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use syn::{Type, TypePtr};
+use quote::ToTokens;
+use syn::{
+    Expr, ExprLit, GenericArgument, Lit, PathArguments, Type, TypePtr, TypeReference, TypeSlice,
+};
 
 use crate::conversion::{
     analysis::fun::function_wrapper::{CppConversionType, TypeConversionPolicy},
     api::Pointerness,
+    utilities::std_function_converter_name,
     ConvertError,
 };
 
@@ -32,7 +36,10 @@ impl TypeConversionPolicy {
 
     pub(super) fn converted_type(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
         match self.cpp_conversion {
-            CppConversionType::FromValueToUniquePtr => self.unique_ptr_wrapped_type(cpp_name_map),
+            CppConversionType::FromValueToUniquePtr
+            | CppConversionType::FromOwningPtrToUniquePtr => {
+                self.unique_ptr_wrapped_type(cpp_name_map)
+            }
             CppConversionType::FromReferenceToPointer => {
                 let (const_string, ty) = match self.cxxbridge_type() {
                     Type::Ptr(TypePtr {
@@ -53,10 +60,63 @@ impl TypeConversionPolicy {
         }
     }
 
+    /// The type which the wrapped C++ function itself takes or returns,
+    /// if we can be sure of it. Pointers may have been references in the
+    /// original C++, and some conversions don't record whether a value
+    /// was passed by value or by rvalue reference, so we don't know.
+    pub(super) fn original_cpp_type(
+        &self,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<Option<String>, ConvertError> {
+        Ok(match (&self.cpp_conversion, self.cxxbridge_type()) {
+            (CppConversionType::None, Type::Ptr(_)) => None,
+            (CppConversionType::None, _)
+            | (CppConversionType::FromUniquePtrToValue, _)
+            | (CppConversionType::FromValueToUniquePtr, _)
+            | (CppConversionType::FromReturnValueToPlacementPtr, _) => {
+                Some(self.unwrapped_type_as_string(cpp_name_map)?)
+            }
+            _ => None,
+        })
+    }
+
     fn unwrapped_type_as_string(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
         type_to_cpp(self.cxxbridge_type(), cpp_name_map)
     }
 
+    fn pointee_type_as_string(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
+        match self.cxxbridge_type() {
+            Type::Ptr(TypePtr { elem, .. }) => type_to_cpp(elem, cpp_name_map),
+            _ => panic!("Not a pointer"),
+        }
+    }
+
+    fn unique_ptr_pointee_as_string(
+        &self,
+        cpp_name_map: &CppNameMap,
+    ) -> Result<String, ConvertError> {
+        match self.cxxbridge_type() {
+            Type::Path(typ) => match typ.path.segments.last().map(|seg| &seg.arguments) {
+                Some(PathArguments::AngleBracketed(ab)) => match ab.args.first() {
+                    Some(GenericArgument::Type(ty)) => type_to_cpp(ty, cpp_name_map),
+                    _ => panic!("Not a UniquePtr"),
+                },
+                _ => panic!("Not a UniquePtr"),
+            },
+            _ => panic!("Not a UniquePtr"),
+        }
+    }
+
+    fn slice_elem_type_as_string(&self, cpp_name_map: &CppNameMap) -> Result<String, ConvertError> {
+        match self.cxxbridge_type() {
+            Type::Reference(TypeReference { elem, .. }) => match elem.as_ref() {
+                Type::Slice(TypeSlice { elem, .. }) => type_to_cpp(elem, cpp_name_map),
+                _ => panic!("Not a slice"),
+            },
+            _ => panic!("Not a slice"),
+        }
+    }
+
     pub(crate) fn is_a_pointer(&self) -> Pointerness {
         match self.cxxbridge_type() {
             Type::Ptr(TypePtr {
@@ -87,9 +147,10 @@ impl TypeConversionPolicy {
         // If is_return we want to avoid unnecessary std::moves because they
         // make RVO less effective
         Ok(match self.cpp_conversion {
-            CppConversionType::None | CppConversionType::FromReturnValueToPlacementPtr => {
-                Some(var_name.to_string())
-            }
+            // Returning by value from the wrapper makes the copy.
+            CppConversionType::None
+            | CppConversionType::FromReturnValueToPlacementPtr
+            | CppConversionType::FromReferenceToValue => Some(var_name.to_string()),
             CppConversionType::FromPointerToReference { .. } => Some(format!("(*{})", var_name)),
             CppConversionType::Move => Some(format!("std::move({})", var_name)),
             CppConversionType::FromUniquePtrToValue | CppConversionType::FromPtrToMove => {
@@ -100,6 +161,11 @@ impl TypeConversionPolicy {
                 self.unconverted_type(cpp_name_map)?,
                 var_name
             )),
+            CppConversionType::FromOwningPtrToUniquePtr => Some(format!(
+                "{}({})",
+                self.unique_ptr_wrapped_type(cpp_name_map)?,
+                var_name
+            )),
             CppConversionType::FromPtrToValue => {
                 let dereference = format!("*{}", var_name);
                 Some(if is_return {
@@ -110,6 +176,169 @@ impl TypeConversionPolicy {
             }
             CppConversionType::IgnoredPlacementPtrParameter => None,
             CppConversionType::FromReferenceToPointer { .. } => Some(format!("&{}", var_name)),
+            CppConversionType::FromStrToStringView => Some(format!(
+                "std::string_view({}.data(), {}.size())",
+                var_name, var_name
+            )),
+            // This converts implicitly to a span of const elements if need be.
+            CppConversionType::FromSliceToSpan => Some(format!(
+                "std::span({}.data(), {}.size())",
+                var_name, var_name
+            )),
+            // Braced, so that it can initialize a slice of either const or
+            // non-const elements, whichever the C++ function takes.
+            CppConversionType::FromSliceToRustSlice => {
+                Some(format!("{{{}.data(), {}.size()}}", var_name, var_name))
+            }
+            // This stands in for two parameters of the original function.
+            CppConversionType::FromSliceToPtrAndLen { len_first: false } => {
+                Some(format!("{}.data(), {}.size()", var_name, var_name))
+            }
+            CppConversionType::FromSliceToPtrAndLen { len_first: true } => {
+                Some(format!("{}.size(), {}.data()", var_name, var_name))
+            }
+            CppConversionType::FromCStrSliceToPtrArray {
+                ref ptr_type,
+                len_first,
+            } => {
+                // rust::Slice<const char*> gives us a `const char**`, which
+                // C++ may want as `char**` even though it shouldn't modify
+                // the strings.
+                let data = match ptr_type.as_ref() {
+                    Type::Ptr(TypePtr {
+                        mutability: outer_mut,
+                        elem,
+                        ..
+                    }) if matches!(
+                        elem.as_ref(),
+                        Type::Ptr(TypePtr {
+                            mutability: Some(_),
+                            ..
+                        })
+                    ) =>
+                    {
+                        format!(
+                            "const_cast<char*{}*>({}.data())",
+                            if outer_mut.is_some() { "" } else { " const" },
+                            var_name
+                        )
+                    }
+                    _ => format!("{}.data()", var_name),
+                };
+                let size = format!("{}.size()", var_name);
+                Some(if len_first {
+                    format!("{}, {}", size, data)
+                } else {
+                    format!("{}, {}", data, size)
+                })
+            }
+            // These make temporary copies for the duration of the call.
+            CppConversionType::FromSliceToVector => Some(format!(
+                "std::vector<{}>({}.data(), {}.data() + {}.size())",
+                self.slice_elem_type_as_string(cpp_name_map)?,
+                var_name,
+                var_name,
+                var_name
+            )),
+            CppConversionType::FromStrToString => Some(format!(
+                "std::string({}.data(), {}.size())",
+                var_name, var_name
+            )),
+            CppConversionType::FromStdTupleToBridge => Some(format!(
+                "{}::from({})",
+                self.unwrapped_type_as_string(cpp_name_map)?,
+                var_name
+            )),
+            // This is given both the pointer and the length.
+            CppConversionType::FromPtrAndLenToSlice(_) => Some(format!(
+                "{}({})",
+                self.unwrapped_type_as_string(cpp_name_map)?,
+                var_name
+            )),
+            CppConversionType::FromStdVariantToBridge => Some(format!(
+                "{}::from({})",
+                self.unwrapped_type_as_string(cpp_name_map)?,
+                var_name
+            )),
+            // The bridge struct is value-initialized if there's no value.
+            CppConversionType::FromStdOptionalToBridge => Some(format!(
+                "[](const auto& opt) {{ return opt ? {bridge}{{true, *opt}} : {bridge}{{}}; }}({var})",
+                bridge = self.unwrapped_type_as_string(cpp_name_map)?,
+                var = var_name
+            )),
+            // The category goes in the upper half, so that the value itself
+            // is never lost.
+            CppConversionType::FromErrorCodeToInt => Some(format!(
+                "[](const std::error_code& ec) {{ enum : int64_t {{ SYSTEM_CATEGORY, GENERIC_CATEGORY, OTHER_CATEGORY }}; const int64_t category = ec.category() == std::system_category() ? SYSTEM_CATEGORY : ec.category() == std::generic_category() ? GENERIC_CATEGORY : OTHER_CATEGORY; return category << 32 | static_cast<uint32_t>(ec.value()); }}({})",
+                var_name
+            )),
+            CppConversionType::FromVoidUniquePtrToOpaqueHandle => Some(format!(
+                "{}::from({})",
+                self.unique_ptr_pointee_as_string(cpp_name_map)?,
+                var_name
+            )),
+            // Use a lambda so as to evaluate the function call only once.
+            CppConversionType::FromStringViewToStr => Some(format!(
+                "[](std::string_view sv) {{ return rust::Str(sv.data(), sv.size()); }}({})",
+                var_name
+            )),
+            // Unlike rust::Str, this can't throw.
+            CppConversionType::FromStringViewToBytes => Some(format!(
+                "[](std::string_view sv) {{ return rust::Slice<const uint8_t>(reinterpret_cast<const uint8_t*>(sv.data()), sv.size()); }}({})",
+                var_name
+            )),
+            CppConversionType::FromBoxToStdFunction => Some(format!(
+                "{}(std::move({}))",
+                std_function_converter_name(self.std_function_adapter()?),
+                var_name
+            )),
+            CppConversionType::FromPointerToArrayReference(ref len) => {
+                let const_string = match self.is_a_pointer() {
+                    Pointerness::ConstPtr => "const ",
+                    _ => "",
+                };
+                Some(format!(
+                    "*reinterpret_cast<{}{}(*)[{}]>({})",
+                    const_string,
+                    self.pointee_type_as_string(cpp_name_map)?,
+                    array_len_to_cpp(len),
+                    var_name
+                ))
+            }
+            // The smart pointer takes its own reference, so drop the one
+            // which Rust handed over.
+            CppConversionType::FromPtrToIntrusivePtr(ref ptr) => {
+                let pointee = self.pointee_type_as_string(cpp_name_map)?;
+                Some(format!(
+                    "[]({pointee}* p) {{ {ptr}<{pointee}> r(p); if (p) {{ {release}(p); }} return r; }}({var})",
+                    pointee = pointee,
+                    ptr = ptr.cpp_name,
+                    release = ptr.release,
+                    var = var_name
+                ))
+            }
+            // Take an extra reference on behalf of Rust, since the smart
+            // pointer will release its own when it goes out of scope.
+            CppConversionType::FromIntrusivePtrToPtr(ref ptr) => {
+                let pointee = self.pointee_type_as_string(cpp_name_map)?;
+                Some(format!(
+                    "[]({ptr}<{pointee}> r) {{ {pointee}* p = r.get(); if (p) {{ {add_ref}(p); }} return p; }}({var})",
+                    pointee = pointee,
+                    ptr = ptr.cpp_name,
+                    add_ref = ptr.add_ref,
+                    var = var_name
+                ))
+            }
         })
     }
 }
+
+/// bindgen gives us array lengths such as `4usize`, which C++ won't accept.
+fn array_len_to_cpp(len: &Expr) -> String {
+    match len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_digits().to_string(),
+        _ => len.to_token_stream().to_string(),
+    }
+}
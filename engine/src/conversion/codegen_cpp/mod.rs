@@ -15,11 +15,12 @@ use crate::{
     types::{make_ident, QualifiedName},
     CppCodegenOptions, CppFilePair,
 };
-use autocxx_parser::IncludeCppConfig;
+use autocxx_parser::{CustomDeleter, IncludeCppConfig, IntrusivePtr};
 use indexmap::map::IndexMap as HashMap;
 use indexmap::set::IndexSet as HashSet;
 use itertools::Itertools;
 use std::borrow::Cow;
+use syn::{ReturnType, Type};
 use type_to_cpp::{original_name_map_from_apis, type_to_cpp, CppNameMap};
 
 use self::type_to_cpp::{
@@ -34,8 +35,12 @@ use super::{
         },
         pod::PodAnalysis,
     },
-    api::{Api, Provenance, SubclassName, TypeKind},
+    api::{Api, ApiName, Provenance, SubclassName, TypeKind},
     apivec::ApiVec,
+    utilities::{
+        bool_conversion_fn_name, comparison_fn_names, index_fn_names, intrusive_ptr_fn_names,
+        std_function_call_name, std_function_converter_name, string_table_accessor_name,
+    },
     ConvertError,
 };
 
@@ -108,6 +113,41 @@ pub(crate) struct CppCodeGenerator<'a> {
     cxxgen_header_name: &'a str,
 }
 
+/// Finds protected superclass methods which need to be made accessible
+/// in each subclass. Each name appears once even if it's overloaded, since
+/// a single `using` declaration covers all overloads.
+fn find_protected_methods_by_subclass(
+    apis: &ApiVec<FnPhase>,
+) -> HashMap<SubclassName, Vec<String>> {
+    let mut protected_methods_by_subclass: HashMap<SubclassName, Vec<String>> = HashMap::new();
+    for api in apis.iter() {
+        if let Api::Function {
+            fun,
+            analysis:
+                FnAnalysis {
+                    ignore_reason: Ok(_),
+                    ..
+                },
+            ..
+        } = api
+        {
+            if let Provenance::SynthesizedProtectedMethodAccess(subclass) = &fun.provenance {
+                let method = fun
+                    .original_name
+                    .clone()
+                    .unwrap_or_else(|| fun.ident.to_string());
+                let methods = protected_methods_by_subclass
+                    .entry(subclass.clone())
+                    .or_default();
+                if !methods.contains(&method) {
+                    methods.push(method);
+                }
+            }
+        }
+    }
+    protected_methods_by_subclass
+}
+
 struct SubclassFunction<'a> {
     fun: &'a CppFunction,
     is_pure_virtual: bool,
@@ -131,7 +171,13 @@ impl<'a> CppCodeGenerator<'a> {
         };
         // The 'filter' on the following line is designed to ensure we don't accidentally
         // end up out of sync with needs_cpp_codegen
-        gen.add_needs(apis.iter().filter(|api| api.needs_cpp_codegen()))?;
+        gen.add_needs(
+            apis.iter().filter(|api| api.needs_cpp_codegen()),
+            find_protected_methods_by_subclass(apis),
+        )?;
+        for deleter in config.get_custom_deleters() {
+            gen.generate_custom_deleter(deleter);
+        }
         Ok(gen.generate())
     }
 
@@ -139,6 +185,7 @@ impl<'a> CppCodeGenerator<'a> {
     fn add_needs<'b>(
         &mut self,
         apis: impl Iterator<Item = &'a Api<FnPhase>>,
+        mut protected_methods_by_subclass: HashMap<SubclassName, Vec<String>>,
     ) -> Result<(), ConvertError> {
         let mut constructors_by_subclass: HashMap<SubclassName, Vec<&CppFunction>> = HashMap::new();
         let mut methods_by_subclass: HashMap<SubclassName, Vec<SubclassFunction>> = HashMap::new();
@@ -180,6 +227,31 @@ impl<'a> CppCodeGenerator<'a> {
                     self.generate_typedef(api.name(), &effective_cpp_definition)
                 }
                 Api::CType { typename, .. } => self.generate_ctype_typedef(typename),
+                Api::StringTable { name, .. } => self.generate_string_table_accessor(name),
+                Api::StdFunctionAdapter { name, params, ret } => {
+                    self.generate_std_function_adapter(name, params, ret)?
+                }
+                Api::StdTupleBridge { name, elems } => {
+                    self.generate_std_tuple_bridge(name, elems)?
+                }
+                Api::StdVariantBridge {
+                    name, alternatives, ..
+                } => self.generate_std_variant_bridge(name, alternatives)?,
+                Api::OpaqueHandle { name } => self.generate_opaque_handle(name),
+                Api::IntrusivePtrTarget { name, target, ptr } => {
+                    self.generate_intrusive_ptr_target(name, target, ptr)?
+                }
+                Api::ComparisonOperators { name, target } => {
+                    self.generate_comparison_operators(name, target)?
+                }
+                Api::IndexOperators {
+                    name,
+                    target,
+                    element,
+                } => self.generate_index_operators(name, target, element)?,
+                Api::BoolConversion { name, target } => {
+                    self.generate_bool_conversion(name, target)?
+                }
                 Api::Subclass { .. } => deferred_apis.push(api),
                 Api::RustSubclassFn {
                     subclass, details, ..
@@ -218,6 +290,9 @@ impl<'a> CppCodeGenerator<'a> {
                     name,
                     constructors_by_subclass.remove(name).unwrap_or_default(),
                     methods_by_subclass.remove(name).unwrap_or_default(),
+                    protected_methods_by_subclass
+                        .remove(name)
+                        .unwrap_or_default(),
                 )?,
                 _ => panic!("Unexpected deferred API"),
             }
@@ -226,7 +301,9 @@ impl<'a> CppCodeGenerator<'a> {
     }
 
     fn generate(&self) -> Option<CppFilePair> {
-        if self.additional_functions.is_empty() {
+        // If there's a preamble or instantiation shims we always generate a
+        // header, since that's how they get into the C++ which cxx generates.
+        if self.additional_functions.is_empty() && !self.config.has_extra_header_content() {
             None
         } else {
             let headers = self.collect_headers(|additional_need| &additional_need.headers);
@@ -324,6 +401,290 @@ impl<'a> CppCodeGenerator<'a> {
         })
     }
 
+    fn generate_string_table_accessor(&mut self, name: &ApiName) {
+        let accessor_name = string_table_accessor_name(self.config, &name.name);
+        let declaration = Some(format!(
+            "inline const char* {}(size_t idx) {{ return {}[idx]; }}",
+            accessor_name,
+            name.qualified_cpp_name()
+        ));
+        self.additional_functions.push(ExtraCpp {
+            declaration,
+            headers: vec![Header::System("cstddef")],
+            ..Default::default()
+        })
+    }
+
+    fn generate_intrusive_ptr_target(
+        &mut self,
+        name: &ApiName,
+        target: &QualifiedName,
+        ptr: &IntrusivePtr,
+    ) -> Result<(), ConvertError> {
+        let (add_ref, release) = intrusive_ptr_fn_names(&name.name.get_final_ident());
+        let target = type_to_cpp(&Type::Path(target.to_type_path()), &self.original_name_map)?;
+        self.additional_functions.push(ExtraCpp {
+            declaration: Some(format!(
+                "inline void {}({}* ptr) {{ {}(ptr); }}
+inline void {}({}* ptr) {{ {}(ptr); }}",
+                add_ref, target, ptr.add_ref, release, target, ptr.release
+            )),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn generate_comparison_operators(
+        &mut self,
+        name: &ApiName,
+        target: &QualifiedName,
+    ) -> Result<(), ConvertError> {
+        let (eq, lt) = comparison_fn_names(&name.name.get_final_ident());
+        let target = type_to_cpp(&Type::Path(target.to_type_path()), &self.original_name_map)?;
+        self.additional_functions.push(ExtraCpp {
+            declaration: Some(format!(
+                "inline bool {eq}(const {ty}& a, const {ty}& b) {{ return a == b; }}
+inline bool {lt}(const {ty}& a, const {ty}& b) {{ return a < b; }}",
+                eq = eq,
+                lt = lt,
+                ty = target
+            )),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn generate_index_operators(
+        &mut self,
+        name: &ApiName,
+        target: &QualifiedName,
+        element: &QualifiedName,
+    ) -> Result<(), ConvertError> {
+        let (index, index_mut) = index_fn_names(&name.name.get_final_ident());
+        let target = type_to_cpp(&Type::Path(target.to_type_path()), &self.original_name_map)?;
+        let element = type_to_cpp(&Type::Path(element.to_type_path()), &self.original_name_map)?;
+        self.additional_functions.push(ExtraCpp {
+            declaration: Some(format!(
+                "inline const {elem}& {index}(const {ty}& me, size_t idx) {{ return me[idx]; }}
+inline {elem}& {index_mut}({ty}& me, size_t idx) {{ return me[idx]; }}",
+                elem = element,
+                index = index,
+                index_mut = index_mut,
+                ty = target
+            )),
+            headers: vec![Header::System("cstddef")],
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn generate_bool_conversion(
+        &mut self,
+        name: &ApiName,
+        target: &QualifiedName,
+    ) -> Result<(), ConvertError> {
+        let as_bool = bool_conversion_fn_name(&name.name.get_final_ident());
+        let target = type_to_cpp(&Type::Path(target.to_type_path()), &self.original_name_map)?;
+        // static_cast, since operator bool is usually explicit.
+        self.additional_functions.push(ExtraCpp {
+            declaration: Some(format!(
+                "inline bool {}(const {}& me) {{ return static_cast<bool>(me); }}",
+                as_bool, target
+            )),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    /// `std::unique_ptr`, and therefore `cxx::UniquePtr`, destroys its
+    /// pointee using `std::default_delete`, so specialize that to call the
+    /// user's deleter instead. This must precede any use of the type within
+    /// a `std::unique_ptr`, so it's emitted alongside the type definitions.
+    /// It must also be seen by every other translation unit which uses
+    /// such a `std::unique_ptr`, or we'd violate the one definition rule;
+    /// we can't arrange that ourselves, so the `custom_deleter!`
+    /// documentation tells users to include this header.
+    fn generate_custom_deleter(&mut self, deleter: &CustomDeleter) {
+        self.additional_functions.push(ExtraCpp {
+            type_definition: Some(format!(
+                "// Every translation unit which uses std::unique_ptr<{ty}> must
+// include this header before doing so.
+namespace std {{
+template<> struct default_delete<{ty}> {{
+  void operator()({ty}* ptr) const {{ {deleter}(ptr); }}
+}};
+}}",
+                ty = deleter.cpp_name,
+                deleter = deleter.deleter
+            )),
+            headers: vec![Header::System("memory")],
+            ..Default::default()
+        })
+    }
+
+    /// The deleter of a `std::unique_ptr<void, D>` is part of its type,
+    /// which cxx can't represent, so we move the pointer and its deleter
+    /// into this type-erased holder instead.
+    fn generate_opaque_handle(&mut self, name: &ApiName) {
+        // The original std::unique_ptr is kept as it is, so that its deleter
+        // is neither copied nor required to be copyable.
+        self.additional_functions.push(ExtraCpp {
+            type_definition: Some(format!(
+                "class {name} {{
+public:
+  template<typename D> static std::unique_ptr<{name}> from(std::unique_ptr<void, D> p) {{
+    return std::unique_ptr<{name}>(new {name}(std::unique_ptr<Owner>(new OwnerOf<D>(std::move(p)))));
+  }}
+  {name}(const {name}&) = delete;
+  {name}& operator=(const {name}&) = delete;
+  void* as_ptr() const {{ return owner->get(); }}
+private:
+  struct Owner {{
+    virtual ~Owner() {{}}
+    virtual void* get() const = 0;
+  }};
+  template<typename D> struct OwnerOf : Owner {{
+    explicit OwnerOf(std::unique_ptr<void, D> p) : p(std::move(p)) {{}}
+    void* get() const override {{ return p.get(); }}
+    std::unique_ptr<void, D> p;
+  }};
+  explicit {name}(std::unique_ptr<Owner> owner) : owner(std::move(owner)) {{}}
+  std::unique_ptr<Owner> owner;
+}};",
+                name = name.name.get_final_item()
+            )),
+            headers: vec![Header::System("memory")],
+            ..Default::default()
+        })
+    }
+
+    fn generate_std_tuple_bridge(
+        &mut self,
+        name: &ApiName,
+        elems: &[Type],
+    ) -> Result<(), ConvertError> {
+        let fields = elems
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                Ok(format!(
+                    "{} f{};",
+                    type_to_cpp(ty, &self.original_name_map)?,
+                    i
+                ))
+            })
+            .collect::<Result<Vec<_>, ConvertError>>()?;
+        let bridge = name.name.get_final_item();
+        // Spell out each element rather than using std::apply, which
+        // would need C++17. Each element is moved out of the tuple, so
+        // move-only types work too.
+        let gets = (0..elems.len())
+            .map(|i| format!("std::get<{i}>(std::move(t))"))
+            .join(", ");
+        self.additional_functions.push(ExtraCpp {
+            type_definition: Some(format!(
+                "struct {bridge} {{ {} template <typename T> static {bridge} from(T&& t) {{ return {bridge}{{{gets}}}; }} }};",
+                fields.join(" ")
+            )),
+            headers: vec![Header::System("tuple"), Header::System("utility")],
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn generate_std_variant_bridge(
+        &mut self,
+        name: &ApiName,
+        alternatives: &[Type],
+    ) -> Result<(), ConvertError> {
+        let bridge = name.name.get_final_item();
+        let fields = alternatives
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                Ok(format!(
+                    "{} v{};",
+                    type_to_cpp(ty, &self.original_name_map)?,
+                    i
+                ))
+            })
+            .collect::<Result<Vec<_>, ConvertError>>()?;
+        let copies = (0..alternatives.len())
+            .map(|i| format!("if (auto p = std::get_if<{i}>(&v)) {{ b.v{i} = *p; }}"))
+            .join(" ");
+        self.additional_functions.push(ExtraCpp {
+            type_definition: Some(format!(
+                "struct {bridge} {{ size_t index; {} template <typename V> static {bridge} from(const V& v) {{ {bridge} b{{}}; b.index = v.index(); {copies} return b; }} }};",
+                fields.join(" ")
+            )),
+            headers: vec![Header::System("variant"), Header::System("cstddef")],
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn generate_std_function_adapter(
+        &mut self,
+        name: &ApiName,
+        params: &[Type],
+        ret: &ReturnType,
+    ) -> Result<(), ConvertError> {
+        let adapter = name.name.get_final_ident();
+        let converter = std_function_converter_name(&adapter);
+        let param_types = params
+            .iter()
+            .map(|ty| type_to_cpp(ty, &self.original_name_map))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ret_type = match ret {
+            ReturnType::Default => "void".to_string(),
+            ReturnType::Type(_, ty) => type_to_cpp(ty, &self.original_name_map)?,
+        };
+        let signature = format!(
+            "std::function<{}({})> {}(rust::Box<{}> f)",
+            ret_type,
+            param_types.join(", "),
+            converter,
+            adapter
+        );
+        let lambda_params = param_types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| format!("{} arg{}", ty, i))
+            .join(", ");
+        let lambda_args = std::iter::once("**shared".to_string())
+            .chain((0..param_types.len()).map(|i| format!("arg{}", i)))
+            .join(", ");
+        // std::function requires its payload to be copyable, which a
+        // rust::Box isn't, so share ownership between all copies.
+        self.additional_functions.push(ExtraCpp {
+            type_definition: Some(format!(
+                "struct {};
+{};",
+                adapter, signature
+            )),
+            definition: Some(format!(
+                "{} {{
+auto shared = std::make_shared<rust::Box<{}>>(std::move(f));
+return [shared]({}) {{ return {}({}); }};
+}}
+",
+                signature,
+                adapter,
+                lambda_params,
+                std_function_call_name(&adapter),
+                lambda_args
+            )),
+            headers: vec![
+                Header::System("functional"),
+                Header::System("memory"),
+                Header::CxxH,
+            ],
+            cpp_headers: vec![Header::CxxgenH],
+            ..Default::default()
+        });
+        Ok(())
+    }
+
     fn generate_cpp_function(&mut self, details: &CppFunction) -> Result<(), ConvertError> {
         self.additional_functions
             .push(self.generate_cpp_function_inner(
@@ -333,9 +694,76 @@ impl<'a> CppCodeGenerator<'a> {
                 false,
                 None,
             )?);
+        if self.config.check_signatures() {
+            if let Some(check) = self.generate_signature_check(details)? {
+                self.additional_functions.push(check);
+            }
+        }
         Ok(())
     }
 
+    /// Generates a check that the free function or static method called by
+    /// a wrapper really has the signature for which we generated bindings.
+    /// The wrapper calls it in the ordinary way, so implicit conversions
+    /// would otherwise hide any mismatch. The check is the `static_cast` to
+    /// the exact function pointer type, which doesn't compile unless some
+    /// overload has exactly that type; it also selects the right overload,
+    /// which a plain `decltype(&f)` couldn't do.
+    fn generate_signature_check(
+        &self,
+        details: &CppFunction,
+    ) -> Result<Option<ExtraCpp>, ConvertError> {
+        let target = match &details.payload {
+            CppFunctionBody::FunctionCall(ns, id)
+                if matches!(details.kind, CppFunctionKind::Function) =>
+            {
+                ns.into_iter()
+                    .cloned()
+                    .chain(std::iter::once(id.to_string()))
+                    .join("::")
+            }
+            CppFunctionBody::StaticMethodCall(ns, ty_id, fn_id) => ns
+                .into_iter()
+                .cloned()
+                .chain([ty_id.to_string(), fn_id.to_string()])
+                .join("::"),
+            _ => return Ok(None),
+        };
+        if details.pass_obs_field
+            || details
+                .return_conversion
+                .as_ref()
+                .and_then(|ret| ret.slice_len_function())
+                .is_some()
+        {
+            return Ok(None);
+        }
+        let ret_type = match &details.return_conversion {
+            None => Some("void".to_string()),
+            Some(ret) => ret.original_cpp_type(&self.original_name_map)?,
+        };
+        let param_types: Option<Vec<_>> = details
+            .argument_conversion
+            .iter()
+            .filter(|conv| !conv.is_placement_parameter())
+            .map(|conv| conv.original_cpp_type(&self.original_name_map))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .collect();
+        let (ret_type, param_types) = match (ret_type, param_types) {
+            (Some(ret_type), Some(param_types)) => (ret_type, param_types),
+            _ => return Ok(None),
+        };
+        let fn_ptr_type = format!("{}(*)({})", ret_type, param_types.join(", "));
+        Ok(Some(ExtraCpp {
+            definition: Some(format!(
+                "// Fails to compile unless {} has the signature for which autocxx generated bindings.\nusing {}_signature_check = decltype(static_cast<{}>(&{}));",
+                target, details.wrapper_function_name, fn_ptr_type, target
+            )),
+            ..Default::default()
+        }))
+    }
+
     fn generate_cpp_function_inner(
         &self,
         details: &CppFunction,
@@ -477,6 +905,32 @@ impl<'a> CppCodeGenerator<'a> {
         } else {
             arg_list.join(", ")
         };
+        // A returned array whose length comes from a companion function
+        // which we call in just the same way.
+        let slice_len_call = details
+            .return_conversion
+            .as_ref()
+            .and_then(|ret| ret.slice_len_function())
+            .and_then(|len_function| match &details.payload {
+                CppFunctionBody::FunctionCall(ns, _) => Some(match &receiver {
+                    Some(receiver) => format!("{}.{}()", receiver, len_function),
+                    None => format!(
+                        "{}()",
+                        ns.into_iter()
+                            .cloned()
+                            .chain(std::iter::once(len_function.to_string()))
+                            .join("::")
+                    ),
+                }),
+                CppFunctionBody::StaticMethodCall(ns, ty_id, _) => Some(format!(
+                    "{}()",
+                    ns.into_iter()
+                        .cloned()
+                        .chain([ty_id.to_string(), len_function.to_string()])
+                        .join("::")
+                )),
+                _ => None,
+            });
         let (mut underlying_function_call, field_assignments, need_allocators) = match &details
             .payload
         {
@@ -490,6 +944,11 @@ impl<'a> CppCodeGenerator<'a> {
                     false,
                 )
             }
+            CppFunctionBody::MoveAssignment => (
+                format!("{} = {}", receiver.unwrap(), arg_list),
+                "".to_string(),
+                false,
+            ),
             CppFunctionBody::Destructor(ns, id) => {
                 let ty_id = QualifiedName::new(ns, id.clone());
                 let ty_id = final_ident_using_original_name_map(&ty_id, &self.original_name_map);
@@ -535,12 +994,25 @@ impl<'a> CppCodeGenerator<'a> {
                     true,
                 )
             }
+            CppFunctionBody::FieldGetter(id) => (
+                format!("{}.{}", receiver.unwrap(), id),
+                "".to_string(),
+                false,
+            ),
+            CppFunctionBody::FieldSetter(id) => (
+                format!("{}.{} = {}", receiver.unwrap(), id, arg_list),
+                "".to_string(),
+                false,
+            ),
             CppFunctionBody::FreeUninitialized(ty) => (
                 format!("delete_appropriately<{}>(arg0);", self.namespaced_name(ty)),
                 "".to_string(),
                 true,
             ),
         };
+        if let Some(len_call) = slice_len_call {
+            underlying_function_call = format!("{}, {}", underlying_function_call, len_call);
+        }
         if let Some(ret) = &details.return_conversion {
             let call_itself = match conversion_direction {
                 ConversionDirection::RustCallsCpp => {
@@ -631,6 +1103,7 @@ impl<'a> CppCodeGenerator<'a> {
         subclass: &SubclassName,
         constructors: Vec<&CppFunction>,
         methods: Vec<SubclassFunction>,
+        protected_methods: Vec<String>,
     ) -> Result<(), ConvertError> {
         let holder = subclass.holder();
         self.additional_functions.push(ExtraCpp {
@@ -674,6 +1147,11 @@ impl<'a> CppCodeGenerator<'a> {
                 self.additional_functions.push(super_fn_impl);
             }
         }
+        // Make any protected superclass methods which Rust wants to call
+        // public in the subclass.
+        for method in protected_methods {
+            method_decls.push(format!("using {}::{};", superclass.to_cpp_name(), method));
+        }
         // In future, for each superclass..
         let super_name = superclass.get_final_item();
         method_decls.push(format!(
@@ -103,12 +103,25 @@ pub(crate) fn type_to_cpp(ty: &Type, cpp_name_map: &CppNameMap) -> Result<String
         }
         Type::Reference(typr) => match &*typr.elem {
             Type::Path(typ) if typ.path.is_ident("str") => Ok("rust::Str".into()),
+            Type::Slice(slice) => Ok(format!(
+                "rust::Slice<{}{}>",
+                if typr.mutability.is_some() {
+                    ""
+                } else {
+                    "const "
+                },
+                type_to_cpp(slice.elem.as_ref(), cpp_name_map)?
+            )),
             _ => Ok(format!(
                 "{}{}&",
                 get_mut_string(&typr.mutability),
                 type_to_cpp(typr.elem.as_ref(), cpp_name_map)?
             )),
         },
+        // A const pointer to a pointer, such as `const char* const*`.
+        Type::Ptr(typp) if typp.mutability.is_none() && matches!(*typp.elem, Type::Ptr(_)) => Ok(
+            format!("{} const*", type_to_cpp(typp.elem.as_ref(), cpp_name_map)?),
+        ),
         Type::Ptr(typp) => Ok(format!(
             "{}{}*",
             get_mut_string(&typp.mutability),
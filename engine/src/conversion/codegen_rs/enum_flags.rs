@@ -0,0 +1,101 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use proc_macro2::Span;
+use quote::format_ident;
+use syn::{parse_quote, Expr, Ident, Item, ItemEnum, Type};
+
+/// The name of the newtype which holds combinations of the
+/// variants of a flags enum.
+pub(super) fn flags_type_name(id: &Ident) -> Ident {
+    format_ident!("{}Flags", id)
+}
+
+/// Generate a newtype which can hold any bitwise combination of the
+/// variants of the given enum, together with the bitwise operators
+/// needed to create one. We can't simply implement these operators on the
+/// enum itself, since the result of combining two variants is generally
+/// not itself a valid variant.
+pub(super) fn generate_flags_items(item: &ItemEnum, is_newtype: bool) -> Vec<Item> {
+    let id = &item.ident;
+    let flags_id = flags_type_name(id);
+    let repr = get_repr(item);
+    let doc = format!("A bitwise combination of [`{}`] values.", id);
+    let from_enum: Expr = if is_newtype {
+        parse_quote! { val.0 }
+    } else {
+        parse_quote! { val as #repr }
+    };
+    let mut items: Vec<Item> = vec![
+        parse_quote! {
+            #[doc = #doc]
+            #[repr(transparent)]
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            pub struct #flags_id(pub #repr);
+        },
+        parse_quote! {
+            impl #flags_id {
+                /// The raw bits of this combination of flags.
+                pub fn bits(self) -> #repr {
+                    self.0
+                }
+
+                /// Whether all the flags set in `other` are also set here.
+                pub fn contains(self, other: impl Into<#flags_id>) -> bool {
+                    let other = other.into();
+                    self.0 & other.0 == other.0
+                }
+            }
+        },
+        parse_quote! {
+            impl From<#id> for #flags_id {
+                fn from(val: #id) -> Self {
+                    Self(#from_enum)
+                }
+            }
+        },
+    ];
+    for ty in [id, &flags_id] {
+        for (trait_name, fn_name) in [
+            ("BitOr", "bitor"),
+            ("BitAnd", "bitand"),
+            ("BitXor", "bitxor"),
+        ] {
+            let trait_name = Ident::new(trait_name, Span::call_site());
+            let fn_name = Ident::new(fn_name, Span::call_site());
+            items.push(parse_quote! {
+                impl<T: Into<#flags_id>> ::std::ops::#trait_name<T> for #ty {
+                    type Output = #flags_id;
+                    fn #fn_name(self, rhs: T) -> #flags_id {
+                        let lhs: #flags_id = self.into();
+                        #flags_id(::std::ops::#trait_name::#fn_name(lhs.0, rhs.into().0))
+                    }
+                }
+            });
+        }
+        items.push(parse_quote! {
+            impl ::std::ops::Not for #ty {
+                type Output = #flags_id;
+                fn not(self) -> #flags_id {
+                    let val: #flags_id = self.into();
+                    #flags_id(!val.0)
+                }
+            }
+        });
+    }
+    items
+}
+
+/// bindgen always tells us the underlying type of an enum using `#[repr]`.
+pub(super) fn get_repr(item: &ItemEnum) -> Type {
+    item.attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .find_map(|attr| attr.parse_args::<Type>().ok())
+        .unwrap_or_else(|| parse_quote! { u32 })
+}
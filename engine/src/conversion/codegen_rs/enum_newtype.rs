@@ -0,0 +1,58 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use quote::quote;
+use syn::{parse_quote, Attribute, Expr, Item, ItemEnum, ItemStruct};
+
+use super::enum_flags::get_repr;
+
+/// Represent a C++ enum as a newtype wrapping its underlying integer,
+/// rather than as a Rust enum. C++ is free to produce values which aren't
+/// among the enum's listed values, and holding such a value in a Rust
+/// enum is undefined behavior, whereas this newtype can hold any value.
+/// Returns the newtype itself, and an impl block containing an associated
+/// constant for each listed value.
+pub(super) fn generate_enum_newtype(item: ItemEnum) -> (ItemStruct, Item) {
+    let id = &item.ident;
+    let repr = get_repr(&item);
+    let attrs: Vec<Attribute> = item
+        .attrs
+        .iter()
+        .filter(|attr| !attr.path.is_ident("repr"))
+        .cloned()
+        .collect();
+    let vis = &item.vis;
+    let newtype: ItemStruct = parse_quote! {
+        #(#attrs)*
+        #[repr(transparent)]
+        #vis struct #id(pub #repr);
+    };
+    // bindgen always gives explicit discriminants, but just in case...
+    let mut next_value: Expr = parse_quote! { 0 };
+    let mut consts = Vec::new();
+    for variant in item.variants.iter() {
+        let value = match &variant.discriminant {
+            Some((_, discriminant)) => discriminant.clone(),
+            None => next_value,
+        };
+        next_value = parse_quote! { #value + 1 };
+        let variant_attrs = &variant.attrs;
+        let variant_id = &variant.ident;
+        consts.push(quote! {
+            #(#variant_attrs)*
+            pub const #variant_id: #id = #id(#value);
+        });
+    }
+    let impl_block = parse_quote! {
+        #[allow(non_upper_case_globals)]
+        impl #id {
+            #(#consts)*
+        }
+    };
+    (newtype, impl_block)
+}
@@ -17,7 +17,7 @@ use syn::{
     parse_quote,
     punctuated::Punctuated,
     token::{Comma, Unsafe},
-    Attribute, FnArg, ForeignItem, Ident, ImplItem, Item, ReturnType,
+    Attribute, FnArg, ForeignItem, Ident, ImplItem, Item, Pat, ReturnType, Type,
 };
 
 use super::{
@@ -33,7 +33,9 @@ use crate::{
             MethodKind, RustRenameStrategy, TraitMethodDetails,
         },
         api::{Pointerness, UnsafetyNeeded},
+        doc_attr::FnAttrs,
     },
+    known_types::known_types,
     types::{Namespace, QualifiedName},
 };
 use crate::{
@@ -104,7 +106,23 @@ pub(super) fn gen_function(
     let params = analysis.params;
     let vis = analysis.vis;
     let kind = analysis.kind;
-    let doc_attrs = fun.doc_attrs;
+    // Users call the Rust wrapper if there is one, otherwise the
+    // cxx::bridge function itself.
+    let mut doc_attrs = fun.doc_attrs;
+    if analysis.thread_unsafe {
+        doc_attrs.push(parse_quote! { #[doc = ""] });
+        doc_attrs.push(parse_quote! { #[doc = " This function is not thread-safe."] });
+    }
+    let fn_attrs = FnAttrs::new(doc_attrs);
+    let user_facing_attrs = match kind {
+        FnKind::TraitMethod { .. } => fn_attrs.for_internal_item(),
+        _ => fn_attrs.for_user_facing_item(),
+    };
+    let (bridge_attrs, wrapper_attrs) = if analysis.rust_wrapper_needed {
+        (fn_attrs.for_internal_item(), user_facing_attrs)
+    } else {
+        (user_facing_attrs, fn_attrs.for_internal_item())
+    };
 
     let mut cpp_name_attr = Vec::new();
     let mut impl_entry = None;
@@ -120,11 +138,13 @@ pub(super) fn gen_function(
         rust_name,
         unsafety: &analysis.requires_unsafe,
         always_unsafe_due_to_trait_definition,
-        doc_attrs: &doc_attrs,
+        doc_attrs: &wrapper_attrs,
         non_pod_types,
         ret_type: &ret_type,
         ret_conversion: &ret_conversion,
         reference_wrappers: config.unsafe_policy.requires_cpprefs(),
+        inline_wrappers: config.inline_wrappers(),
+        trace_calls: config.trace_calls(),
     };
     // In rare occasions, we might need to give an explicit lifetime.
     let (lifetime_tokens, params, ret_type) = add_explicit_lifetime_if_necessary(
@@ -153,6 +173,10 @@ pub(super) fn gen_function(
                 impl_entry = Some(fn_generator.generate_method_impl(
                     matches!(method_kind, MethodKind::Constructor { .. }),
                     impl_for,
+                    matches!(
+                        method_kind,
+                        MethodKind::Virtual(_) | MethodKind::PureVirtual(_)
+                    ),
                 ));
             }
             FnKind::TraitMethod { ref details, .. } => {
@@ -212,7 +236,7 @@ pub(super) fn gen_function(
     let extern_c_mod_item = ForeignItem::Fn(parse_quote!(
         #(#namespace_attr)*
         #(#cpp_name_attr)*
-        #(#doc_attrs)*
+        #(#bridge_attrs)*
         #vis #bridge_unsafety fn #cxxbridge_name #lifetime_tokens ( #params ) #ret_type;
     ));
     RsCodegenResult {
@@ -238,9 +262,57 @@ struct FnGenerator<'a> {
     doc_attrs: &'a Vec<Attribute>,
     non_pod_types: &'a HashSet<QualifiedName>,
     reference_wrappers: bool,
+    inline_wrappers: bool,
+    trace_calls: bool,
 }
 
 impl<'a> FnGenerator<'a> {
+    /// Our wrappers are thin shims around the cxx::bridge functions,
+    /// so it's generally worth inlining them.
+    fn inline_attr(&self) -> Option<TokenStream> {
+        if self.inline_wrappers {
+            Some(quote! { #[inline] })
+        } else {
+            None
+        }
+    }
+
+    /// If requested, a statement entering a `tracing` span for the duration
+    /// of the call. Only primitive parameters are recorded, since those are
+    /// the only ones we know to be `Debug`. Like any other local variables,
+    /// this goes inside the closure of an `impl New`, so the span covers
+    /// emplacement rather than just creation of the `New`.
+    fn trace_span(&self) -> Option<MaybeUnsafeStmt> {
+        if !self.trace_calls {
+            return None;
+        }
+        let span_name = self.rust_name;
+        let fields = self.param_details.iter().filter_map(|pd| {
+            if pd.self_type.is_some()
+                || pd.is_placement_return_destination
+                || pd.conversion.rust_work_needed()
+            {
+                return None;
+            }
+            let is_primitive = match pd.conversion.cxxbridge_type() {
+                Type::Path(typ) => {
+                    known_types().is_simple_by_value(&QualifiedName::from_type_path(typ))
+                }
+                _ => false,
+            };
+            match &pd.name {
+                Pat::Ident(pi) if is_primitive => {
+                    let id = &pi.ident;
+                    Some(quote! { #id = ?#id })
+                }
+                _ => None,
+            }
+        });
+        Some(MaybeUnsafeStmt::new(quote! {
+            let _autocxx_trace_span = autocxx::tracing::trace_span!(#span_name #(, #fields)*).entered();
+        }))
+    }
+
     fn common_parts<'b>(
         &'b self,
         avoid_self: bool,
@@ -253,7 +325,7 @@ impl<'a> FnGenerator<'a> {
         TokenStream,
     ) {
         let mut wrapper_params: Punctuated<FnArg, Comma> = Punctuated::new();
-        let mut local_variables = Vec::new();
+        let mut local_variables: Vec<_> = self.trace_span().into_iter().collect();
         let mut arg_list = Vec::new();
         let mut ptr_arg_name = None;
         let mut ret_type: Cow<'a, _> = ret_type
@@ -261,6 +333,7 @@ impl<'a> FnGenerator<'a> {
             .unwrap_or(Cow::Borrowed(self.ret_type));
         let mut any_conversion_requires_unsafe = false;
         let mut variable_counter = 0usize;
+        let mut out_params = Vec::new();
         for pd in self.param_details {
             let wrapper_arg_name = if pd.self_type.is_some() && !avoid_self {
                 parse_quote!(self)
@@ -297,6 +370,32 @@ impl<'a> FnGenerator<'a> {
                     });
                     arg_list.push(pd.name.to_token_stream());
                 }
+                RustParamConversion::OutParam { ty } => {
+                    local_variables.push(MaybeUnsafeStmt::new(quote! {
+                        let mut #wrapper_arg_name = ::std::mem::MaybeUninit::< #ty >::uninit();
+                    }));
+                    arg_list.push(quote! { #wrapper_arg_name.as_mut_ptr() });
+                    out_params.push((quote! { #wrapper_arg_name.assume_init() }, ty));
+                }
+                RustParamConversion::InOutParam { ty } => {
+                    wrapper_params.push(parse_quote!(#wrapper_arg_name: #ty));
+                    local_variables.push(MaybeUnsafeStmt::new(quote! {
+                        let mut #wrapper_arg_name = ::std::mem::MaybeUninit::new(#wrapper_arg_name);
+                    }));
+                    arg_list.push(quote! { #wrapper_arg_name.as_mut_ptr() });
+                    out_params.push((quote! { #wrapper_arg_name.assume_init() }, ty));
+                }
+                RustParamConversion::UniquePtrOutParam { ty } => {
+                    // Start with null in case C++ doesn't assign anything.
+                    local_variables.push(MaybeUnsafeStmt::new(quote! {
+                        let mut #wrapper_arg_name: *mut #ty = ::std::ptr::null_mut();
+                    }));
+                    arg_list.push(quote! { &mut #wrapper_arg_name });
+                    out_params.push((
+                        quote! { cxx::UniquePtr::from_raw(#wrapper_arg_name) },
+                        parse_quote! { cxx::UniquePtr< #ty > },
+                    ));
+                }
             }
         }
         if let Some(parameter_reordering) = &parameter_reordering {
@@ -348,6 +447,11 @@ impl<'a> FnGenerator<'a> {
             }
             _ => (call_body, ret_type),
         };
+        let (call_body, ret_type) = if out_params.is_empty() {
+            (call_body, ret_type)
+        } else {
+            Self::return_out_params(call_body, ret_type, out_params, context_is_unsafe)
+        };
 
         let call_stmts = if let Some(ptr_arg_name) = ptr_arg_name {
             let mut closure_stmts = local_variables;
@@ -376,12 +480,14 @@ impl<'a> FnGenerator<'a> {
         &self,
         avoid_self: bool,
         impl_block_type_name: &QualifiedName,
+        is_virtual: bool,
     ) -> Box<ImplBlockDetails> {
         let (lifetime_tokens, wrapper_params, ret_type, call_body) =
             self.common_parts(avoid_self, &None, None);
         let rust_name = make_ident(self.rust_name);
         let unsafety = self.unsafety.wrapper_token();
         let doc_attrs = self.doc_attrs;
+        let inline_attr = self.inline_attr();
         let receiver_pointerness = self
             .param_details
             .iter()
@@ -417,11 +523,13 @@ impl<'a> FnGenerator<'a> {
         Box::new(ImplBlockDetails {
             item: ImplItem::Method(parse_quote! {
                 #(#doc_attrs)*
+                #inline_attr
                 pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
                     #call_body
                 }
             }),
             ty,
+            is_virtual,
         })
     }
 
@@ -430,11 +538,13 @@ impl<'a> FnGenerator<'a> {
         let (lifetime_tokens, wrapper_params, ret_type, call_body) =
             self.common_parts(details.avoid_self, &details.parameter_reordering, None);
         let doc_attrs = self.doc_attrs;
+        let inline_attr = self.inline_attr();
         let unsafety = self.unsafety.wrapper_token();
         let key = details.trt.clone();
         let method_name = &details.method_name;
         let item = parse_quote! {
             #(#doc_attrs)*
+            #inline_attr
             #unsafety fn #method_name #lifetime_tokens ( #wrapper_params ) #ret_type {
                 #call_body
             }
@@ -453,11 +563,13 @@ impl<'a> FnGenerator<'a> {
             self.common_parts(true, &None, Some(ret_type));
         let rust_name = make_ident(&self.rust_name);
         let doc_attrs = self.doc_attrs;
+        let inline_attr = self.inline_attr();
         let unsafety = self.unsafety.wrapper_token();
         let ty = impl_block_type_name.get_final_ident();
         let ty = parse_quote! { #ty };
         let stuff = quote! {
                 #(#doc_attrs)*
+                #inline_attr
                 pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
                     #call_body
                 }
@@ -465,6 +577,7 @@ impl<'a> FnGenerator<'a> {
         Box::new(ImplBlockDetails {
             item: ImplItem::Method(parse_quote! { #stuff }),
             ty: ImplBlockKey { ty, lifetime: None },
+            is_virtual: false,
         })
     }
 
@@ -474,15 +587,80 @@ impl<'a> FnGenerator<'a> {
             self.common_parts(false, &None, None);
         let rust_name = make_ident(self.rust_name);
         let doc_attrs = self.doc_attrs;
+        let inline_attr = self.inline_attr();
         let unsafety = self.unsafety.wrapper_token();
         Item::Fn(parse_quote! {
             #(#doc_attrs)*
+            #inline_attr
             pub #unsafety fn #rust_name #lifetime_tokens ( #wrapper_params ) #ret_type {
                 #call_body
             }
         })
     }
 
+    /// Bundle up any out-parameters into the return value. If the C++
+    /// function returns `bool`, we assume that indicates whether the
+    /// out-parameters were populated, and return an `Option`; otherwise
+    /// we return a tuple of the original return value and the out-parameters.
+    fn return_out_params(
+        call_body: MaybeUnsafeStmt,
+        ret_type: Cow<'a, ReturnType>,
+        out_params: Vec<(TokenStream, Type)>,
+        context_is_unsafe: bool,
+    ) -> (MaybeUnsafeStmt, Cow<'a, ReturnType>) {
+        let call_expr = maybe_unsafes_to_tokens(vec![call_body], context_is_unsafe);
+        let (values, types): (Vec<_>, Vec<_>) = out_params.into_iter().unzip();
+        let (out_values, out_type) = if values.len() == 1 {
+            let value = &values[0];
+            let ty = &types[0];
+            (quote! { #value }, quote! { #ty })
+        } else {
+            (quote! { ( #(#values),* ) }, quote! { ( #(#types),* ) })
+        };
+        let original_ret = match ret_type.as_ref() {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some(ty.as_ref().clone()),
+        };
+        let is_bool = matches!(&original_ret, Some(Type::Path(tp)) if tp.path.is_ident("bool"));
+        let (in_safe_context, in_unsafe_context, ret_type) = match original_ret {
+            _ if is_bool => (
+                quote! {
+                    if #call_expr { Some(unsafe { #out_values }) } else { None }
+                },
+                quote! {
+                    if #call_expr { Some(#out_values) } else { None }
+                },
+                parse_quote! { -> Option< #out_type > },
+            ),
+            None => (
+                quote! {
+                    #call_expr;
+                    unsafe { #out_values }
+                },
+                quote! {
+                    #call_expr;
+                    #out_values
+                },
+                parse_quote! { -> #out_type },
+            ),
+            Some(ty) => (
+                quote! {
+                    let autocxx_ret = #call_expr;
+                    (autocxx_ret, unsafe { #out_values })
+                },
+                quote! {
+                    let autocxx_ret = #call_expr;
+                    (autocxx_ret, #out_values)
+                },
+                parse_quote! { -> (#ty, #out_type) },
+            ),
+        };
+        (
+            MaybeUnsafeStmt::binary(in_safe_context, in_unsafe_context),
+            Cow::Owned(ret_type),
+        )
+    }
+
     fn reorder_parameters(
         params: Punctuated<FnArg, Comma>,
         parameter_ordering: &[usize],
@@ -0,0 +1,203 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use itertools::Itertools;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Ident, ImplItem, Item, ItemStruct, Type, TypeBareFn};
+
+use crate::{
+    conversion::analysis::function_pointer_type, known_types::known_types, types::QualifiedName,
+};
+
+/// The name of the builder for a struct of C function pointers.
+pub(super) fn function_table_builder_name(id: &Ident) -> Ident {
+    format_ident!("{}Builder", id)
+}
+
+/// C libraries often take a table of function pointers through which they
+/// call back into their user. For a POD struct containing such pointers,
+/// generate a builder with a `set_x` method to fill in each of them from a
+/// Rust `extern "C" fn`. The setters are prefixed so that they can't clash
+/// with `new` or `build`.
+///
+/// A C function pointer carries no state, so closures can only be reached
+/// through a context pointer. If the struct has a single `void*` field,
+/// we take it to be such a pointer, and each function pointer whose first
+/// parameter is a `void*` also gets a `bind_x` method taking a closure.
+/// `build` then points the context at the closures, and returns them along
+/// with the table as an `autocxx::FunctionTable`.
+///
+/// Returns nothing if the struct has no function pointers, or if it has
+/// other fields for which zero isn't a valid starting value.
+pub(super) fn generate_function_table_builder(item: &ItemStruct) -> Vec<Item> {
+    let context_field = find_context_field(item);
+    let mut setters: Vec<ImplItem> = Vec::new();
+    for (idx, f) in item.fields.iter().enumerate() {
+        let field_name = match &f.ident {
+            Some(field_name) => field_name,
+            None => return Vec::new(),
+        };
+        let bare_fn = match function_pointer_type(&f.ty) {
+            Some(bare_fn) => bare_fn,
+            None if is_zeroable(&f.ty) => continue,
+            None => return Vec::new(),
+        };
+        let fn_ty = Type::BareFn(bare_fn.clone());
+        let setter_name = format_ident!("set_{}", field_name);
+        let doc = format!("Sets `{}` to call the given function.", field_name);
+        setters.push(parse_quote! {
+            #[doc = #doc]
+            pub fn #setter_name(mut self, f: #fn_ty) -> Self {
+                self.table.#field_name = Some(f);
+                self
+            }
+        });
+        if let Some(context_field) = context_field {
+            if let Some(binder) = generate_binder(idx, field_name, bare_fn, context_field) {
+                setters.push(binder);
+            }
+        }
+    }
+    if setters.is_empty() {
+        return Vec::new();
+    }
+    let id = &item.ident;
+    let builder_id = function_table_builder_name(id);
+    let doc = format!(
+        "Builds a [`{}`] whose function pointers call Rust functions.",
+        id
+    );
+    let (closures_field, closures_init, build) = match context_field {
+        Some(context_field) => (
+            quote! { closures: autocxx::FunctionTableClosures, },
+            quote! { closures: Default::default(), },
+            quote! {
+                /// Finishes the table, pointing its `context` field at any
+                /// closures given to the `bind_` methods.
+                pub fn build(self) -> autocxx::FunctionTable<#id> {
+                    autocxx::FunctionTable::new(self.table, self.closures, |table, context| {
+                        table.#context_field = context as _;
+                    })
+                }
+            },
+        ),
+        None => (
+            quote! {},
+            quote! {},
+            quote! {
+                pub fn build(self) -> #id {
+                    self.table
+                }
+            },
+        ),
+    };
+    vec![
+        parse_quote! {
+            #[doc = #doc]
+            pub struct #builder_id {
+                table: #id,
+                #closures_field
+            }
+        },
+        parse_quote! {
+            impl #builder_id {
+                /// Starts with every field zero and every function pointer null.
+                pub fn new() -> Self {
+                    Self {
+                        table: unsafe { ::std::mem::zeroed() },
+                        #closures_init
+                    }
+                }
+
+                #(#setters)*
+
+                #build
+            }
+        },
+        parse_quote! {
+            impl Default for #builder_id {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        },
+    ]
+}
+
+/// The struct's one `void*` field, if it has exactly one.
+fn find_context_field(item: &ItemStruct) -> Option<&Ident> {
+    item.fields
+        .iter()
+        .filter(|f| is_void_ptr(&f.ty))
+        .exactly_one()
+        .ok()
+        .and_then(|f| f.ident.as_ref())
+}
+
+/// A `bind_x` method which installs a trampoline into the given function
+/// pointer field, calling a closure found through the context pointer
+/// which C passes as the first parameter. The closures are stored by the
+/// index of their field.
+fn generate_binder(
+    idx: usize,
+    field_name: &Ident,
+    bare_fn: &TypeBareFn,
+    context_field: &Ident,
+) -> Option<ImplItem> {
+    let mut inputs = bare_fn.inputs.iter();
+    let context_ty = &inputs.next().filter(|arg| is_void_ptr(&arg.ty))?.ty;
+    let arg_tys: Vec<_> = inputs.map(|arg| &arg.ty).collect();
+    let arg_names: Vec<_> = (0..arg_tys.len())
+        .map(|i| format_ident!("arg{}", i))
+        .collect();
+    let output = &bare_fn.output;
+    let binder_name = format_ident!("bind_{}", field_name);
+    let doc = format!("Sets `{}` to call the given closure.", field_name);
+    let safety = format!(
+        "C must pass this table's `{}` as the first parameter of `{}`.",
+        context_field, field_name
+    );
+    Some(parse_quote! {
+        #[doc = #doc]
+        ///
+        /// # Safety
+        ///
+        #[doc = #safety]
+        pub unsafe fn #binder_name<F>(mut self, f: F) -> Self
+        where
+            F: Fn(#(#arg_tys),*) #output + Send + Sync + 'static,
+        {
+            type Closure = Box<dyn Fn(#(#arg_tys),*) #output + Send + Sync>;
+            unsafe extern "C" fn trampoline(context: #context_ty, #(#arg_names: #arg_tys),*) #output {
+                autocxx::FunctionTableClosures::get::<Closure>(context as _, #idx)(#(#arg_names),*)
+            }
+            self.closures.set::<Closure>(#idx, Box::new(f));
+            self.table.#field_name = Some(trampoline);
+            self
+        }
+    })
+}
+
+fn is_void_ptr(ty: &Type) -> bool {
+    match ty {
+        Type::Ptr(ptr) => matches!(
+            ptr.elem.as_ref(),
+            Type::Path(typ) if typ.path.segments.last().is_some_and(|seg| seg.ident == "c_void")
+        ),
+        _ => false,
+    }
+}
+
+fn is_zeroable(ty: &Type) -> bool {
+    match ty {
+        Type::Ptr(_) => true,
+        Type::Array(arr) => is_zeroable(&arr.elem),
+        Type::Path(typ) => known_types().is_simple_by_value(&QualifiedName::from_type_path(typ)),
+        _ => false,
+    }
+}
@@ -7,7 +7,7 @@
 // except according to those terms.
 
 use proc_macro2::TokenStream;
-use syn::{Expr, Type, TypePtr};
+use syn::{Expr, Type, TypePtr, TypeReference, TypeSlice, TypeTraitObject};
 
 use crate::{
     conversion::analysis::fun::function_wrapper::{RustConversionType, TypeConversionPolicy},
@@ -29,6 +29,22 @@ pub(super) enum RustParamConversion {
     ReturnValue {
         ty: Type,
     },
+    /// The parameter is allocated by the wrapper function and then
+    /// returned to the caller instead of being passed in.
+    OutParam {
+        ty: Type,
+    },
+    /// Like [`RustParamConversion::OutParam`], but the caller also passes
+    /// in the initial value.
+    InOutParam {
+        ty: Type,
+    },
+    /// Like [`RustParamConversion::OutParam`], but C++ fills in a pointer
+    /// to an object which we then own, so we return a `cxx::UniquePtr`
+    /// to it. `ty` is the pointee.
+    UniquePtrOutParam {
+        ty: Type,
+    },
 }
 
 impl TypeConversionPolicy {
@@ -164,6 +180,30 @@ impl TypeConversionPolicy {
                 };
                 RustParamConversion::ReturnValue { ty }
             }
+            RustConversionType::FromOutParamToReturn => {
+                let ty = match self.cxxbridge_type() {
+                    Type::Ptr(TypePtr { elem, .. }) => *(*elem).clone(),
+                    _ => panic!("Not a ptr"),
+                };
+                RustParamConversion::OutParam { ty }
+            }
+            RustConversionType::FromInOutParamToReturn => {
+                let ty = match self.cxxbridge_type() {
+                    Type::Ptr(TypePtr { elem, .. }) => *(*elem).clone(),
+                    _ => panic!("Not a ptr"),
+                };
+                RustParamConversion::InOutParam { ty }
+            }
+            RustConversionType::FromOutPtrToUniquePtr => {
+                let ty = match self.cxxbridge_type() {
+                    Type::Ptr(TypePtr { elem, .. }) => match elem.as_ref() {
+                        Type::Ptr(TypePtr { elem, .. }) => *(*elem).clone(),
+                        _ => panic!("Not a ptr to a ptr"),
+                    },
+                    _ => panic!("Not a ptr"),
+                };
+                RustParamConversion::UniquePtrOutParam { ty }
+            }
             RustConversionType::FromPointerToReferenceWrapper => {
                 let (is_mut, ty) = match self.cxxbridge_type() {
                     Type::Ptr(TypePtr {
@@ -207,6 +247,204 @@ impl TypeConversionPolicy {
                     conversion_requires_unsafe: false,
                 }
             }
+            RustConversionType::FromArrayRefToPtr(ref len) => {
+                let (is_mut, ty) = match self.cxxbridge_type() {
+                    Type::Ptr(TypePtr {
+                        mutability, elem, ..
+                    }) => (mutability.is_some(), elem.as_ref()),
+                    _ => panic!("Not a pointer"),
+                };
+                let (ty, conversion) = if is_mut {
+                    (
+                        parse_quote! { &mut [#ty; #len] },
+                        quote! { #var.as_mut_ptr() },
+                    )
+                } else {
+                    (parse_quote! { &[#ty; #len] }, quote! { #var.as_ptr() })
+                };
+                RustParamConversion::Param {
+                    ty,
+                    local_variables: Vec::new(),
+                    conversion,
+                    conversion_requires_unsafe: false,
+                }
+            }
+            RustConversionType::FromIntrusivePtrToPtr => {
+                let ty = match self.cxxbridge_type() {
+                    Type::Ptr(TypePtr { elem, .. }) => elem,
+                    _ => panic!("Not a ptr"),
+                };
+                RustParamConversion::Param {
+                    ty: parse_quote! { autocxx::IntrusivePtr< #ty > },
+                    local_variables: Vec::new(),
+                    conversion: quote! { #var .into_raw() },
+                    conversion_requires_unsafe: false,
+                }
+            }
+            RustConversionType::FromContextToPtr => RustParamConversion::Param {
+                ty: parse_quote! { autocxx::Context },
+                local_variables: Vec::new(),
+                conversion: quote! { #var .into_raw() },
+                conversion_requires_unsafe: false,
+            },
+            RustConversionType::FromPtrToOptionalReference => match self.cxxbridge_type() {
+                Type::Ptr(TypePtr {
+                    elem,
+                    mutability: None,
+                    ..
+                }) => RustParamConversion::Param {
+                    ty: parse_quote! { Option<&#elem> },
+                    local_variables: Vec::new(),
+                    conversion: quote! { #var .as_ref() },
+                    conversion_requires_unsafe: true,
+                },
+                Type::Ptr(TypePtr { elem, .. }) => RustParamConversion::Param {
+                    ty: parse_quote! { Option<std::pin::Pin<&mut #elem>> },
+                    local_variables: Vec::new(),
+                    conversion: quote! {
+                        #var .as_mut().map(|r| std::pin::Pin::new_unchecked(r))
+                    },
+                    conversion_requires_unsafe: true,
+                },
+                _ => panic!("Not a ptr"),
+            },
+            RustConversionType::FromPtrToIntrusivePtr => {
+                let ty = match self.cxxbridge_type() {
+                    Type::Ptr(TypePtr { elem, .. }) => elem,
+                    _ => panic!("Not a ptr"),
+                };
+                RustParamConversion::Param {
+                    ty: parse_quote! { autocxx::IntrusivePtr< #ty > },
+                    local_variables: Vec::new(),
+                    conversion: quote! { autocxx::IntrusivePtr::from_raw(#var) },
+                    conversion_requires_unsafe: true,
+                }
+            }
+            RustConversionType::FromCStrSliceToPtrSlice => {
+                let ptrs_var = make_ident(format!("cstr_ptrs{}", counter));
+                *counter += 1;
+                RustParamConversion::Param {
+                    ty: parse_quote! { &[&::std::ffi::CStr] },
+                    // Like argv, the array is terminated by a null pointer
+                    // which isn't included in its length.
+                    local_variables: vec![MaybeUnsafeStmt::new(quote! {
+                        let #ptrs_var: ::std::vec::Vec<*mut ::std::os::raw::c_char> = #var
+                            .iter()
+                            .map(|s| s.as_ptr() as *mut _)
+                            .chain(::std::iter::once(::std::ptr::null_mut()))
+                            .collect();
+                    })],
+                    conversion: quote! { &#ptrs_var[..#var.len()] },
+                    conversion_requires_unsafe: false,
+                }
+            }
+            RustConversionType::FromSliceToVec => match self.cxxbridge_type() {
+                Type::Reference(TypeReference { elem, .. }) => match elem.as_ref() {
+                    Type::Slice(TypeSlice { elem, .. }) => RustParamConversion::Param {
+                        ty: parse_quote! { Vec<#elem> },
+                        local_variables: Vec::new(),
+                        conversion: quote! { #var .to_vec() },
+                        conversion_requires_unsafe: false,
+                    },
+                    _ => panic!("Not a slice"),
+                },
+                _ => panic!("Not a slice"),
+            },
+            RustConversionType::FromBridgeToTuple(ref elems) => {
+                let fields = (0..elems.len()).map(|i| make_ident(format!("f{}", i)));
+                RustParamConversion::Param {
+                    ty: parse_quote! { ( #(#elems,)* ) },
+                    local_variables: Vec::new(),
+                    conversion: quote! {
+                        {
+                            let bridge = #var;
+                            ( #(bridge.#fields,)* )
+                        }
+                    },
+                    conversion_requires_unsafe: false,
+                }
+            }
+            RustConversionType::FromBridgeToVariant(ref enum_type) => RustParamConversion::Param {
+                ty: enum_type.as_ref().clone(),
+                local_variables: Vec::new(),
+                conversion: quote! {
+                    <#enum_type>::from(#var)
+                },
+                conversion_requires_unsafe: false,
+            },
+            RustConversionType::FromBridgeToOption(ref value_type) => RustParamConversion::Param {
+                ty: parse_quote! { Option<#value_type> },
+                local_variables: Vec::new(),
+                conversion: quote! {
+                    {
+                        let bridge = #var;
+                        if bridge.f0 {
+                            Some(bridge.f1)
+                        } else {
+                            None
+                        }
+                    }
+                },
+                conversion_requires_unsafe: false,
+            },
+            RustConversionType::FromIntToIoResult => RustParamConversion::Param {
+                ty: parse_quote! { Result<(), std::io::Error> },
+                local_variables: Vec::new(),
+                conversion: quote! {
+                    {
+                        const SYSTEM_CATEGORY: i64 = 0;
+                        const GENERIC_CATEGORY: i64 = 1;
+                        let error_code = #var;
+                        match (error_code >> 32, error_code as i32) {
+                            (_, 0) => Ok(()),
+                            (SYSTEM_CATEGORY, code) => Err(std::io::Error::from_raw_os_error(code)),
+                            #[cfg(unix)]
+                            (GENERIC_CATEGORY, code) => Err(std::io::Error::from_raw_os_error(code)),
+                            #[cfg(not(unix))]
+                            (GENERIC_CATEGORY, code) => Err(std::io::Error::new(
+                                std::io::ErrorKind::Other,
+                                format!("std::error_code {} in std::generic_category", code),
+                            )),
+                            (_, code) => Err(std::io::Error::new(
+                                std::io::ErrorKind::Other,
+                                format!("std::error_code {} outside std::system_category", code),
+                            )),
+                        }
+                    }
+                },
+                conversion_requires_unsafe: false,
+            },
+            RustConversionType::FromStatusToResult(ref success) => {
+                let ty = self.cxxbridge_type();
+                RustParamConversion::Param {
+                    ty: parse_quote! { Result<(), #ty> },
+                    local_variables: Vec::new(),
+                    conversion: quote! {
+                        match #var {
+                            status if status == <#ty>::#success => Ok(()),
+                            status => Err(status),
+                        }
+                    },
+                    conversion_requires_unsafe: false,
+                }
+            }
+            RustConversionType::FromClosureToStdFunctionAdapter {
+                ref fn_trait,
+                ref adapter,
+            } => {
+                let bounds = match fn_trait.as_ref() {
+                    Type::TraitObject(TypeTraitObject { bounds, .. }) => bounds,
+                    _ => panic!("Not a trait object"),
+                };
+                RustParamConversion::Param {
+                    ty: parse_quote! { impl #bounds + 'static },
+                    local_variables: Vec::new(),
+                    conversion: quote! {
+                        Box::new(#adapter(Box::new(#var)))
+                    },
+                    conversion_requires_unsafe: false,
+                }
+            }
         }
     }
 }
@@ -0,0 +1,198 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use quote::format_ident;
+use syn::{parse_quote, Expr, FnArg, Ident, ImplItem, ImplItemMethod, Item, Pat, Stmt, Type};
+
+use crate::conversion::doc_attr::is_forwardable_attr;
+
+/// The name of the wrapper type which owns a `UniquePtr` to a type
+/// marked with `handle!`.
+pub(super) fn handle_type_name(id: &Ident) -> Ident {
+    format_ident!("{}Handle", id)
+}
+
+/// The method which a handle should call before destroying its object,
+/// as requested using `pre_drop!`.
+pub(super) struct PreDropHook {
+    pub(super) method: Ident,
+    /// Whether we know the method to be an `unsafe` function.
+    pub(super) is_unsafe: bool,
+    /// Whether the user has said it's OK to call the method even so.
+    pub(super) unsafe_allowed: bool,
+}
+
+/// Generate a newtype around a `UniquePtr` to the given type, so that
+/// users don't need to juggle the `UniquePtr` themselves. If a `pre_drop`
+/// method is given, the handle calls it before destroying the object.
+pub(super) fn generate_handle_items(id: &Ident, pre_drop: Option<&PreDropHook>) -> Vec<Item> {
+    let handle_id = handle_type_name(id);
+    let doc = format!("An owning handle to a C++ [`{}`].", id);
+    let mut items = vec![
+        parse_quote! {
+            #[doc = #doc]
+            pub struct #handle_id(pub cxx::UniquePtr<#id>);
+        },
+        parse_quote! {
+            impl From<cxx::UniquePtr<#id>> for #handle_id {
+                fn from(val: cxx::UniquePtr<#id>) -> Self {
+                    Self(val)
+                }
+            }
+        },
+    ];
+    match pre_drop {
+        None => items.push(parse_quote! {
+            impl #handle_id {
+                /// Returns the `UniquePtr` held by this handle.
+                pub fn into_inner(self) -> cxx::UniquePtr<#id> {
+                    self.0
+                }
+            }
+        }),
+        Some(PreDropHook {
+            method,
+            is_unsafe: true,
+            unsafe_allowed: false,
+        }) => {
+            // Dropping can't be unsafe, so nothing would stand between the
+            // user and whatever preconditions the method has.
+            let msg = format!(
+                "pre_drop! names {}::{}, which is unsafe to call. If it's always OK to call it as {} is dropped, say so using pre_drop!(unsafe ...).",
+                id, method, id
+            );
+            items.push(parse_quote! {
+                ::std::compile_error!(#msg);
+            });
+        }
+        Some(PreDropHook {
+            method,
+            unsafe_allowed,
+            ..
+        }) => {
+            let hook_doc = format!(
+                "Returns the `UniquePtr` held by this handle. `{}` will not be called.",
+                method
+            );
+            items.push(parse_quote! {
+                impl #handle_id {
+                    #[doc = #hook_doc]
+                    pub fn into_inner(mut self) -> cxx::UniquePtr<#id> {
+                        ::std::mem::replace(&mut self.0, cxx::UniquePtr::null())
+                    }
+                }
+            });
+            let call: Stmt = if *unsafe_allowed {
+                parse_quote! {
+                    #[allow(unused_unsafe)]
+                    unsafe {
+                        obj.#method();
+                    }
+                }
+            } else {
+                parse_quote! {
+                    obj.#method();
+                }
+            };
+            items.push(parse_quote! {
+                impl Drop for #handle_id {
+                    fn drop(&mut self) {
+                        if let Some(obj) = self.0.as_mut() {
+                            #call
+                        }
+                    }
+                }
+            });
+        }
+    }
+    items
+}
+
+/// Generate an `impl` block for the handle to the given type which
+/// forwards to each of the given methods. Methods which take `&self`
+/// are forwarded as-is, whilst methods which take `Pin<&mut Self>` are
+/// exposed as taking `&mut self`. Methods without a receiver, such as
+/// constructors, are not forwarded.
+pub(super) fn generate_handle_forwarders(id: &Ident, methods: &[&ImplItem]) -> Option<Item> {
+    let forwarders: Vec<ImplItem> = methods
+        .iter()
+        .filter_map(|method| match method {
+            ImplItem::Method(method) => generate_forwarder(method),
+            _ => None,
+        })
+        .collect();
+    if forwarders.is_empty() {
+        return None;
+    }
+    let handle_id = handle_type_name(id);
+    Some(parse_quote! {
+        impl #handle_id {
+            #(#forwarders)*
+        }
+    })
+}
+
+fn generate_forwarder(method: &ImplItemMethod) -> Option<ImplItem> {
+    let mut sig = method.sig.clone();
+    let receiver = match sig.inputs.first()? {
+        FnArg::Typed(pt) if matches!(&*pt.pat, Pat::Ident(pi) if pi.ident == "self") => &pt.ty,
+        _ => return None,
+    };
+    let (new_receiver, target): (FnArg, Expr) = match receiver.as_ref() {
+        Type::Reference(r) if r.mutability.is_none() => {
+            let lt = &r.lifetime;
+            (parse_quote! { & #lt self }, parse_quote! { self.0 })
+        }
+        Type::Path(tp) => {
+            let lt = match pinned_reference(tp) {
+                Some(r) if r.mutability.is_some() => &r.lifetime,
+                _ => return None,
+            };
+            (
+                parse_quote! { & #lt mut self },
+                parse_quote! { self.0.pin_mut() },
+            )
+        }
+        _ => return None,
+    };
+    let mut args = Vec::new();
+    for arg in sig.inputs.iter().skip(1) {
+        match arg {
+            FnArg::Typed(pt) => match &*pt.pat {
+                Pat::Ident(pi) => args.push(pi.ident.clone()),
+                _ => return None,
+            },
+            FnArg::Receiver(_) => return None,
+        }
+    }
+    *sig.inputs.first_mut().unwrap() = new_receiver;
+    let name = &sig.ident;
+    let attrs = method.attrs.iter().filter(|attr| is_forwardable_attr(attr));
+    Some(parse_quote! {
+        #(#attrs)*
+        #[allow(deprecated)]
+        pub #sig {
+            #target.#name(#(#args),*)
+        }
+    })
+}
+
+/// If this is a `Pin<&T>` or `Pin<&mut T>`, the reference within.
+pub(super) fn pinned_reference(tp: &syn::TypePath) -> Option<&syn::TypeReference> {
+    let seg = tp.path.segments.last()?;
+    if seg.ident != "Pin" {
+        return None;
+    }
+    match &seg.arguments {
+        syn::PathArguments::AngleBracketed(ab) => match ab.args.first()? {
+            syn::GenericArgument::Type(Type::Reference(r)) => Some(r),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,29 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use syn::{parse_quote, Ident, Item};
+
+/// Generate `PartialEq` and `Eq` for a type marked with `identity_eq!`.
+/// Non-POD objects never move once Rust can see them, so the address
+/// identifies the C++ object.
+pub(super) fn generate_identity_eq_items(id: &Ident) -> Vec<Item> {
+    vec![
+        parse_quote! {
+            impl PartialEq for #id {
+                /// Whether these are the same C++ object. This compares
+                /// addresses, not values.
+                fn eq(&self, other: &Self) -> bool {
+                    ::std::ptr::eq(self, other)
+                }
+            }
+        },
+        parse_quote! {
+            impl Eq for #id {}
+        },
+    ]
+}
@@ -0,0 +1,37 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use proc_macro2::Literal;
+use syn::{parse_quote, Ident, Item};
+
+use crate::conversion::api::Layout;
+
+/// Generate compile-time assertions that the Rust definition of a POD
+/// type has the size and alignment which bindgen found for the C++ type.
+/// Any disagreement would otherwise result in silent memory corruption
+/// when values are passed between the two languages.
+pub(super) fn generate_layout_assertions(id: &Ident, layout: &Layout) -> Vec<Item> {
+    let size = Literal::usize_unsuffixed(layout.size);
+    let align = Literal::usize_unsuffixed(layout.align);
+    let size_msg = format!(
+        "Rust and C++ disagree about the size of {}: C++ says {} bytes",
+        id, layout.size
+    );
+    let align_msg = format!(
+        "Rust and C++ disagree about the alignment of {}: C++ says {} bytes",
+        id, layout.align
+    );
+    vec![
+        parse_quote! {
+            const _: () = assert!(::std::mem::size_of::<#id>() == #size, #size_msg);
+        },
+        parse_quote! {
+            const _: () = assert!(::std::mem::align_of::<#id>() == #align, #align_msg);
+        },
+    ]
+}
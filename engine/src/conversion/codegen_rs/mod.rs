@@ -6,50 +6,71 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+mod enum_flags;
+mod enum_newtype;
 mod fun_codegen;
+mod function_table;
 mod function_wrapper_rs;
+mod handle;
+mod identity_eq;
 mod impl_item_creator;
+mod layout_assertions;
 mod lifetime;
 mod namespace_organizer;
 mod non_pod_struct;
 pub(crate) mod unqualify;
+mod virtual_trait;
 
 use indexmap::map::IndexMap as HashMap;
 use indexmap::set::IndexSet as HashSet;
 
-use autocxx_parser::{ExternCppType, IncludeCppConfig, RustFun, UnsafePolicy};
+use autocxx_parser::{
+    ConditionalPod, EnumRepresentation, ExternCppType, IncludeCppConfig, RustFun, UnsafePolicy,
+};
 
 use itertools::Itertools;
 use proc_macro2::{Span, TokenStream};
 use syn::{
     parse_quote, punctuated::Punctuated, token::Comma, Attribute, Expr, FnArg, ForeignItem,
-    ForeignItemFn, Ident, ImplItem, Item, ItemForeignMod, ItemMod, Lifetime, TraitItem, Type,
-    TypePath,
+    ForeignItemFn, Ident, ImplItem, Item, ItemForeignMod, ItemMod, Lifetime, ReturnType, TraitItem,
+    Type, TypePath,
 };
 
 use crate::{
+    bridge_mod_name,
     conversion::{
         codegen_rs::{
             non_pod_struct::{make_non_pod, new_non_pod_struct},
-            unqualify::{unqualify_params, unqualify_ret_type},
+            unqualify::{unqualify_params, unqualify_ret_type, unqualify_type},
         },
-        doc_attr::get_doc_attrs,
+        doc_attr::{add_deprecated_attr, allow_deprecated_uses, get_doc_attrs},
     },
+    known_types::{known_types, CxxGenericType},
     types::{make_ident, Namespace, QualifiedName},
+    SHARED_TYPES_BRIDGE_MOD,
 };
 use impl_item_creator::create_impl_items;
 
 use self::{
+    enum_flags::{flags_type_name, generate_flags_items},
+    enum_newtype::generate_enum_newtype,
     fun_codegen::gen_function,
+    function_table::{function_table_builder_name, generate_function_table_builder},
+    handle::{generate_handle_forwarders, generate_handle_items, handle_type_name, PreDropHook},
+    identity_eq::generate_identity_eq_items,
+    layout_assertions::generate_layout_assertions,
     namespace_organizer::{HasNs, NamespaceEntries},
+    virtual_trait::{generate_virtual_trait, virtual_trait_name},
 };
 
 use super::{
     analysis::{
-        fun::{FnPhase, PodAndDepAnalysis, ReceiverMutability},
+        deps::HasDependencies,
+        fun::{FnAnalysis, FnKind, FnPhase, MethodKind, PodAndDepAnalysis, ReceiverMutability},
+        generators::find_generator_items,
         pod::PodAnalysis,
     },
-    api::{AnalysisPhase, Api, SubclassName, TypeKind, TypedefKind},
+    api::{AnalysisPhase, Api, ApiName, SubclassName, TypeKind, TypedefKind, UnsafetyNeeded},
     convert_error::ErrorContextType,
 };
 use super::{
@@ -59,9 +80,19 @@ use super::{
         namespaced_name_using_original_name_map, original_name_map_from_apis, CppNameMap,
     },
 };
-use super::{convert_error::ErrorContext, ConvertError};
+use super::{
+    convert_error::ErrorContext,
+    utilities::{
+        bool_conversion_fn_name, comparison_fn_names, index_fn_names, intrusive_ptr_fn_names,
+        std_function_call_name, string_table_accessor_name,
+    },
+    ConvertError,
+};
 use quote::quote;
 
+/// Items for `#[cxx::bridge]` mods, grouped by namespace.
+type BridgeItemsByNamespace = HashMap<String, Vec<Item>>;
+
 #[derive(Clone, Hash, PartialEq, Eq)]
 struct ImplBlockKey {
     ty: Type,
@@ -72,6 +103,8 @@ struct ImplBlockKey {
 struct ImplBlockDetails {
     item: ImplItem,
     ty: ImplBlockKey,
+    /// Whether this is a C++ virtual method.
+    is_virtual: bool,
 }
 
 struct TraitImplBlockDetails {
@@ -133,6 +166,19 @@ fn get_string_items() -> Vec<Item> {
                 }
             }
         }),
+        Item::Fn(parse_quote! {
+            /// Copies a C++ string into a Rust `String`, replacing any
+            /// invalid UTF-8 with U+FFFD.
+            pub fn string_to_rust(s: &cxx::CxxString) -> String {
+                s.to_string_lossy().into_owned()
+            }
+        }),
+        Item::Fn(parse_quote! {
+            /// Copies a Rust string into a new C++ string.
+            pub fn string_from_rust(s: &str) -> cxx::UniquePtr<cxx::CxxString> {
+                make_string(s)
+            }
+        }),
     ]
     .to_vec()
 }
@@ -232,10 +278,19 @@ pub(crate) struct RsCodeGenerator<'a> {
     original_name_map: CppNameMap,
     config: &'a IncludeCppConfig,
     header_name: Option<String>,
+    thread_unsafe_types: HashSet<QualifiedName>,
+    generator_items: HashMap<QualifiedName, (Ident, Type)>,
+    pre_drop_hooks: HashMap<QualifiedName, PreDropHook>,
+    api_names: HashSet<QualifiedName>,
 }
 
 impl<'a> RsCodeGenerator<'a> {
     /// Generate code for a set of APIs that was discovered during parsing.
+    /// Returns the items for the output mod, and separately any items for
+    /// the `#[cxx::bridge]` mod if we've been asked not to wrap them in one,
+    /// or those items grouped by namespace if we've been asked to split them.
+    /// In the latter case, also returns all the bridge items together, from
+    /// which to generate the C++.
     pub(crate) fn generate_rs_code(
         all_apis: ApiVec<FnPhase>,
         unsafe_policy: &'a UnsafePolicy,
@@ -243,7 +298,7 @@ impl<'a> RsCodeGenerator<'a> {
         bindgen_mod: ItemMod,
         config: &'a IncludeCppConfig,
         header_name: Option<String>,
-    ) -> Vec<Item> {
+    ) -> (Vec<Item>, Vec<Item>, BridgeItemsByNamespace, Vec<Item>) {
         let c = Self {
             unsafe_policy,
             include_list,
@@ -251,11 +306,18 @@ impl<'a> RsCodeGenerator<'a> {
             original_name_map: original_name_map_from_apis(&all_apis),
             config,
             header_name,
+            thread_unsafe_types: find_thread_unsafe_types(&all_apis),
+            generator_items: find_generator_items(&all_apis, config),
+            pre_drop_hooks: find_pre_drop_hooks(&all_apis, config),
+            api_names: all_apis.iter().map(|api| api.name().clone()).collect(),
         };
         c.rs_codegen(all_apis)
     }
 
-    fn rs_codegen(mut self, all_apis: ApiVec<FnPhase>) -> Vec<Item> {
+    fn rs_codegen(
+        mut self,
+        all_apis: ApiVec<FnPhase>,
+    ) -> (Vec<Item>, Vec<Item>, BridgeItemsByNamespace, Vec<Item>) {
         // ... and now let's start to generate the output code.
         // First off, when we generate structs we may need to add some methods
         // if they're superclasses.
@@ -263,6 +325,16 @@ impl<'a> RsCodeGenerator<'a> {
         let subclasses_with_a_single_trivial_constructor =
             find_trivially_constructed_subclasses(&all_apis);
         let non_pod_types = find_non_pod_types(&all_apis);
+        // If type declarations are to be shared between bridges, we need to
+        // know which types each bridge refers to before the APIs are consumed.
+        let (cxx_types, type_references_by_group) = if self.config.shared_type_declarations() {
+            (
+                find_cxx_types(&all_apis),
+                find_type_references_by_group(&all_apis),
+            )
+        } else {
+            (HashSet::new(), HashMap::new())
+        };
         // Now let's generate the Rust code.
         let (rs_codegen_results_and_namespaces, additional_cpp_needs): (Vec<_>, Vec<_>) = all_apis
             .into_iter()
@@ -282,13 +354,222 @@ impl<'a> RsCodeGenerator<'a> {
         // which is the final API exposed as 'ffi'.
         let mut use_statements =
             Self::generate_final_use_statements(&rs_codegen_results_and_namespaces);
+        // Optionally, a prelude mod re-exporting some of those.
+        let prelude = self.generate_prelude();
         // And work out what we need for the bindgen mod.
         let bindgen_root_items =
             self.generate_final_bindgen_mods(&rs_codegen_results_and_namespaces);
         // Both of the above ('use' hierarchy and bindgen mod) are organized into
-        // sub-mods by namespace. From here on, things are flat.
-        let (_, rs_codegen_results): (Vec<_>, Vec<_>) =
-            rs_codegen_results_and_namespaces.into_iter().unzip();
+        // sub-mods by namespace. From here on, things are flat, except that
+        // the bridge items may be grouped by namespace if we've been asked to
+        // split them up.
+        // Custom deleters don't correspond to any API, but still need our
+        // generated header.
+        let has_additional_cpp_needs = additional_cpp_needs.into_iter().any(std::convert::identity)
+            || !self.config.get_custom_deleters().is_empty();
+        let mut rs_codegen_results_by_namespace: HashMap<String, Vec<RsCodegenResult>> =
+            HashMap::new();
+        // Any hand-written bridge items go in the root group.
+        if !self.config.split_by_namespace() || !self.config.get_bridge_items().is_empty() {
+            rs_codegen_results_by_namespace.insert(String::new(), Vec::new());
+        }
+        let mut shared_types = HashSet::new();
+        for (name, rs_codegen_result) in rs_codegen_results_and_namespaces {
+            let group = if cxx_types.contains(&name) {
+                // Generic types, for instance, aren't declared to cxx at all.
+                if !rs_codegen_result.extern_c_mod_items.is_empty() {
+                    shared_types.insert(name.clone());
+                }
+                SHARED_TYPES_BRIDGE_MOD.to_string()
+            } else if self.config.split_by_namespace() {
+                name.get_namespace().to_string()
+            } else {
+                String::new()
+            };
+            rs_codegen_results_by_namespace
+                .entry(group)
+                .or_default()
+                .push(rs_codegen_result);
+        }
+        // Other bridges refer to the shared declarations rather than
+        // declaring the types again.
+        let mut shared_type_aliases: HashMap<String, Vec<ForeignItem>> = type_references_by_group
+            .into_iter()
+            .map(|(group, type_references)| {
+                let aliases = type_references
+                    .iter()
+                    .filter(|tn| shared_types.contains(*tn))
+                    .map(|tn| {
+                        self.generate_cxxbridge_type(tn, CxxTypeTarget::SharedBridge, Vec::new())
+                    })
+                    .collect();
+                (group, aliases)
+            })
+            .collect();
+        let mut all_items = Vec::new();
+        let mut bridge_items_by_namespace = HashMap::new();
+        // cxx generates C++ from all the bridges together, and the aliases
+        // would clash with the shared declarations, so they're left out.
+        let mut merged_bridge_items = Vec::new();
+        for (group, rs_codegen_results) in rs_codegen_results_by_namespace {
+            let hand_written_bridge_items = if group.is_empty() {
+                self.config.get_bridge_items()
+            } else {
+                &[]
+            };
+            // Hand-written items are re-exported just like the ones we generate.
+            for item in hand_written_bridge_items {
+                use_statements.extend(Self::hand_written_bridge_item_idents(item).map(|id| {
+                    Self::generate_cxx_use_stmt(&QualifiedName::new(&Namespace::new(), id), None)
+                }));
+            }
+            let (mut global_items, mut bridge_items) = self.assemble_bridge_items(
+                rs_codegen_results,
+                hand_written_bridge_items,
+                has_additional_cpp_needs,
+            );
+            if self.config.split_by_namespace() {
+                merged_bridge_items.extend(bridge_items.iter().cloned());
+            }
+            if let Some(mut aliases) = shared_type_aliases.remove(&group) {
+                if self.config.explicit_cxx_names() {
+                    aliases.iter_mut().for_each(add_explicit_cxx_name);
+                }
+                bridge_items.push(Self::make_foreign_mod_unsafe(parse_quote! {
+                    extern "C++" {
+                        #(#aliases)*
+                    }
+                }));
+            }
+            all_items.append(&mut global_items);
+            bridge_items_by_namespace.insert(group, bridge_items);
+        }
+        if self.config.unsafe_policy.requires_cpprefs() {
+            all_items.append(&mut get_cppref_items())
+        }
+        // The extensive use of parse_quote here could end up
+        // being a performance bottleneck. If so, we might want
+        // to set the 'contents' field of the ItemMod
+        // structures directly.
+        if !bindgen_root_items.is_empty() {
+            self.bindgen_mod.vis = parse_quote! {};
+            self.bindgen_mod.content.as_mut().unwrap().1 = vec![Item::Mod(parse_quote! {
+                pub(super) mod root {
+                    #(#bindgen_root_items)*
+                }
+            })];
+            all_items.push(Item::Mod(self.bindgen_mod));
+        }
+        let (unwrapped_bridge_items, bridge_items_by_namespace) =
+            if self.config.split_by_namespace() {
+                if self.config.emit_bridge_wrapper() {
+                    all_items.extend(Self::generate_split_bridge_mods(
+                        bridge_items_by_namespace,
+                        &shared_types,
+                    ));
+                    (Vec::new(), HashMap::new())
+                } else {
+                    (Vec::new(), bridge_items_by_namespace)
+                }
+            } else {
+                // There's only a single group.
+                let bridge_items: Vec<Item> =
+                    bridge_items_by_namespace.into_values().flatten().collect();
+                if self.config.emit_bridge_wrapper() {
+                    all_items.push(Item::Mod(parse_quote! {
+                        #[cxx::bridge]
+                        mod cxxbridge {
+                            #(#bridge_items)*
+                        }
+                    }));
+                    (Vec::new(), HashMap::new())
+                } else {
+                    (bridge_items, HashMap::new())
+                }
+            };
+
+        all_items.push(Item::Use(parse_quote! {
+            #[allow(unused_imports)]
+            use bindgen::root;
+        }));
+        all_items.append(&mut use_statements);
+        all_items.extend(prelude);
+        allow_deprecated_uses(&mut all_items);
+        (
+            all_items,
+            unwrapped_bridge_items,
+            bridge_items_by_namespace,
+            merged_bridge_items,
+        )
+    }
+
+    /// Generates a `prelude` mod re-exporting the items listed in
+    /// `prelude!`, if any.
+    fn generate_prelude(&self) -> Option<Item> {
+        let prelude_items = self.config.get_prelude_items();
+        if prelude_items.is_empty() {
+            return None;
+        }
+        let uses = prelude_items.iter().map(|item| {
+            let segs = QualifiedName::new_from_cpp_name(item)
+                .segment_iter()
+                .map(make_ident)
+                .collect::<Vec<_>>();
+            quote! { pub use super::#(#segs)::*; }
+        });
+        Some(parse_quote! {
+            pub mod prelude {
+                #(#uses)*
+            }
+        })
+    }
+
+    /// Wraps each group of bridge items in its own `#[cxx::bridge]` mod,
+    /// and adds a `cxxbridge` mod re-exporting all of them, so that the
+    /// paths we generate elsewhere work just as if there were one bridge.
+    fn generate_split_bridge_mods(
+        bridge_items_by_namespace: BridgeItemsByNamespace,
+        shared_types: &HashSet<QualifiedName>,
+    ) -> impl Iterator<Item = Item> {
+        let mod_names: Vec<_> = bridge_items_by_namespace
+            .keys()
+            .map(|group| make_ident(bridge_mod_name(group)))
+            .collect();
+        // Each bridge which uses a shared type declares it again, so the
+        // glob imports would be ambiguous; an explicit import of the
+        // original declaration takes precedence over them.
+        let shared_mod = make_ident(SHARED_TYPES_BRIDGE_MOD);
+        let shared_type_ids = shared_types.iter().map(|tn| tn.get_final_ident());
+        let reexports: Item = parse_quote! {
+            #[allow(unused_imports)]
+            mod cxxbridge {
+                #(pub use super::#mod_names::*;)*
+                #(pub use super::#shared_mod::#shared_type_ids;)*
+            }
+        };
+        bridge_items_by_namespace
+            .into_iter()
+            .map(|(group, bridge_items)| {
+                let mod_name = make_ident(bridge_mod_name(&group));
+                Item::Mod(parse_quote! {
+                    #[cxx::bridge]
+                    mod #mod_name {
+                        #(#bridge_items)*
+                    }
+                })
+            })
+            .chain(std::iter::once(reexports))
+    }
+
+    /// Gathers up the items for a single `#[cxx::bridge]` mod from the
+    /// given codegen results. Returns the items which belong outside
+    /// the bridge mod, followed by those which belong inside it.
+    fn assemble_bridge_items(
+        &self,
+        rs_codegen_results: Vec<RsCodegenResult>,
+        hand_written_bridge_items: &[Item],
+        has_additional_cpp_needs: bool,
+    ) -> (Vec<Item>, Vec<Item>) {
         let (extern_c_mod_items, extern_rust_mod_items, all_items, bridge_items): (
             Vec<_>,
             Vec<_>,
@@ -307,18 +588,21 @@ impl<'a> RsCodeGenerator<'a> {
             .multiunzip();
         // Items for the [cxx::bridge] mod...
         let mut bridge_items: Vec<Item> = bridge_items.into_iter().flatten().collect();
+        // ... plus any which the user wrote by hand.
+        bridge_items.extend(hand_written_bridge_items.iter().cloned());
         // Things to include in the "extern "C"" mod passed within the cxx::bridge
         let mut extern_c_mod_items: Vec<ForeignItem> =
             extern_c_mod_items.into_iter().flatten().collect();
+        if self.config.explicit_cxx_names() {
+            extern_c_mod_items
+                .iter_mut()
+                .for_each(add_explicit_cxx_name);
+        }
         // The same for extern "Rust"
         let mut extern_rust_mod_items = extern_rust_mod_items.into_iter().flatten().collect();
         // And a list of global items to include at the top level.
-        let mut all_items: Vec<Item> = all_items.into_iter().flatten().collect();
-        if self.config.unsafe_policy.requires_cpprefs() {
-            all_items.append(&mut get_cppref_items())
-        }
+        let all_items: Vec<Item> = all_items.into_iter().flatten().collect();
         // And finally any C++ we need to generate. And by "we" I mean autocxx not cxx.
-        let has_additional_cpp_needs = additional_cpp_needs.into_iter().any(std::convert::identity);
         extern_c_mod_items.extend(self.build_include_foreign_items(has_additional_cpp_needs));
         // We will always create an extern "C" mod even if bindgen
         // didn't generate one, e.g. because it only generated types.
@@ -333,32 +617,7 @@ impl<'a> RsCodeGenerator<'a> {
         );
         extern_rust_mod.items.append(&mut extern_rust_mod_items);
         bridge_items.push(Item::ForeignMod(extern_rust_mod));
-        // The extensive use of parse_quote here could end up
-        // being a performance bottleneck. If so, we might want
-        // to set the 'contents' field of the ItemMod
-        // structures directly.
-        if !bindgen_root_items.is_empty() {
-            self.bindgen_mod.vis = parse_quote! {};
-            self.bindgen_mod.content.as_mut().unwrap().1 = vec![Item::Mod(parse_quote! {
-                pub(super) mod root {
-                    #(#bindgen_root_items)*
-                }
-            })];
-            all_items.push(Item::Mod(self.bindgen_mod));
-        }
-        all_items.push(Item::Mod(parse_quote! {
-            #[cxx::bridge]
-            mod cxxbridge {
-                #(#bridge_items)*
-            }
-        }));
-
-        all_items.push(Item::Use(parse_quote! {
-            #[allow(unused_imports)]
-            use bindgen::root;
-        }));
-        all_items.append(&mut use_statements);
-        all_items
+        (all_items, bridge_items)
     }
 
     fn accumulate_superclass_methods(
@@ -391,12 +650,20 @@ impl<'a> RsCodeGenerator<'a> {
     }
 
     fn build_include_foreign_items(&self, has_additional_cpp_needs: bool) -> Vec<ForeignItem> {
-        let extra_inclusion = if has_additional_cpp_needs {
+        let has_extra_header_content = self.config.has_extra_header_content();
+        let extra_inclusion = if has_additional_cpp_needs || has_extra_header_content {
             Some(self.header_name.clone().unwrap())
         } else {
             None
         };
-        let chained = self.include_list.iter().chain(extra_inclusion.iter());
+        // Our generated header contains the preamble followed by the
+        // user's headers and then any instantiation shims, so if there's
+        // any of those it must come first.
+        let chained: Box<dyn Iterator<Item = &String>> = if has_extra_header_content {
+            Box::new(extra_inclusion.iter().chain(self.include_list.iter()))
+        } else {
+            Box::new(self.include_list.iter().chain(extra_inclusion.iter()))
+        };
         chained
             .map(|inc| {
                 ForeignItem::Macro(parse_quote! {
@@ -489,6 +756,7 @@ impl<'a> RsCodeGenerator<'a> {
         ns: &Namespace,
     ) {
         let mut impl_entries_by_type: HashMap<_, Vec<_>> = HashMap::new();
+        let mut virtual_methods_by_type: HashMap<_, Vec<_>> = HashMap::new();
         let mut trait_impl_entries_by_trait_and_ty: HashMap<_, Vec<_>> = HashMap::new();
         for item in ns_entries.entries() {
             output_items.extend(item.1.bindgen_mod_items.iter().cloned());
@@ -497,6 +765,12 @@ impl<'a> RsCodeGenerator<'a> {
                     .entry(impl_entry.ty.clone())
                     .or_default()
                     .push(&impl_entry.item);
+                if impl_entry.is_virtual {
+                    virtual_methods_by_type
+                        .entry(impl_entry.ty.clone())
+                        .or_default()
+                        .push(&impl_entry.item);
+                }
             }
             if let Some(trait_impl_entry) = &item.1.trait_impl_entry {
                 trait_impl_entries_by_trait_and_ty
@@ -505,7 +779,30 @@ impl<'a> RsCodeGenerator<'a> {
                     .push(&trait_impl_entry.item);
             }
         }
+        // Types marked with virtual_trait! get a trait, even if it turns
+        // out to be empty, because we've already arranged to export it.
+        for (name, _) in ns_entries.entries() {
+            if self.config.has_virtual_trait(&name.to_cpp_name()) {
+                let id = name.get_final_ident();
+                let key = ImplBlockKey {
+                    ty: parse_quote! { #id },
+                    lifetime: None,
+                };
+                let methods = virtual_methods_by_type.remove(&key).unwrap_or_default();
+                output_items.extend(generate_virtual_trait(&id, &methods));
+            }
+        }
         for (ty, entries) in impl_entries_by_type.into_iter() {
+            if ty.lifetime.is_none() {
+                if let Type::Path(typ) = &ty.ty {
+                    if let Some(id) = typ.path.get_ident() {
+                        let name = QualifiedName::new(ns, id.clone());
+                        if self.config.is_handle_type(&name.to_cpp_name()) {
+                            output_items.extend(generate_handle_forwarders(id, &entries));
+                        }
+                    }
+                }
+            }
             let lt = ty.lifetime.map(|lt| quote! { < #lt > });
             let ty = ty.ty;
             output_items.push(Item::Impl(parse_quote! {
@@ -595,16 +892,228 @@ impl<'a> RsCodeGenerator<'a> {
                 materializations: vec![Use::UsedFromBindgen],
                 ..Default::default()
             },
-            Api::Typedef { analysis, .. } => RsCodegenResult {
-                bindgen_mod_items: vec![match analysis.kind {
-                    TypedefKind::Type(type_item) => Item::Type(type_item),
-                    TypedefKind::Use(use_item, _) => Item::Use(use_item),
-                }],
-                materializations: vec![Use::UsedFromBindgen],
+            Api::StringTable { len, .. } => {
+                let accessor = make_ident(string_table_accessor_name(self.config, &name));
+                RsCodegenResult {
+                    extern_c_mod_items: vec![ForeignItem::Fn(parse_quote!(
+                        fn #accessor(idx: usize) -> *const ::std::os::raw::c_char;
+                    ))],
+                    bindgen_mod_items: vec![Item::Fn(parse_quote! {
+                        /// Returns the entries of this C++ string table, stopping
+                        /// at the first null entry. The table is a C++ static
+                        /// array of string literals, so the strings are `'static`.
+                        pub fn #id() -> ::std::vec::Vec<&'static ::std::ffi::CStr> {
+                            (0..#len)
+                                .map(|idx| cxxbridge::#accessor(idx))
+                                .take_while(|ptr| !ptr.is_null())
+                                .map(|ptr| unsafe { ::std::ffi::CStr::from_ptr(ptr) })
+                                .collect()
+                        }
+                    })],
+                    materializations: vec![Use::SpecificNameFromBindgen(id)],
+                    ..Default::default()
+                }
+            }
+            Api::StdFunctionAdapter { params, ret, .. } => {
+                Self::generate_std_function_adapter(id, params, ret)
+            }
+            Api::StdTupleBridge { name, elems } => self.generate_std_tuple_bridge(&name, elems),
+            Api::StdVariantBridge {
+                name,
+                enum_name,
+                alternatives,
+            } => self.generate_std_variant_bridge(&name, &enum_name, alternatives),
+            Api::OpaqueHandle { .. } => RsCodegenResult {
+                extern_c_mod_items: vec![
+                    ForeignItem::Verbatim(quote! {
+                        /// Owns an object which C++ handed over as a
+                        /// `std::unique_ptr<void>`, and destroys it using
+                        /// that `std::unique_ptr`'s deleter when dropped.
+                        type #id;
+                    }),
+                    ForeignItem::Fn(parse_quote! {
+                        /// Returns a pointer to the object owned by this handle.
+                        fn as_ptr(self: &#id) -> *mut c_void;
+                    }),
+                ],
+                materializations: vec![Use::UsedFromCxxBridgeWithAlias(make_ident("OpaqueHandle"))],
                 ..Default::default()
             },
+            Api::IntrusivePtrTarget { target, .. } => {
+                let (add_ref, release) = intrusive_ptr_fn_names(&id);
+                let target_path = target.type_path_from_root();
+                let target_id = target.get_final_ident();
+                RsCodegenResult {
+                    extern_c_mod_items: vec![
+                        ForeignItem::Fn(parse_quote!(
+                            unsafe fn #add_ref(ptr: *mut #target_id);
+                        )),
+                        ForeignItem::Fn(parse_quote!(
+                            unsafe fn #release(ptr: *mut #target_id);
+                        )),
+                    ],
+                    bindgen_mod_items: vec![Item::Impl(parse_quote! {
+                        unsafe impl autocxx::IntrusivePtrTarget for #target_path {
+                            unsafe fn add_ref(ptr: *mut Self) {
+                                cxxbridge::#add_ref(ptr)
+                            }
+                            unsafe fn release(ptr: *mut Self) {
+                                cxxbridge::#release(ptr)
+                            }
+                        }
+                    })],
+                    ..Default::default()
+                }
+            }
+            Api::ComparisonOperators { target, .. } => {
+                let (eq, lt) = comparison_fn_names(&id);
+                let target_path = target.type_path_from_root();
+                let target_id = target.get_final_ident();
+                RsCodegenResult {
+                    extern_c_mod_items: vec![
+                        ForeignItem::Fn(parse_quote!(
+                            fn #eq(a: &#target_id, b: &#target_id) -> bool;
+                        )),
+                        ForeignItem::Fn(parse_quote!(
+                            fn #lt(a: &#target_id, b: &#target_id) -> bool;
+                        )),
+                    ],
+                    bindgen_mod_items: vec![
+                        Item::Impl(parse_quote! {
+                            impl PartialEq for #target_path {
+                                fn eq(&self, other: &Self) -> bool {
+                                    cxxbridge::#eq(self, other)
+                                }
+                            }
+                        }),
+                        Item::Impl(parse_quote! {
+                            impl Eq for #target_path {}
+                        }),
+                        Item::Impl(parse_quote! {
+                            impl PartialOrd for #target_path {
+                                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                                    Some(self.cmp(other))
+                                }
+                            }
+                        }),
+                        Item::Impl(parse_quote! {
+                            impl Ord for #target_path {
+                                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                                    if cxxbridge::#lt(self, other) {
+                                        std::cmp::Ordering::Less
+                                    } else if cxxbridge::#lt(other, self) {
+                                        std::cmp::Ordering::Greater
+                                    } else {
+                                        std::cmp::Ordering::Equal
+                                    }
+                                }
+                            }
+                        }),
+                    ],
+                    ..Default::default()
+                }
+            }
+            Api::BoolConversion { target, .. } => {
+                let as_bool = bool_conversion_fn_name(&id);
+                let target_path = target.type_path_from_root();
+                let target_id = target.get_final_ident();
+                RsCodegenResult {
+                    extern_c_mod_items: vec![ForeignItem::Fn(parse_quote!(
+                        fn #as_bool(me: &#target_id) -> bool;
+                    ))],
+                    bindgen_mod_items: vec![
+                        Item::Impl(parse_quote! {
+                            impl #target_path {
+                                /// Converts this to `bool` using its C++ `operator bool`.
+                                pub fn as_bool(&self) -> bool {
+                                    cxxbridge::#as_bool(self)
+                                }
+                            }
+                        }),
+                        Item::Impl(parse_quote! {
+                            impl From<&#target_path> for bool {
+                                fn from(me: &#target_path) -> bool {
+                                    cxxbridge::#as_bool(me)
+                                }
+                            }
+                        }),
+                    ],
+                    ..Default::default()
+                }
+            }
+            Api::IndexOperators {
+                target, element, ..
+            } => {
+                let (index, index_mut) = index_fn_names(&id);
+                let target_path = target.type_path_from_root();
+                let target_id = target.get_final_ident();
+                let (element_path, element_id): (Type, Type) =
+                    if known_types().is_known_type(&element) {
+                        let known = Type::Path(element.to_type_path());
+                        (known.clone(), known)
+                    } else {
+                        let element_id = element.get_final_ident();
+                        (
+                            Type::Path(element.type_path_from_root()),
+                            parse_quote! { #element_id },
+                        )
+                    };
+                let mut extern_c_mod_items = vec![ForeignItem::Fn(parse_quote!(
+                    fn #index(me: &#target_id, idx: usize) -> &#element_id;
+                ))];
+                let mut bindgen_mod_items = vec![Item::Impl(parse_quote! {
+                    impl std::ops::Index<usize> for #target_path {
+                        type Output = #element_path;
+                        fn index(&self, idx: usize) -> &Self::Output {
+                            cxxbridge::#index(self, idx)
+                        }
+                    }
+                })];
+                // cxx only hands out a plain `&mut` to an element which Rust
+                // is allowed to move, so `IndexMut` is reserved for those.
+                let element_movable = if known_types().is_known_type(&element) {
+                    known_types().is_simple_by_value(&element)
+                } else {
+                    !non_pod_types.contains(&element)
+                };
+                if element_movable {
+                    extern_c_mod_items.push(ForeignItem::Fn(parse_quote!(
+                        fn #index_mut(me: Pin<&mut #target_id>, idx: usize) -> &mut #element_id;
+                    )));
+                    bindgen_mod_items.push(Item::Impl(parse_quote! {
+                        impl std::ops::IndexMut<usize> for #target_path {
+                            fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+                                cxxbridge::#index_mut(
+                                    unsafe { std::pin::Pin::new_unchecked(self) },
+                                    idx,
+                                )
+                            }
+                        }
+                    }));
+                }
+                RsCodegenResult {
+                    extern_c_mod_items,
+                    bindgen_mod_items,
+                    ..Default::default()
+                }
+            }
+            Api::Typedef { analysis, .. } => match analysis.kind {
+                // Alias templates are resolved wherever they're used, so
+                // there's nothing to generate for the template itself.
+                TypedefKind::Type(type_item) if !type_item.generics.params.is_empty() => {
+                    RsCodegenResult::default()
+                }
+                kind => RsCodegenResult {
+                    bindgen_mod_items: vec![match kind {
+                        TypedefKind::Type(type_item) => Item::Type(type_item),
+                        TypedefKind::Use(use_item, _) => Item::Use(use_item),
+                    }],
+                    materializations: vec![Use::UsedFromBindgen],
+                    ..Default::default()
+                },
+            },
             Api::Struct {
-                details,
+                mut details,
                 analysis:
                     PodAndDepAnalysis {
                         pod:
@@ -616,11 +1125,27 @@ impl<'a> RsCodeGenerator<'a> {
                     },
                 ..
             } => {
+                add_deprecated_attr(&mut details.item.attrs);
                 let doc_attrs = get_doc_attrs(&details.item.attrs);
                 let layout = details.layout.clone();
-                self.generate_type(
+                // The builder is left out if its name is already taken.
+                let builder_name =
+                    QualifiedName::new(name.get_namespace(), function_table_builder_name(&id));
+                let function_table_items = if !is_generic
+                    && matches!(kind, TypeKind::Pod)
+                    && !self.api_names.contains(&builder_name)
+                {
+                    generate_function_table_builder(&details.item)
+                } else {
+                    Vec::new()
+                };
+                let clonable = !is_generic
+                    && matches!(kind, TypeKind::NonPod)
+                    && constructors.copy_constructor
+                    && self.config.is_clonable(&name.to_cpp_name());
+                let mut result = self.generate_type(
                     &name,
-                    id,
+                    id.clone(),
                     kind,
                     constructors.move_constructor,
                     constructors.destructor,
@@ -628,33 +1153,109 @@ impl<'a> RsCodeGenerator<'a> {
                     associated_methods,
                     layout,
                     is_generic,
-                )
+                );
+                if clonable {
+                    result.bindgen_mod_items.push(parse_quote! {
+                        impl #id {
+                            /// Makes a copy of this object on the heap, using
+                            /// its C++ copy constructor.
+                            pub fn clone_unique(&self) -> cxx::UniquePtr<Self> {
+                                use autocxx::WithinUniquePtr;
+                                autocxx::moveit::new::copy(self).within_unique_ptr()
+                            }
+                        }
+                    });
+                }
+                if let Some(cp) = self.config.get_conditional_pod(&name.to_cpp_name()) {
+                    result
+                        .bindgen_mod_items
+                        .push(Self::generate_conditional_pod_guard(cp));
+                }
+                if !function_table_items.is_empty() {
+                    result.bindgen_mod_items.extend(function_table_items);
+                    result.materializations.push(Use::SpecificNameFromBindgen(
+                        function_table_builder_name(&id),
+                    ));
+                }
+                result
             }
-            Api::Enum { item, .. } => {
+            Api::Enum { mut item, .. } => {
+                add_deprecated_attr(&mut item.attrs);
                 let doc_attrs = get_doc_attrs(&item.attrs);
-                self.generate_type(
+                let is_newtype = matches!(
+                    self.config.enum_representation(),
+                    EnumRepresentation::Newtype
+                );
+                if !is_newtype && self.config.non_exhaustive_enums() {
+                    item.attrs.push(parse_quote! { #[non_exhaustive] });
+                }
+                let flags_items = if self.config.is_flags_enum(&name.to_cpp_name()) {
+                    generate_flags_items(&item, is_newtype)
+                } else {
+                    Vec::new()
+                };
+                let flags_id = flags_type_name(&id);
+                let (item, newtype_impl) = if is_newtype {
+                    let (newtype, newtype_impl) = generate_enum_newtype(item);
+                    (Item::Struct(newtype), Some(newtype_impl))
+                } else {
+                    (Item::Enum(item), None)
+                };
+                let mut result = self.generate_type(
                     &name,
                     id,
                     TypeKind::Pod,
                     true,
                     true,
-                    || Some((Item::Enum(item), doc_attrs)),
+                    || Some((item, doc_attrs)),
                     associated_methods,
                     None,
                     false,
-                )
+                );
+                result.bindgen_mod_items.extend(newtype_impl);
+                if !flags_items.is_empty() {
+                    result.bindgen_mod_items.extend(flags_items);
+                    result
+                        .materializations
+                        .push(Use::SpecificNameFromBindgen(flags_id));
+                }
+                result
+            }
+            Api::ConcreteType { rs_definition, .. } => {
+                // A vector within a vector, which we made concrete only because
+                // cxx can't nest its own vectors. It can be kept in the outer
+                // vector, and dereferenced to the vector which it really is.
+                let vector = rs_definition.filter(|ty| match ty.as_ref() {
+                    Type::Path(typ) => {
+                        known_types().cxx_generic_behavior(&QualifiedName::from_type_path(typ))
+                            == CxxGenericType::CppVector
+                    }
+                    _ => false,
+                });
+                let mut result = self.generate_type(
+                    &name,
+                    id.clone(),
+                    TypeKind::Abstract,
+                    vector.is_some(), // otherwise assume these types can't be kept in a Vector
+                    true, // assume for now that these types can be put in a smart pointer
+                    || None,
+                    associated_methods,
+                    None,
+                    false,
+                );
+                if let Some(vector) = vector {
+                    result.bindgen_mod_items.push(Item::Impl(parse_quote! {
+                        impl std::ops::Deref for #id {
+                            type Target = #vector;
+                            fn deref(&self) -> &Self::Target {
+                                // Safety: this is a C++ typedef of exactly that vector type.
+                                unsafe { &*(self as *const Self as *const Self::Target) }
+                            }
+                        }
+                    }));
+                }
+                result
             }
-            Api::ConcreteType { .. } => self.generate_type(
-                &name,
-                id,
-                TypeKind::Abstract,
-                false, // assume for now that these types can't be kept in a Vector
-                true,  // assume for now that these types can be put in a smart pointer
-                || None,
-                associated_methods,
-                None,
-                false,
-            ),
             Api::ForwardDeclaration { .. } | Api::OpaqueTypedef { .. } => self.generate_type(
                 &name,
                 id,
@@ -681,6 +1282,17 @@ impl<'a> RsCodeGenerator<'a> {
                 }],
                 ..Default::default()
             },
+            Api::RustSharedStruct { mut item, .. } => {
+                for f in item.fields.iter_mut() {
+                    f.ty = unqualify_type(f.ty.clone());
+                }
+                RsCodegenResult {
+                    bridge_items: vec![Item::Struct(item)],
+                    bindgen_mod_items: vec![Item::Use(parse_quote! { pub use cxxbridge::#id; })],
+                    materializations: vec![Use::UsedFromBindgen],
+                    ..Default::default()
+                }
+            }
             Api::RustFn {
                 details:
                     RustFun {
@@ -776,7 +1388,7 @@ impl<'a> RsCodeGenerator<'a> {
             },
         ];
         let mut extern_c_mod_items = vec![
-            self.generate_cxxbridge_type(&full_cpp, false, Vec::new()),
+            self.generate_cxxbridge_type(&full_cpp, CxxTypeTarget::Opaque, Vec::new()),
             parse_quote! {
                 fn #relinquish_ownership_call(self: &#cpp_id);
             },
@@ -896,6 +1508,143 @@ impl<'a> RsCodeGenerator<'a> {
         }
     }
 
+    fn generate_std_tuple_bridge(&self, name: &ApiName, elems: Vec<Type>) -> RsCodegenResult {
+        let id = &name.name.get_final_ident();
+        let fields = (0..elems.len()).map(|i| make_ident(format!("f{}", i)));
+        RsCodegenResult {
+            extern_c_mod_items: vec![self.generate_cxxbridge_type(
+                &name.name,
+                CxxTypeTarget::Bindgen,
+                Vec::new(),
+            )],
+            bindgen_mod_items: vec![parse_quote! {
+                /// Holds the elements of a C++ `std::tuple` on their way
+                /// to becoming a Rust tuple, or whether a `std::optional`
+                /// has a value and that value, on their way to becoming
+                /// a Rust `Option`.
+                #[repr(C)]
+                pub struct #id {
+                    #(pub #fields: #elems,)*
+                }
+            }],
+            global_items: self.generate_extern_type_impl(TypeKind::Pod, &name.name),
+            ..Default::default()
+        }
+    }
+
+    fn generate_std_variant_bridge(
+        &self,
+        name: &ApiName,
+        enum_name: &QualifiedName,
+        alternatives: Vec<Type>,
+    ) -> RsCodegenResult {
+        let id = &name.name.get_final_ident();
+        let enum_id = enum_name.get_final_ident();
+        let fields: Vec<_> = (0..alternatives.len())
+            .map(|i| make_ident(format!("v{}", i)))
+            .collect();
+        let variants: Vec<_> = (0..alternatives.len())
+            .map(|i| make_ident(format!("V{}", i)))
+            .collect();
+        let indices = 0..alternatives.len();
+        let error = format!("{} was valueless by exception", enum_id);
+        RsCodegenResult {
+            extern_c_mod_items: vec![self.generate_cxxbridge_type(
+                &name.name,
+                CxxTypeTarget::Bindgen,
+                Vec::new(),
+            )],
+            bindgen_mod_items: vec![
+                parse_quote! {
+                    /// Holds the index and value of the active alternative
+                    /// of a C++ `std::variant` on its way to becoming a
+                    /// Rust enum.
+                    #[repr(C)]
+                    pub struct #id {
+                        pub index: usize,
+                        #(pub #fields: #alternatives,)*
+                    }
+                },
+                parse_quote! {
+                    /// A C++ `std::variant`, with one variant per alternative
+                    /// in the same order as in C++.
+                    #[allow(non_camel_case_types)]
+                    #[derive(Clone, Copy, Debug, PartialEq)]
+                    pub enum #enum_id {
+                        #(#variants(#alternatives),)*
+                    }
+                },
+                parse_quote! {
+                    impl From<#id> for #enum_id {
+                        fn from(bridge: #id) -> Self {
+                            match bridge.index {
+                                #(#indices => Self::#variants(bridge.#fields),)*
+                                _ => panic!(#error),
+                            }
+                        }
+                    }
+                },
+            ],
+            global_items: self.generate_extern_type_impl(TypeKind::Pod, &name.name),
+            materializations: vec![Use::SpecificNameFromBindgen(enum_id)],
+            ..Default::default()
+        }
+    }
+
+    fn generate_std_function_adapter(
+        id: Ident,
+        params: Vec<Type>,
+        ret: ReturnType,
+    ) -> RsCodegenResult {
+        let call = std_function_call_name(&id);
+        let arg_names: Vec<_> = (0..params.len())
+            .map(|i| make_ident(format!("arg{}", i)))
+            .collect();
+        let fn_params: Punctuated<FnArg, Comma> =
+            std::iter::once::<FnArg>(parse_quote! { me: &#id })
+                .chain(
+                    arg_names
+                        .iter()
+                        .zip(params.iter())
+                        .map(|(arg, ty)| parse_quote! { #arg: #ty }),
+                )
+                .collect();
+        let bridge_params = unqualify_params(fn_params.clone());
+        let bridge_ret = unqualify_ret_type(ret.clone());
+        RsCodegenResult {
+            global_items: vec![
+                parse_quote! {
+                    #[doc(hidden)]
+                    pub use bindgen::root::#id;
+                },
+                parse_quote! {
+                    #[doc(hidden)]
+                    #[allow(non_snake_case)]
+                    pub fn #call(#fn_params) #ret {
+                        (me.0)(#(#arg_names),*)
+                    }
+                },
+            ],
+            bindgen_mod_items: vec![parse_quote! {
+                /// Holds a Rust closure which C++ receives as a `std::function`.
+                /// Every copy of that `std::function` shares this one closure,
+                /// possibly across threads and possibly re-entrantly, so it
+                /// must be `Fn + Send + Sync`.
+                #[doc(hidden)]
+                pub struct #id(pub Box<dyn Fn(#(#params),*) #ret + Send + Sync>);
+            }],
+            extern_rust_mod_items: vec![
+                parse_quote! {
+                    pub type #id;
+                },
+                parse_quote! {
+                    fn #call(#bridge_params) #bridge_ret;
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
     fn generate_subclass_fn(
         api_name: Ident,
         details: RustSubclassFnDetails,
@@ -984,6 +1733,34 @@ impl<'a> RsCodeGenerator<'a> {
             &mut materializations,
             associated_methods.get(name),
         );
+        if !is_generic && destroyable && self.config.is_handle_type(&name.to_cpp_name()) {
+            bindgen_mod_items.extend(generate_handle_items(&id, self.pre_drop_hooks.get(name)));
+            materializations.push(Use::SpecificNameFromBindgen(handle_type_name(&id)));
+        }
+        if !is_generic && self.config.has_virtual_trait(&name.to_cpp_name()) {
+            materializations.push(Use::SpecificNameFromBindgen(virtual_trait_name(&id)));
+        }
+        if !is_generic
+            && self.config.is_thread_safe(&name.to_cpp_name())
+            && !self.thread_unsafe_types.contains(name)
+        {
+            // The user has promised us this is OK.
+            bindgen_mod_items.push(parse_quote! { unsafe impl Send for #id {} });
+            bindgen_mod_items.push(parse_quote! { unsafe impl Sync for #id {} });
+        }
+        if let Some((next_fn, item_type)) = self.generator_items.get(name).filter(|_| !is_generic) {
+            bindgen_mod_items.push(parse_quote! {
+                impl autocxx::CppGenerator for #id {
+                    type Item = #item_type;
+                    fn next_item(self: ::std::pin::Pin<&mut Self>) -> Option<#item_type> {
+                        self.#next_fn()
+                    }
+                }
+            });
+        }
+        if !is_generic && self.config.has_identity_eq(&name.to_cpp_name()) {
+            bindgen_mod_items.extend(generate_identity_eq_items(&id));
+        }
         let orig_item = item_creator();
         let doc_attrs = orig_item
             .as_ref()
@@ -1015,6 +1792,22 @@ impl<'a> RsCodeGenerator<'a> {
                         // enum
                         item = Item::Struct(new_non_pod_struct(id.clone()));
                     }
+                } else {
+                    if self.config.is_serde_pod(&name.to_cpp_name()) {
+                        if let Item::Struct(ref mut s) = item {
+                            s.attrs.push(parse_quote! {
+                                #[derive(::autocxx::serde::Serialize, ::autocxx::serde::Deserialize)]
+                            });
+                            s.attrs.push(parse_quote! {
+                                #[serde(crate = "::autocxx::serde")]
+                            });
+                        }
+                    }
+                    if !is_generic {
+                        if let Some(layout) = &layout {
+                            bindgen_mod_items.extend(generate_layout_assertions(&id, layout));
+                        }
+                    }
                 }
                 bindgen_mod_items.push(item);
 
@@ -1030,9 +1823,11 @@ impl<'a> RsCodeGenerator<'a> {
                     RsCodegenResult {
                         global_items: self.generate_extern_type_impl(type_kind, name),
                         bridge_items: create_impl_items(&id, movable, destroyable, self.config),
-                        extern_c_mod_items: vec![
-                            self.generate_cxxbridge_type(name, true, doc_attrs)
-                        ],
+                        extern_c_mod_items: vec![self.generate_cxxbridge_type(
+                            name,
+                            CxxTypeTarget::Bindgen,
+                            doc_attrs,
+                        )],
                         bindgen_mod_items,
                         materializations,
                         ..Default::default()
@@ -1048,9 +1843,11 @@ impl<'a> RsCodeGenerator<'a> {
                     // instantiated using UniquePtr etc.
                     bindgen_mod_items.push(Item::Use(parse_quote! { pub use cxxbridge::#id; }));
                     RsCodegenResult {
-                        extern_c_mod_items: vec![
-                            self.generate_cxxbridge_type(name, false, doc_attrs)
-                        ],
+                        extern_c_mod_items: vec![self.generate_cxxbridge_type(
+                            name,
+                            CxxTypeTarget::Opaque,
+                            doc_attrs,
+                        )],
                         bridge_items: create_impl_items(&id, movable, destroyable, self.config),
                         bindgen_mod_items,
                         materializations,
@@ -1148,7 +1945,11 @@ impl<'a> RsCodeGenerator<'a> {
         };
         RsCodegenResult {
             bindgen_mod_items: vec![use_statement],
-            extern_c_mod_items: vec![self.generate_cxxbridge_type(name, true, Vec::new())],
+            extern_c_mod_items: vec![self.generate_cxxbridge_type(
+                name,
+                CxxTypeTarget::Bindgen,
+                Vec::new(),
+            )],
             materializations: vec![Use::Custom(Box::new(
                 parse_quote! { pub use #rust_path as #name_final; },
             ))],
@@ -1191,6 +1992,7 @@ impl<'a> RsCodeGenerator<'a> {
                         ty: parse_quote! { #self_ty },
                         lifetime: None,
                     },
+                    is_virtual: false,
                 })),
                 None,
                 None,
@@ -1204,6 +2006,22 @@ impl<'a> RsCodeGenerator<'a> {
         }
     }
 
+    /// Names of functions and types declared in a hand-written
+    /// `bridge_item!`, which should be exposed from the output mod.
+    fn hand_written_bridge_item_idents(item: &Item) -> impl Iterator<Item = Ident> + '_ {
+        let foreign_items = match item {
+            Item::ForeignMod(foreign_mod) => foreign_mod.items.as_slice(),
+            _ => &[],
+        };
+        foreign_items
+            .iter()
+            .filter_map(|foreign_item| match foreign_item {
+                ForeignItem::Fn(fun) => Some(fun.sig.ident.clone()),
+                ForeignItem::Type(ty) => Some(ty.ident.clone()),
+                _ => None,
+            })
+    }
+
     fn generate_cxx_use_stmt(name: &QualifiedName, alias: Option<&Ident>) -> Item {
         let segs = Self::find_output_mod_root(name.get_namespace())
             .chain(std::iter::once(make_ident("cxxbridge")))
@@ -1226,8 +2044,40 @@ impl<'a> RsCodeGenerator<'a> {
         })
     }
 
+    /// Bindings are generated for one target at a time, so a type from
+    /// `generate_pod_if!` is laid out as either POD or opaque, not both.
+    /// Refuse to compile them for a target which would have chosen otherwise.
+    fn generate_conditional_pod_guard(cp: &ConditionalPod) -> Item {
+        let condition = &cp.condition;
+        let condition_text = quote! { #condition }.to_string();
+        let (guard, message): (Attribute, _) = if cp.pod {
+            (
+                parse_quote! { #[cfg(not(#condition))] },
+                format!(
+                    "{} was generated as POD for a target where `{}` holds; regenerate the bindings for this target",
+                    cp.ty, condition_text
+                ),
+            )
+        } else {
+            (
+                parse_quote! { #[cfg(#condition)] },
+                format!(
+                    "{} was generated as opaque for a target where `{}` doesn't hold; regenerate the bindings for this target",
+                    cp.ty, condition_text
+                ),
+            )
+        };
+        parse_quote! {
+            #guard
+            ::std::compile_error! { #message }
+        }
+    }
+
     fn generate_extern_type_impl(&self, type_kind: TypeKind, tyname: &QualifiedName) -> Vec<Item> {
-        let tynamestring = namespaced_name_using_original_name_map(tyname, &self.original_name_map);
+        let tynamestring = match self.config.get_type_link_name(&tyname.to_cpp_name()) {
+            Some(link_name) => link_name.to_string(),
+            None => namespaced_name_using_original_name_map(tyname, &self.original_name_map),
+        };
         let fulltypath = tyname.get_bindgen_path_idents();
         let kind_item = match type_kind {
             TypeKind::Pod => "Trivial",
@@ -1245,7 +2095,7 @@ impl<'a> RsCodeGenerator<'a> {
     fn generate_cxxbridge_type(
         &self,
         name: &QualifiedName,
-        references_bindgen: bool,
+        target: CxxTypeTarget,
         doc_attrs: Vec<Attribute>,
     ) -> ForeignItem {
         let ns = name.get_namespace();
@@ -1256,10 +2106,17 @@ impl<'a> RsCodeGenerator<'a> {
         // within namespace A.
         let mut ns_components: Vec<_> = ns.iter().cloned().collect();
         let mut cxx_name = None;
-        if let Some(cpp_name) = self.original_name_map.get(name) {
+        if let Some(link_name) = self.config.get_type_link_name(&name.to_cpp_name()) {
+            // The user has told us what cxx should call it instead.
+            let link_name = QualifiedName::new_from_cpp_name(link_name);
+            cxx_name = Some(link_name.get_final_item().to_string());
+            ns_components = link_name.ns_segment_iter().cloned().collect();
+        } else if let Some(cpp_name) = self.original_name_map.get(name) {
             let cpp_name = QualifiedName::new_from_cpp_name(cpp_name);
             cxx_name = Some(cpp_name.get_final_item().to_string());
             ns_components.extend(cpp_name.ns_segment_iter().cloned());
+        } else if self.config.explicit_cxx_names() {
+            cxx_name = Some(id.to_string());
         };
 
         let mut for_extern_c_ts = if !ns_components.is_empty() {
@@ -1281,22 +2138,31 @@ impl<'a> RsCodeGenerator<'a> {
             #(#doc_attrs)*
         });
 
-        if references_bindgen {
-            for_extern_c_ts.extend(quote! {
-                type #id = super::bindgen::root::
-            });
-            for_extern_c_ts.extend(ns.iter().map(make_ident).map(|id| {
-                quote! {
-                    #id::
-                }
-            }));
-            for_extern_c_ts.extend(quote! {
-                #id;
-            });
-        } else {
-            for_extern_c_ts.extend(quote! {
-                type #id;
-            });
+        match target {
+            CxxTypeTarget::Bindgen => {
+                for_extern_c_ts.extend(quote! {
+                    type #id = super::bindgen::root::
+                });
+                for_extern_c_ts.extend(ns.iter().map(make_ident).map(|id| {
+                    quote! {
+                        #id::
+                    }
+                }));
+                for_extern_c_ts.extend(quote! {
+                    #id;
+                });
+            }
+            CxxTypeTarget::SharedBridge => {
+                let shared_mod = make_ident(SHARED_TYPES_BRIDGE_MOD);
+                for_extern_c_ts.extend(quote! {
+                    type #id = super::#shared_mod::#id;
+                });
+            }
+            CxxTypeTarget::Opaque => {
+                for_extern_c_ts.extend(quote! {
+                    type #id;
+                });
+            }
         }
         ForeignItem::Verbatim(for_extern_c_ts)
     }
@@ -1306,6 +2172,56 @@ impl<'a> RsCodeGenerator<'a> {
     }
 }
 
+/// What a type declared within a `#[cxx::bridge]` mod refers to.
+enum CxxTypeTarget {
+    /// A new opaque type: `type A;`
+    Opaque,
+    /// The type which bindgen generated.
+    Bindgen,
+    /// The declaration within the bridge holding shared type declarations,
+    /// if `shared_type_declarations!()` was specified.
+    SharedBridge,
+}
+
+/// Types which are declared to cxx, and whose declarations will therefore
+/// be gathered into a single bridge if `shared_type_declarations!()` was
+/// specified.
+fn find_cxx_types(apis: &ApiVec<FnPhase>) -> HashSet<QualifiedName> {
+    apis.iter()
+        .filter(|api| {
+            matches!(
+                api,
+                Api::ForwardDeclaration { .. }
+                    | Api::OpaqueTypedef { .. }
+                    | Api::ConcreteType { .. }
+                    | Api::Enum { .. }
+                    | Api::Struct { .. }
+                    | Api::ExternCppType { .. }
+                    | Api::StdTupleBridge { .. }
+                    | Api::StdVariantBridge { .. }
+                    | Api::OpaqueHandle { .. }
+                    | Api::CType { .. }
+            )
+        })
+        .map(|api| api.name().clone())
+        .collect()
+}
+
+/// The dependencies of all the other APIs, grouped by namespace.
+fn find_type_references_by_group(
+    apis: &ApiVec<FnPhase>,
+) -> HashMap<String, HashSet<QualifiedName>> {
+    let cxx_types = find_cxx_types(apis);
+    let mut results: HashMap<String, HashSet<QualifiedName>> = HashMap::new();
+    for api in apis.iter().filter(|api| !cxx_types.contains(api.name())) {
+        results
+            .entry(api.name().get_namespace().to_string())
+            .or_default()
+            .extend(api.deps().cloned());
+    }
+    results
+}
+
 fn find_trivially_constructed_subclasses(apis: &ApiVec<FnPhase>) -> HashSet<QualifiedName> {
     let (simple_constructors, complex_constructors): (Vec<_>, Vec<_>) = apis
         .iter()
@@ -1329,6 +2245,109 @@ fn find_trivially_constructed_subclasses(apis: &ApiVec<FnPhase>) -> HashSet<Qual
         .collect()
 }
 
+/// Types with a method marked using `thread_unsafe!`, which must never be
+/// `Send` or `Sync` whatever else the user has told us.
+fn find_thread_unsafe_types(apis: &ApiVec<FnPhase>) -> HashSet<QualifiedName> {
+    apis.iter()
+        .filter_map(|api| match api {
+            Api::Function {
+                analysis:
+                    FnAnalysis {
+                        kind: FnKind::Method { impl_for, .. },
+                        thread_unsafe: true,
+                        ..
+                    },
+                ..
+            } => Some(impl_for.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the Rust name of the method which each type's handle should call
+/// before destroying the object, as requested using `pre_drop!`. If the
+/// method can't be found we use its C++ name, so that the user gets a
+/// compile error rather than a handle which silently skips the hook.
+fn find_pre_drop_hooks(
+    apis: &ApiVec<FnPhase>,
+    config: &IncludeCppConfig,
+) -> HashMap<QualifiedName, PreDropHook> {
+    let mut hooks: HashMap<QualifiedName, PreDropHook> = apis
+        .iter()
+        .filter_map(|api| match api {
+            Api::Struct { name, .. } => {
+                let cpp_name = name.name.to_cpp_name();
+                config.get_pre_drop_hook(&cpp_name).map(|hook| {
+                    (
+                        name.name.clone(),
+                        PreDropHook {
+                            method: make_ident(hook),
+                            is_unsafe: false,
+                            unsafe_allowed: config.is_pre_drop_hook_unsafe(&cpp_name),
+                        },
+                    )
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    for api in apis.iter() {
+        if let Api::Function {
+            analysis:
+                FnAnalysis {
+                    kind:
+                        FnKind::Method {
+                            impl_for,
+                            method_kind:
+                                MethodKind::Normal(_)
+                                | MethodKind::Virtual(_)
+                                | MethodKind::PureVirtual(_),
+                            ..
+                        },
+                    rust_name,
+                    params,
+                    requires_unsafe,
+                    ignore_reason: Ok(()),
+                    ..
+                },
+            ..
+        } = api
+        {
+            if params.len() == 1
+                && config.get_pre_drop_hook(&impl_for.to_cpp_name())
+                    == Some(api.effective_cpp_name())
+            {
+                hooks.insert(
+                    impl_for.clone(),
+                    PreDropHook {
+                        method: make_ident(rust_name),
+                        is_unsafe: matches!(requires_unsafe, UnsafetyNeeded::Always),
+                        unsafe_allowed: config.is_pre_drop_hook_unsafe(&impl_for.to_cpp_name()),
+                    },
+                );
+            }
+        }
+    }
+    hooks
+}
+
+/// Gives a function in the `cxx::bridge` a `cxx_name` attribute naming
+/// the C++ function it calls, if it doesn't already have one. Types are
+/// instead given theirs as they're generated.
+fn add_explicit_cxx_name(item: &mut ForeignItem) {
+    if let ForeignItem::Fn(fun) = item {
+        if !fun
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("cxx_name") || attr.path.is_ident("rust_name"))
+        {
+            let cxx_name = fun.sig.ident.to_string();
+            fun.attrs
+                .insert(0, parse_quote! { #[cxx_name = #cxx_name] });
+        }
+    }
+}
+
 fn find_non_pod_types(apis: &ApiVec<FnPhase>) -> HashSet<QualifiedName> {
     apis.iter()
         .filter_map(|api| match api {
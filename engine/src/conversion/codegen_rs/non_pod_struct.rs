@@ -7,6 +7,7 @@
 // except according to those terms.
 
 use crate::conversion::api::Layout;
+use crate::conversion::doc_attr::is_forwardable_attr;
 use crate::types::make_ident;
 use proc_macro2::{Ident, Span};
 use quote::quote;
@@ -25,8 +26,8 @@ pub(crate) fn new_non_pod_struct(id: Ident) -> ItemStruct {
 
 pub(crate) fn make_non_pod(s: &mut ItemStruct, layout: Option<Layout>) {
     // Make an opaque struct. If we have layout information, we pass
-    // that through to Rust. We keep only doc attrs, plus add a #[repr(C)]
-    // if necessary.
+    // that through to Rust. We keep only doc and deprecated attrs, plus add
+    // a #[repr(C)] if necessary.
     // Constraints here (thanks to dtolnay@ for this explanation of why the
     // following is needed:)
     // (1) If the real alignment of the C++ type is smaller and a reference
@@ -52,11 +53,7 @@ pub(crate) fn make_non_pod(s: &mut ItemStruct, layout: Option<Layout>) {
     // We use (c) for abstract types. For everything else, we do it ourselves
     // for maximal control. See codegen_rs/mod.rs generate_type for more notes.
     // First work out attributes.
-    let doc_attr = s
-        .attrs
-        .iter()
-        .filter(|a| a.path.get_ident().iter().any(|p| *p == "doc"))
-        .cloned();
+    let doc_attr = s.attrs.iter().filter(|a| is_forwardable_attr(a)).cloned();
     let repr_attr = if let Some(layout) = &layout {
         let align = make_lit_int(layout.align);
         if layout.packed {
@@ -50,7 +50,7 @@ where
     new_pun
 }
 
-fn unqualify_type(typ: Type) -> Type {
+pub(crate) fn unqualify_type(typ: Type) -> Type {
     match typ {
         Type::Path(typ) => Type::Path(unqualify_type_path(typ)),
         Type::Reference(mut typeref) => {
@@ -61,6 +61,10 @@ fn unqualify_type(typ: Type) -> Type {
             typeptr.elem = unqualify_boxed_type(typeptr.elem);
             Type::Ptr(typeptr)
         }
+        Type::Slice(mut typeslice) => {
+            typeslice.elem = unqualify_boxed_type(typeslice.elem);
+            Type::Slice(typeslice)
+        }
         _ => typ,
     }
 }
@@ -0,0 +1,107 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use quote::format_ident;
+use syn::{
+    parse_quote, FnArg, Ident, ImplItem, ImplItemMethod, Item, Pat, ReturnType, TraitItem, Type,
+};
+
+use super::handle::pinned_reference;
+
+/// The name of the trait mirroring the virtual methods of a type marked
+/// with `virtual_trait!`.
+pub(super) fn virtual_trait_name(id: &Ident) -> Ident {
+    format_ident!("{}Trait", id)
+}
+
+/// Generate a trait with a method for each of the given virtual methods,
+/// along with an implementation of it for the type itself which forwards
+/// to those methods. Methods which can't be called through a trait
+/// object, because they take or return an `impl Trait`, are left out.
+pub(super) fn generate_virtual_trait(id: &Ident, methods: &[&ImplItem]) -> Vec<Item> {
+    let (trait_items, impl_items): (Vec<TraitItem>, Vec<ImplItem>) = methods
+        .iter()
+        .filter_map(|method| match method {
+            ImplItem::Method(method) => generate_trait_method(id, method),
+            _ => None,
+        })
+        .unzip();
+    let trait_id = virtual_trait_name(id);
+    let doc = format!(
+        "The virtual methods of [`{}`], for use as a trait object.",
+        id
+    );
+    vec![
+        parse_quote! {
+            #[doc = #doc]
+            pub trait #trait_id {
+                #(#trait_items)*
+            }
+        },
+        parse_quote! {
+            impl #trait_id for #id {
+                #(#impl_items)*
+            }
+        },
+    ]
+}
+
+fn generate_trait_method(id: &Ident, method: &ImplItemMethod) -> Option<(TraitItem, ImplItem)> {
+    let mut sig = method.sig.clone();
+    // The receiver names the type itself, which won't do within a trait.
+    let receiver = match sig.inputs.first()? {
+        FnArg::Typed(pt) if matches!(&*pt.pat, Pat::Ident(pi) if pi.ident == "self") => &pt.ty,
+        _ => return None,
+    };
+    let new_receiver: FnArg = match receiver.as_ref() {
+        Type::Reference(r) => {
+            let lt = &r.lifetime;
+            let mutability = &r.mutability;
+            parse_quote! { & #lt #mutability self }
+        }
+        Type::Path(tp) => {
+            let r = pinned_reference(tp)?;
+            let lt = &r.lifetime;
+            let mutability = &r.mutability;
+            parse_quote! { self: ::std::pin::Pin<& #lt #mutability Self> }
+        }
+        _ => return None,
+    };
+    *sig.inputs.first_mut().unwrap() = new_receiver;
+    if matches!(&sig.output, ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_))) {
+        return None;
+    }
+    let mut args = Vec::new();
+    for arg in sig.inputs.iter().skip(1) {
+        match arg {
+            FnArg::Typed(pt) => match (&*pt.pat, &*pt.ty) {
+                (_, Type::ImplTrait(_)) => return None,
+                (Pat::Ident(pi), _) => args.push(pi.ident.clone()),
+                _ => return None,
+            },
+            FnArg::Receiver(_) => return None,
+        }
+    }
+    let name = &sig.ident;
+    let attrs: Vec<_> = method
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .collect();
+    Some((
+        parse_quote! {
+            #(#attrs)*
+            #sig;
+        },
+        parse_quote! {
+            #sig {
+                #id::#name(self, #(#args),*)
+            }
+        },
+    ))
+}
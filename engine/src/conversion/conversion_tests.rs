@@ -7,13 +7,14 @@
 // except according to those terms.
 
 use autocxx_parser::UnsafePolicy;
+use quote::{quote, ToTokens};
 #[allow(unused_imports)]
 use syn::parse_quote;
 use syn::ItemMod;
 
-use crate::CppCodegenOptions;
+use crate::{types::QualifiedName, AutocxxgenHeaderNamer, CppCodegenOptions};
 
-use super::BridgeConverter;
+use super::{cache::ConversionCache, BridgeConverter};
 
 // This mod is for tests which take bindgen output directly.
 // This should be avoided where possible, since these tests will
@@ -27,7 +28,7 @@ use super::BridgeConverter;
 #[allow(dead_code)]
 fn do_test(input: ItemMod) {
     let tc = parse_quote! {};
-    let bc = BridgeConverter::new(&[], &tc);
+    let bc = BridgeConverter::new(&[], &tc, None, None);
     let inclusions = "".into();
     bc.convert(
         input,
@@ -44,3 +45,1724 @@ fn do_test(input: ItemMod) {
 // fn test_xyz() {
 //      do_test(parse_quote!{ /* paste bindgen output here */})
 // }
+
+/// Generates only the given symbol, returning the Rust output as a string.
+fn do_symbol_test(input: ItemMod, symbol: &str) -> String {
+    let tc = parse_quote! { generate_all!() };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let inclusions = "".into();
+    let results = bc
+        .convert_symbol(
+            input,
+            UnsafePolicy::AllFunctionsUnsafe,
+            inclusions,
+            &CppCodegenOptions::default(),
+            &QualifiedName::new_from_cpp_name(symbol),
+        )
+        .unwrap();
+    let rs = results.rs;
+    quote! { #(#rs)* }.to_string()
+}
+
+/// Bindings for:
+/// ```cpp
+/// struct Apple {};
+/// struct Banana {};
+/// void eat_apple(Apple*);
+/// void eat_banana(Banana*);
+/// ```
+fn fruit_bindings() -> ItemMod {
+    parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Apple {
+                    pub _address: u8,
+                }
+                #[repr(C)]
+                pub struct Banana {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    pub fn eat_apple(apple: *mut root::Apple);
+                }
+                extern "C" {
+                    pub fn eat_banana(banana: *mut root::Banana);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_convert_symbol_follows_dependencies() {
+    let rs = do_symbol_test(fruit_bindings(), "eat_apple");
+    assert!(rs.contains("eat_apple"));
+    assert!(rs.contains("Apple"));
+    assert!(!rs.contains("eat_banana"));
+    assert!(!rs.contains("Banana"));
+}
+
+#[test]
+fn test_convert_symbol_ignores_dependents() {
+    let rs = do_symbol_test(fruit_bindings(), "Banana");
+    assert!(rs.contains("Banana"));
+    assert!(!rs.contains("eat_banana"));
+    assert!(!rs.contains("Apple"));
+}
+
+#[test]
+fn test_convert_symbol_not_found() {
+    let tc = parse_quote! { generate_all!() };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let err = bc
+        .convert_symbol(
+            fruit_bindings(),
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+            &QualifiedName::new_from_cpp_name("eat_cherry"),
+        )
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "The requested symbol `eat_cherry` was not found"
+    );
+}
+
+#[test]
+fn test_conversion_cache_hit() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let tc = parse_quote! { generate_all!() };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let options = CppCodegenOptions {
+        conversion_cache_dir: Some(cache_dir.path().to_path_buf()),
+        ..Default::default()
+    };
+    let convert = || {
+        bc.convert(
+            fruit_bindings(),
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &options,
+        )
+        .unwrap()
+    };
+    let first = convert();
+    let first_rs = &first.rs;
+    assert!(quote! { #(#first_rs)* }.to_string().contains("eat_apple"));
+    // Replace the cached results with something we'd never generate, so
+    // that we can tell whether the second conversion used them.
+    let key = bc.input_hash(
+        &fruit_bindings(),
+        &UnsafePolicy::AllFunctionsUnsafe,
+        "",
+        &first.cxxgen_header_name,
+        &options,
+    );
+    let cache = ConversionCache::new(cache_dir.path().to_path_buf());
+    let mut altered = cache.get(&key).expect("first conversion wasn't cached");
+    altered.rs = vec![parse_quote! { struct FromTheCache; }];
+    cache.put(&key, &altered).unwrap();
+    let second = convert();
+    let second_rs = &second.rs;
+    assert_eq!(
+        quote! { #(#second_rs)* }.to_string(),
+        quote! { struct FromTheCache; }.to_string()
+    );
+    assert_eq!(second.bridge_items.len(), first.bridge_items.len());
+    assert_eq!(
+        second.cpp.map(|cpp| cpp.header),
+        first.cpp.map(|cpp| cpp.header)
+    );
+}
+
+#[test]
+fn test_conversion_cache_key() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let tc = parse_quote! { generate_all!() };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let options = CppCodegenOptions {
+        conversion_cache_dir: Some(cache_dir.path().to_path_buf()),
+        ..Default::default()
+    };
+    let key_for = |options: &CppCodegenOptions| {
+        bc.input_hash(
+            &fruit_bindings(),
+            &UnsafePolicy::AllFunctionsUnsafe,
+            "",
+            "cxxgen.h",
+            options,
+        )
+    };
+    let key = key_for(&options);
+    assert_eq!(key, key_for(&options));
+    let renamed_header = CppCodegenOptions {
+        autocxxgen_header_namer: AutocxxgenHeaderNamer(Box::new(|_| "renamed.h".into())),
+        ..Default::default()
+    };
+    let other_key = key_for(&renamed_header);
+    assert_ne!(key, other_key);
+    // An entry which finds its way under the wrong name mustn't be used.
+    let results = bc
+        .convert(
+            fruit_bindings(),
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &options,
+        )
+        .unwrap();
+    let cache = ConversionCache::new(cache_dir.path().to_path_buf());
+    cache.put(&key, &results).unwrap();
+    assert!(cache.get(&key).is_some());
+    std::fs::copy(
+        cache_dir.path().join(format!("{}.autocxx", key)),
+        cache_dir.path().join(format!("{}.autocxx", other_key)),
+    )
+    .unwrap();
+    assert!(cache.get(&other_key).is_none());
+}
+
+#[test]
+fn test_convert_from_tokens() {
+    let tc = parse_quote! { generate_all!() };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let rs_string = |results: super::CodegenResults| {
+        let rs = results.rs;
+        quote! { #(#rs)* }.to_string()
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bindgen.rs");
+    std::fs::write(&path, fruit_bindings().to_token_stream().to_string()).unwrap();
+    let from_tokens = bc
+        .convert_from_tokens(
+            &path,
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let direct = bc
+        .convert(
+            fruit_bindings(),
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(rs_string(from_tokens), rs_string(direct));
+    assert!(bc
+        .convert_from_tokens(
+            &dir.path().join("missing.rs"),
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .is_err());
+}
+
+/// Converts with the given POD request, returning the resulting error.
+fn pod_request_error(pod_request: &str) -> String {
+    let tc = parse_quote! { generate_pod!(#pod_request) };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    match bc.convert(
+        fruit_bindings(),
+        UnsafePolicy::AllFunctionsUnsafe,
+        "".into(),
+        &CppCodegenOptions::default(),
+    ) {
+        Ok(_) => panic!("Expected POD request for {} to fail", pod_request),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn test_misspelled_pod_request() {
+    assert_eq!(
+        pod_request_error("Aple"),
+        "Requested POD type `Aple` was not found; did you mean `Apple`?"
+    );
+    assert_eq!(
+        pod_request_error("fruit::Banana"),
+        "Requested POD type `fruit::Banana` was not found; did you mean `Banana`?"
+    );
+    assert_eq!(
+        pod_request_error("Durian"),
+        "Requested POD type `Durian` was not found"
+    );
+}
+
+#[test]
+fn test_trace_calls_records_primitive_params() {
+    let tc = parse_quote! { generate_all!() trace_calls!() };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            parse_quote! {
+                mod bindgen {
+                    pub mod root {
+                        #[repr(C)]
+                        pub struct Apple {
+                            pub _address: u8,
+                        }
+                        extern "C" {
+                            pub fn count_fruit(n: u32, m: ::std::os::raw::c_int, apple: *mut root::Apple) -> u32;
+                        }
+                    }
+                }
+            },
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains(
+        "autocxx :: tracing :: trace_span ! (\"count_fruit\" , n = ? n , m = ? m) . entered ()"
+    )); // A constructor only calls into C++ when it's emplaced, so that's
+        // when the span is entered.
+    assert!(rs.contains(
+        "by_raw (move | this | { let _autocxx_trace_span = autocxx :: tracing :: trace_span ! (\"new\") . entered () ;"
+    ));
+}
+
+/// Bindings for:
+/// ```cpp
+/// namespace fruit {
+///     enum Color { Red, Green };
+/// }
+/// struct Apple {
+///     void tint(fruit::Color c);
+/// };
+/// fruit::Color paint(fruit::Color c);
+/// ```
+fn enum_param_bindings() -> ItemMod {
+    parse_quote! {
+        mod bindgen {
+            pub mod root {
+                pub mod fruit {
+                    #[repr(u32)]
+                    #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+                    pub enum Color {
+                        Red = 0,
+                        Green = 1,
+                    }
+                }
+                #[repr(C)]
+                pub struct Apple {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("tint"))]
+                    #[link_name = "\u{1}_ZN5Apple4tintEN5fruit5ColorE"]
+                    pub fn Apple_tint(this: *mut root::Apple, c: root::fruit::Color);
+                }
+                impl Apple {
+                    #[inline]
+                    pub unsafe fn tint(&mut self, c: root::fruit::Color) {
+                        Apple_tint(self, c)
+                    }
+                }
+                extern "C" {
+                    pub fn paint(c: root::fruit::Color) -> root::fruit::Color;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_enum_params_use_generated_enum() {
+    for (representation, generated) in [
+        (quote! { rust_enum }, "pub enum Color"),
+        (quote! { newtype }, "pub struct Color (pub u32)"),
+    ] {
+        let tc = parse_quote! {
+            generate!("Apple")
+            generate!("paint")
+            enum_representation!(#representation)
+        };
+        let bc = BridgeConverter::new(&[], &tc, None, None);
+        let results = bc
+            .convert(
+                enum_param_bindings(),
+                UnsafePolicy::AllFunctionsSafe,
+                "".into(),
+                &CppCodegenOptions::default(),
+            )
+            .unwrap();
+        let rs = results.rs;
+        let rs = quote! { #(#rs)* }.to_string();
+        assert!(rs.contains(generated));
+        assert!(rs.contains("type Color = super :: bindgen :: root :: fruit :: Color ;"));
+        assert!(rs.contains("pub fn tint (self : Pin < & mut Apple > , c : Color) ;"));
+        assert!(rs.contains("pub fn paint (c : Color) -> Color ;"));
+    }
+}
+
+/// Returns the names of the functions within the `cxx::bridge`, and
+/// whether each has a `cxx_name` attribute.
+fn bridge_fns_with_cxx_names(tc: autocxx_parser::IncludeCppConfig) -> Vec<(String, bool)> {
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            enum_param_bindings(),
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let bridge = find_mod(&results.rs, "cxxbridge").expect("no cxx::bridge mod");
+    bridge
+        .content
+        .as_ref()
+        .unwrap()
+        .1
+        .iter()
+        .filter_map(|item| match item {
+            // We emit `unsafe extern "C++"`, which syn can only represent
+            // verbatim.
+            syn::Item::Verbatim(ts) => {
+                syn::parse2::<syn::ItemForeignMod>(ts.clone().into_iter().skip(1).collect()).ok()
+            }
+            _ => None,
+        })
+        .flat_map(|fm| fm.items)
+        .filter_map(|item| match item {
+            syn::ForeignItem::Fn(f) => Some((
+                f.sig.ident.to_string(),
+                f.attrs.iter().any(|attr| attr.path.is_ident("cxx_name")),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the mod with the given name, however deeply it's nested.
+fn find_mod<'a>(items: &'a [syn::Item], name: &str) -> Option<&'a ItemMod> {
+    items.iter().find_map(|item| match item {
+        syn::Item::Mod(m) if m.ident == name => Some(m),
+        syn::Item::Mod(m) => m
+            .content
+            .as_ref()
+            .and_then(|(_, items)| find_mod(items, name)),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_non_exhaustive_enums() {
+    for (representation, non_exhaustive) in
+        [(quote! { rust_enum }, true), (quote! { newtype }, false)]
+    {
+        let tc = parse_quote! {
+            generate!("paint")
+            enum_representation!(#representation)
+            non_exhaustive_enums!()
+        };
+        let bc = BridgeConverter::new(&[], &tc, None, None);
+        let results = bc
+            .convert(
+                enum_param_bindings(),
+                UnsafePolicy::AllFunctionsSafe,
+                "".into(),
+                &CppCodegenOptions::default(),
+            )
+            .unwrap();
+        let rs = results.rs;
+        let rs = quote! { #(#rs)* }.to_string();
+        assert_eq!(
+            rs.contains("# [non_exhaustive] pub enum Color"),
+            non_exhaustive
+        );
+        assert_eq!(rs.contains("non_exhaustive"), non_exhaustive);
+    }
+}
+
+#[test]
+fn test_explicit_cxx_names() {
+    let fns = bridge_fns_with_cxx_names(parse_quote! { generate_all!() });
+    assert!(fns.contains(&("paint".into(), false)));
+    let fns = bridge_fns_with_cxx_names(parse_quote! {
+        generate_all!()
+        explicit_cxx_names!()
+    });
+    assert!(fns.contains(&("paint".into(), true)));
+    assert!(fns.contains(&("tint".into(), true)));
+    assert!(fns.iter().all(|(_, has_cxx_name)| *has_cxx_name));
+}
+
+#[test]
+fn test_reference_params() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Apple {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("absorb"))]
+                    #[link_name = "\u{1}_ZN5Apple6absorbEPS_"]
+                    pub fn Apple_absorb(this: *mut root::Apple, other: *mut root::Apple);
+                }
+                impl Apple {
+                    #[inline]
+                    pub unsafe fn absorb(&mut self, other: *mut root::Apple) {
+                        Apple_absorb(self, other)
+                    }
+                }
+                extern "C" {
+                    pub fn total(c: *const root::Apple) -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Apple")
+        generate!("total")
+        reference_param!("Apple::absorb", "other")
+        reference_param!("total", "c")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains("other : Pin < & mut Apple >"));
+    assert!(rs.contains("pub fn total_autocxx_wrapper (c : & Apple) -> u32 ;"));
+    let header = String::from_utf8(results.cpp.unwrap().header).unwrap();
+    assert!(header.contains("autocxx_gen_this.absorb(&arg1);"));
+    assert!(header.contains("const Apple& arg0"));
+    assert!(header.contains("return total(&arg0);"));
+}
+
+#[test]
+fn test_check_signatures() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Label {
+                    pub _address: u8,
+                }
+                pub mod text {
+                    extern "C" {
+                        pub fn describe(n: u32) -> root::Label;
+                        pub fn rename(l: *const root::Label) -> root::Label;
+                    }
+                }
+                extern "C" {
+                    pub fn scale(n: u32) -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Label")
+        generate!("text::describe")
+        generate!("text::rename")
+        generate!("scale")
+        check_signatures!()
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let implementation = String::from_utf8(results.cpp.unwrap().implementation.unwrap()).unwrap();
+    assert!(implementation.contains(
+        "_signature_check = decltype(static_cast<Label(*)(uint32_t)>(&text::describe));"
+    ));
+    // We can't tell whether a pointer was a reference in the original C++,
+    // and functions without wrappers are already checked by cxx.
+    assert!(!implementation.contains("&text::rename"));
+    assert!(!implementation.contains("&scale"));
+    assert_eq!(implementation.matches("_signature_check").count(), 1);
+}
+
+#[test]
+fn test_nested_vectors() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                extern "C" {
+                    pub fn make_grid() -> root::std::vector<root::std::vector<autocxx::c_int>>;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("make_grid")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains(
+        "fn make_grid_autocxx_wrapper () -> UniquePtr < CxxVector < std_vector_int32_t_AutocxxConcrete > > ;"
+    ));
+    assert!(rs.contains("impl CxxVector < std_vector_int32_t_AutocxxConcrete > { }"));
+    assert!(rs.contains("type Target = cxx :: CxxVector < i32 > ;"));
+    let header = String::from_utf8(results.cpp.unwrap().header).unwrap();
+    assert!(header.contains("typedef std::vector<int32_t> std_vector_int32_t_AutocxxConcrete;"));
+}
+
+#[test]
+fn test_function_table_builder() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Ops {
+                    pub context: *mut ::std::os::raw::c_void,
+                    pub version: ::std::os::raw::c_int,
+                    pub read: ::std::option::Option<
+                        unsafe extern "C" fn(
+                            context: *mut ::std::os::raw::c_void,
+                            n: ::std::os::raw::c_int,
+                        ) -> ::std::os::raw::c_int,
+                    >,
+                    pub close: ::std::option::Option<unsafe extern "C" fn()>,
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate_pod!("Ops")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub struct OpsBuilder {"));
+    assert!(rs.contains("pub fn set_read("));
+    assert!(rs.contains("pub unsafe fn bind_read<F>(mut self, f: F) -> Self"));
+    assert!(rs.contains("pub fn set_close(mut self, f: unsafe extern \"C\" fn()) -> Self"));
+    // close isn't given the context, so can't call a closure.
+    assert!(!rs.contains("bind_close"));
+    assert!(rs.contains("pub fn build(self) -> autocxx::FunctionTable<Ops>"));
+    assert!(rs.contains("pub use bindgen::root::OpsBuilder;"));
+}
+
+#[test]
+fn test_function_table_builder_name_taken() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Ops {
+                    pub close: ::std::option::Option<unsafe extern "C" fn()>,
+                }
+                #[repr(C)]
+                pub struct OpsBuilder {
+                    pub a: u32,
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate_pod!("Ops")
+        generate_pod!("OpsBuilder")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub a: u32"));
+    assert!(!rs.contains("set_close"));
+}
+
+#[test]
+fn test_conditional_pod() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Here {
+                    pub a: u32,
+                }
+                #[repr(C)]
+                pub struct Elsewhere {
+                    pub a: u32,
+                }
+            }
+        }
+    };
+    // Outside a build script, predicates are evaluated for the host.
+    let os = std::env::consts::OS;
+    let tc = parse_quote! {
+        generate_pod_if!("Here", target_os = #os)
+        generate_pod_if!("Elsewhere", target_pointer_width = "8")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("type Id = cxx::type_id!(\"Here\");\n    type Kind = cxx::kind::Trivial;"));
+    assert!(
+        rs.contains("type Id = cxx::type_id!(\"Elsewhere\");\n    type Kind = cxx::kind::Opaque;")
+    );
+    assert!(rs.contains(&format!("#[cfg(not(target_os = \"{}\"))]", os)));
+    assert!(rs.contains("Here was generated as POD"));
+    assert!(rs.contains("#[cfg(target_pointer_width = \"8\")]"));
+    assert!(rs.contains("Elsewhere was generated as opaque"));
+}
+
+#[test]
+fn test_factory() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Widget {
+                    pub size: u32,
+                }
+                #[repr(C)]
+                pub struct Gadget {
+                    pub size: u32,
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z13create_widgetj"]
+                    pub fn create_widget(size: u32) -> *mut root::Widget;
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z13create_gadgetj"]
+                    pub fn create_gadget(size: u32) -> *mut root::Widget;
+                }
+                #[repr(C)]
+                pub struct Pool {
+                    pub size: u32,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("create"))]
+                    #[link_name = "\u{1}_ZN4Pool6createEj"]
+                    pub fn Pool_create(size: u32) -> *mut root::Pool;
+                }
+                impl Pool {
+                    #[inline]
+                    pub unsafe fn create(size: u32) -> *mut root::Pool {
+                        Pool_create(size)
+                    }
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Widget")
+        generate!("Gadget")
+        generate!("Pool")
+        generate!("create_widget")
+        generate!("create_gadget")
+        factory!("Widget", "create_widget")
+        factory!("Gadget", "create_gadget")
+        factory!("Pool", "Pool::create")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub fn make_unique(size: u32) -> cxx::UniquePtr<root::Widget>"));
+    assert!(rs.contains("doesn't return a non-const pointer to Gadget"));
+    let header = String::from_utf8(results.cpp.unwrap().header).unwrap();
+    assert!(header.contains("return std::unique_ptr<Widget>(create_widget(arg0));"));
+    assert!(header.contains("return std::unique_ptr<Pool>(Pool::create(arg0));"));
+}
+
+#[test]
+fn test_nullable_return() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Child {
+                    pub id: u32,
+                }
+                #[repr(C)]
+                pub struct Tree {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("current"))]
+                    #[cpp_semantics(ret_type_reference)]
+                    #[link_name = "\u{1}_ZNK4Tree7currentEv"]
+                    pub fn Tree_current(this: *const root::Tree) -> *const root::Child;
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z11child_countv"]
+                    pub fn child_count() -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate_pod!("Child")
+        generate!("Tree")
+        generate!("child_count")
+        nullable_return!("Tree::current")
+        nullable_return!("child_count")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub fn current(self: &root::Tree) -> Option<&root::Child>"));
+    assert!(rs.contains("cxxbridge::current_autocxx_wrapper(self).as_ref()"));
+    // There's nothing to check for null here, so rather than silently
+    // ignoring the directive we refuse to generate the function.
+    assert!(rs.contains("marked with nullable_return!, but it doesn't return a reference"));
+    assert!(!rs.contains("pub fn child_count"));
+}
+
+#[test]
+fn test_reference_to_pointer_param() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Foo {
+                    pub val: u32,
+                }
+                extern "C" {
+                    #[cpp_semantics(arg_type_reference(out))]
+                    #[link_name = "\u{1}_Z7acquireRP3Foo"]
+                    pub fn acquire(out: *mut *mut root::Foo);
+                }
+                extern "C" {
+                    #[cpp_semantics(arg_type_reference(cursor))]
+                    #[link_name = "\u{1}_Z7advanceRP3Foo"]
+                    pub fn advance(cursor: *mut *mut root::Foo);
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Foo")
+        generate!("acquire")
+        generate!("advance")
+        owning_out_param!("acquire", "out")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub fn acquire() -> cxx::UniquePtr<root::Foo>"));
+    // Without owning_out_param!, we can't assume C++ gives us ownership.
+    assert!(rs.contains("pub unsafe fn advance_autocxx_wrapper(cursor: *mut *mut Foo);"));
+    assert!(rs.contains("pub use cxxbridge::advance_autocxx_wrapper as advance;"));
+}
+
+#[test]
+fn test_out_param_requires_pod() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Foo {
+                    pub val: u32,
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z9get_valuePi"]
+                    pub fn get_value(out: *mut i32);
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z8get_fooP3Foo"]
+                    pub fn get_foo(out: *mut root::Foo);
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Foo")
+        generate!("get_value")
+        generate!("get_foo")
+        out_param!("get_value", "out")
+        out_param!("get_foo", "out")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub fn get_value() -> i32"));
+    // Foo isn't POD, so C++ can't safely assign into uninitialized memory.
+    assert!(!rs.contains("pub fn get_foo"));
+    assert!(rs.contains("but it refers to something other than a primitive or POD type"));
+}
+
+#[test]
+fn test_c_string_slice_params() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                extern "C" {
+                    #[link_name = "\u{1}_Z9total_leniPPc"]
+                    pub fn total_len(
+                        argc: ::std::os::raw::c_int,
+                        argv: *mut *mut ::std::os::raw::c_char,
+                    ) -> ::std::os::raw::c_int;
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z5countPKPKcm"]
+                    pub fn count(
+                        items: *const *const ::std::os::raw::c_char,
+                        n: usize,
+                    ) -> usize;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("total_len")
+        generate!("count")
+        slice_param!("total_len", "argv", "argc")
+        slice_param!("count", "items", "n")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    // C++ could modify the strings through a char**.
+    assert!(rs.contains("pub unsafe fn total_len(argv: &[&::std::ffi::CStr])"));
+    assert!(rs.contains("pub fn count(items: &[&::std::ffi::CStr])"));
+    assert!(rs.contains(".chain(::std::iter::once(::std::ptr::null_mut()))"));
+}
+
+#[test]
+fn test_pod_by_default_skips_user_declared_destructors() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Point {
+                    pub x: i32,
+                }
+                #[repr(C)]
+                pub struct Tracked {
+                    pub x: i32,
+                }
+                #[repr(C)]
+                pub struct Wrapper {
+                    pub t: root::Tracked,
+                }
+                extern "C" {
+                    #[cpp_semantics(special_member("dtor"))]
+                    #[link_name = "\u{1}_ZN7TrackedD1Ev"]
+                    pub fn Tracked_Tracked_destructor(this: *mut root::Tracked);
+                }
+                impl Tracked {
+                    #[inline]
+                    pub unsafe fn destruct(&mut self) {
+                        Tracked_Tracked_destructor(self)
+                    }
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Point")
+        generate!("Tracked")
+        generate!("Wrapper")
+        pod_by_default!()
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("type Id = cxx::type_id!(\"Point\");\n    type Kind = cxx::kind::Trivial;"));
+    assert!(
+        rs.contains("type Id = cxx::type_id!(\"Tracked\");\n    type Kind = cxx::kind::Opaque;")
+    );
+    // Wrapper would need a bytewise copy of its Tracked too.
+    assert!(
+        rs.contains("type Id = cxx::type_id!(\"Wrapper\");\n    type Kind = cxx::kind::Opaque;")
+    );
+}
+
+#[test]
+fn test_identity_eq_pod() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Point {
+                    pub x: i32,
+                }
+                #[repr(C)]
+                pub struct Node {
+                    pub x: i32,
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate_pod!("Point")
+        generate!("Node")
+        identity_eq!("Point")
+        identity_eq!("Node")
+        pod_by_default!()
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("marked with identity_eq!, but it's POD"));
+    // Node would otherwise be made POD by default.
+    assert!(rs.contains("type Id = cxx::type_id!(\"Node\");\n    type Kind = cxx::kind::Opaque;"));
+    assert!(rs.contains("impl PartialEq for Node"));
+}
+
+#[test]
+fn test_generator_without_next() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Countdown {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("advance"))]
+                    #[link_name = "\u{1}_ZN9Countdown7advanceEv"]
+                    pub fn Countdown_advance(this: *mut root::Countdown) -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Countdown")
+        generator!("Countdown")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("marked with generator!, but it doesn't have a next() method"));
+    assert!(!rs.contains("CppGenerator"));
+}
+
+fn safe_getter_output(tc: autocxx_parser::IncludeCppConfig) -> String {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Counter {
+                    pub count: u32,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("get_count"))]
+                    #[link_name = "\u{1}_ZNK7Counter9get_countEv"]
+                    pub fn Counter_get_count(this: *const root::Counter) -> u32;
+                }
+            }
+        }
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    quote! { #(#rs)* }.to_string()
+}
+
+#[test]
+fn test_safe_getters() {
+    // Under this unsafe policy, getters are unsafe unless we've been told
+    // to treat them as safe.
+    let rs = safe_getter_output(parse_quote! {
+        generate_pod!("Counter")
+    });
+    assert!(rs.contains("pub unsafe fn get_count (self : & Counter) -> u32 ;"));
+    let rs = safe_getter_output(parse_quote! {
+        generate_pod!("Counter")
+        safe_getters!()
+    });
+    assert!(rs.contains("pub fn get_count (self : & Counter) -> u32 ;"));
+}
+
+fn pre_drop_output(tc: autocxx_parser::IncludeCppConfig) -> String {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Connection {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("flush"))]
+                    #[link_name = "\u{1}_ZN10Connection5flushEv"]
+                    pub fn Connection_flush(this: *mut root::Connection);
+                }
+                impl Connection {
+                    #[inline]
+                    pub unsafe fn flush(&mut self) {
+                        Connection_flush(self)
+                    }
+                }
+            }
+        }
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsUnsafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    prettyplease::unparse(&parse_quote! { #(#rs)* })
+}
+
+#[test]
+fn test_unsafe_pre_drop_hook() {
+    // Under this unsafe policy, flush is an unsafe function, which a handle
+    // mustn't call unless told it may.
+    let rs = pre_drop_output(parse_quote! {
+        generate!("Connection")
+        pre_drop!("Connection", "flush")
+    });
+    assert!(rs.contains("::std::compile_error!("));
+    assert!(!rs.contains("impl Drop for ConnectionHandle"));
+    let rs = pre_drop_output(parse_quote! {
+        generate!("Connection")
+        pre_drop!(unsafe "Connection", "flush")
+    });
+    assert!(!rs.contains("compile_error!"));
+    assert!(rs.contains("impl Drop for ConnectionHandle"));
+    assert!(rs.contains("obj.flush();"));
+}
+
+/// Captures the conversion trace logged by the current thread.
+struct TraceCapture;
+
+thread_local! {
+    static TRACE_LINES: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+impl log::Log for TraceCapture {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == super::TRACE_TARGET
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            TRACE_LINES.with(|lines| lines.borrow_mut().push(record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static TRACE_CAPTURE: TraceCapture = TraceCapture;
+
+#[test]
+fn test_conversion_trace() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Apple {
+                    pub weight: u32,
+                }
+                #[repr(C)]
+                pub struct Banana {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("eat"))]
+                    #[link_name = "\u{1}_Z3eatP5Apple"]
+                    pub fn eat(apple: *mut root::Apple);
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("eat"))]
+                    #[link_name = "\u{1}_Z3eatP6Banana"]
+                    pub fn eat1(banana: *mut root::Banana);
+                }
+                extern "C" {
+                    pub fn weigh_all(n: u32, ...) -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate_pod!("Apple")
+        generate!("Banana")
+        generate!("eat")
+        generate!("weigh_all")
+    };
+    // Other tests may also log, but we only capture from this thread.
+    let _ = log::set_logger(&TRACE_CAPTURE);
+    log::set_max_level(log::LevelFilter::Debug);
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    bc.convert(
+        m,
+        UnsafePolicy::AllFunctionsSafe,
+        "".into(),
+        &CppCodegenOptions::default(),
+    )
+    .unwrap();
+    let lines = TRACE_LINES.with(|lines| lines.take());
+    assert!(lines.contains(&"Apple: POD".to_string()));
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with("Banana: non-POD: ") && l.contains("generate_pod!")));
+    assert!(lines.contains(&"eat1: eat is called eat1 in Rust".to_string()));
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with("weigh_all: ignored: Variadic")));
+}
+
+#[test]
+fn test_large_return_has_no_hidden_pointer() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Big {
+                    pub data: [u64; 64usize],
+                }
+                #[repr(C)]
+                pub struct Opaque {
+                    pub data: [u64; 64usize],
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z8make_bigj"]
+                    pub fn make_big(seed: u32) -> root::Big;
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z11make_opaquej"]
+                    pub fn make_opaque(seed: u32) -> root::Opaque;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate_pod!("Big")
+        generate!("Opaque")
+        generate!("make_big")
+        generate!("make_opaque")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    // bindgen describes these functions as C++ declares them, not as the
+    // ABI lowers them, so there's no hidden return pointer to fold away.
+    // The POD is returned by value, and the non-POD is constructed in
+    // place by our own C++ wrapper.
+    assert!(rs.contains("pub fn make_big (seed : u32) -> Big ;"));
+    assert!(rs.contains(
+        "pub fn make_opaque (seed : u32) -> impl autocxx :: moveit :: new :: New < Output = root :: Opaque >"
+    ));
+    let header = String::from_utf8(results.cpp.unwrap().header).unwrap();
+    assert!(header.contains("new(arg1) Opaque(make_opaque(arg0));"));
+}
+
+#[test]
+fn test_const_and_mutable_overloads() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Row {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("at"))]
+                    #[cpp_semantics(ret_type_reference)]
+                    #[link_name = "\u{1}_ZNK3Row2atEj"]
+                    pub fn Row_at(this: *const root::Row, i: u32) -> *const u32;
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("at"))]
+                    #[cpp_semantics(ret_type_reference)]
+                    #[link_name = "\u{1}_ZN3Row2atEj"]
+                    pub fn Row_at1(this: *mut root::Row, i: u32) -> *mut u32;
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("len"))]
+                    #[link_name = "\u{1}_ZNK3Row3lenEv"]
+                    pub fn Row_len(this: *const root::Row) -> u32;
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("len"))]
+                    #[link_name = "\u{1}_ZNK3Row3lenEj"]
+                    pub fn Row_len1(this: *const root::Row, from: u32) -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Row")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains("fn at < 'a > (self : & 'a Row , i : u32) -> & 'a u32"));
+    assert!(rs.contains("fn at_mut < 'a > (self : Pin < & 'a mut Row > , i : u32)"));
+    assert!(!rs.contains("fn at1"));
+    // Overloads which aren't simply const and non-const are numbered as usual.
+    assert!(rs.contains("fn len1 (self : & Row , from : u32) -> u32"));
+}
+
+#[test]
+fn test_copy_return_names_class() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct Point {
+                    pub x: u32,
+                }
+                #[repr(C)]
+                pub struct Space {
+                    pub o: root::Point,
+                }
+                #[repr(C)]
+                pub struct Shape {
+                    pub o: root::Point,
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("origin"))]
+                    #[cpp_semantics(ret_type_reference)]
+                    #[link_name = "\u{1}_ZNK5Space6originEv"]
+                    pub fn Space_origin(this: *const root::Space) -> *const root::Point;
+                }
+                extern "C" {
+                    #[cpp_semantics(original_name("origin"))]
+                    #[cpp_semantics(ret_type_reference)]
+                    #[link_name = "\u{1}_ZNK5Shape6originEv"]
+                    pub fn Shape_origin(this: *const root::Shape) -> *const root::Point;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("Space")
+        generate!("Shape")
+        generate_pod!("Point")
+        copy_return!("Space::origin")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains("fn origin (self : & root :: Space) -> root :: Point"));
+    // A bare method name would have matched this one too.
+    assert!(rs.contains("fn origin < 'a > (self : & 'a root :: Shape) -> & 'a root :: Point"));
+}
+
+#[test]
+fn test_rust_slice_param() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                extern "C" {
+                    #[link_name = "\u{1}_Z8checksumN4rust6cxxbridge15SliceIKhEE"]
+                    pub fn checksum(data: root::rust::Slice<u8>) -> u32;
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z10double_allN4rust6cxxbridge15SliceIjEE"]
+                    pub fn double_all(data: root::rust::Slice<u32>);
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("checksum")
+        generate!("double_all")
+        const_slice_param!("checksum", "data")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains("pub fn checksum (data : & [u8]) -> u32 ;"));
+    assert!(!rs.contains("checksum_autocxx_wrapper"));
+    assert!(rs.contains("pub fn double_all_autocxx_wrapper (data : & mut [u32]) ;"));
+    let header = String::from_utf8(results.cpp.unwrap().header).unwrap();
+    assert!(header.contains("double_all_autocxx_wrapper(rust::Slice<uint32_t> arg0)"));
+    assert!(header.contains("double_all({arg0.data(), arg0.size()});"));
+}
+
+#[test]
+fn test_const_span_param() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                extern "C" {
+                    #[link_name = "\u{1}_Z3sumSt4spanIKjLm18446744073709551615EE"]
+                    pub fn sum(s: root::std::span<u32>) -> u32;
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z10double_allSt4spanIjLm18446744073709551615EE"]
+                    pub fn double_all(s: root::std::span<u32>);
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("sum")
+        generate!("double_all")
+        const_slice_param!("sum", "s")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains("pub fn sum_autocxx_wrapper (s : & [u32]) -> u32 ;"));
+    assert!(rs.contains("pub fn double_all_autocxx_wrapper (s : & mut [u32]) ;"));
+}
+
+#[test]
+fn test_void_unique_ptr_return() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                pub struct PluginDeleter {
+                    pub _address: u8,
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z11load_pluginj"]
+                    pub fn load_plugin(id: u32) -> root::std::unique_ptr<autocxx::c_void, root::PluginDeleter>;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("load_plugin")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let handle = tc.uniquify_name_per_mod("OpaqueHandle");
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains(&format!("{handle} as OpaqueHandle ;")));
+    let header = String::from_utf8(results.cpp.unwrap().header).unwrap();
+    assert!(header.contains(&format!("class {handle} {{")));
+    assert!(header.contains(&format!("return {handle}::from(load_plugin(arg0));")));
+}
+
+#[test]
+fn test_volatile_param() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                extern "C" {
+                    #[cpp_semantics(arg_type_volatile(reg))]
+                    #[link_name = "\u{1}_Z9write_regPVjj"]
+                    pub fn write_reg(reg: *mut u32, val: u32);
+                }
+                extern "C" {
+                    #[link_name = "\u{1}_Z7get_tenv"]
+                    pub fn get_ten() -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("write_reg")
+        generate!("get_ten")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = prettyplease::unparse(&parse_quote! { #(#rs)* });
+    assert!(rs.contains("pub fn get_ten"));
+    assert!(!rs.contains("pub fn write_reg"));
+    assert!(rs.contains("The parameter reg points or refers to something volatile"));
+}
+
+#[test]
+fn test_deprecated() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                #[repr(C)]
+                #[cpp_semantics(deprecated)]
+                pub struct Point {
+                    pub x: u32,
+                }
+                extern "C" {
+                    #[cpp_semantics(deprecated("Use add_two instead."))]
+                    #[link_name = "\u{1}_Z7add_onej"]
+                    pub fn add_one(a: u32) -> u32;
+                }
+                extern "C" {
+                    #[doc = " @deprecatedness isn't a Doxygen command."]
+                    #[link_name = "\u{1}_Z7add_twoj"]
+                    pub fn add_two(a: u32) -> u32;
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("add_one")
+        generate!("add_two")
+        generate_pod!("Point")
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    assert!(rs.contains("# [deprecated] pub struct Point"));
+    assert!(rs.contains("# [deprecated (note = \"Use add_two instead.\")] pub fn add_one"));
+    assert!(rs.contains("# [doc = \" @deprecatedness isn't a Doxygen command.\"] pub fn add_two"));
+    // Only our own uses of deprecated items are allowed.
+    assert!(rs.contains("# [allow (deprecated)] pub use cxxbridge :: add_one ;"));
+    assert!(rs.contains("; pub use cxxbridge :: add_two ;"));
+}
+
+#[test]
+fn test_split_by_namespace_bridges() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                pub mod a {
+                    extern "C" {
+                        #[link_name = "\u{1}_ZN1a8give_intEv"]
+                        pub fn give_int() -> u32;
+                    }
+                }
+                pub mod b {
+                    extern "C" {
+                        #[link_name = "\u{1}_ZN1b8give_intEv"]
+                        pub fn give_int() -> u32;
+                    }
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("a::give_int")
+        generate!("b::give_int")
+        exclude_utilities!()
+        split_by_namespace!()
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    for ns in ["a", "b"] {
+        let bridge = format!("# [cxx :: bridge] mod cxxbridge_ns_{}", ns);
+        assert!(rs.contains(&bridge));
+        let reexport = format!("pub use super :: cxxbridge_ns_{} :: * ;", ns);
+        assert!(rs.contains(&reexport));
+    }
+    assert!(rs.contains("mod cxxbridge {"));
+    assert!(results.bridge_items_by_namespace.is_empty());
+}
+
+#[test]
+fn test_shared_type_declarations_merged_bridge() {
+    let m: ItemMod = parse_quote! {
+        mod bindgen {
+            pub mod root {
+                pub mod a {
+                    #[repr(C)]
+                    pub struct Thing {
+                        pub _address: u8,
+                    }
+                }
+                pub mod b {
+                    extern "C" {
+                        #[link_name = "\u{1}_ZN1b5touchEPN1a5ThingE"]
+                        pub fn touch(thing: *mut root::a::Thing);
+                    }
+                }
+            }
+        }
+    };
+    let tc = parse_quote! {
+        generate!("a::Thing")
+        generate!("b::touch")
+        exclude_utilities!()
+        split_by_namespace!()
+        shared_type_declarations!()
+    };
+    let bc = BridgeConverter::new(&[], &tc, None, None);
+    let results = bc
+        .convert(
+            m,
+            UnsafePolicy::AllFunctionsSafe,
+            "".into(),
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    let rs = results.rs;
+    let rs = quote! { #(#rs)* }.to_string();
+    let alias = "type Thing = super :: cxxbridge_types :: Thing ;";
+    assert!(rs.contains(alias));
+    let merged = results.merged_bridge_items;
+    let merged = quote! { #(#merged)* }.to_string();
+    assert!(merged.contains("fn touch"));
+    assert!(merged.contains("type Thing"));
+    assert!(!merged.contains(alias));
+}
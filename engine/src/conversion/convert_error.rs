@@ -8,6 +8,7 @@
 
 use indexmap::set::IndexSet as HashSet;
 
+use autocxx_parser::CppStd;
 use itertools::Itertools;
 use syn::Ident;
 use thiserror::Error;
@@ -21,8 +22,14 @@ use crate::{
 pub enum ConvertError {
     #[error("The initial run of 'bindgen' did not generate any content. This might be because none of the requested items for generation could be converted.")]
     NoContent,
+    #[error("Unable to read bindgen output from {0}: {1}")]
+    BindgenTokensUnreadable(String, String),
     #[error("An item was requested using 'generate_pod' which was not safe to hold by value in Rust. {0}")]
     UnsafePodType(String),
+    #[error("Requested POD type `{}` was not found{}", .0.to_cpp_name(), .1.as_ref().map(|suggestion| format!("; did you mean `{}`?", suggestion.to_cpp_name())).unwrap_or_default())]
+    PodRequestNotFound(QualifiedName, Option<QualifiedName>),
+    #[error("The requested symbol `{}` was not found", .0.to_cpp_name())]
+    SymbolNotFound(QualifiedName),
     #[error("Bindgen generated some unexpected code in a foreign mod section. You may have specified something in a 'generate' directive which is not currently compatible with autocxx.")]
     UnexpectedForeignItem,
     #[error("Bindgen generated some unexpected code in its outermost mod section. You may have specified something in a 'generate' directive which is not currently compatible with autocxx.")]
@@ -41,6 +48,60 @@ pub enum ConvertError {
     UnacceptableParam(String),
     #[error("Function {0} has a return reference parameter, but 0 or >1 input reference parameters, so the lifetime of the output reference cannot be deduced.")]
     NotOneInputReference(String),
+    #[error("std::string_view is only supported when passed or returned by value")]
+    StringViewNotByValue,
+    #[error("std::function is only supported as a parameter passed by value or const reference, and only where its arguments and return value are simple primitive types")]
+    UnsupportedStdFunction,
+    #[error("std::tuple is only supported as a return value, and only where its elements are simple primitive types")]
+    UnsupportedStdTuple,
+    #[error("std::variant is only supported as a return value, and only where its alternatives are simple primitive types")]
+    UnsupportedStdVariant,
+    #[error("std::optional is only supported as a return value, and only where its value is a simple primitive type")]
+    UnsupportedStdOptional,
+    #[error("{} requires {1} or later, but cpp_std! specifies {2}", .0.to_cpp_name())]
+    RequiresNewerCppStd(QualifiedName, CppStd, CppStd),
+    #[error("std::unique_ptr<void> is only supported as a return value")]
+    UnsupportedVoidUniquePtr,
+    #[error("std::span is only supported as a parameter passed by value, and only where its elements are simple primitive types")]
+    UnsupportedStdSpan,
+    #[error("rust::Slice is only supported as a parameter passed by value, and only where its elements are simple primitive types")]
+    UnsupportedRustSlice,
+    #[error("The parameter {0} was marked with slice_param!, but it isn't a pointer to primitive or POD types or to C strings, immediately next to the given length parameter")]
+    InvalidSliceParam(String),
+    #[error("The function {0} was marked with slice_return!, but it doesn't return a pointer to primitive or POD types")]
+    InvalidSliceReturn(String),
+    #[error("The function {0} was marked with owning_return!, but it doesn't return a non-const pointer to a type which can be held in a UniquePtr")]
+    InvalidOwningReturn(String),
+    #[error("This function was marked with nullable_return!, but it doesn't return a reference")]
+    InvalidNullableReturn,
+    #[error("The function {0} was registered with factory! as constructing {1}, but it doesn't return a non-const pointer to {1}")]
+    InvalidFactory(String, String),
+    #[error("The function {0} was registered with factory!, but it's a non-static method; only free functions and static methods can be factories")]
+    FactoryNotStatic(String),
+    #[error("The parameter {0} was marked with container_param!, but it isn't a std::string or a std::vector of primitive or POD types, passed by value or by const reference")]
+    InvalidContainerParam(String),
+    #[error("The parameter {0} was marked with reference_param!, but it isn't a pointer to a non-POD type")]
+    InvalidReferenceParam(String),
+    #[error("The parameter {0} was marked with const_slice_param!, but it isn't a std::span or rust::Slice passed by value")]
+    InvalidConstSliceParam(String),
+    #[error("The parameter {0} was marked with out_param! or inout_param!, but it refers to something other than a primitive or POD type")]
+    InvalidOutParam(String),
+    #[error("The parameter {0} was marked with owning_out_param!, but it isn't a non-const reference to a pointer to one of our types")]
+    InvalidOwningOutParam(String),
+    #[error("The parameter {0} points or refers to something volatile, which the generated bindings would not preserve")]
+    VolatileParam(String),
+    #[error("This type was marked with serde_pod!, but it isn't POD")]
+    SerdePodNotPod,
+    #[error("This type was marked with identity_eq!, but it's POD, so has no identity to compare")]
+    IdentityEqPod,
+    #[error("This type was marked with generator!, but it doesn't have a next() method which takes no parameters and returns a std::optional of a simple primitive type")]
+    GeneratorWithoutNext,
+    #[error("This type was marked with serde_pod!, but it has a field of type {0} which isn't a primitive type or another serde_pod! type")]
+    SerdePodFieldNotSerializable(String),
+    #[error("This type was marked as transparent, but only POD types with exactly one field can be transparent")]
+    TransparentTypeNotSingleFieldPod,
+    #[error("The intrusive pointer {0} is only supported with a single type argument which is a named type")]
+    UnsupportedIntrusivePtr(String),
     #[error("Encountered type not yet supported by autocxx: {0}")]
     UnsupportedType(String),
     #[error("Encountered type not yet known by autocxx: {0}")]
@@ -121,6 +182,8 @@ pub enum ConvertError {
     TypedefTakesGenericParameters,
     #[error("This method belonged to an item in an anonymous namespace, not currently supported.")]
     MethodInAnonymousNamespace,
+    #[error("This function is in an anonymous namespace and so has internal linkage, meaning it can't be linked against from Rust.")]
+    FunctionInAnonymousNamespace,
     #[error("We're unable to make a concrete version of this template, because we found an error handling the template.")]
     ConcreteVersionOfIgnoredTemplate,
     #[error("bindgen decided to call this type _bindgen_ty_N because it couldn't deduce the correct name for it. That means we can't generate C++ bindings to it.")]
@@ -6,7 +6,15 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use syn::Attribute;
+use std::collections::HashSet;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    parse_quote, Attribute, ForeignItem, ImplItem, Item, ItemForeignMod, Lit, Meta, MetaNameValue,
+};
+
+use super::parse::BindgenSemanticAttributes;
 
 /// Returns the attribute (if any) which contains a doc comment.
 pub(super) fn get_doc_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
@@ -16,3 +24,215 @@ pub(super) fn get_doc_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
         .cloned()
         .collect()
 }
+
+/// The attributes which we carry over from a C++ function to the Rust
+/// items generated for it. Doc comments go on all of them, whereas any
+/// `#[deprecated]` goes only on the item which users will call, so that
+/// calls within our own generated code don't trigger warnings.
+pub(super) struct FnAttrs {
+    doc_attrs: Vec<Attribute>,
+    deprecated_attr: Option<Attribute>,
+}
+
+impl FnAttrs {
+    pub(super) fn new(attrs: Vec<Attribute>) -> Self {
+        let deprecated_attr = get_deprecated_attr(&attrs);
+        let doc_attrs = get_doc_attrs(&attrs);
+        Self {
+            doc_attrs,
+            deprecated_attr,
+        }
+    }
+
+    /// Attributes for the item which users will call.
+    pub(super) fn for_user_facing_item(&self) -> Vec<Attribute> {
+        self.doc_attrs
+            .iter()
+            .chain(self.deprecated_attr.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Attributes for items which users won't call directly, or which
+    /// can't be deprecated, such as trait implementations.
+    pub(super) fn for_internal_item(&self) -> Vec<Attribute> {
+        self.doc_attrs.clone()
+    }
+}
+
+/// Whether an attribute on a generated item should also go on any items
+/// we generate to forward to it.
+pub(super) fn is_forwardable_attr(attr: &Attribute) -> bool {
+    attr.path.is_ident("doc") || attr.path.is_ident("deprecated")
+}
+
+/// Adds a `#[deprecated]` attribute to a struct or enum whose doc comment
+/// says it's deprecated, unless it already has one.
+pub(super) fn add_deprecated_attr(attrs: &mut Vec<Attribute>) {
+    if !attrs.iter().any(|a| a.path.is_ident("deprecated")) {
+        attrs.extend(get_deprecated_attr(attrs));
+    }
+}
+
+/// Returns a `#[deprecated]` attribute if the item is deprecated. That's
+/// either because bindgen told us it's `[[deprecated]]` in C++, or because
+/// the doc comment contains a Doxygen-style `@deprecated` (or `\deprecated`)
+/// paragraph, in which case the rest of that line is the note.
+pub(super) fn get_deprecated_attr(attrs: &[Attribute]) -> Option<Attribute> {
+    if let Some(attr) = attrs.iter().find(|a| a.path.is_ident("deprecated")) {
+        return Some(attr.clone());
+    }
+    if let Some(attr) = BindgenSemanticAttributes::new(attrs).get_deprecated_attr() {
+        return Some(attr);
+    }
+    let note = get_doc_attrs(attrs)
+        .iter()
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(s), ..
+            })) => Some(s.value()),
+            _ => None,
+        })
+        .find_map(|doc| {
+            doc.lines().find_map(|line| {
+                let line = line.trim_start().trim_start_matches('*').trim_start();
+                line.strip_prefix("@deprecated")
+                    .or_else(|| line.strip_prefix("\\deprecated"))
+                    .filter(|note| note.is_empty() || note.starts_with(char::is_whitespace))
+                    .map(|note| note.trim().to_string())
+            })
+        })?;
+    Some(if note.is_empty() {
+        parse_quote! { #[deprecated] }
+    } else {
+        parse_quote! { #[deprecated(note = #note)] }
+    })
+}
+
+/// Our generated code refers to deprecated items in all sorts of places
+/// (`use` statements, the `cxx::bridge`, trait implementations) which
+/// would otherwise each produce a warning. Adds `#[allow(deprecated)]` to
+/// each top-level item, or impl item, which mentions something deprecated,
+/// without hiding uses of deprecated items in the user's own code.
+pub(super) fn allow_deprecated_uses(items: &mut [Item]) {
+    let mut deprecated = HashSet::new();
+    find_deprecated_idents(items, &mut deprecated);
+    if !deprecated.is_empty() {
+        add_allow_deprecated(items, &deprecated);
+    }
+}
+
+fn is_deprecated(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|a| a.path.is_ident("deprecated"))
+}
+
+fn find_deprecated_idents(items: &[Item], deprecated: &mut HashSet<String>) {
+    for item in items {
+        match item {
+            Item::Struct(s) if is_deprecated(&s.attrs) => {
+                deprecated.insert(s.ident.to_string());
+            }
+            Item::Enum(e) if is_deprecated(&e.attrs) => {
+                deprecated.insert(e.ident.to_string());
+            }
+            Item::Fn(f) if is_deprecated(&f.attrs) => {
+                deprecated.insert(f.sig.ident.to_string());
+            }
+            Item::Impl(imp) => deprecated.extend(imp.items.iter().filter_map(|i| match i {
+                ImplItem::Method(m) if is_deprecated(&m.attrs) => Some(m.sig.ident.to_string()),
+                _ => None,
+            })),
+            Item::ForeignMod(fm) => find_deprecated_foreign_fns(fm, deprecated),
+            // syn doesn't understand the `unsafe extern "C++"` blocks
+            // within a `cxx::bridge`.
+            Item::Verbatim(tokens) => {
+                let mut tokens = tokens.clone().into_iter().peekable();
+                if matches!(tokens.peek(), Some(TokenTree::Ident(id)) if id == "unsafe") {
+                    tokens.next();
+                }
+                if let Ok(fm) = syn::parse2(tokens.collect()) {
+                    find_deprecated_foreign_fns(&fm, deprecated);
+                }
+            }
+            Item::Mod(m) => {
+                if let Some((_, items)) = &m.content {
+                    find_deprecated_idents(items, deprecated);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn find_deprecated_foreign_fns(fm: &ItemForeignMod, deprecated: &mut HashSet<String>) {
+    deprecated.extend(fm.items.iter().filter_map(|i| match i {
+        ForeignItem::Fn(f) if is_deprecated(&f.attrs) => Some(f.sig.ident.to_string()),
+        _ => None,
+    }))
+}
+
+fn add_allow_deprecated(items: &mut [Item], deprecated: &HashSet<String>) {
+    let allow: Attribute = parse_quote! { #[allow(deprecated)] };
+    for item in items {
+        if !mentions_any(item.to_token_stream(), deprecated) {
+            continue;
+        }
+        let attrs = match item {
+            // Recurse into our own mods, but a `cxx::bridge` is expanded
+            // into code we can't see, so the whole thing gets the allow.
+            Item::Mod(m)
+                if m.attrs.is_empty() || m.attrs.iter().all(|a| a.path.is_ident("doc")) =>
+            {
+                if let Some((_, items)) = m.content.as_mut() {
+                    add_allow_deprecated(items, deprecated);
+                }
+                continue;
+            }
+            Item::Impl(imp) => {
+                let header_mentions = mentions_any(imp.self_ty.to_token_stream(), deprecated)
+                    || imp
+                        .trait_
+                        .as_ref()
+                        .map(|(_, path, _)| mentions_any(path.to_token_stream(), deprecated))
+                        .unwrap_or_default();
+                if header_mentions {
+                    &mut imp.attrs
+                } else {
+                    for impl_item in imp.items.iter_mut() {
+                        if let ImplItem::Method(m) = impl_item {
+                            if !is_deprecated(&m.attrs)
+                                && mentions_any(m.to_token_stream(), deprecated)
+                            {
+                                m.attrs.push(allow.clone());
+                            }
+                        }
+                    }
+                    continue;
+                }
+            }
+            Item::Mod(m) => &mut m.attrs,
+            Item::Use(u) => &mut u.attrs,
+            Item::Struct(s) => &mut s.attrs,
+            Item::Enum(e) => &mut e.attrs,
+            Item::Fn(f) => &mut f.attrs,
+            Item::Type(t) => &mut t.attrs,
+            Item::Trait(t) => &mut t.attrs,
+            Item::ForeignMod(fm) => &mut fm.attrs,
+            Item::Const(c) => &mut c.attrs,
+            Item::Static(s) => &mut s.attrs,
+            _ => continue,
+        };
+        if !is_deprecated(attrs) {
+            attrs.push(allow.clone());
+        }
+    }
+}
+
+/// Whether these tokens include any of the given identifiers.
+fn mentions_any(tokens: TokenStream, idents: &HashSet<String>) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(id) => idents.contains(&id.to_string()),
+        TokenTree::Group(g) => mentions_any(g.stream(), idents),
+        _ => false,
+    })
+}
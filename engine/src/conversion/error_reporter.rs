@@ -12,7 +12,7 @@ use super::{
     api::{AnalysisPhase, Api, ApiName, FuncToConvert, StructDetails, TypedefKind},
     apivec::ApiVec,
     convert_error::{ConvertErrorWithContext, ErrorContext},
-    ConvertError,
+    trace_decision, ConvertError, TRACE_TARGET,
 };
 use crate::{
     conversion::convert_error::ErrorContextType,
@@ -34,6 +34,12 @@ where
         Ok(result) => Some(result),
         Err(ConvertErrorWithContext(err, None)) => {
             eprintln!("Ignored item: {}", err);
+            log::debug!(
+                target: TRACE_TARGET,
+                "(unnamed item in namespace '{}'): ignored: {}",
+                ns,
+                err
+            );
             None
         }
         Err(ConvertErrorWithContext(err, Some(ctx))) => {
@@ -42,6 +48,7 @@ where
                 ErrorContextType::Method { self_ty, .. } => self_ty,
             };
             let name = ApiName::new_from_qualified_name(QualifiedName::new(ns, id.clone()));
+            trace_decision(&name.name, format_args!("ignored: {}", err));
             apis.push(Api::IgnoredItem {
                 name,
                 err,
@@ -118,12 +125,73 @@ pub(crate) fn convert_apis<FF, SF, EF, TF, A, B: 'static>(
             Api::Const { name, const_item } => {
                 Ok(Box::new(std::iter::once(Api::Const { name, const_item })))
             }
+            Api::StringTable { name, len } => {
+                Ok(Box::new(std::iter::once(Api::StringTable { name, len })))
+            }
+            Api::StdFunctionAdapter { name, params, ret } => {
+                Ok(Box::new(std::iter::once(Api::StdFunctionAdapter {
+                    name,
+                    params,
+                    ret,
+                })))
+            }
+            Api::StdTupleBridge { name, elems } => {
+                Ok(Box::new(std::iter::once(Api::StdTupleBridge {
+                    name,
+                    elems,
+                })))
+            }
+            Api::StdVariantBridge {
+                name,
+                enum_name,
+                alternatives,
+            } => Ok(Box::new(std::iter::once(Api::StdVariantBridge {
+                name,
+                enum_name,
+                alternatives,
+            }))),
+            Api::OpaqueHandle { name } => Ok(Box::new(std::iter::once(Api::OpaqueHandle { name }))),
+            Api::IntrusivePtrTarget { name, target, ptr } => {
+                Ok(Box::new(std::iter::once(Api::IntrusivePtrTarget {
+                    name,
+                    target,
+                    ptr,
+                })))
+            }
+            Api::IndexOperators {
+                name,
+                target,
+                element,
+            } => Ok(Box::new(std::iter::once(Api::IndexOperators {
+                name,
+                target,
+                element,
+            }))),
+            Api::ComparisonOperators { name, target } => {
+                Ok(Box::new(std::iter::once(Api::ComparisonOperators {
+                    name,
+                    target,
+                })))
+            }
+            Api::BoolConversion { name, target } => {
+                Ok(Box::new(std::iter::once(Api::BoolConversion {
+                    name,
+                    target,
+                })))
+            }
             Api::CType { name, typename } => {
                 Ok(Box::new(std::iter::once(Api::CType { name, typename })))
             }
             Api::RustType { name, path } => {
                 Ok(Box::new(std::iter::once(Api::RustType { name, path })))
             }
+            Api::RustSharedStruct { name, item, deps } => {
+                Ok(Box::new(std::iter::once(Api::RustSharedStruct {
+                    name,
+                    item,
+                    deps,
+                })))
+            }
             Api::RustFn {
                 name,
                 details,
@@ -196,6 +264,7 @@ fn api_or_error<T: AnalysisPhase + 'static>(
     match api_or_error {
         Ok(opt) => opt,
         Err(ConvertErrorWithContext(err, ctx)) => {
+            trace_decision(&name.name, format_args!("ignored: {}", err));
             Box::new(std::iter::once(Api::IgnoredItem { name, err, ctx }))
         }
     }
@@ -9,6 +9,7 @@
 mod analysis;
 mod api;
 mod apivec;
+mod cache;
 mod codegen_cpp;
 mod codegen_rs;
 #[cfg(test)]
@@ -23,11 +24,16 @@ use analysis::fun::FnAnalyzer;
 use autocxx_parser::IncludeCppConfig;
 pub(crate) use codegen_cpp::CppCodeGenerator;
 pub(crate) use convert_error::ConvertError;
+use indexmap::map::IndexMap as HashMap;
 use itertools::Itertools;
+use quote::ToTokens;
+use std::hash::Hash;
+use std::path::Path;
 use syn::{Item, ItemMod};
 
 use crate::{
-    conversion::analysis::deps::HasDependencies, CppCodegenOptions, CppFilePair, UnsafePolicy,
+    conversion::analysis::deps::HasDependencies, is_constructor, types::QualifiedName,
+    ConstructorDetector, CppCodegenOptions, CppFilePair, ForeignFnHook, UnsafePolicy,
 };
 
 use self::{
@@ -37,8 +43,13 @@ use self::{
         casts::add_casts,
         check_names,
         constructor_deps::decorate_types_with_constructor_deps,
+        field_accessors::add_field_accessors,
         fun::FnPhase,
-        gc::filter_apis_by_following_edges_from_allowlist,
+        gc::{
+            filter_apis_by_following_edges_from_allowlist,
+            filter_apis_by_following_edges_from_symbol,
+        },
+        generators::check_generators,
         pod::analyze_pod_apis,
         remove_ignored::filter_apis_by_ignored_dependents,
         replace_hopeless_typedef_targets,
@@ -46,12 +57,24 @@ use self::{
     },
     api::AnalysisPhase,
     apivec::ApiVec,
+    cache::{CacheKey, CacheKeyHasher, ConversionCache},
     codegen_rs::RsCodeGenerator,
     parse::ParseBindgen,
 };
 
 const LOG_APIS: bool = true;
 
+/// The `log` target under which we record each decision taken about an
+/// item during conversion, such as whether a type is POD or why an item
+/// was ignored. Enable with `RUST_LOG=autocxx_trace=debug`.
+pub(crate) const TRACE_TARGET: &str = "autocxx_trace";
+
+/// Record a decision taken about the named item, for the benefit of
+/// anyone wondering why it came out the way it did.
+pub(crate) fn trace_decision(name: &QualifiedName, decision: impl std::fmt::Display) {
+    log::debug!(target: TRACE_TARGET, "{}: {}", name.to_cpp_name(), decision);
+}
+
 /// Converts the bindings generated by bindgen into a form suitable
 /// for use with `cxx`.
 /// In fact, most of the actual operation happens within an
@@ -67,20 +90,38 @@ const LOG_APIS: bool = true;
 pub(crate) struct BridgeConverter<'a> {
     include_list: &'a [String],
     config: &'a IncludeCppConfig,
+    foreign_fn_hook: Option<ForeignFnHook>,
+    constructor_detector: Option<ConstructorDetector>,
 }
 
 /// C++ and Rust code generation output.
 pub(crate) struct CodegenResults {
     pub(crate) rs: Vec<Item>,
+    /// Items for the `#[cxx::bridge]` mod, if they're not already
+    /// wrapped up within `rs`.
+    pub(crate) bridge_items: Vec<Item>,
+    /// Items for `#[cxx::bridge]` mods, grouped by the namespace
+    /// from which they came, if we've been asked to split them up.
+    pub(crate) bridge_items_by_namespace: HashMap<String, Vec<Item>>,
+    /// If we've split up the `#[cxx::bridge]` mod, all the items from
+    /// the bridges together, for generating the C++.
+    pub(crate) merged_bridge_items: Vec<Item>,
     pub(crate) cpp: Option<CppFilePair>,
     pub(crate) cxxgen_header_name: String,
 }
 
 impl<'a> BridgeConverter<'a> {
-    pub fn new(include_list: &'a [String], config: &'a IncludeCppConfig) -> Self {
+    pub fn new(
+        include_list: &'a [String],
+        config: &'a IncludeCppConfig,
+        foreign_fn_hook: Option<ForeignFnHook>,
+        constructor_detector: Option<ConstructorDetector>,
+    ) -> Self {
         Self {
             include_list,
             config,
+            foreign_fn_hook,
+            constructor_detector,
         }
     }
 
@@ -116,6 +157,9 @@ impl<'a> BridgeConverter<'a> {
     /// This is really the heart of autocxx. It parses the output of `bindgen`
     /// (although really by "parse" we mean to interpret the structures already built
     /// up by the `syn` crate).
+    ///
+    /// If a conversion cache is configured, and we've previously converted
+    /// exactly the same inputs, the previous results are returned instead.
     pub(crate) fn convert(
         &self,
         mut bindgen_mod: ItemMod,
@@ -123,91 +167,245 @@ impl<'a> BridgeConverter<'a> {
         inclusions: String,
         cpp_codegen_options: &CppCodegenOptions,
     ) -> Result<CodegenResults, ConvertError> {
-        match &mut bindgen_mod.content {
-            None => Err(ConvertError::NoContent),
-            Some((_, items)) => {
-                // Parse the bindgen mod.
-                let items_to_process = items.drain(..).collect();
-                let parser = ParseBindgen::new(self.config);
-                let apis = parser.parse_items(items_to_process)?;
-                Self::dump_apis("parsing", &apis);
-                // Inside parse_results, we now have a list of APIs.
-                // We now enter various analysis phases.
-                // Next, convert any typedefs.
-                // "Convert" means replacing bindgen-style type targets
-                // (e.g. root::std::unique_ptr) with cxx-style targets (e.g. UniquePtr).
-                let apis = convert_typedef_targets(self.config, apis);
-                Self::dump_apis("typedefs", &apis);
-                // Now analyze which of them can be POD (i.e. trivial, movable, pass-by-value
-                // versus which need to be opaque).
-                // Specifically, let's confirm that the items requested by the user to be
-                // POD really are POD, and duly mark any dependent types.
-                // This returns a new list of `Api`s, which will be parameterized with
-                // the analysis results.
-                let analyzed_apis = analyze_pod_apis(apis, self.config)?;
-                Self::dump_apis("pod analysis", &analyzed_apis);
-                let analyzed_apis = replace_hopeless_typedef_targets(self.config, analyzed_apis);
-                let analyzed_apis = add_casts(analyzed_apis);
-                let analyzed_apis = create_alloc_and_frees(analyzed_apis);
-                // Next, figure out how we materialize different functions.
-                // Some will be simple entries in the cxx::bridge module; others will
-                // require C++ wrapper functions. This is probably the most complex
-                // part of `autocxx`. Again, this returns a new set of `Api`s, but
-                // parameterized by a richer set of metadata.
-                Self::dump_apis("adding casts", &analyzed_apis);
-                let analyzed_apis =
-                    FnAnalyzer::analyze_functions(analyzed_apis, &unsafe_policy, self.config);
-                // If any of those functions turned out to be pure virtual, don't attempt
-                // to generate UniquePtr implementations for the type, since it can't
-                // be instantiated.
-                Self::dump_apis("analyze fns", &analyzed_apis);
-                let analyzed_apis = mark_types_abstract(analyzed_apis);
-                Self::dump_apis("marking abstract", &analyzed_apis);
-                // Annotate structs with a note of any copy/move constructors which
-                // we may want to retain to avoid garbage collecting them later.
-                let analyzed_apis = decorate_types_with_constructor_deps(analyzed_apis);
-                Self::dump_apis_with_deps("adding constructor deps", &analyzed_apis);
-                let analyzed_apis = discard_ignored_functions(analyzed_apis);
-                Self::dump_apis_with_deps("ignoring ignorable fns", &analyzed_apis);
-                // Remove any APIs whose names are not compatible with cxx.
-                let analyzed_apis = check_names(analyzed_apis);
-                // During parsing or subsequent processing we might have encountered
-                // items which we couldn't process due to as-yet-unsupported features.
-                // There might be other items depending on such things. Let's remove them
-                // too.
-                let analyzed_apis = filter_apis_by_ignored_dependents(analyzed_apis);
-                Self::dump_apis_with_deps("removing ignored dependents", &analyzed_apis);
-
-                // We now garbage collect the ones we don't need...
-                let mut analyzed_apis =
-                    filter_apis_by_following_edges_from_allowlist(analyzed_apis, self.config);
-                // Determine what variably-sized C types (e.g. int) we need to include
-                analysis::ctypes::append_ctype_information(&mut analyzed_apis);
-                Self::dump_apis_with_deps("GC", &analyzed_apis);
-                // And finally pass them to the code gen phases, which outputs
-                // code suitable for cxx to consume.
-                let cxxgen_header_name = cpp_codegen_options.cxxgen_header_namer.name_header();
-                let cpp = CppCodeGenerator::generate_cpp_code(
-                    inclusions,
-                    &analyzed_apis,
-                    self.config,
-                    cpp_codegen_options,
-                    &cxxgen_header_name,
-                )?;
-                let rs = RsCodeGenerator::generate_rs_code(
-                    analyzed_apis,
-                    &unsafe_policy,
-                    self.include_list,
-                    bindgen_mod,
-                    self.config,
-                    cpp.as_ref().map(|file_pair| file_pair.header_name.clone()),
-                );
-                Ok(CodegenResults {
-                    rs,
-                    cpp,
-                    cxxgen_header_name,
-                })
+        let cxxgen_header_name = cpp_codegen_options.cxxgen_header_namer.name_header();
+        let cache = self.conversion_cache(cpp_codegen_options).map(|cache| {
+            let key = self.input_hash(
+                &bindgen_mod,
+                &unsafe_policy,
+                &inclusions,
+                &cxxgen_header_name,
+                cpp_codegen_options,
+            );
+            (cache, key)
+        });
+        if let Some((cache, key)) = &cache {
+            if let Some(results) = cache.get(key) {
+                log::info!("Using cached conversion {}", key);
+                return Ok(results);
+            }
+        }
+        let analyzed_apis = self.analyze(&mut bindgen_mod, &unsafe_policy)?;
+        // We now garbage collect the ones we don't need...
+        let analyzed_apis =
+            filter_apis_by_following_edges_from_allowlist(analyzed_apis, self.config);
+        let results = self.generate(
+            analyzed_apis,
+            bindgen_mod,
+            unsafe_policy,
+            inclusions,
+            cpp_codegen_options,
+            cxxgen_header_name,
+        )?;
+        if let Some((cache, key)) = cache {
+            // Failing to cache just means we'll do the work again next time.
+            if let Err(err) = cache.put(&key, &results) {
+                log::warn!("Unable to cache conversion {}: {}", key, err);
             }
         }
+        Ok(results)
+    }
+
+    /// As [Self::convert], but reads the bindgen output from a file
+    /// containing the tokens of its `ItemMod`, as written by
+    /// [`crate::IncludeCppEngine::save_bindgen_tokens`]. This means the
+    /// slow step of running bindgen needn't be repeated for each conversion.
+    pub(crate) fn convert_from_tokens(
+        &self,
+        path: &Path,
+        unsafe_policy: UnsafePolicy,
+        inclusions: String,
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<CodegenResults, ConvertError> {
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            ConvertError::BindgenTokensUnreadable(path.display().to_string(), err.to_string())
+        })?;
+        let bindgen_mod = syn::parse_str::<ItemMod>(&contents).map_err(|err| {
+            ConvertError::BindgenTokensUnreadable(path.display().to_string(), err.to_string())
+        })?;
+        self.convert(bindgen_mod, unsafe_policy, inclusions, cpp_codegen_options)
+    }
+
+    /// The cache to use for conversions, if any. Conversions involving
+    /// hooks aren't cached, since we can't tell whether they've changed.
+    fn conversion_cache(&self, cpp_codegen_options: &CppCodegenOptions) -> Option<ConversionCache> {
+        if self.foreign_fn_hook.is_some() || self.constructor_detector.is_some() {
+            return None;
+        }
+        cpp_codegen_options
+            .conversion_cache_dir
+            .clone()
+            .map(ConversionCache::new)
+    }
+
+    /// A hash of everything which can influence the results of
+    /// [Self::convert], used as the key for the conversion cache.
+    pub(crate) fn input_hash(
+        &self,
+        bindgen_mod: &ItemMod,
+        unsafe_policy: &UnsafePolicy,
+        inclusions: &str,
+        cxxgen_header_name: &str,
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> CacheKey {
+        let mut hasher = CacheKeyHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        self.include_list.hash(&mut hasher);
+        self.config.hash(&mut hasher);
+        bindgen_mod.to_token_stream().to_string().hash(&mut hasher);
+        unsafe_policy.hash(&mut hasher);
+        inclusions.hash(&mut hasher);
+        cxxgen_header_name.hash(&mut hasher);
+        cpp_codegen_options
+            .autocxxgen_header_namer
+            .name_header(self.config.get_mod_name().to_string())
+            .hash(&mut hasher);
+        cpp_codegen_options
+            .suppress_system_headers
+            .hash(&mut hasher);
+        cpp_codegen_options.path_to_cxx_h.hash(&mut hasher);
+        cpp_codegen_options.path_to_cxxgen_h.hash(&mut hasher);
+        cpp_codegen_options.cxx_impl_annotations.hash(&mut hasher);
+        hasher.key()
+    }
+
+    /// As [Self::convert], but generates only the given symbol and
+    /// whatever it depends upon, rather than everything on the allowlist.
+    /// This is useful where only a handful of items from a large header
+    /// are needed at any one time, e.g. when answering queries from an IDE.
+    ///
+    /// All the APIs in the bindgen output are still analyzed, since we
+    /// can't know what the symbol depends upon until we've done so; it's
+    /// code generation which is restricted.
+    pub(crate) fn convert_symbol(
+        &self,
+        mut bindgen_mod: ItemMod,
+        unsafe_policy: UnsafePolicy,
+        inclusions: String,
+        cpp_codegen_options: &CppCodegenOptions,
+        symbol: &QualifiedName,
+    ) -> Result<CodegenResults, ConvertError> {
+        let analyzed_apis = self.analyze(&mut bindgen_mod, &unsafe_policy)?;
+        let analyzed_apis = filter_apis_by_following_edges_from_symbol(analyzed_apis, symbol)?;
+        self.generate(
+            analyzed_apis,
+            bindgen_mod,
+            unsafe_policy,
+            inclusions,
+            cpp_codegen_options,
+            cpp_codegen_options.cxxgen_header_namer.name_header(),
+        )
+    }
+
+    /// Parse the bindgen mod and run all our analysis phases on the
+    /// resulting APIs, up to but not including garbage collection.
+    fn analyze(
+        &self,
+        bindgen_mod: &mut ItemMod,
+        unsafe_policy: &UnsafePolicy,
+    ) -> Result<ApiVec<FnPhase>, ConvertError> {
+        let items_to_process = match &mut bindgen_mod.content {
+            None => return Err(ConvertError::NoContent),
+            Some((_, items)) => items.drain(..).collect(),
+        };
+        // Parse the bindgen mod.
+        let parser = ParseBindgen::new(self.config, self.foreign_fn_hook);
+        let apis = parser.parse_items(items_to_process)?;
+        Self::dump_apis("parsing", &apis);
+        // Inside parse_results, we now have a list of APIs.
+        // We now enter various analysis phases.
+        // Next, convert any typedefs.
+        // "Convert" means replacing bindgen-style type targets
+        // (e.g. root::std::unique_ptr) with cxx-style targets (e.g. UniquePtr).
+        let apis = convert_typedef_targets(self.config, apis);
+        Self::dump_apis("typedefs", &apis);
+        // Now analyze which of them can be POD (i.e. trivial, movable, pass-by-value
+        // versus which need to be opaque).
+        // Specifically, let's confirm that the items requested by the user to be
+        // POD really are POD, and duly mark any dependent types.
+        // This returns a new list of `Api`s, which will be parameterized with
+        // the analysis results.
+        let analyzed_apis = analyze_pod_apis(apis, self.config)?;
+        Self::dump_apis("pod analysis", &analyzed_apis);
+        let analyzed_apis = replace_hopeless_typedef_targets(self.config, analyzed_apis);
+        let analyzed_apis = add_casts(analyzed_apis);
+        let analyzed_apis = add_field_accessors(analyzed_apis, self.config);
+        let analyzed_apis = create_alloc_and_frees(analyzed_apis);
+        // Next, figure out how we materialize different functions.
+        // Some will be simple entries in the cxx::bridge module; others will
+        // require C++ wrapper functions. This is probably the most complex
+        // part of `autocxx`. Again, this returns a new set of `Api`s, but
+        // parameterized by a richer set of metadata.
+        Self::dump_apis("adding casts", &analyzed_apis);
+        let analyzed_apis = FnAnalyzer::analyze_functions(
+            analyzed_apis,
+            unsafe_policy,
+            self.config,
+            self.constructor_detector.unwrap_or(is_constructor),
+        );
+        // If any of those functions turned out to be pure virtual, don't attempt
+        // to generate UniquePtr implementations for the type, since it can't
+        // be instantiated.
+        Self::dump_apis("analyze fns", &analyzed_apis);
+        let analyzed_apis = mark_types_abstract(analyzed_apis);
+        Self::dump_apis("marking abstract", &analyzed_apis);
+        // Annotate structs with a note of any copy/move constructors which
+        // we may want to retain to avoid garbage collecting them later.
+        let analyzed_apis = decorate_types_with_constructor_deps(analyzed_apis);
+        Self::dump_apis_with_deps("adding constructor deps", &analyzed_apis);
+        let analyzed_apis = discard_ignored_functions(analyzed_apis);
+        Self::dump_apis_with_deps("ignoring ignorable fns", &analyzed_apis);
+        // Types marked as generators must have a suitable method to call.
+        let analyzed_apis = check_generators(analyzed_apis, self.config);
+        // Remove any APIs whose names are not compatible with cxx.
+        let analyzed_apis = check_names(analyzed_apis);
+        // During parsing or subsequent processing we might have encountered
+        // items which we couldn't process due to as-yet-unsupported features.
+        // There might be other items depending on such things. Let's remove them
+        // too.
+        let analyzed_apis = filter_apis_by_ignored_dependents(analyzed_apis);
+        Self::dump_apis_with_deps("removing ignored dependents", &analyzed_apis);
+        Ok(analyzed_apis)
+    }
+
+    /// Generate C++ and Rust code for the APIs which survived garbage
+    /// collection.
+    fn generate(
+        &self,
+        mut analyzed_apis: ApiVec<FnPhase>,
+        bindgen_mod: ItemMod,
+        unsafe_policy: UnsafePolicy,
+        inclusions: String,
+        cpp_codegen_options: &CppCodegenOptions,
+        cxxgen_header_name: String,
+    ) -> Result<CodegenResults, ConvertError> {
+        // Determine what variably-sized C types (e.g. int) we need to include
+        analysis::ctypes::append_ctype_information(&mut analyzed_apis);
+        Self::dump_apis_with_deps("GC", &analyzed_apis);
+        // And finally pass them to the code gen phases, which outputs
+        // code suitable for cxx to consume.
+        let cpp = CppCodeGenerator::generate_cpp_code(
+            inclusions,
+            &analyzed_apis,
+            self.config,
+            cpp_codegen_options,
+            &cxxgen_header_name,
+        )?;
+        let (rs, bridge_items, bridge_items_by_namespace, merged_bridge_items) =
+            RsCodeGenerator::generate_rs_code(
+                analyzed_apis,
+                &unsafe_policy,
+                self.include_list,
+                bindgen_mod,
+                self.config,
+                cpp.as_ref().map(|file_pair| file_pair.header_name.clone()),
+            );
+        Ok(CodegenResults {
+            rs,
+            bridge_items,
+            bridge_items_by_namespace,
+            merged_bridge_items,
+            cpp,
+            cxxgen_header_name,
+        })
     }
 }
@@ -10,7 +10,7 @@ use proc_macro2::{Ident, TokenStream};
 use syn::{
     parenthesized,
     parse::{Parse, Parser},
-    Attribute, LitStr,
+    parse_quote, Attribute, LitStr,
 };
 
 use crate::conversion::{
@@ -76,6 +76,29 @@ impl BindgenSemanticAttributes {
         self.0.iter().any(|a| a.is_ident(attr_name))
     }
 
+    /// Whether this struct field is a C++ lvalue or rvalue reference,
+    /// which bindgen represents as a pointer.
+    pub(crate) fn is_reference_field(&self) -> bool {
+        self.has_attr("reference") || self.has_attr("rvalue_reference")
+    }
+
+    /// Whether this struct field is `volatile`.
+    pub(crate) fn is_volatile_field(&self) -> bool {
+        self.has_attr("volatile")
+    }
+
+    /// A `#[deprecated]` attribute, if the item is `[[deprecated]]` in C++,
+    /// including the message if there is one.
+    pub(crate) fn get_deprecated_attr(&self) -> Option<Attribute> {
+        let a = self.0.iter().find(|a| a.is_ident("deprecated"))?;
+        Some(
+            match a.body.as_ref().and_then(|_| a.parse_args::<LitStr>().ok()) {
+                Some(note) => parse_quote! { #[deprecated(note = #note)] },
+                None => parse_quote! { #[deprecated] },
+            },
+        )
+    }
+
     /// The C++ visibility of the item.
     pub(super) fn get_cpp_visibility(&self) -> CppVisibility {
         if self.has_attr("visibility_private") {
@@ -151,6 +174,11 @@ impl BindgenSemanticAttributes {
                 if let Ok(ls) = r {
                     results.rvalue_ref_params.insert(ls);
                 }
+            } else if a.is_ident("arg_type_volatile") {
+                let r: Result<Ident, syn::Error> = a.parse_args();
+                if let Ok(ls) = r {
+                    results.volatile_params.insert(ls);
+                }
             }
         }
         results
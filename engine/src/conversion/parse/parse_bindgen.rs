@@ -23,7 +23,8 @@ use crate::{
         convert_error::{ConvertErrorWithContext, ErrorContext},
         error_reporter::report_any_error,
     },
-    types::validate_ident_ok_for_cxx,
+    types::{make_ident, validate_ident_ok_for_cxx},
+    ForeignFnHook,
 };
 use autocxx_parser::{IncludeCppConfig, RustPath};
 use syn::{parse_quote, Fields, Ident, Item, Type, TypePath, UseTree};
@@ -37,6 +38,7 @@ use super::parse_foreign_mod::ParseForeignMod;
 /// Parses a bindgen mod in order to understand the APIs within it.
 pub(crate) struct ParseBindgen<'a> {
     config: &'a IncludeCppConfig,
+    foreign_fn_hook: Option<ForeignFnHook>,
     apis: ApiVec<NullPhase>,
 }
 
@@ -59,9 +61,13 @@ pub(crate) fn api_name_qualified(
 }
 
 impl<'a> ParseBindgen<'a> {
-    pub(crate) fn new(config: &'a IncludeCppConfig) -> Self {
+    pub(crate) fn new(
+        config: &'a IncludeCppConfig,
+        foreign_fn_hook: Option<ForeignFnHook>,
+    ) -> Self {
         ParseBindgen {
             config,
+            foreign_fn_hook,
             apis: ApiVec::new(),
         }
     }
@@ -111,6 +117,17 @@ impl<'a> ParseBindgen<'a> {
                 path: path.clone(),
             }
         }));
+        self.apis
+            .extend(
+                self.config
+                    .get_shared_structs()
+                    .iter()
+                    .map(|item| Api::RustSharedStruct {
+                        name: ApiName::new_in_root_namespace(item.ident.clone()),
+                        item: item.clone(),
+                        deps: Vec::new(),
+                    }),
+            );
         self.apis.extend(
             self.config
                 .concretes
@@ -125,6 +142,42 @@ impl<'a> ParseBindgen<'a> {
                     }
                 }),
         );
+        self.apis
+            .extend(self.config.get_ordered_types().iter().map(|cpp_name| {
+                Api::ComparisonOperators {
+                    name: ApiName::new_in_root_namespace(make_ident(format!(
+                        "{}_ordering",
+                        cpp_name.replace("::", "_")
+                    ))),
+                    target: QualifiedName::new_from_cpp_name(cpp_name),
+                }
+            }));
+        self.apis
+            .extend(
+                self.config
+                    .get_indexed_types()
+                    .iter()
+                    .map(|indexed| Api::IndexOperators {
+                        name: ApiName::new_in_root_namespace(make_ident(format!(
+                            "{}_indexing",
+                            indexed.cpp_name.replace("::", "_")
+                        ))),
+                        target: QualifiedName::new_from_cpp_name(&indexed.cpp_name),
+                        element: QualifiedName::new_from_cpp_name(&indexed.element),
+                    }),
+            );
+        self.apis.extend(
+            self.config
+                .get_bool_conversion_types()
+                .iter()
+                .map(|cpp_name| Api::BoolConversion {
+                    name: ApiName::new_in_root_namespace(make_ident(format!(
+                        "{}_bool_conversion",
+                        cpp_name.replace("::", "_")
+                    ))),
+                    target: QualifiedName::new_from_cpp_name(cpp_name),
+                }),
+        );
     }
 
     /// We do this last, _after_ we've parsed all the APIs, because we might want to actually
@@ -177,9 +230,12 @@ impl<'a> ParseBindgen<'a> {
     fn parse_mod_items(&mut self, items: Vec<Item>, ns: Namespace) {
         // This object maintains some state specific to this namespace, i.e.
         // this particular mod.
-        let mut mod_converter = ParseForeignMod::new(ns.clone());
+        let mut mod_converter = ParseForeignMod::new(ns.clone(), self.foreign_fn_hook);
         let mut more_apis = ApiVec::new();
-        for item in items {
+        for item in items
+            .into_iter()
+            .filter(|item| !Self::is_bindgen_helper_item(item))
+        {
             report_any_error(&ns, &mut more_apis, || {
                 self.parse_item(item, &mut mod_converter, &ns)
             });
@@ -199,11 +255,11 @@ impl<'a> ParseBindgen<'a> {
                 mod_converter.convert_foreign_mod_items(fm.items);
                 Ok(())
             }
-            Item::Struct(s) => {
-                if s.ident.to_string().ends_with("__bindgen_vtable") {
-                    return Ok(());
-                }
+            Item::Struct(mut s) => {
                 let annotations = BindgenSemanticAttributes::new(&s.attrs);
+                // The cpp_semantics attributes are removed during analysis,
+                // so turn this one into a real attribute now.
+                s.attrs.extend(annotations.get_deprecated_attr());
                 // cxx::bridge can't cope with type aliases to generic
                 // types at the moment.
                 let name = api_name_qualified(ns, s.ident.clone(), &annotations)?;
@@ -250,12 +306,21 @@ impl<'a> ParseBindgen<'a> {
                 }
                 Ok(())
             }
-            Item::Enum(e) => {
+            Item::Enum(mut e) => {
                 let annotations = BindgenSemanticAttributes::new(&e.attrs);
-                let api = UnanalyzedApi::Enum {
-                    name: api_name_qualified(ns, e.ident.clone(), &annotations)?,
-                    item: e,
-                };
+                e.attrs.extend(annotations.get_deprecated_attr());
+                let name = api_name_qualified(ns, e.ident.clone(), &annotations)?;
+                // Rust has no way to nest a type within a struct, so an enum
+                // nested within a class is flattened to e.g. `Foo_Mode`. At
+                // least record which class it belongs to. (cxx won't accept
+                // `#[doc(alias)]`, or we'd add one for the C++ name.)
+                let cpp_name = name.qualified_cpp_name();
+                let is_nested = matches!(name.cpp_name_if_present(), Some(n) if n.contains("::"));
+                if let Some((class, _)) = cpp_name.rsplit_once("::").filter(|_| is_nested) {
+                    let doc = format!(" Nested within `{}` in C++, as `{}`.", class, cpp_name);
+                    e.attrs.push(parse_quote! { #[doc = #doc] });
+                }
+                let api = UnanalyzedApi::Enum { name, item: e };
                 if !self.config.is_on_blocklist(&api.name().to_cpp_name()) {
                     self.apis.push(api);
                 }
@@ -366,6 +431,20 @@ impl<'a> ParseBindgen<'a> {
         }
     }
 
+    /// bindgen emits some items purely for its own purposes, such as
+    /// vtable structs and layout assertions. These don't correspond to
+    /// any C++ API so we drop them before we start.
+    fn is_bindgen_helper_item(item: &Item) -> bool {
+        let id = match item {
+            Item::Struct(s) => &s.ident,
+            Item::Fn(f) => &f.sig.ident,
+            Item::Const(c) => &c.ident,
+            _ => return false,
+        };
+        let id = id.to_string();
+        id.ends_with("__bindgen_vtable") || id.starts_with("bindgen_test_layout_") || id == "_"
+    }
+
     fn spot_forward_declaration(s: &Fields) -> bool {
         Self::spot_field(s, "_unused")
     }
@@ -388,9 +467,50 @@ impl<'a> ParseBindgen<'a> {
             .collect();
         for generate_directive in self.config.must_generate_list() {
             if !api_names.contains(&generate_directive) {
+                if self.config.get_pod_requests().contains(&generate_directive) {
+                    let requested = QualifiedName::new_from_cpp_name(&generate_directive);
+                    let suggestion = self.suggest_type(&requested);
+                    return Err(ConvertError::PodRequestNotFound(requested, suggestion));
+                }
                 return Err(ConvertError::DidNotGenerateAnything(generate_directive));
             }
         }
         Ok(())
     }
+
+    /// Find the type which the user most likely meant when they asked for
+    /// one which doesn't exist. We prefer a type of the same name in a
+    /// different namespace, and otherwise look for a close spelling.
+    fn suggest_type(&self, requested: &QualifiedName) -> Option<QualifiedName> {
+        let candidates: Vec<_> = self
+            .apis
+            .iter()
+            .filter(|api| {
+                matches!(
+                    api,
+                    Api::Struct { .. } | Api::Typedef { .. } | Api::Enum { .. }
+                )
+            })
+            .map(|api| api.name())
+            .collect();
+        if let Some(same_name) = candidates
+            .iter()
+            .find(|candidate| candidate.get_final_item() == requested.get_final_item())
+        {
+            return Some((*same_name).clone());
+        }
+        let requested = requested.to_cpp_name();
+        let max_distance = std::cmp::max(1, requested.len() / 3);
+        candidates
+            .into_iter()
+            .map(|candidate| {
+                (
+                    strsim::levenshtein(&requested, &candidate.to_cpp_name()),
+                    candidate,
+                )
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate.clone())
+    }
 }
@@ -8,7 +8,7 @@
 
 use crate::conversion::api::{ApiName, NullPhase, Provenance};
 use crate::conversion::apivec::ApiVec;
-use crate::conversion::doc_attr::get_doc_attrs;
+use crate::conversion::doc_attr::{get_deprecated_attr, get_doc_attrs};
 use crate::conversion::error_reporter::report_any_error;
 use crate::conversion::{
     api::{FuncToConvert, UnanalyzedApi},
@@ -18,9 +18,13 @@ use crate::conversion::{
 use crate::{
     conversion::ConvertError,
     types::{Namespace, QualifiedName},
+    FnAction, ForeignFnHook,
 };
 use std::collections::HashMap;
-use syn::{Block, Expr, ExprCall, ForeignItem, Ident, ImplItem, ItemImpl, Stmt, Type};
+use syn::{
+    Block, Expr, ExprCall, ExprLit, ForeignItem, ForeignItemStatic, Ident, ImplItem, ItemImpl, Lit,
+    Stmt, Type, TypeArray, TypePtr,
+};
 
 use super::bindgen_semantic_attributes::BindgenSemanticAttributes;
 
@@ -40,15 +44,21 @@ pub(crate) struct ParseForeignMod {
     // function name to type name.
     method_receivers: HashMap<Ident, QualifiedName>,
     ignored_apis: ApiVec<NullPhase>,
+    string_tables: ApiVec<NullPhase>,
+    // A user-supplied function which may ask us to skip or rename
+    // each function before we convert it.
+    foreign_fn_hook: Option<ForeignFnHook>,
 }
 
 impl ParseForeignMod {
-    pub(crate) fn new(ns: Namespace) -> Self {
+    pub(crate) fn new(ns: Namespace, foreign_fn_hook: Option<ForeignFnHook>) -> Self {
         Self {
             ns,
             funcs_to_convert: Vec::new(),
             method_receivers: HashMap::new(),
             ignored_apis: ApiVec::new(),
+            string_tables: ApiVec::new(),
+            foreign_fn_hook,
         }
     }
 
@@ -57,18 +67,31 @@ impl ParseForeignMod {
     pub(crate) fn convert_foreign_mod_items(&mut self, foreign_mod_items: Vec<ForeignItem>) {
         let mut extra_apis = ApiVec::new();
         for i in foreign_mod_items {
+            let rust_name = match (&i, self.foreign_fn_hook) {
+                (ForeignItem::Fn(item), Some(hook)) => match hook(item) {
+                    FnAction::Keep => None,
+                    FnAction::Skip => continue,
+                    FnAction::Rename(rust_name) => Some(rust_name),
+                },
+                _ => None,
+            };
             report_any_error(&self.ns.clone(), &mut extra_apis, || {
-                self.parse_foreign_item(i)
+                self.parse_foreign_item(i, rust_name)
             });
         }
         self.ignored_apis.append(&mut extra_apis);
     }
 
-    fn parse_foreign_item(&mut self, i: ForeignItem) -> Result<(), ConvertErrorWithContext> {
+    fn parse_foreign_item(
+        &mut self,
+        i: ForeignItem,
+        rust_name: Option<String>,
+    ) -> Result<(), ConvertErrorWithContext> {
         match i {
             ForeignItem::Fn(item) => {
                 let annotations = BindgenSemanticAttributes::new(&item.attrs);
-                let doc_attrs = get_doc_attrs(&item.attrs);
+                let mut doc_attrs = get_doc_attrs(&item.attrs);
+                doc_attrs.extend(get_deprecated_attr(&item.attrs));
                 self.funcs_to_convert.push(FuncToConvert {
                     provenance: Provenance::Bindgen,
                     self_ty: None,
@@ -84,6 +107,7 @@ impl ParseForeignMod {
                         .has_attr("incomprehensible_param_in_arg_or_return"),
                     references: annotations.get_reference_parameters_and_return(),
                     original_name: annotations.get_original_name(),
+                    rust_name,
                     synthesized_this_type: None,
                     add_to_trait: None,
                     is_deleted: annotations.has_attr("deleted"),
@@ -92,10 +116,19 @@ impl ParseForeignMod {
                 });
                 Ok(())
             }
-            ForeignItem::Static(item) => Err(ConvertErrorWithContext(
-                ConvertError::StaticData(item.ident.to_string()),
-                Some(ErrorContext::new_for_item(item.ident)),
-            )),
+            ForeignItem::Static(item) => match get_string_table_len(&item) {
+                Some(len) => {
+                    self.string_tables.push(UnanalyzedApi::StringTable {
+                        name: ApiName::new(&self.ns, item.ident),
+                        len,
+                    });
+                    Ok(())
+                }
+                None => Err(ConvertErrorWithContext(
+                    ConvertError::StaticData(item.ident.to_string()),
+                    Some(ErrorContext::new_for_item(item.ident)),
+                )),
+            },
             _ => Err(ConvertErrorWithContext(
                 ConvertError::UnexpectedForeignItem,
                 None,
@@ -129,6 +162,7 @@ impl ParseForeignMod {
     /// the resulting APIs.
     pub(crate) fn finished(mut self, apis: &mut ApiVec<NullPhase>) {
         apis.append(&mut self.ignored_apis);
+        apis.append(&mut self.string_tables);
         while !self.funcs_to_convert.is_empty() {
             let mut fun = self.funcs_to_convert.remove(0);
             fun.self_ty = self.method_receivers.get(&fun.ident).cloned();
@@ -159,11 +193,48 @@ fn get_called_function(block: &Block) -> Option<&Ident> {
     }
 }
 
+/// If this static is an immutable, fixed-length array of C strings
+/// (`const char* const[]` in C++) return its length.
+fn get_string_table_len(item: &ForeignItemStatic) -> Option<usize> {
+    if item.mutability.is_some() {
+        return None;
+    }
+    match item.ty.as_ref() {
+        Type::Array(TypeArray {
+            elem,
+            len: Expr::Lit(ExprLit {
+                lit: Lit::Int(len), ..
+            }),
+            ..
+        }) => match elem.as_ref() {
+            Type::Ptr(TypePtr {
+                const_token: Some(_),
+                elem,
+                ..
+            }) if is_c_char(elem) => len.base10_parse().ok().filter(|len| *len > 0),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_c_char(ty: &Type) -> bool {
+    match ty {
+        Type::Path(typ) => typ
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident == "c_char")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::get_called_function;
+    use super::{get_called_function, get_string_table_len};
     use syn::parse_quote;
-    use syn::Block;
+    use syn::{Block, ForeignItemStatic};
 
     #[test]
     fn test_get_called_function() {
@@ -174,4 +245,20 @@ mod test {
         };
         assert_eq!(get_called_function(&b).unwrap().to_string(), "call_foo");
     }
+
+    #[test]
+    fn test_get_string_table_len() {
+        let table: ForeignItemStatic = parse_quote! {
+            pub static names: [*const ::std::os::raw::c_char; 3usize];
+        };
+        assert_eq!(get_string_table_len(&table), Some(3));
+        let mutable: ForeignItemStatic = parse_quote! {
+            pub static mut names: [*const ::std::os::raw::c_char; 3usize];
+        };
+        assert_eq!(get_string_table_len(&mutable), None);
+        let ints: ForeignItemStatic = parse_quote! {
+            pub static values: [::std::os::raw::c_int; 3usize];
+        };
+        assert_eq!(get_string_table_len(&ints), None);
+    }
 }
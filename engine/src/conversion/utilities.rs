@@ -12,7 +12,9 @@ use super::{
     api::{ApiName, NullPhase, UnanalyzedApi},
     apivec::ApiVec,
 };
-use crate::types::{make_ident, Namespace};
+use crate::types::{make_ident, Namespace, QualifiedName};
+use itertools::Itertools;
+use proc_macro2::Ident;
 
 /// Adds items which we always add, cos they're useful.
 /// Any APIs or techniques which do not involve actual C++ interop
@@ -28,3 +30,67 @@ pub(crate) fn generate_utilities(apis: &mut ApiVec<NullPhase>, config: &IncludeC
         name: ApiName::new(&Namespace::new(), make_ident(config.get_makestring_name())),
     });
 }
+
+/// The name of the type we synthesize to own the pointee of a
+/// `std::unique_ptr<void, D>`, along with its deleter. Rust code refers
+/// to it as plain `OpaqueHandle`.
+pub(crate) fn opaque_handle_name(config: &IncludeCppConfig) -> ApiName {
+    ApiName::new_in_root_namespace(make_ident(config.uniquify_name_per_mod("OpaqueHandle")))
+}
+
+/// The name of the C++ function we synthesize to read entries
+/// out of a `const char* const[]` table.
+pub(crate) fn string_table_accessor_name(
+    config: &IncludeCppConfig,
+    table: &QualifiedName,
+) -> String {
+    config.uniquify_name_per_mod(&format!(
+        "autocxx_string_table_{}",
+        table.segment_iter().join("_")
+    ))
+}
+
+/// The name of the C++ function we synthesize to turn a boxed
+/// `std::function` adapter into a real `std::function`.
+pub(crate) fn std_function_converter_name(adapter: &Ident) -> String {
+    format!("{}_to_std_function", adapter)
+}
+
+/// The name of the Rust function which C++ calls to invoke the closure
+/// held within a `std::function` adapter.
+pub(crate) fn std_function_call_name(adapter: &Ident) -> Ident {
+    make_ident(format!("{}_call", adapter))
+}
+
+/// The names of the C++ functions which adjust the reference count of the
+/// target of an intrusive pointer, in the order (add_ref, release).
+pub(crate) fn intrusive_ptr_fn_names(target_api: &Ident) -> (Ident, Ident) {
+    (
+        make_ident(format!("{}_add_ref", target_api)),
+        make_ident(format!("{}_release", target_api)),
+    )
+}
+
+/// The names of the C++ functions which compare two instances of a type
+/// marked with `ordered!`, in the order (eq, lt).
+pub(crate) fn comparison_fn_names(target_api: &Ident) -> (Ident, Ident) {
+    (
+        make_ident(format!("{}_eq", target_api)),
+        make_ident(format!("{}_lt", target_api)),
+    )
+}
+
+/// The name of the C++ function which converts a type marked with
+/// `bool_conversion!` to `bool`.
+pub(crate) fn bool_conversion_fn_name(target_api: &Ident) -> Ident {
+    make_ident(format!("{}_as_bool", target_api))
+}
+
+/// The names of the C++ functions which subscript a type marked with
+/// `indexed!`, in the order (index, index_mut).
+pub(crate) fn index_fn_names(target_api: &Ident) -> (Ident, Ident) {
+    (
+        make_ident(format!("{}_index", target_api)),
+        make_ident(format!("{}_index_mut", target_api)),
+    )
+}
@@ -10,6 +10,7 @@ use crate::{
     conversion::ConvertError,
     types::{make_ident, QualifiedName},
 };
+use autocxx_parser::CppStd;
 use indexmap::map::IndexMap as HashMap;
 use indoc::indoc;
 use once_cell::sync::OnceCell;
@@ -21,6 +22,13 @@ enum Behavior {
     CxxContainerPtr,
     CxxContainerVector,
     CxxString,
+    CxxStringView,
+    CxxStdFunction,
+    CxxStdTuple,
+    CxxStdVariant,
+    CxxStdOptional,
+    CxxSpan,
+    RustSlice,
     RustStr,
     RustString,
     RustByValue,
@@ -72,15 +80,20 @@ impl TypeDetails {
             Behavior::RustString
             | Behavior::RustStr
             | Behavior::CxxString
+            | Behavior::CxxStringView
+            | Behavior::CxxStdFunction
             | Behavior::CxxContainerPtr
             | Behavior::CxxContainerVector
-            | Behavior::RustContainerByValueSafe => {
+            | Behavior::RustContainerByValueSafe
+            | Behavior::RustSlice => {
                 let tn = QualifiedName::new_from_cpp_name(&self.rs_name);
                 let cxx_name = tn.get_final_item();
                 let (templating, payload) = match self.behavior {
                     Behavior::CxxContainerPtr
                     | Behavior::CxxContainerVector
-                    | Behavior::RustContainerByValueSafe => ("template<typename T> ", "T* ptr"),
+                    | Behavior::CxxStdFunction
+                    | Behavior::RustContainerByValueSafe
+                    | Behavior::RustSlice => ("template<typename T> ", "T* ptr"),
                     _ => ("", "char* ptr"),
                 };
                 Some(format!(
@@ -205,6 +218,7 @@ impl TypeDatabase {
                     match self.get(tn).unwrap().behavior {
                         Behavior::CxxContainerPtr
                         | Behavior::RustStr
+                        | Behavior::CxxStringView
                         | Behavior::RustString
                         | Behavior::RustByValue
                         | Behavior::CByValueVecSafe
@@ -212,9 +226,15 @@ impl TypeDatabase {
                         | Behavior::CVariableLengthByValue
                         | Behavior::CChar16
                         | Behavior::RustContainerByValueSafe => true,
-                        Behavior::CxxString | Behavior::CxxContainerVector | Behavior::CVoid => {
-                            false
-                        }
+                        Behavior::CxxString
+                        | Behavior::CxxStdFunction
+                        | Behavior::CxxStdTuple
+                        | Behavior::CxxStdVariant
+                        | Behavior::CxxStdOptional
+                        | Behavior::CxxSpan
+                        | Behavior::RustSlice
+                        | Behavior::CxxContainerVector
+                        | Behavior::CVoid => false,
                     },
                 )
             })
@@ -241,6 +261,104 @@ impl TypeDatabase {
             .unwrap_or(false)
     }
 
+    /// Whether this is a `std::string_view`, which we represent as `&str`
+    /// in Rust, or `&[u8]` when returned, and which needs conversion to
+    /// and from `rust::Str` or `rust::Slice` in C++.
+    pub(crate) fn is_string_view(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CxxStringView))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a `std::function`, which we allow Rust to provide
+    /// in the form of a closure.
+    pub(crate) fn is_std_function(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CxxStdFunction))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a `std::span`, which we represent as a slice.
+    pub(crate) fn is_std_span(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CxxSpan))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is cxx's own `rust::Slice`, which we also represent
+    /// as a slice.
+    pub(crate) fn is_rust_slice(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::RustSlice))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a `std::tuple`, which we allow C++ to return
+    /// as a Rust tuple.
+    pub(crate) fn is_std_tuple(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CxxStdTuple))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a `std::variant`, which we allow C++ to return
+    /// as a Rust enum.
+    pub(crate) fn is_std_variant(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CxxStdVariant))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a `std::optional`, which we allow C++ to return
+    /// as a Rust `Option`.
+    pub(crate) fn is_std_optional(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CxxStdOptional))
+            .unwrap_or(false)
+    }
+
+    /// The C++ standard which introduced this type, if it's newer than
+    /// the oldest standard we support.
+    pub(crate) fn required_cpp_std(&self, tn: &QualifiedName) -> Option<CppStd> {
+        self.get(tn).and_then(|td| match td.behavior {
+            Behavior::CxxStringView | Behavior::CxxStdVariant | Behavior::CxxStdOptional => {
+                Some(CppStd::Cpp17)
+            }
+            Behavior::CxxSpan => Some(CppStd::Cpp20),
+            _ => None,
+        })
+    }
+
+    /// Whether this is a `std::unique_ptr`.
+    pub(crate) fn is_unique_ptr(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| td.rs_name == "cxx::UniquePtr")
+            .unwrap_or(false)
+    }
+
+    /// Whether this is `void`, which can only be pointed to.
+    pub(crate) fn is_c_void(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| matches!(td.behavior, Behavior::CVoid))
+            .unwrap_or(false)
+    }
+
+    /// Whether this is a simple primitive type which can be passed by
+    /// value identically in Rust and C++ without any wrapping.
+    pub(crate) fn is_simple_by_value(&self, tn: &QualifiedName) -> bool {
+        self.get(tn)
+            .map(|td| {
+                matches!(
+                    td.behavior,
+                    Behavior::CByValue
+                        | Behavior::CByValueVecSafe
+                        | Behavior::CVariableLengthByValue
+                        | Behavior::CChar16
+                )
+            })
+            .unwrap_or(false)
+    }
+
     /// Whether this can only be passed around using `std::move`
     pub(crate) fn lacks_copy_constructor(&self, tn: &QualifiedName) -> bool {
         self.get(tn)
@@ -405,6 +523,62 @@ fn create_type_database() -> TypeDatabase {
         true,
         true,
     ));
+    db.insert(TypeDetails::new(
+        "autocxx::StringView",
+        "std::string_view",
+        Behavior::CxxStringView,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "autocxx::StdFunction",
+        "std::function",
+        Behavior::CxxStdFunction,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "autocxx::StdTuple",
+        "std::tuple",
+        Behavior::CxxStdTuple,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "autocxx::StdVariant",
+        "std::variant",
+        Behavior::CxxStdVariant,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "autocxx::StdOptional",
+        "std::optional",
+        Behavior::CxxStdOptional,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "autocxx::Span",
+        "std::span",
+        Behavior::CxxSpan,
+        None,
+        true,
+        true,
+    ));
+    db.insert(TypeDetails::new(
+        "autocxx::RustSlice",
+        "rust::Slice",
+        Behavior::RustSlice,
+        None,
+        true,
+        true,
+    ));
     db.insert(TypeDetails::new(
         "str",
         "rust::Str",
@@ -460,6 +634,8 @@ fn create_type_database() -> TypeDatabase {
             true,
         ));
     }
+    // C++ `bool` is always Rust `bool` rather than some integer type, in
+    // both parameters and return values. cxx checks their layouts match.
     db.insert(TypeDetails::new(
         "bool",
         "bool",
@@ -554,6 +730,16 @@ fn create_type_database() -> TypeDatabase {
     db
 }
 
+/// Whether this is a pointer to `char`, such that an array of them is an
+/// array of C strings like `argv`.
+pub(crate) fn is_c_string_ptr(ty: &Type) -> bool {
+    match ty {
+        Type::Ptr(ptr) => matches!(ptr.elem.as_ref(), Type::Path(tp)
+            if tp.path.segments.last().is_some_and(|seg| seg.ident == "c_char")),
+        _ => false,
+    }
+}
+
 pub(crate) fn ensure_pointee_is_valid(ptr: &TypePtr) -> Result<(), ConvertError> {
     match *ptr.elem {
         Type::Path(..) => Ok(()),
@@ -30,7 +30,8 @@ mod builder;
 
 use autocxx_bindgen::BindgenError;
 use autocxx_parser::{IncludeCppConfig, UnsafePolicy};
-use conversion::BridgeConverter;
+use conversion::{BridgeConverter, CodegenResults};
+use indexmap::map::IndexMap as HashMap;
 use miette::{SourceOffset, SourceSpan};
 use parse_callbacks::AutocxxParseCallbacks;
 use parse_file::CppBuildable;
@@ -45,11 +46,11 @@ use std::{
 };
 use tempfile::NamedTempFile;
 
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use syn::Result as ParseResult;
 use syn::{
     parse::{Parse, ParseStream},
-    parse_quote, ItemMod, Macro,
+    parse_quote, ForeignItemFn, Item, ItemMod, Macro,
 };
 use thiserror::Error;
 
@@ -57,6 +58,7 @@ use itertools::{join, Itertools};
 use known_types::known_types;
 use log::info;
 use miette::Diagnostic;
+use types::QualifiedName;
 
 /// We use a forked version of bindgen - for now.
 /// We hope to unfork.
@@ -71,6 +73,26 @@ pub use parse_file::{parse_file, ParseError, ParsedFile};
 
 pub use cxx_gen::HEADER;
 
+/// The key, within [`IncludeCppEngine::get_bridge_items_by_namespace`], of
+/// the group holding all type declarations if `shared_type_declarations!()`
+/// was specified. That group must be wrapped in a `#[cxx::bridge]` mod of
+/// this name, alongside the mods for the other groups.
+pub const SHARED_TYPES_BRIDGE_MOD: &str = "cxxbridge_types";
+
+/// The name of the `#[cxx::bridge]` mod for a given group of items within
+/// [`IncludeCppEngine::get_bridge_items_by_namespace`]. The generated code
+/// refers to everything via a mod called `cxxbridge`, which should
+/// `pub use` everything from each of these mods.
+pub fn bridge_mod_name(group: &str) -> String {
+    if group == SHARED_TYPES_BRIDGE_MOD {
+        group.to_string()
+    } else if group.is_empty() {
+        "cxxbridge_global".to_string()
+    } else {
+        format!("cxxbridge_ns_{}", group.replace("::", "_"))
+    }
+}
+
 #[derive(Clone)]
 /// Some C++ content which should be written to disk and built.
 pub struct CppFilePair {
@@ -125,6 +147,8 @@ pub enum Error {
     NoAutoCxxInc,
     #[error(transparent)]
     Conversion(conversion::ConvertError),
+    #[error("Unable to write bindgen output: {0}")]
+    BindgenTokensWrite(std::io::Error),
 }
 
 /// Result type.
@@ -132,6 +156,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 struct GenerationResults {
     item_mod: ItemMod,
+    bridge_items: Vec<Item>,
+    bridge_items_by_namespace: HashMap<String, Vec<Item>>,
+    merged_bridge_items: Vec<Item>,
     cpp: Option<CppFilePair>,
     #[allow(dead_code)]
     inc_dirs: Vec<PathBuf>,
@@ -286,9 +313,20 @@ impl IncludeCppEngine {
     fn build_header(&self) -> String {
         join(
             self.config
-                .inclusions
+                .get_preamble()
                 .iter()
-                .map(|path| format!("#include \"{}\"\n", path)),
+                .map(|line| format!("{}\n", line))
+                .chain(
+                    self.config
+                        .inclusions
+                        .iter()
+                        .map(|path| format!("#include \"{}\"\n", path)),
+                )
+                .chain(
+                    self.config
+                        .get_instantiation_shims()
+                        .map(|shim| format!("{}\n", shim)),
+                ),
             "",
         )
     }
@@ -314,6 +352,7 @@ impl IncludeCppEngine {
             .cpp_semantic_attributes(true)
             .represent_cxx_operators(true)
             .use_distinct_char16_t(true)
+            .array_pointers_in_arguments(self.config.has_array_params())
             .layout_tests(false); // TODO revisit later
         for item in known_types().get_initial_blocklist() {
             builder = builder.blocklist_item(item);
@@ -358,6 +397,39 @@ impl IncludeCppEngine {
         }
     }
 
+    /// Returns the items destined for a `#[cxx::bridge]` mod, if
+    /// `exclude_bridge_wrapper!()` was specified such that they're not
+    /// already included within [`Self::get_rs_output`]. Call `generate` first.
+    pub fn get_bridge_items(&self) -> TokenStream2 {
+        match &self.state {
+            State::NotGenerated => panic!("Generate first"),
+            State::Generated(gen_results) => {
+                let items = &gen_results.bridge_items;
+                quote! { #(#items)* }
+            }
+            State::ParseOnly => TokenStream2::new(),
+        }
+    }
+
+    /// Returns the items destined for `#[cxx::bridge]` mods, grouped by
+    /// the C++ namespace from which they came (as a Rust module path),
+    /// if `split_by_namespace!()` and `exclude_bridge_wrapper!()` were
+    /// specified. Each group should be wrapped in its own `#[cxx::bridge]`
+    /// mod named by [`bridge_mod_name`]. If `shared_type_declarations!()`
+    /// was also specified, types are instead declared in the group keyed
+    /// [`SHARED_TYPES_BRIDGE_MOD`]. Call `generate` first.
+    pub fn get_bridge_items_by_namespace(&self) -> HashMap<String, TokenStream2> {
+        match &self.state {
+            State::NotGenerated => panic!("Generate first"),
+            State::Generated(gen_results) => gen_results
+                .bridge_items_by_namespace
+                .iter()
+                .map(|(ns, items)| (ns.clone(), quote! { #(#items)* }))
+                .collect(),
+            State::ParseOnly => HashMap::new(),
+        }
+    }
+
     /// Returns the name of the mod which this `include_cpp!` will generate.
     /// Can and should be used to ensure multiple mods in a file don't conflict.
     pub fn get_mod_name(&self) -> String {
@@ -388,6 +460,45 @@ impl IncludeCppEngine {
         extra_clang_args: &[&str],
         dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
         cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<()> {
+        self.generate_impl(
+            inc_dirs,
+            extra_clang_args,
+            dep_recorder,
+            cpp_codegen_options,
+            None,
+        )
+    }
+
+    /// As [Self::generate], but generates bindings only for the given
+    /// C++ symbol (e.g. `"ns::Foo"` or `"do_thing"`) and whatever it
+    /// depends upon, instead of everything on the allowlist. This can
+    /// be much quicker for tools which need bindings for only a few
+    /// items from a large set of headers. It's an error if the symbol
+    /// can't be found.
+    pub fn generate_symbol(
+        &mut self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        cpp_codegen_options: &CppCodegenOptions,
+        symbol: &str,
+    ) -> Result<()> {
+        self.generate_impl(
+            inc_dirs,
+            extra_clang_args,
+            None,
+            cpp_codegen_options,
+            Some(&QualifiedName::new_from_cpp_name(symbol)),
+        )
+    }
+
+    fn generate_impl(
+        &mut self,
+        inc_dirs: Vec<PathBuf>,
+        extra_clang_args: &[&str],
+        dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+        cpp_codegen_options: &CppCodegenOptions,
+        symbol: Option<&QualifiedName>,
     ) -> Result<()> {
         // If we are in parse only mode, do nothing. This is used for
         // doc tests to ensure the parsing is valid, but we can't expect
@@ -398,30 +509,107 @@ impl IncludeCppEngine {
             State::Generated(_) => panic!("Only call generate once"),
         }
 
-        let mod_name = self.config.get_mod_name();
-        let mut builder = self.make_bindgen_builder(&inc_dirs, extra_clang_args);
-        if let Some(dep_recorder) = dep_recorder {
-            builder = builder.parse_callbacks(Box::new(AutocxxParseCallbacks(dep_recorder)));
-        }
         let header_contents = self.build_header();
-        self.dump_header_if_so_configured(&header_contents, &inc_dirs, extra_clang_args);
-        let header_and_prelude = format!("{}\n\n{}", known_types().get_prelude(), header_contents);
-        log::info!("Header and prelude for bindgen:\n{}", header_and_prelude);
-        builder = builder.header_contents("example.hpp", &header_and_prelude);
+        let bindings =
+            self.run_bindgen(&header_contents, &inc_dirs, extra_clang_args, dep_recorder)?;
+        let converter = BridgeConverter::new(
+            &self.config.inclusions,
+            &self.config,
+            cpp_codegen_options.foreign_fn_hook,
+            cpp_codegen_options.constructor_detector,
+        );
 
-        let bindings = builder.generate().map_err(Error::Bindgen)?;
-        let bindings = self.parse_bindings(bindings)?;
+        let unsafe_policy = self.config.unsafe_policy.clone();
+        let conversion = match symbol {
+            None => converter.convert(
+                bindings,
+                unsafe_policy,
+                header_contents,
+                cpp_codegen_options,
+            ),
+            Some(symbol) => converter.convert_symbol(
+                bindings,
+                unsafe_policy,
+                header_contents,
+                cpp_codegen_options,
+                symbol,
+            ),
+        }
+        .map_err(Error::Conversion)?;
+        self.store_conversion(conversion, inc_dirs);
+        Ok(())
+    }
 
-        let converter = BridgeConverter::new(&self.config.inclusions, &self.config);
+    /// Runs bindgen over the headers and writes its output to the given
+    /// file, without converting it. [Self::generate_from_bindgen_tokens]
+    /// can later generate bindings from that file without running bindgen
+    /// again, provided the `include_cpp!` directives and headers haven't
+    /// changed in the meantime.
+    pub fn save_bindgen_tokens(
+        &self,
+        inc_dirs: &[PathBuf],
+        extra_clang_args: &[&str],
+        path: &Path,
+    ) -> Result<()> {
+        let header_contents = self.build_header();
+        let bindings = self.run_bindgen(&header_contents, inc_dirs, extra_clang_args, None)?;
+        std::fs::write(path, bindings.to_token_stream().to_string())
+            .map_err(Error::BindgenTokensWrite)
+    }
 
+    /// As [Self::generate], but uses bindgen output previously written by
+    /// [Self::save_bindgen_tokens] instead of running bindgen.
+    pub fn generate_from_bindgen_tokens(
+        &mut self,
+        inc_dirs: Vec<PathBuf>,
+        path: &Path,
+        cpp_codegen_options: &CppCodegenOptions,
+    ) -> Result<()> {
+        match self.state {
+            State::ParseOnly => return Ok(()),
+            State::NotGenerated => {}
+            State::Generated(_) => panic!("Only call generate once"),
+        }
+        let converter = BridgeConverter::new(
+            &self.config.inclusions,
+            &self.config,
+            cpp_codegen_options.foreign_fn_hook,
+            cpp_codegen_options.constructor_detector,
+        );
         let conversion = converter
-            .convert(
-                bindings,
+            .convert_from_tokens(
+                path,
                 self.config.unsafe_policy.clone(),
-                header_contents,
+                self.build_header(),
                 cpp_codegen_options,
             )
             .map_err(Error::Conversion)?;
+        self.store_conversion(conversion, inc_dirs);
+        Ok(())
+    }
+
+    fn run_bindgen(
+        &self,
+        header_contents: &str,
+        inc_dirs: &[PathBuf],
+        extra_clang_args: &[&str],
+        dep_recorder: Option<Box<dyn RebuildDependencyRecorder>>,
+    ) -> Result<ItemMod> {
+        let mut builder = self.make_bindgen_builder(inc_dirs, extra_clang_args);
+        if let Some(dep_recorder) = dep_recorder {
+            builder = builder.parse_callbacks(Box::new(AutocxxParseCallbacks(dep_recorder)));
+        }
+        self.dump_header_if_so_configured(header_contents, inc_dirs, extra_clang_args);
+        let header_and_prelude = format!("{}\n\n{}", known_types().get_prelude(), header_contents);
+        log::info!("Header and prelude for bindgen:\n{}", header_and_prelude);
+        builder = builder.header_contents("example.hpp", &header_and_prelude);
+
+        let bindings = builder.generate().map_err(Error::Bindgen)?;
+        self.parse_bindings(bindings)
+    }
+
+    fn store_conversion(&mut self, conversion: CodegenResults, inc_dirs: Vec<PathBuf>) {
+        let mod_name = self.config.get_mod_name();
         let mut items = conversion.rs;
         let mut new_bindings: ItemMod = parse_quote! {
             #[allow(non_snake_case)]
@@ -438,11 +626,13 @@ impl IncludeCppEngine {
         );
         self.state = State::Generated(Box::new(GenerationResults {
             item_mod: new_bindings,
+            bridge_items: conversion.bridge_items,
+            bridge_items_by_namespace: conversion.bridge_items_by_namespace,
+            merged_bridge_items: conversion.merged_bridge_items,
             cpp: conversion.cpp,
             inc_dirs,
             cxxgen_header_name: conversion.cxxgen_header_name,
         }));
-        Ok(())
     }
 
     /// Return the include directories used for this include_cpp invocation.
@@ -591,7 +781,20 @@ impl CppBuildable for IncludeCppEngine {
             State::ParseOnly => panic!("Cannot generate C++ in parse-only mode"),
             State::NotGenerated => panic!("Call generate() first"),
             State::Generated(gen_results) => {
-                let rs = gen_results.item_mod.to_token_stream();
+                // cxx checks all the bridges it's given together, so if
+                // we've split our bridge up, the C++ comes from a single
+                // bridge holding all their contents instead.
+                let rs = if self.config.split_by_namespace() {
+                    let bridge_items = &gen_results.merged_bridge_items;
+                    quote! {
+                        #[cxx::bridge]
+                        mod cxxbridge {
+                            #(#bridge_items)*
+                        }
+                    }
+                } else {
+                    gen_results.item_mod.to_token_stream()
+                };
                 files.push(do_cxx_cpp_generation(
                     rs,
                     cpp_codegen_options,
@@ -688,6 +891,41 @@ impl CxxgenHeaderNamer<'_> {
     }
 }
 
+/// What to do with a function found in the bindgen output, as decided by
+/// a [`ForeignFnHook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FnAction {
+    /// Generate bindings for this function as normal.
+    Keep,
+    /// Don't generate bindings for this function at all.
+    Skip,
+    /// Generate bindings for this function, but give it this name in Rust.
+    Rename(String),
+}
+
+/// A function called for each function found in the bindgen output before
+/// we convert it, allowing project-specific rules to skip or rename it.
+pub type ForeignFnHook = fn(&ForeignItemFn) -> FnAction;
+
+/// A function called with the name which bindgen gave to a method, and the
+/// name of the class to which it belongs, to decide whether that method is
+/// a constructor. If it is, this returns the suffix which distinguishes it
+/// from any other overloaded constructors (which may be empty) and the
+/// Rust constructor will be called `new` followed by that suffix. If not,
+/// this returns `None`. [`is_constructor`] is used unless another is
+/// specified.
+pub type ConstructorDetector = fn(&str, &str) -> Option<String>;
+
+/// The default [`ConstructorDetector`], which recognizes bindgen's naming
+/// scheme for constructors: the name of the class itself, followed by a
+/// number if there are several overloads.
+pub fn is_constructor(fn_name: &str, class_name: &str) -> Option<String> {
+    fn_name
+        .strip_prefix(class_name)
+        .filter(|suffix| suffix.is_empty() || suffix.parse::<u32>().is_ok())
+        .map(str::to_string)
+}
+
 /// Options for C++ codegen
 #[derive(Default)]
 pub struct CppCodegenOptions<'a> {
@@ -711,6 +949,17 @@ pub struct CppCodegenOptions<'a> {
     /// An annotation optionally to include on each C++ function.
     /// For example to export the symbol from a library.
     pub cxx_impl_annotations: Option<String>,
+    /// Optionally, a function to decide whether and how to convert each
+    /// function found in the bindgen output. See [`ForeignFnHook`].
+    pub foreign_fn_hook: Option<ForeignFnHook>,
+    /// Optionally, a function to decide which methods found in the bindgen
+    /// output are constructors. See [`ConstructorDetector`].
+    pub constructor_detector: Option<ConstructorDetector>,
+    /// Optionally, a directory in which to cache the results of converting
+    /// bindgen's output, so that it needn't be repeated if nothing has
+    /// changed. The header namers are assumed to give the same names each
+    /// time for the same inputs.
+    pub conversion_cache_dir: Option<PathBuf>,
 }
 
 fn proc_macro_span_to_miette_span(span: &proc_macro2::Span) -> SourceSpan {
@@ -735,3 +984,18 @@ fn proc_macro_span_to_miette_span(span: &proc_macro2::Span) -> SourceSpan {
     let (start, end) = r.unwrap_or((0, 0));
     SourceSpan::new(SourceOffset::from(start), SourceOffset::from(end))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_constructor;
+
+    #[test]
+    fn test_is_constructor() {
+        assert_eq!(is_constructor("Widget", "Widget"), Some("".into()));
+        assert_eq!(is_constructor("Widget1", "Widget"), Some("1".into()));
+        assert_eq!(is_constructor("Widget12", "Widget"), Some("12".into()));
+        assert_eq!(is_constructor("WidgetSize", "Widget"), None);
+        assert_eq!(is_constructor("Widget_new", "Widget"), None);
+        assert_eq!(is_constructor("make_Widget", "Widget"), None);
+    }
+}
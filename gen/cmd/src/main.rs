@@ -246,6 +246,9 @@ fn main() -> miette::Result<()> {
         path_to_cxxgen_h: get_option_string("cxxgen-h-path", &matches),
         autocxxgen_header_namer,
         cxxgen_header_namer,
+        foreign_fn_hook: None,
+        constructor_detector: None,
+        conversion_cache_dir: None,
     };
     let depfile = match matches.value_of("depfile") {
         None => None,
@@ -16,7 +16,8 @@ use std::{
 };
 
 use autocxx_engine::{
-    Builder, BuilderBuild, BuilderContext, BuilderError, RebuildDependencyRecorder, HEADER,
+    parse_file, Builder, BuilderBuild, BuilderContext, BuilderError, CppCodegenOptions,
+    RebuildDependencyRecorder, HEADER,
 };
 use log::info;
 use once_cell::sync::OnceCell;
@@ -310,7 +311,7 @@ pub fn run_test_expect_fail_ex(
     code_checker: Option<CodeChecker>,
     extra_rust: Option<TokenStream>,
 ) {
-    do_run_test(
+    match do_run_test(
         cxx_code,
         header_code,
         rust_code,
@@ -319,8 +320,13 @@ pub fn run_test_expect_fail_ex(
         code_checker,
         extra_rust,
         "unsafe_ffi",
-    )
-    .expect_err("Unexpected success");
+    ) {
+        Ok(()) => panic!("Unexpected success"),
+        // A failing code checker means we didn't fail in the expected way.
+        Err(TestError::RsCodeExaminationFail(msg)) => panic!("Code checker failed: {}", msg),
+        Err(TestError::CppCodeExaminationFail) => panic!("C++ code checker failed"),
+        Err(_) => {}
+    }
 }
 
 /// In the future maybe the tests will distinguish the exact type of failure expected.
@@ -397,6 +403,51 @@ pub fn do_run_test(
     )
 }
 
+/// Runs just the autocxx code generation phase - not cxx, nor any
+/// compilation - and returns the namespaces into which the items for
+/// `#[cxx::bridge]` mods were grouped. Only useful alongside
+/// `split_by_namespace!()`.
+pub fn generate_bridge_namespaces(header_code: &str, directives: TokenStream) -> Vec<String> {
+    generate_bridges_by_namespace(header_code, directives)
+        .into_iter()
+        .map(|(ns, _)| ns)
+        .collect()
+}
+
+/// Like [`generate_bridge_namespaces`], but also returns the items for
+/// each `#[cxx::bridge]` mod.
+pub fn generate_bridges_by_namespace(
+    header_code: &str,
+    directives: TokenStream,
+) -> Vec<(String, TokenStream)> {
+    let tdir = tempdir().unwrap();
+    write_to_file(&tdir, "input.h", &format!("#pragma once\n{}", header_code));
+    let hexathorpe = Token![#](Span::call_site());
+    let rust_code = quote! {
+        use autocxx::prelude::*;
+
+        include_cpp!(
+            #hexathorpe include "input.h"
+            safety!(unsafe_ffi)
+            #directives
+        );
+    };
+    let rs_path = write_to_file(&tdir, "input.rs", &rust_code.to_string());
+    let mut parsed_file = parse_file(&rs_path, false).unwrap();
+    parsed_file
+        .resolve_all(
+            vec![tdir.path().to_path_buf()],
+            &[],
+            None,
+            &CppCodegenOptions::default(),
+        )
+        .unwrap();
+    parsed_file
+        .get_autocxxes()
+        .flat_map(|autocxx| autocxx.get_bridge_items_by_namespace().into_iter())
+        .collect()
+}
+
 /// The [`BuilderContext`] used in autocxx's integration tests.
 pub struct TestBuilderContext;
 
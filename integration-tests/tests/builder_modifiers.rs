@@ -6,7 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use autocxx_engine::Builder;
+use autocxx_engine::{Builder, ConstructorDetector, ForeignFnHook};
 
 use autocxx_integration_tests::{BuilderModifier, BuilderModifierFns, TestBuilderContext};
 
@@ -14,6 +14,10 @@ pub(crate) fn make_cpp17_adder() -> Option<BuilderModifier> {
     make_clang_arg_adder(&["-std=c++17"])
 }
 
+pub(crate) fn make_cpp20_adder() -> Option<BuilderModifier> {
+    make_clang_arg_adder(&["-std=c++20"])
+}
+
 struct ClangArgAdder(Vec<String>, Vec<String>);
 
 pub(crate) fn make_clang_arg_adder(args: &[&str]) -> Option<BuilderModifier> {
@@ -70,3 +74,25 @@ impl BuilderModifierFns for EnableAutodiscover {
         builder.auto_allowlist(true)
     }
 }
+
+pub(crate) struct SetForeignFnHook(pub(crate) ForeignFnHook);
+
+impl BuilderModifierFns for SetForeignFnHook {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.foreign_fn_hook(self.0)
+    }
+}
+
+pub(crate) struct SetConstructorDetector(pub(crate) ConstructorDetector);
+
+impl BuilderModifierFns for SetConstructorDetector {
+    fn modify_autocxx_builder<'a>(
+        &self,
+        builder: Builder<'a, TestBuilderContext>,
+    ) -> Builder<'a, TestBuilderContext> {
+        builder.constructor_detector(self.0)
+    }
+}
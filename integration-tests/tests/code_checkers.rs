@@ -101,6 +101,28 @@ pub(crate) fn make_string_finder(error_texts: Vec<String>) -> CodeChecker {
     Box::new(StringFinder(error_texts))
 }
 
+struct StringAbsenceChecker(Vec<String>);
+
+impl CodeCheckerFns for StringAbsenceChecker {
+    fn check_rust(&self, rs: syn::File) -> Result<(), TestError> {
+        let toks = rs.to_token_stream().to_string();
+        for msg in &self.0 {
+            if toks.contains(msg) {
+                return Err(TestError::RsCodeExaminationFail(format!(
+                    "Unexpectedly found string '{}'",
+                    msg
+                )));
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Returns a code checker which ensures a given string is _not_ in the results
+pub(crate) fn make_string_absence_checker(texts: Vec<String>) -> CodeChecker {
+    Box::new(StringAbsenceChecker(texts))
+}
+
 struct RustCodeFinder(Vec<TokenStream>);
 
 impl CodeCheckerFns for RustCodeFinder {
@@ -180,3 +202,22 @@ impl CodeCheckerFns for NoSystemHeadersChecker {
         true
     }
 }
+
+/// Ensures no `#[cxx::bridge]` mod is generated. The resulting bindings
+/// can't be built on their own, so we don't try.
+pub(crate) struct NoCxxBridgeModChecker;
+
+impl CodeCheckerFns for NoCxxBridgeModChecker {
+    fn check_rust(&self, rs: syn::File) -> Result<(), TestError> {
+        let toks = rs.to_token_stream().to_string();
+        if toks.contains("mod cxxbridge") {
+            return Err(TestError::RsCodeExaminationFail(
+                "Unexpectedly found cxxbridge mod".into(),
+            ));
+        }
+        Ok(())
+    }
+    fn skip_build(&self) -> bool {
+        true
+    }
+}
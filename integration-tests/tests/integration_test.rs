@@ -8,17 +8,18 @@
 
 use crate::{
     builder_modifiers::{
-        make_clang_arg_adder, make_clang_optional_arg_adder, make_cpp17_adder, EnableAutodiscover,
-        SetSuppressSystemHeaders,
+        make_clang_arg_adder, make_clang_optional_arg_adder, make_cpp17_adder, make_cpp20_adder,
+        EnableAutodiscover, SetConstructorDetector, SetForeignFnHook, SetSuppressSystemHeaders,
     },
     code_checkers::{
-        make_error_finder, make_rust_code_finder, make_string_finder, CppMatcher,
-        NoSystemHeadersChecker,
+        make_error_finder, make_rust_code_finder, make_string_absence_checker, make_string_finder,
+        CppMatcher, NoCxxBridgeModChecker, NoSystemHeadersChecker,
     },
 };
 use autocxx_integration_tests::{
-    directives_from_lists, do_run_test, do_run_test_manual, run_generate_all_test, run_test,
-    run_test_ex, run_test_expect_fail, run_test_expect_fail_ex, TestError,
+    directives_from_lists, do_run_test, do_run_test_manual, generate_bridge_namespaces,
+    generate_bridges_by_namespace, run_generate_all_test, run_test, run_test_ex,
+    run_test_expect_fail, run_test_expect_fail_ex, TestError,
 };
 use indoc::indoc;
 use itertools::Itertools;
@@ -118,6 +119,63 @@ fn test_take_i32() {
     run_test(cxx, hdr, rs, &["take_int"], &[]);
 }
 
+#[test]
+fn test_give_bool() {
+    let cxx = indoc! {"
+        bool give_bool(uint32_t a) {
+            return a > 3;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        bool give_bool(uint32_t a);
+    "};
+    let rs = quote! {
+        let yes: bool = ffi::give_bool(4);
+        assert!(yes);
+        assert!(!ffi::give_bool(2));
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(&["give_bool"], &[], None),
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            pub fn give_bool(a: u32) -> bool;
+        }])),
+        None,
+    );
+}
+
+#[test]
+fn test_take_bool() {
+    let cxx = indoc! {"
+        uint32_t take_bool(bool a) {
+            return a ? 1 : 0;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        uint32_t take_bool(bool a);
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::take_bool(true), 1);
+        assert_eq!(ffi::take_bool(false), 0);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(&["take_bool"], &[], None),
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            pub fn take_bool(a: bool) -> u32;
+        }])),
+        None,
+    );
+}
+
 #[test]
 fn test_nested_module() {
     let cxx = indoc! {"
@@ -739,6 +797,65 @@ fn test_take_nonpod_by_up() {
     run_test(cxx, hdr, rs, &["take_bob", "Bob", "make_bob", "NOP"], &[]);
 }
 
+#[test]
+fn test_transform_nonpod_by_up() {
+    let cxx = indoc! {"
+        std::unique_ptr<Bob> make_bob(uint32_t a) {
+            auto b = std::make_unique<Bob>();
+            b->a = a;
+            return b;
+        }
+        std::unique_ptr<Anna> bob_to_anna(std::unique_ptr<Bob> b) {
+            auto a = std::make_unique<Anna>();
+            a->a = b->a * 2;
+            return a;
+        }
+        std::unique_ptr<Bob> anna_to_bob(std::unique_ptr<Anna> a) {
+            auto b = std::make_unique<Bob>();
+            b->a = a->a + 1;
+            return b;
+        }
+        uint32_t get_bob(const Bob& b) {
+            return b.a;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            uint32_t a;
+        };
+        struct Anna {
+            uint32_t a;
+        };
+
+        std::unique_ptr<Bob> make_bob(uint32_t a);
+        std::unique_ptr<Anna> bob_to_anna(std::unique_ptr<Bob> b);
+        std::unique_ptr<Bob> anna_to_bob(std::unique_ptr<Anna> a);
+        uint32_t get_bob(const Bob& b);
+    "};
+    let rs = quote! {
+        let b = ffi::make_bob(3);
+        let a = ffi::bob_to_anna(b);
+        let b = ffi::anna_to_bob(a);
+        assert_eq!(ffi::get_bob(&b), 7);
+    };
+    run_test(
+        cxx,
+        hdr,
+        rs,
+        &[
+            "make_bob",
+            "bob_to_anna",
+            "anna_to_bob",
+            "get_bob",
+            "Bob",
+            "Anna",
+        ],
+        &[],
+    );
+}
+
 #[test]
 fn test_take_nonpod_by_ptr_simple() {
     let cxx = indoc! {"
@@ -2011,6 +2128,59 @@ fn test_pass_rust_str() {
     run_test(cxx, hdr, rs, &["measure_string"], &[]);
 }
 
+#[test]
+fn test_pass_rust_slice_of_const() {
+    let cxx = indoc! {"
+        uint32_t checksum(rust::Slice<const uint8_t> data) {
+            uint32_t total = 0;
+            for (auto b : data) {
+                total += b;
+            }
+            return total;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cxx.h>
+        uint32_t checksum(rust::Slice<const uint8_t> data);
+    "};
+    let rs = quote! {
+        let data = [1u8, 2, 3, 250];
+        assert_eq!(ffi::checksum(&data), 256);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        quote! {
+            generate!("checksum")
+            const_slice_param!("checksum", "data")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pass_rust_slice() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cxx.h>
+        inline void double_all(rust::Slice<uint32_t> data) {
+            for (auto& x : data) {
+                x *= 2;
+            }
+        }
+    "};
+    let rs = quote! {
+        let mut data = vec![1u32, 2, 3];
+        ffi::double_all(&mut data);
+        assert_eq!(data, [2, 4, 6]);
+    };
+    run_test("", hdr, rs, &["double_all"], &[]);
+}
+
 #[test]
 fn test_multiple_classes_with_methods() {
     let hdr = indoc! {"
@@ -2217,6 +2387,28 @@ fn test_overload_constructors() {
     run_test(cxx, hdr, rs, &["Bob"], &[]);
 }
 
+#[test]
+fn test_overload_non_copy_constructors() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Bob {
+        public:
+            Bob(uint32_t _a) : a(_a), b(0) {}
+            Bob(uint32_t _a, uint32_t _b) : a(_a), b(_b) {}
+            Bob(const Bob& other) : a(other.a), b(other.b) {}
+            uint32_t sum() const { return a + b; }
+        private:
+            uint32_t a;
+            uint32_t b;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::Bob::new(3).within_unique_ptr().sum(), 3);
+        assert_eq!(ffi::Bob::new1(3, 4).within_unique_ptr().sum(), 7);
+    };
+    run_test("", hdr, rs, &["Bob"], &[]);
+}
+
 #[test]
 fn test_overload_functions() {
     let cxx = indoc! {"
@@ -2350,6 +2542,28 @@ fn test_overload_methods() {
     run_test(cxx, hdr, rs, &["Norma"], &["Fred", "Bob"]);
 }
 
+#[test]
+fn test_overload_const_and_mutable_methods() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Row {
+        public:
+            Row() : cells{1, 2, 3} {}
+            const uint32_t& at(uint32_t i) const { return cells[i]; }
+            uint32_t& at(uint32_t i) { return cells[i]; }
+        private:
+            uint32_t cells[3];
+        };
+    "};
+    let rs = quote! {
+        let mut row = ffi::Row::new().within_box();
+        *row.as_mut().at_mut(1) = 20;
+        assert_eq!(*row.at(1), 20);
+        assert_eq!(*row.at(2), 3);
+    };
+    run_test("", hdr, rs, &["Row"], &[]);
+}
+
 #[test]
 fn test_ns_constructor() {
     let cxx = indoc! {"
@@ -3023,6 +3237,21 @@ fn test_make_string() {
     run_test("", hdr, rs, &["Bob"], &[]);
 }
 
+#[test]
+fn test_string_conversion_helpers() {
+    let hdr = indoc! {"
+        #include <string>
+        inline std::string shout(const std::string& s) { return s + \"!\"; }
+    "};
+    let rs = quote! {
+        let cpp = ffi::string_from_rust("hello");
+        assert_eq!(cpp.to_str().unwrap(), "hello");
+        let shouted = ffi::shout(&cpp);
+        assert_eq!(ffi::string_to_rust(&shouted), "hello!");
+    };
+    run_test("", hdr, rs, &["shout"], &[]);
+}
+
 #[test]
 fn test_string_make_unique() {
     let hdr = indoc! {"
@@ -4041,6 +4270,91 @@ fn test_forward_declaration() {
     run_test(cpp, hdr, rs, &["B", "get_a", "delete_a"], &[]);
 }
 
+#[test]
+fn test_forward_declared_param_type_only() {
+    // Handle is never defined in the header, and we don't ask for it
+    // to be generated; it's only ever used behind a pointer or reference.
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace store {
+            struct Handle;
+            Handle* open_handle();
+            uint32_t handle_value(const Handle& h);
+            void close_handle(Handle* h);
+        }
+    "};
+    let cpp = indoc! {"
+        namespace store {
+            struct Handle {
+                uint32_t value;
+            };
+            Handle* open_handle() {
+                return new Handle { 7 };
+            }
+            uint32_t handle_value(const Handle& h) {
+                return h.value;
+            }
+            void close_handle(Handle* h) {
+                delete h;
+            }
+        }
+    "};
+    let rs = quote! {
+        let h = ffi::store::open_handle();
+        assert_eq!(ffi::store::handle_value(unsafe { h.as_ref().unwrap() }), 7);
+        unsafe { ffi::store::close_handle(h) };
+    };
+    run_test(
+        cpp,
+        hdr,
+        rs,
+        &[
+            "store::open_handle",
+            "store::handle_value",
+            "store::close_handle",
+        ],
+        &[],
+    );
+}
+
+#[test]
+fn test_forward_declared_pointer_param_in_method() {
+    // Incomplete stays a raw pointer, rather than becoming a reference
+    // which would imply we know about its contents.
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Incomplete;
+        Incomplete* make_incomplete();
+        class Registry {
+        public:
+            Registry() : adopted(0) {}
+            void adopt(Incomplete* item);
+            uint32_t count() const { return adopted; }
+        private:
+            uint32_t adopted;
+        };
+    "};
+    let cpp = indoc! {"
+        struct Incomplete {
+            uint32_t value;
+        };
+        Incomplete* make_incomplete() {
+            return new Incomplete { 3 };
+        }
+        void Registry::adopt(Incomplete* item) {
+            adopted += item->value;
+            delete item;
+        }
+    "};
+    let rs = quote! {
+        let item = ffi::make_incomplete();
+        let mut registry = ffi::Registry::new().within_unique_ptr();
+        unsafe { registry.pin_mut().adopt(item) };
+        assert_eq!(registry.count(), 3);
+    };
+    run_test(cpp, hdr, rs, &["Registry", "make_incomplete"], &[]);
+}
+
 #[test]
 fn test_ulong() {
     let hdr = indoc! {"
@@ -4199,6 +4513,38 @@ fn test_nested_enum_in_namespace() {
     run_test("", hdr, rs, &["take_A_B"], &[]);
 }
 
+#[test]
+fn test_nested_enum_in_class() {
+    let hdr = indoc! {"
+        class Widget {
+        public:
+            enum class Mode {
+                Fast,
+                Slow,
+            };
+            Widget() : mode(Mode::Slow) {}
+            Mode get_mode() const { return mode; }
+        private:
+            Mode mode;
+        };
+    "};
+    let rs = quote! {
+        let w = ffi::Widget::new().within_unique_ptr();
+        assert!(matches!(w.get_mode(), ffi::Widget_Mode::Slow));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("Widget") },
+        None,
+        Some(make_string_finder(vec![
+            "Nested within `Widget` in C++, as `Widget::Mode`.".into(),
+        ])),
+        None,
+    );
+}
+
 #[test]
 fn test_abstract_nested_type() {
     let hdr = indoc! {"
@@ -5173,6 +5519,68 @@ fn test_unexpected_use() {
     run_test("", hdr, rs, &["content::RenderFrameHost"], &[]);
 }
 
+#[test]
+fn test_function_in_anonymous_namespace() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace {
+        inline uint32_t hidden() { return 1; }
+        }
+        inline uint32_t visible() { return 2; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::visible(), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate_all!() },
+        None,
+        Some(make_string_finder(vec!["internal linkage".into()])),
+        None,
+    );
+}
+
+#[test]
+#[ignore] // because autocxx-bindgen doesn't yet tell us about volatile
+          // qualifiers, so we can't tell this apart from a plain uint32_t*.
+fn test_volatile_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline void write_reg(volatile uint32_t* reg, uint32_t val) { *reg = val; }
+        inline uint32_t get_ten() { return 10; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_ten(), 10);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate_all!() },
+        None,
+        Some(make_string_finder(vec![
+            "points or refers to something volatile".into(),
+        ])),
+        None,
+    );
+}
+
+#[test]
+#[ignore] // for the same reason as test_volatile_param
+fn test_volatile_field_not_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Regs {
+            volatile uint32_t status;
+            uint32_t ctrl;
+        };
+    "};
+    let rs = quote! {};
+    run_test_expect_fail("", hdr, rs, &[], &["Regs"]);
+}
+
 #[test]
 fn test_get_pure_virtual() {
     let hdr = indoc! {"
@@ -5304,7 +5712,7 @@ fn test_vec_and_up_of_primitives() {
         public:
             Value(std::vector<uint32_t>) {} // OK
             Value(std::unique_ptr<uint32_t>) {} // should be ignored
-            Value(std::vector<int>) {} // should be ignored
+            Value(std::vector<int>) {} // OK
             Value(std::unique_ptr<int>) {} // should be ignored
             Value(std::vector<char>) {} // should be ignored
             Value(std::unique_ptr<char>) {} // should be ignored
@@ -5364,6 +5772,24 @@ fn test_pointer_to_pointer() {
     run_test("", hdr, rs, &["operations_research::Solver"], &[]);
 }
 
+#[test]
+fn test_restrict_pointers() {
+    let hdr = indoc! {"
+        inline void f(int* __restrict a, int* __restrict b) {
+            *a += *b;
+            *b = 0;
+        }
+    "};
+    let rs = quote! {
+        let mut a = autocxx::c_int(1);
+        let mut b = autocxx::c_int(2);
+        unsafe { ffi::f(&mut a, &mut b) };
+        assert_eq!(a, autocxx::c_int(3));
+        assert_eq!(b, autocxx::c_int(0));
+    };
+    run_test("", hdr, rs, &["f"], &[]);
+}
+
 #[test]
 fn test_defines_effective() {
     let hdr = indoc! {"
@@ -5992,12 +6418,166 @@ fn test_doc_passthru() {
 }
 
 #[test]
-fn test_closure() {
-    // Ensuring presence of this closure doesn't break other things
+fn test_doc_deprecated() {
     let hdr = indoc! {"
-    #include <functional>
-    #include <cstdint>
-
+        #include <cstdint>
+        /// Adds one.
+        /// @deprecated Use add_two instead.
+        inline uint32_t add_one(uint32_t a) { return a + 1; }
+        /// Adds two.
+        inline uint32_t add_two(uint32_t a) { return a + 2; }
+    "};
+    let rs = quote! {
+        #[allow(deprecated)]
+        let a = ffi::add_one(1);
+        assert_eq!(a, 2);
+        assert_eq!(ffi::add_two(1), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["add_one", "add_two"], &[], None),
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            #[deprecated(note = "Use add_two instead.")]
+        }])),
+        None,
+    );
+}
+
+#[test]
+fn test_doc_deprecated_wrapper() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        /// Measures a string.
+        /// @deprecated Use size instead.
+        inline uint32_t measure(std::string s) { return s.size(); }
+    "};
+    let rs = quote! {
+        #[allow(deprecated)]
+        let n = ffi::measure("hello");
+        assert_eq!(n, 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["measure"], &[], None),
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            #[deprecated(note = "Use size instead.")]
+            #[inline]
+            pub fn measure
+        }])),
+        None,
+    );
+}
+
+#[test]
+fn test_doc_deprecated_warns() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        /// @deprecated Use add_two instead.
+        inline uint32_t add_one(uint32_t a) { return a + 1; }
+        /// A point.
+        /// @deprecated Use Vec2 instead.
+        struct Point {
+            uint32_t x;
+        };
+    "};
+    run_test_expect_fail(
+        "",
+        hdr,
+        quote! {
+            #[deny(deprecated)]
+            let _ = ffi::add_one(1);
+        },
+        &["add_one"],
+        &[],
+    );
+    run_test_expect_fail(
+        "",
+        hdr,
+        quote! {
+            #[deny(deprecated)]
+            let _ = ffi::Point { x: 1 };
+        },
+        &[],
+        &["Point"],
+    );
+}
+
+const CPP_DEPRECATED_HDR: &str = indoc! {"
+    #include <cstdint>
+    [[deprecated(\"Use add_two instead.\")]]
+    inline uint32_t add_one(uint32_t a) { return a + 1; }
+    struct [[deprecated]] Point {
+        uint32_t x;
+    };
+    enum class [[deprecated(\"Use Shade instead.\")]] Color {
+        Red,
+    };
+"};
+
+#[test]
+#[ignore] // autocxx-bindgen doesn't yet tell us about [[deprecated]]
+fn test_cpp_deprecated() {
+    let rs = quote! {
+        #[allow(deprecated)]
+        let a = ffi::add_one(1);
+        assert_eq!(a, 2);
+        #[allow(deprecated)]
+        let p = ffi::Point { x: 1 };
+        assert_eq!(p.x, 1);
+        #[allow(deprecated)]
+        let _ = ffi::Color::Red;
+    };
+    run_test_ex(
+        "",
+        CPP_DEPRECATED_HDR,
+        rs,
+        directives_from_lists(&["add_one", "Color"], &["Point"], None),
+        None,
+        Some(make_rust_code_finder(vec![
+            quote! { #[deprecated(note = "Use add_two instead.")] },
+            quote! { #[deprecated] pub struct Point },
+            quote! { #[deprecated(note = "Use Shade instead.")] pub enum Color },
+        ])),
+        None,
+    );
+}
+
+#[test]
+#[ignore] // autocxx-bindgen doesn't yet tell us about [[deprecated]]
+fn test_cpp_deprecated_warns() {
+    let uses = [
+        quote! { let _ = ffi::add_one(1); },
+        quote! { let _ = ffi::Point { x: 1 }; },
+        quote! { let _ = ffi::Color::Red; },
+    ];
+    for use_of_deprecated_item in uses {
+        run_test_expect_fail(
+            "",
+            CPP_DEPRECATED_HDR,
+            quote! {
+                #[deny(deprecated)]
+                #use_of_deprecated_item
+            },
+            &["add_one", "Color"],
+            &["Point"],
+        );
+    }
+}
+
+#[test]
+fn test_closure() {
+    // Ensuring presence of this closure doesn't break other things
+    let hdr = indoc! {"
+    #include <functional>
+    #include <cstdint>
+
     inline bool take_closure(std::function<bool(const uint32_t number)> fn) {
         return fn(5);
     }
@@ -6244,7 +6824,6 @@ fn test_cint_vector() {
 }
 
 #[test]
-#[ignore] // https://github.com/google/autocxx/issues/422
 fn test_int_vector() {
     let hdr = indoc! {"
         #include <vector>
@@ -6254,12 +6833,60 @@ fn test_int_vector() {
     "};
 
     let rs = quote! {
-        assert_eq!(ffi::give_vec().as_ref().unwrap().as_slice(), &[autocxx::c_int(1),autocxx::c_int(2)]);
+        assert_eq!(ffi::give_vec().as_ref().unwrap().as_slice(), &[1,2]);
     };
 
     run_test("", hdr, rs, &["give_vec"], &[]);
 }
 
+#[test]
+fn test_nested_int_vector() {
+    let hdr = indoc! {"
+        #include <vector>
+        inline std::vector<std::vector<int>> give_grid() {
+            return std::vector<std::vector<int>> {{1, 2}, {3}};
+        }
+        inline size_t count_cells(const std::vector<std::vector<int>>& grid) {
+            size_t count = 0;
+            for (const auto& row : grid) {
+                count += row.size();
+            }
+            return count;
+        }
+    "};
+
+    let rs = quote! {
+        let grid = ffi::give_grid();
+        let rows = grid.as_ref().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows.get(0).unwrap().as_slice(), &[1, 2]);
+        assert_eq!(rows.get(1).unwrap().as_slice(), &[3]);
+        assert_eq!(ffi::count_cells(rows), 3);
+    };
+
+    run_test("", hdr, rs, &["give_grid", "count_cells"], &[]);
+}
+
+#[test]
+fn test_deduced_vector_return() {
+    let hdr = indoc! {"
+        #include <vector>
+        inline auto give_vec() {
+            return std::vector<int> {1,2};
+        }
+        inline auto give_vec_trailing() -> std::vector<int> {
+            return std::vector<int> {3,4};
+        }
+    "};
+
+    let rs = quote! {
+        assert_eq!(ffi::give_vec().as_ref().unwrap().as_slice(), &[1,2]);
+        assert_eq!(ffi::give_vec_trailing().as_ref().unwrap().as_slice(), &[3,4]);
+    };
+
+    run_test("", hdr, rs, &["give_vec", "give_vec_trailing"], &[]);
+}
+
 #[test]
 fn test_size_t() {
     let hdr = indoc! {"
@@ -9343,6 +9970,46 @@ fn test_explicit_everything() {
     run_test("", hdr, rs, &["A"], &[]);
 }
 
+#[test]
+fn test_move_assignment() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t& move_assignments_made() {
+            static uint32_t count = 0;
+            return count;
+        }
+        inline uint32_t move_assignments() { return move_assignments_made(); }
+        class Tracker {
+        public:
+            explicit Tracker(uint32_t id) : id(id) {}
+            Tracker(Tracker&& other) : id(other.id) { other.id = 0; }
+            Tracker& operator=(Tracker&& other) {
+                id = other.id;
+                other.id = 0;
+                move_assignments_made()++;
+                return *this;
+            }
+            ~Tracker() {}
+            uint32_t get_id() const { return id; }
+        private:
+            uint32_t id;
+        };
+    "};
+    let rs = quote! {
+        let mut a = ffi::Tracker::new(1).within_unique_ptr();
+        let b = ffi::Tracker::new(2).within_unique_ptr();
+        assert_eq!(ffi::move_assignments(), 0);
+        a.pin_mut().move_assign(b);
+        assert_eq!(a.get_id(), 2);
+        assert_eq!(ffi::move_assignments(), 1);
+        let c = Box::emplace(ffi::Tracker::new(3));
+        a.pin_mut().move_assign(c).move_assign(ffi::Tracker::new(4).within_unique_ptr());
+        assert_eq!(a.get_id(), 4);
+        assert_eq!(ffi::move_assignments(), 3);
+    };
+    run_test("", hdr, rs, &["Tracker", "move_assignments"], &[]);
+}
+
 #[test]
 fn test_generate_ns() {
     let hdr = indoc! {"
@@ -11597,9 +12264,3345 @@ fn test_issue_1143() {
     run_test("", hdr, quote! {}, &["mapnik::Map"], &[]);
 }
 
+#[test]
+fn test_preamble() {
+    let hdr = indoc! {"
+        inline int get_preamble_value() {
+            return PREAMBLE_VALUE;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_preamble_value(), autocxx::c_int(42));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("get_preamble_value")
+            preamble!("#define PREAMBLE_VALUE 42")
+        },
+        None,
+        Some(Box::new(CppMatcher::new(
+            &["#define PREAMBLE_VALUE 42"],
+            &[],
+        ))),
+        None,
+    );
+}
+
+#[test]
+fn test_out_param_to_option() {
+    let cxx = indoc! {"
+        bool try_get_value(int32_t* out) {
+            *out = 42;
+            return true;
+        }
+        bool try_get_nothing(int32_t* out) {
+            return false;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        bool try_get_value(int32_t* out);
+        bool try_get_nothing(int32_t* out);
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::try_get_value(), Some(42));
+        assert_eq!(ffi::try_get_nothing(), None);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        quote! {
+            generate!("try_get_value")
+            generate!("try_get_nothing")
+            out_param!("try_get_value", "out")
+            out_param!("try_get_nothing", "out")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_context_param() {
+    let cxx = indoc! {"
+        static void* registered_context = nullptr;
+        void register_callback(void* user_data) {
+            registered_context = user_data;
+        }
+        void* fire_callback() {
+            void* ctx = registered_context;
+            registered_context = nullptr;
+            return ctx;
+        }
+    "};
+    let hdr = indoc! {"
+        void register_callback(void* user_data);
+        void* fire_callback();
+    "};
+    let rs = quote! {
+        ffi::register_callback(autocxx::Context::new(String::from("hello")));
+        let ctx = unsafe { autocxx::Context::from_raw(ffi::fire_callback()) };
+        assert_eq!(ctx.downcast_ref::<String>().map(String::as_str), Some("hello"));
+        assert_eq!(*ctx.downcast::<String>().unwrap(), "hello");
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        quote! {
+            generate!("register_callback")
+            generate!("fire_callback")
+            context_param!("register_callback", "user_data")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_string_table() {
+    let hdr = indoc! {"
+        static const char* const COLOR_NAMES[] = { \"red\", \"green\", \"blue\" };
+    "};
+    let rs = quote! {
+        let names: Vec<&str> = ffi::COLOR_NAMES()
+            .into_iter()
+            .map(|name| name.to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["red", "green", "blue"]);
+    };
+    run_test("", hdr, rs, &["COLOR_NAMES"], &[]);
+}
+
+#[test]
+fn test_string_table_null_terminated() {
+    let hdr = indoc! {"
+        #include <cstddef>
+        static const char* const COLOR_NAMES[] = { \"red\", \"green\", NULL };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::COLOR_NAMES().len(), 2);
+    };
+    run_test("", hdr, rs, &["COLOR_NAMES"], &[]);
+}
+
+#[test]
+fn test_bridge_item() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t get_generated() { return 3; }
+        inline uint32_t get_hand_written() { return 4; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_generated(), 3);
+        assert_eq!(ffi::get_hand_written(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("get_generated")
+            bridge_item!(
+                unsafe extern "C++" {
+                    fn get_hand_written() -> u32;
+                }
+            )
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_flags_enum() {
+    let hdr = indoc! {"
+        enum Permissions {
+            READ = 1,
+            WRITE = 2,
+            EXECUTE = 4,
+        };
+    "};
+    let rs = quote! {
+        let rw = ffi::Permissions::READ | ffi::Permissions::WRITE;
+        assert_eq!(rw.bits(), 3);
+        assert!(rw.contains(ffi::Permissions::WRITE));
+        assert!(!rw.contains(ffi::Permissions::EXECUTE));
+        assert_eq!((rw & ffi::Permissions::READ).bits(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Permissions")
+            flags!("Permissions")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_bindgen_vtable_dropped() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Shape {
+        public:
+            virtual ~Shape() {}
+            virtual uint32_t sides() const { return 4; }
+        };
+    "};
+    let rs = quote! {
+        let shape = ffi::Shape::new().within_unique_ptr();
+        assert_eq!(shape.sides(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Shape"], &[], None),
+        None,
+        Some(make_string_absence_checker(vec![
+            "Shape__bindgen_vtable".into()
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_instantiate_template_fn_on_user_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        class Widget {
+        public:
+            uint32_t get_value() const { return 7; }
+        };
+        template<class T> std::unique_ptr<T> make() {
+            return std::make_unique<T>();
+        }
+    "};
+    let rs = quote! {
+        let widget: cxx::UniquePtr<ffi::Widget> = ffi::make_widget();
+        assert_eq!(widget.get_value(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            instantiate!("make<Widget>", make_widget)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_instantiate_concept_constrained_template_fn() {
+    let hdr = indoc! {"
+        #include <concepts>
+        #include <cstdint>
+        #include <memory>
+        template<typename T>
+        concept Valued = requires(const T& t) {
+            { t.get_value() } -> std::convertible_to<uint32_t>;
+        };
+        class Widget {
+        public:
+            uint32_t get_value() const { return 7; }
+        };
+        template<Valued T> std::unique_ptr<T> make() {
+            return std::make_unique<T>();
+        }
+    "};
+    let rs = quote! {
+        let widget: cxx::UniquePtr<ffi::Widget> = ffi::make_widget();
+        assert_eq!(widget.get_value(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            instantiate!("make<Widget>", make_widget)
+        },
+        make_cpp20_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_exclude_bridge_wrapper() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t give_int() { return 5; }
+    "};
+    let rs = quote! {};
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("give_int")
+            exclude_bridge_wrapper!()
+        },
+        None,
+        Some(Box::new(NoCxxBridgeModChecker)),
+        None,
+    );
+}
+
+#[test]
+fn test_string_view_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string_view>
+        inline uint32_t count_chars(std::string_view s) {
+            return s.size();
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::count_chars("hello"), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["count_chars"], &[], None),
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_string_view_return() {
+    let hdr = indoc! {"
+        #include <string_view>
+        inline std::string_view first_word(std::string_view s) {
+            return s.substr(0, s.find(' '));
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::first_word("hello world"), b"hello");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["first_word"], &[], None),
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_string_view_return_not_utf8() {
+    let hdr = indoc! {"
+        #include <string_view>
+        inline std::string_view latin1_cafe() {
+            return std::string_view(\"caf\\xe9\", 4);
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::latin1_cafe(), b"caf\xe9");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["latin1_cafe"], &[], None),
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_const_span_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <span>
+        inline uint32_t sum(std::span<const uint32_t> s) {
+            uint32_t total = 0;
+            for (auto x : s) {
+                total += x;
+            }
+            return total;
+        }
+    "};
+    let rs = quote! {
+        let v = vec![1u32, 2, 3];
+        assert_eq!(ffi::sum(&v), 6);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("sum")
+            const_slice_param!("sum", "s")
+        },
+        make_cpp20_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_mut_span_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <span>
+        inline void double_all(std::span<uint32_t> s) {
+            for (auto& x : s) {
+                x *= 2;
+            }
+        }
+    "};
+    let rs = quote! {
+        let mut v = vec![1u32, 2, 3];
+        ffi::double_all(&mut v);
+        assert_eq!(v, [2, 4, 6]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["double_all"], &[], None),
+        make_cpp20_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_string_view_method_return() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <string_view>
+        struct Person {
+            Person() : name(\"Ada\") {}
+            std::string_view get_name() const { return name; }
+        private:
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        let p = ffi::Person::new().within_unique_ptr();
+        assert_eq!(p.get_name(), b"Ada");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Person"], &[], None),
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_type_mapping() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        template<typename T>
+        class MyPtr : public std::unique_ptr<T> {
+        public:
+            MyPtr(T* t) : std::unique_ptr<T>(t) {}
+        };
+        struct Widget {
+            uint32_t a;
+            static MyPtr<Widget> make() {
+                return MyPtr<Widget>(new Widget { 3 });
+            }
+        };
+    "};
+    let rs = quote! {
+        let w: cxx::UniquePtr<ffi::Widget> = ffi::Widget::make();
+        assert_eq!(w.a, 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Widget")
+            type_mapping!("MyPtr", cxx::UniquePtr)
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pod_layout_mismatch() {
+    // Mapping this field to a smaller Rust type makes the Rust struct
+    // smaller than the C++ one, which must be caught at compile time.
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Handle {
+            uint32_t id;
+        };
+        struct Holder {
+            Handle handle;
+        };
+    "};
+    let rs = quote! {};
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Holder")
+            type_mapping!("Handle", u8)
+        },
+        None,
+        Some(make_string_finder(vec![
+            "Rust and C++ disagree about the size of Holder: C++ says 4 bytes".into(),
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_self_referential_struct_is_opaque() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct ListNode {
+            ListNode() : next(this), val(0) {}
+            ListNode* next;
+            uint32_t val;
+            bool is_alone() const { return next == this; }
+        };
+    "};
+    let rs = quote! {
+        let node = ffi::ListNode::new().within_unique_ptr();
+        assert!(node.is_alone());
+    };
+    run_test("", hdr, rs, &["ListNode"], &[]);
+}
+
+#[test]
+fn test_self_referential_struct_not_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct ListNode {
+            ListNode* next;
+            uint32_t val;
+        };
+    "};
+    let rs = quote! {};
+    run_test_expect_fail("", hdr, rs, &[], &["ListNode"]);
+}
+
+#[test]
+fn test_std_function_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <functional>
+        inline void call_three_times(std::function<void(uint32_t)> f) {
+            f(1);
+            f(2);
+            f(3);
+        }
+        inline uint32_t apply(const std::function<uint32_t(uint32_t)>& f, uint32_t a) {
+            return f(a);
+        }
+    "};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        let total = std::sync::Arc::new(AtomicU32::new(0));
+        let total2 = total.clone();
+        ffi::call_three_times(move |a: u32| {
+            total2.fetch_add(a, Ordering::SeqCst);
+        });
+        assert_eq!(total.load(Ordering::SeqCst), 6);
+        assert_eq!(ffi::apply(|a: u32| a * 2, 21), 42);
+    };
+    run_test("", hdr, rs, &["call_three_times", "apply"], &[]);
+}
+
+#[test]
+fn test_transparent_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Meters {
+            double value;
+        };
+        inline double double_distance(Meters m) {
+            return m.value * 2.0;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(
+            std::mem::size_of::<ffi::Meters>(),
+            std::mem::size_of::<f64>()
+        );
+        assert_eq!(
+            std::mem::align_of::<ffi::Meters>(),
+            std::mem::align_of::<f64>()
+        );
+        let m: ffi::Meters = unsafe { std::mem::transmute(1.5f64) };
+        assert_eq!(ffi::double_distance(m), 3.0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("double_distance")
+            generate_pod!("Meters")
+            transparent!("Meters")
+        },
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            #[repr(transparent)]
+        }])),
+        None,
+    );
+}
+
+#[test]
+fn test_transparent_type_multiple_fields() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+    "};
+    let rs = quote! {};
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Point")
+            transparent!("Point")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_subclass_calls_protected_method() {
+    let hdr = indoc! {"
+    #include <cstdint>
+
+    class Counter {
+    public:
+        Counter() : count(0) {}
+        virtual void tick() { bump(1); }
+        uint32_t get() const { return count; }
+        virtual ~Counter() {}
+    protected:
+        void bump(uint32_t by) { count += by; }
+    private:
+        uint32_t count;
+    };
+    inline Counter*& registered_counter() { static Counter* c = nullptr; return c; }
+    inline void register_counter(Counter& c) { registered_counter() = &c; }
+    inline void tick_registered_counter() { registered_counter()->tick(); }
+    inline uint32_t get_registered_count() { return registered_counter()->get(); }
+    "};
+    run_test_ex(
+        "",
+        hdr,
+        quote! {
+            let obs = MyCounter::new_rust_owned(MyCounter { cpp_peer: Default::default() });
+            ffi::register_counter(obs.as_ref().borrow_mut().pin_mut());
+            ffi::tick_registered_counter();
+            assert_eq!(ffi::get_registered_count(), 10);
+        },
+        quote! {
+            generate!("register_counter")
+            generate!("tick_registered_counter")
+            generate!("get_registered_count")
+            subclass!("Counter",MyCounter)
+        },
+        None,
+        None,
+        Some(quote! {
+            use autocxx::subclass::CppSubclass;
+            use ffi::Counter_methods;
+            #[autocxx::subclass::subclass]
+            pub struct MyCounter;
+            impl Counter_methods for MyCounter {
+                fn tick(&mut self) {
+                    self.peer_mut().bump(10);
+                }
+            }
+        }),
+    );
+}
+
+#[test]
+fn test_split_by_namespace() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            inline uint32_t give_int() { return 5; }
+        }
+        namespace b {
+            inline uint32_t give_int() { return 6; }
+        }
+    "};
+    let directives = quote! {
+        generate!("a::give_int")
+        generate!("b::give_int")
+        exclude_utilities!()
+        split_by_namespace!()
+        exclude_bridge_wrapper!()
+    };
+    run_test_ex(
+        "",
+        hdr,
+        quote! {},
+        directives.clone(),
+        None,
+        Some(Box::new(NoCxxBridgeModChecker)),
+        None,
+    );
+    let namespaces = generate_bridge_namespaces(hdr, directives);
+    assert_eq!(namespaces, vec!["a".to_string(), "b".to_string()]);
+}
+
+#[test]
+fn test_split_by_namespace_calls() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            inline uint32_t give_int() { return 5; }
+        }
+        namespace b {
+            inline uint32_t give_int() { return 6; }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::a::give_int(), 5);
+        assert_eq!(ffi::b::give_int(), 6);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("a::give_int")
+            generate!("b::give_int")
+            split_by_namespace!()
+        },
+        None,
+        Some(make_rust_code_finder(vec![
+            quote! { mod cxxbridge_ns_a },
+            quote! { mod cxxbridge_ns_b },
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_shared_type_declarations() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            struct Widget {
+                uint32_t size;
+            };
+            inline uint32_t get_size(const Widget& w) { return w.size; }
+        }
+        namespace b {
+            inline uint32_t get_double_size(const a::Widget& w) { return w.size * 2; }
+        }
+    "};
+    let directives = quote! {
+        generate_pod!("a::Widget")
+        generate!("a::get_size")
+        generate!("b::get_double_size")
+        exclude_utilities!()
+        split_by_namespace!()
+        shared_type_declarations!()
+        exclude_bridge_wrapper!()
+    };
+    let bridges: std::collections::HashMap<_, _> = generate_bridges_by_namespace(hdr, directives)
+        .into_iter()
+        .map(|(ns, items)| (ns, items.to_string()))
+        .collect();
+    let mut namespaces: Vec<_> = bridges.keys().cloned().collect();
+    namespaces.sort();
+    assert_eq!(namespaces, vec!["a", "b", "cxxbridge_types"]);
+    // The type is declared just once...
+    let declaration = quote! { type Widget = super::bindgen::root::a::Widget; }.to_string();
+    assert!(bridges["cxxbridge_types"].contains(&declaration));
+    // ... and both other bridges refer to that declaration.
+    let reference = quote! { type Widget = super::cxxbridge_types::Widget; }.to_string();
+    for ns in ["a", "b"] {
+        assert!(bridges[ns].contains(&reference));
+        assert!(!bridges[ns].contains(&declaration));
+    }
+}
+
+#[test]
+fn test_shared_type_declarations_calls() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace a {
+            struct Widget {
+                uint32_t size;
+            };
+            inline uint32_t get_size(const Widget& w) { return w.size; }
+        }
+        namespace b {
+            inline uint32_t get_double_size(const a::Widget& w) { return w.size * 2; }
+        }
+    "};
+    let rs = quote! {
+        let w = ffi::a::Widget { size: 3 };
+        assert_eq!(ffi::a::get_size(&w), 3);
+        assert_eq!(ffi::b::get_double_size(&w), 6);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("a::Widget")
+            generate!("a::get_size")
+            generate!("b::get_double_size")
+            split_by_namespace!()
+            shared_type_declarations!()
+        },
+        None,
+        Some(make_rust_code_finder(vec![quote! { mod cxxbridge_types }])),
+        None,
+    );
+}
+
+#[test]
+fn test_sized_array_param() {
+    let hdr = indoc! {"
+        inline void fill(int buf[4]) {
+            for (int i = 0; i < 4; i++) {
+                buf[i] = i * 2;
+            }
+        }
+        inline int sum(const int buf[3]) {
+            return buf[0] + buf[1] + buf[2];
+        }
+    "};
+    let rs = quote! {
+        let mut buf = [autocxx::c_int(0); 4];
+        ffi::fill(&mut buf);
+        assert_eq!(buf[3], autocxx::c_int(6));
+        let buf = [autocxx::c_int(1), autocxx::c_int(2), autocxx::c_int(3)];
+        assert_eq!(ffi::sum(&buf), autocxx::c_int(6));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("fill")
+            generate!("sum")
+            array_params!("fill")
+            array_params!("sum")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_sized_array_param_unchanged_without_directive() {
+    let hdr = indoc! {"
+        inline void fill(int buf[4]) {
+            for (int i = 0; i < 4; i++) {
+                buf[i] = i * 2;
+            }
+        }
+        inline void fill_again(int buf[4]) { fill(buf); }
+    "};
+    let rs = quote! {
+        let mut buf = [autocxx::c_int(0); 4];
+        unsafe { ffi::fill_again(buf.as_mut_ptr()) };
+        assert_eq!(buf[3], autocxx::c_int(6));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("fill")
+            generate!("fill_again")
+            array_params!("fill")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_enum_with_wide_underlying_type() {
+    // The enum's repr is retained from bindgen, so values which need
+    // more than 32 bits aren't narrowed.
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum class Big : int64_t {
+            Small = 1,
+            Huge = 0x100000000,
+        };
+        inline int64_t big_value(Big b) { return static_cast<int64_t>(b); }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::big_value(ffi::Big::Small), 1i64);
+        assert_eq!(ffi::big_value(ffi::Big::Huge), 0x100000000i64);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["big_value", "Big"], &[], None),
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            #[repr(i64)]
+        }])),
+        None,
+    );
+}
+
+#[test]
+fn test_take_move_only_by_value() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            Bob(uint32_t a0) : a(std::make_unique<uint32_t>(a0)) {}
+            Bob(const Bob&) = delete;
+            Bob(Bob&&) = default;
+            std::unique_ptr<uint32_t> a;
+        };
+        inline uint32_t take_bob(Bob b) {
+            return *b.a;
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::Bob::new(12).within_unique_ptr();
+        assert_eq!(ffi::take_bob(a), 12);
+        let b = ffi::Bob::new(13).within_box();
+        assert_eq!(ffi::take_bob(b), 13);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["take_bob", "Bob"], &[], None),
+        None,
+        Some(make_string_finder(vec!["RValueParam".into()])),
+        None,
+    );
+}
+
+#[test]
+fn test_take_implicitly_move_only_by_value() {
+    // The copy constructor isn't declared, but is implicitly deleted
+    // because of the std::unique_ptr field.
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        struct Bob {
+            Bob(uint32_t a0) : a(std::make_unique<uint32_t>(a0)) {}
+            std::unique_ptr<uint32_t> a;
+        };
+        inline uint32_t take_bob(Bob b) {
+            return *b.a;
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::Bob::new(12).within_unique_ptr();
+        assert_eq!(ffi::take_bob(a), 12);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["take_bob", "Bob"], &[], None),
+        None,
+        Some(make_string_finder(vec!["RValueParam".into()])),
+        None,
+    );
+}
+
+#[test]
+fn test_inline_wrappers() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            Bob(uint32_t a0) : a(a0) {}
+            uint32_t a;
+        };
+    "};
+    let rs = quote! {
+        ffi::Bob::new(3).within_box();
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Bob"], &[], None),
+        None,
+        Some(make_rust_code_finder(vec![quote! {
+            #[inline]
+            pub fn new
+        }])),
+        None,
+    );
+}
+
+#[test]
+fn test_exclude_inline_wrappers() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Bob {
+            Bob(uint32_t a0) : a(a0) {}
+            uint32_t a;
+        };
+    "};
+    let rs = quote! {
+        ffi::Bob::new(3).within_box();
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["Bob"], &[], Some(quote! { exclude_inline_wrappers!() })),
+        None,
+        Some(make_string_absence_checker(vec!["# [inline]".into()])),
+        None,
+    );
+}
+
+#[test]
+fn test_intrusive_ptr() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Counted {
+            uint32_t refs;
+            uint32_t val;
+        };
+        void add_ref(Counted* c);
+        void release(Counted* c);
+        uint32_t live_objects();
+        template<typename T>
+        class RefPtr {
+        public:
+            RefPtr(T* t) : p(t) { if (p) { add_ref(p); } }
+            RefPtr(const RefPtr& other) : p(other.p) { if (p) { add_ref(p); } }
+            ~RefPtr() { if (p) { release(p); } }
+            RefPtr& operator=(const RefPtr&) = delete;
+            T* get() const { return p; }
+        private:
+            T* p;
+        };
+        RefPtr<Counted> make_counted(uint32_t val);
+        uint32_t get_val(RefPtr<Counted> c);
+    "};
+    let cxx = indoc! {"
+        static uint32_t live = 0;
+        void add_ref(Counted* c) { c->refs++; }
+        void release(Counted* c) {
+            if (--c->refs == 0) {
+                delete c;
+                live--;
+            }
+        }
+        uint32_t live_objects() { return live; }
+        RefPtr<Counted> make_counted(uint32_t val) {
+            live++;
+            return RefPtr<Counted>(new Counted { 0, val });
+        }
+        uint32_t get_val(RefPtr<Counted> c) { return c.get()->val; }
+    "};
+    let rs = quote! {
+        let c = ffi::make_counted(42);
+        assert_eq!(ffi::live_objects(), 1);
+        assert_eq!(c.refs, 1);
+        assert_eq!(c.val, 42);
+        let c2 = c.clone();
+        assert_eq!(c.refs, 2);
+        assert_eq!(ffi::get_val(c2), 42);
+        assert_eq!(c.refs, 1);
+        assert_eq!(ffi::live_objects(), 1);
+        drop(c);
+        assert_eq!(ffi::live_objects(), 0);
+        assert!(autocxx::IntrusivePtr::<ffi::Counted>::null().is_null());
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_counted", "get_val", "live_objects"],
+            &["Counted"],
+            Some(quote! {
+                intrusive_ptr!("RefPtr", "add_ref", "release")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_array_reference_param() {
+    let hdr = indoc! {"
+        inline void f(int (&arr)[8]) {
+            for (int i = 0; i < 8; i++) {
+                arr[i] = i + 1;
+            }
+        }
+        inline int last(const int (&arr)[8]) {
+            return arr[7];
+        }
+    "};
+    let rs = quote! {
+        let mut arr = [autocxx::c_int(0); 8];
+        ffi::f(&mut arr);
+        assert_eq!(arr[0], autocxx::c_int(1));
+        assert_eq!(ffi::last(&arr), autocxx::c_int(8));
+    };
+    run_test("", hdr, rs, &["f", "last"], &[]);
+}
+
+#[test]
+fn test_rust_shared_struct() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point;
+        int32_t manhattan(Point p);
+        Point make_point(int32_t x, int32_t y);
+    "};
+    let cxx = indoc! {"
+        int32_t manhattan(Point p) {
+            return p.x + p.y;
+        }
+        Point make_point(int32_t x, int32_t y) {
+            return Point { x, y };
+        }
+    "};
+    let rs = quote! {
+        let p = ffi::make_point(3, 4);
+        assert_eq!(p.x, 3);
+        assert_eq!(p.y, 4);
+        assert_eq!(ffi::manhattan(ffi::Point { x: 1, y: 2 }), 3);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["manhattan", "make_point"],
+            &[],
+            Some(quote! {
+                shared_struct!(pub struct Point {
+                    pub x: i32,
+                    pub y: i32,
+                })
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+// With `safe_getters!` but without a `safety!`, parameterless const
+// getters returning primitives should still be callable without `unsafe`.
+#[test]
+fn test_safe_getters() {
+    let hdr = indoc! {"
+        class Counter {
+        public:
+            Counter() : count(3) {}
+            int getCount() const { return count; }
+        private:
+            int count;
+        };
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let unexpanded_rust = quote! {
+        use autocxx::prelude::*;
+
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate!("Counter")
+            safe_getters!()
+        );
+
+        fn main() {
+            let c = ffi::Counter::new().within_unique_ptr();
+            assert_eq!(c.getCount(), autocxx::c_int(3));
+        }
+    };
+
+    do_run_test_manual("", hdr, unexpanded_rust, None, None).unwrap();
+}
+
+#[test]
+fn test_no_safe_getters_by_default() {
+    let hdr = indoc! {"
+        class Counter {
+        public:
+            Counter() : count(3) {}
+            int getCount() const { return count; }
+        private:
+            int count;
+        };
+    "};
+    let hexathorpe = Token![#](Span::call_site());
+    let unexpanded_rust = quote! {
+        use autocxx::prelude::*;
+
+        include_cpp!(
+            #hexathorpe include "input.h"
+            generate!("Counter")
+        );
+
+        fn main() {
+            let c = ffi::Counter::new().within_unique_ptr();
+            assert_eq!(c.getCount(), autocxx::c_int(3));
+        }
+    };
+
+    assert!(do_run_test_manual("", hdr, unexpanded_rust, None, None).is_err());
+}
+
+#[test]
+fn test_copy_return() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            int32_t x;
+            int32_t y;
+        };
+        class Space {
+        public:
+            Space() : o { 1, 2 } {}
+            const Point& origin() const { return o; }
+        private:
+            Point o;
+        };
+    "};
+    let rs = quote! {
+        let space = ffi::Space::new().within_unique_ptr();
+        let origin: ffi::Point = space.origin();
+        drop(space);
+        assert_eq!(origin.x, 1);
+        assert_eq!(origin.y, 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Space")
+            generate_pod!("Point")
+            copy_return!("Space::origin")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_error_code_result() {
+    let hdr = indoc! {"
+        #include <cerrno>
+        #include <cstdint>
+        #include <future>
+        #include <system_error>
+        inline std::error_code open_thing(uint32_t id) {
+            if (id == 0) {
+                return std::error_code(ENOENT, std::system_category());
+            }
+            if (id == 2) {
+                return std::make_error_code(std::errc::no_such_file_or_directory);
+            }
+            if (id == 3) {
+                return std::make_error_code(std::future_errc::broken_promise);
+            }
+            return std::error_code();
+        }
+        inline int broken_promise_value() {
+            return static_cast<int>(std::future_errc::broken_promise);
+        }
+    "};
+    let rs = quote! {
+        assert!(ffi::open_thing(1).is_ok());
+        let err = ffi::open_thing(0).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.raw_os_error().is_some());
+        let err = ffi::open_thing(2).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.raw_os_error().is_some());
+        let err = ffi::open_thing(3).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Other);
+        assert_eq!(err.raw_os_error(), None);
+        let value = ffi::broken_promise_value().0;
+        assert!(err.to_string().contains(&value.to_string()));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("open_thing")
+            generate!("broken_promise_value")
+            error_code_result!("open_thing")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_nullable_return() {
+    // The reference comes from a pointer-returning helper, defined out of
+    // line, as is typical of APIs which can return a null reference.
+    let cxx = indoc! {"
+        const Child* Tree::selected_child() const {
+            return selected;
+        }
+        const Child& Tree::current() const {
+            return *selected_child();
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Child {
+            uint32_t id;
+        };
+        class Tree {
+        public:
+            Tree() : child { 5 }, selected(nullptr) {}
+            void select() { selected = &child; }
+            const Child& current() const;
+        private:
+            const Child* selected_child() const;
+            Child child;
+            Child* selected;
+        };
+    "};
+    let rs = quote! {
+        let mut tree = ffi::Tree::new().within_unique_ptr();
+        assert!(tree.current().is_none());
+        tree.pin_mut().select();
+        assert_eq!(tree.current().unwrap().id, 5);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        quote! {
+            generate!("Tree")
+            generate_pod!("Child")
+            nullable_return!("Tree::current")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_owning_return() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t& live_widgets() {
+            static uint32_t count = 0;
+            return count;
+        }
+        class Widget {
+        public:
+            Widget(uint32_t id) : id(id) { live_widgets()++; }
+            ~Widget() { live_widgets()--; }
+            uint32_t get_id() const { return id; }
+        private:
+            uint32_t id;
+        };
+        inline Widget* create_widget(uint32_t id) {
+            return id ? new Widget(id) : nullptr;
+        }
+        inline uint32_t count_widgets() { return live_widgets(); }
+    "};
+    let rs = quote! {
+        let widget = ffi::create_widget(3);
+        assert_eq!(widget.get_id(), 3);
+        assert_eq!(ffi::count_widgets(), 1);
+        drop(widget);
+        assert_eq!(ffi::count_widgets(), 0);
+        assert!(ffi::create_widget(0).is_null());
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            generate!("create_widget")
+            generate!("count_widgets")
+            owning_return!("create_widget")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_negative_owning_return_primitive() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t* create_number() { return new uint32_t(4); }
+    "};
+    let rs = quote! {
+        ffi::create_number();
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("create_number")
+            owning_return!("create_number")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_force_mut_self() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Memo {
+        public:
+            Memo() : c(0) {}
+            uint32_t& cache() const { return c; }
+        private:
+            mutable uint32_t c;
+        };
+    "};
+    let rs = quote! {
+        let mut memo = ffi::Memo::new().within_unique_ptr();
+        *memo.pin_mut().cache() = 3;
+        assert_eq!(*memo.pin_mut().cache(), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Memo")
+            force_mut_self!("Memo::cache")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_handle() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        class Counter {
+        public:
+            Counter() : c(0) {}
+            uint32_t get() const { return c; }
+            void add(uint32_t n) { c += n; }
+        private:
+            uint32_t c;
+        };
+        inline std::unique_ptr<Counter> make_counter() {
+            return std::make_unique<Counter>();
+        }
+    "};
+    let rs = quote! {
+        let mut counter = ffi::CounterHandle::from(ffi::make_counter());
+        counter.add(3);
+        counter.add(4);
+        assert_eq!(counter.get(), 7);
+        assert_eq!(counter.into_inner().get(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            generate!("make_counter")
+            handle!("Counter")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pre_drop() {
+    let hdr = indoc! {"
+        #include <memory>
+        #include <string>
+        inline std::string& event_log() {
+            static std::string log;
+            return log;
+        }
+        class Connection {
+        public:
+            ~Connection() { event_log() += \"destroyed;\"; }
+            void flush() { event_log() += \"flushed;\"; }
+        };
+        inline std::unique_ptr<Connection> make_connection() {
+            return std::make_unique<Connection>();
+        }
+        inline std::string get_events() { return event_log(); }
+    "};
+    let rs = quote! {
+        let connection = ffi::ConnectionHandle::from(ffi::make_connection());
+        drop(connection);
+        assert_eq!(ffi::get_events().to_str().unwrap(), "flushed;destroyed;");
+        let connection = ffi::ConnectionHandle::from(ffi::make_connection());
+        drop(connection.into_inner());
+        assert_eq!(
+            ffi::get_events().to_str().unwrap(),
+            "flushed;destroyed;destroyed;"
+        );
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Connection")
+            generate!("make_connection")
+            generate!("get_events")
+            pre_drop!("Connection", "flush")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_explicit_cxx_names() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace geometry {
+            struct Square {
+                Square(uint32_t side) : side(side) {}
+                uint32_t area() const { return side * side; }
+                uint32_t side;
+            };
+            inline uint32_t scale(uint32_t n) { return n * 2; }
+            inline uint32_t scale(uint32_t n, uint32_t by) { return n * by; }
+        }
+    "};
+    let rs = quote! {
+        let square = ffi::geometry::Square::new(3).within_unique_ptr();
+        assert_eq!(square.area(), 9);
+        assert_eq!(ffi::geometry::scale(4), 8);
+        assert_eq!(ffi::geometry::scale1(4, 3), 12);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("geometry::Square")
+            generate!("geometry::scale")
+            explicit_cxx_names!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_check_signatures() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        namespace text {
+            inline std::string describe(uint32_t n) { return std::to_string(n); }
+            inline std::string describe(uint32_t n, uint32_t base) {
+                return std::to_string(n * base);
+            }
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::text::describe(4).to_str().unwrap(), "4");
+        assert_eq!(ffi::text::describe1(4, 2).to_str().unwrap(), "8");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("text::describe")
+            check_signatures!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_check_signatures_mismatch() {
+    // bindgen is run with BINDGEN defined, so it sees a different
+    // signature from the C++ compiler. The wrapper's call would still
+    // compile thanks to an implicit conversion.
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        #ifdef BINDGEN
+        std::string describe(uint32_t n);
+        #else
+        inline std::string describe(uint64_t n) { return std::to_string(n); }
+        #endif
+    "};
+    let rs = quote! {
+        ffi::describe(4);
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("describe")
+            check_signatures!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_pod_by_default() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        struct Point {
+            int32_t x;
+            int32_t y;
+        };
+        struct Named {
+            Named() {}
+            std::string name;
+        };
+        inline int32_t sum(Point p) { return p.x + p.y; }
+    "};
+    let rs = quote! {
+        let p = ffi::Point { x: 1, y: 2 };
+        assert_eq!(ffi::sum(p), 3);
+        let _ = ffi::Named::new().within_unique_ptr();
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Point")
+            generate!("Named")
+            generate!("sum")
+            pod_by_default!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_reference_member_is_opaque() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        struct Foo {
+            int32_t a;
+        };
+        struct Holder {
+            Holder(Foo& foo) : foo(foo) {}
+            Foo& foo;
+            int32_t get() const { return foo.a; }
+        };
+        inline std::unique_ptr<Holder> make_holder() {
+            static Foo foo = { 42 };
+            return std::make_unique<Holder>(foo);
+        }
+    "};
+    let rs = quote! {
+        let holder = ffi::make_holder();
+        assert_eq!(holder.get(), 42);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Holder")
+            generate!("make_holder")
+            pod_by_default!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_negative_reference_member_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Foo {
+            int32_t a;
+        };
+        struct Holder {
+            Foo& foo;
+        };
+    "};
+    let rs = quote! {};
+    run_test_expect_fail("", hdr, rs, &[], &["Holder"]);
+}
+
+#[test]
+fn test_clonable() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        class Counter {
+        public:
+            Counter() : count(0) {}
+            Counter(const Counter& other) : count(other.count), name(other.name) {}
+            void inc() { count++; }
+            uint32_t get() const { return count; }
+        private:
+            uint32_t count;
+            std::string name;
+        };
+    "};
+    let rs = quote! {
+        let mut a = ffi::Counter::new().within_unique_ptr();
+        a.pin_mut().inc();
+        let mut b = a.clone_unique();
+        b.pin_mut().inc();
+        assert_eq!(a.get(), 1);
+        assert_eq!(b.get(), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            clonable!("Counter")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_thread_safe() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Counter {
+        public:
+            Counter() : c(3) {}
+            uint32_t get() const { return c; }
+        private:
+            uint32_t c;
+        };
+    "};
+    let rs = quote! {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<ffi::Counter>();
+        let counter = ffi::Counter::new().within_unique_ptr();
+        let val = std::thread::spawn(move || counter.get()).join().unwrap();
+        assert_eq!(val, 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            thread_safe!(unsafe "Counter")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_thread_unsafe() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Counter {
+        public:
+            Counter() : c(3) {}
+            uint32_t get() const { return c; }
+            void reset() { c = 0; }
+        private:
+            uint32_t c;
+        };
+        class Gauge {
+        public:
+            Gauge() : g(4) {}
+            uint32_t get() const { return g; }
+            void reset() { g = 0; }
+        private:
+            uint32_t g;
+        };
+    "};
+    let rs = quote! {
+        trait NotSend {
+            const IS_SEND: bool = false;
+        }
+        impl<T> NotSend for T {}
+        struct Check<T>(std::marker::PhantomData<T>);
+        impl<T: Send> Check<T> {
+            const IS_SEND: bool = true;
+        }
+        assert!(!Check::<ffi::Counter>::IS_SEND);
+        assert!(Check::<ffi::Gauge>::IS_SEND);
+        let mut counter = ffi::Counter::new().within_unique_ptr();
+        counter.pin_mut().reset();
+        assert_eq!(counter.get(), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            generate!("Gauge")
+            thread_safe!(unsafe "Counter")
+            thread_safe!(unsafe "Gauge")
+            thread_unsafe!("Counter::reset")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_field_accessors() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            uint32_t y;
+        };
+    "};
+    let rs = quote! {
+        let mut p = ffi::Point::new().within_unique_ptr();
+        p.pin_mut().set_x(3);
+        p.pin_mut().set_y(4);
+        assert_eq!(p.get_x(), 3);
+        assert_eq!(p.get_y(), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Point")
+            field_accessors!("Point")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_serde_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Point {
+            uint32_t x;
+            int32_t y;
+            bool visible;
+        };
+    "};
+    let rs = quote! {
+        let p = ffi::Point { x: 1, y: -2, visible: true };
+        let json = serde_json::to_string(&p).unwrap();
+        assert_eq!(json, r#"{"x":1,"y":-2,"visible":true}"#);
+        let q: ffi::Point = serde_json::from_str(&json).unwrap();
+        assert_eq!(q.x, 1);
+        assert_eq!(q.y, -2);
+        assert!(q.visible);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Point")
+            serde_pod!("Point")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_ordered() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Version {
+            uint32_t major;
+            uint32_t minor;
+            bool operator==(const Version& other) const {
+                return major == other.major && minor == other.minor;
+            }
+            bool operator<(const Version& other) const {
+                return major < other.major || (major == other.major && minor < other.minor);
+            }
+        };
+    "};
+    let rs = quote! {
+        let mut versions = vec![
+            ffi::Version { major: 2, minor: 0 },
+            ffi::Version { major: 1, minor: 3 },
+            ffi::Version { major: 1, minor: 1 },
+        ];
+        versions.sort();
+        let sorted: Vec<_> = versions.iter().map(|v| (v.major, v.minor)).collect();
+        assert_eq!(sorted, vec![(1, 1), (1, 3), (2, 0)]);
+        assert!(ffi::Version { major: 1, minor: 1 } == ffi::Version { major: 1, minor: 1 });
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Version")
+            ordered!("Version")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_identity_eq() {
+    let hdr = indoc! {"
+        #include <string>
+        #include <memory>
+        class Widget {
+        public:
+            Widget(std::string name) : name(name) {}
+        private:
+            std::string name;
+        };
+        inline std::unique_ptr<Widget> make_widget() {
+            return std::make_unique<Widget>(\"same\");
+        }
+    "};
+    let rs = quote! {
+        let a = ffi::make_widget();
+        let b = ffi::make_widget();
+        let a1: &ffi::Widget = a.as_ref().unwrap();
+        let a2: &ffi::Widget = a.as_ref().unwrap();
+        let b1: &ffi::Widget = b.as_ref().unwrap();
+        assert!(a1 == a2);
+        assert!(a1 != b1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            generate!("make_widget")
+            identity_eq!("Widget")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_alias_template() {
+    // Uses int32_t rather than int because of
+    // https://github.com/google/autocxx/issues/422
+    let hdr = indoc! {"
+        #include <vector>
+        #include <cstdint>
+        template<class T> using Vec = std::vector<T>;
+        inline Vec<int32_t> give_vec() {
+            return Vec<int32_t> {1,2,3};
+        }
+        inline int32_t sum(const Vec<int32_t>& v) {
+            int32_t total = 0;
+            for (auto i : v) {
+                total += i;
+            }
+            return total;
+        }
+    "};
+    let rs = quote! {
+        let v = ffi::give_vec();
+        assert_eq!(v.as_ref().unwrap().as_slice(), &[1,2,3]);
+        assert_eq!(ffi::sum(v.as_ref().unwrap()), 6);
+    };
+    run_test("", hdr, rs, &["give_vec", "sum"], &[]);
+}
+
+#[test]
+fn test_custom_deleter() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstdlib>
+        #include <memory>
+        #include <new>
+        struct Pooled {
+            int32_t value;
+        };
+        Pooled* pool_alloc(int32_t value);
+        void pool_free(Pooled* p);
+        uint32_t pool_frees();
+        std::unique_ptr<Pooled> make_pooled(int32_t value);
+    "};
+    let cxx = indoc! {"
+        static uint32_t frees = 0;
+        Pooled* pool_alloc(int32_t value) {
+            void* mem = std::malloc(sizeof(Pooled));
+            return new (mem) Pooled { value };
+        }
+        void pool_free(Pooled* p) {
+            p->~Pooled();
+            std::free(p);
+            frees++;
+        }
+        uint32_t pool_frees() {
+            return frees;
+        }
+        std::unique_ptr<Pooled> make_pooled(int32_t value) {
+            return std::unique_ptr<Pooled>(pool_alloc(value));
+        }
+    "};
+    let rs = quote! {
+        let p = ffi::make_pooled(3);
+        assert_eq!(ffi::pool_frees(), 0);
+        drop(p);
+        assert_eq!(ffi::pool_frees(), 1);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        directives_from_lists(
+            &["make_pooled", "pool_frees"],
+            &[],
+            Some(quote! {
+                custom_deleter!("Pooled", "pool_free")
+                block_constructors!("Pooled")
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_reference_to_pointer_out_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Foo {
+        public:
+            Foo(uint32_t val) : val(val) {}
+            uint32_t get() const { return val; }
+        private:
+            uint32_t val;
+        };
+        void acquire(Foo*& out);
+    "};
+    let cxx = indoc! {"
+        void acquire(Foo*& out) {
+            out = new Foo(42);
+        }
+    "};
+    let rs = quote! {
+        let foo: cxx::UniquePtr<ffi::Foo> = ffi::acquire();
+        assert_eq!(foo.get(), 42);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        quote! {
+            generate!("acquire")
+            generate!("Foo")
+            owning_out_param!("acquire", "out")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_enum_representation_rust_enum() {
+    let hdr = indoc! {"
+        enum Color {
+            Red,
+            Green,
+            Blue = 7,
+        };
+        inline Color next(Color c) {
+            return c == Red ? Green : Blue;
+        }
+    "};
+    let rs = quote! {
+        assert!(matches!(ffi::next(ffi::Color::Red), ffi::Color::Green));
+        assert!(matches!(ffi::next(ffi::Color::Green), ffi::Color::Blue));
+        assert_eq!(ffi::Color::Blue as u32, 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["next"],
+            &[],
+            Some(quote! {
+                enum_representation!(rust_enum)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_enum_representation_newtype() {
+    let hdr = indoc! {"
+        enum Color {
+            Red,
+            Green,
+            Blue = 7,
+        };
+        inline Color next(Color c) {
+            return c == Red ? Green : Blue;
+        }
+        inline Color unlisted() {
+            return static_cast<Color>(42);
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::next(ffi::Color::Red), ffi::Color::Green);
+        assert_eq!(ffi::next(ffi::Color::Green), ffi::Color::Blue);
+        assert_eq!(ffi::Color::Blue.0, 7);
+        let c = ffi::unlisted();
+        assert_eq!(c.0, 42);
+        assert_ne!(c, ffi::Color::Red);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["next", "unlisted"],
+            &[],
+            Some(quote! {
+                enum_representation!(newtype)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_non_exhaustive_enums() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum class Level : uint8_t {
+            Low,
+            High,
+        };
+        inline Level raise(Level l) { return Level::High; }
+    "};
+    let rs = quote! {
+        let name = match ffi::raise(ffi::Level::Low) {
+            ffi::Level::Low => "low",
+            ffi::Level::High => "high",
+            // Required only in other crates, but permitted here.
+            #[allow(unreachable_patterns)]
+            _ => "unknown",
+        };
+        assert_eq!(name, "high");
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("raise")
+            non_exhaustive_enums!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_enum_param_rust_enum() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace paint {
+            enum class Shade : uint8_t {
+                Light,
+                Dark = 5,
+            };
+        }
+        class Brush {
+        public:
+            Brush() : shade(paint::Shade::Light) {}
+            void set_shade(paint::Shade s) { shade = s; }
+            paint::Shade get_shade() const { return shade; }
+        private:
+            paint::Shade shade;
+        };
+        inline bool is_dark(const paint::Shade& s) {
+            return s == paint::Shade::Dark;
+        }
+        inline uint8_t shade_value(paint::Shade s) {
+            return static_cast<uint8_t>(s);
+        }
+    "};
+    let rs = quote! {
+        let mut brush = ffi::Brush::new().within_unique_ptr();
+        brush.pin_mut().set_shade(ffi::paint::Shade::Dark);
+        assert!(matches!(brush.get_shade(), ffi::paint::Shade::Dark));
+        assert!(ffi::is_dark(&ffi::paint::Shade::Dark));
+        assert!(!ffi::is_dark(&ffi::paint::Shade::Light));
+        assert_eq!(ffi::shade_value(ffi::paint::Shade::Dark), 5);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Brush", "is_dark", "shade_value"],
+            &[],
+            Some(quote! {
+                enum_representation!(rust_enum)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_enum_param_newtype() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        namespace paint {
+            enum class Shade : uint8_t {
+                Light,
+                Dark = 5,
+            };
+        }
+        class Brush {
+        public:
+            Brush() : shade(paint::Shade::Light) {}
+            void set_shade(paint::Shade s) { shade = s; }
+            paint::Shade get_shade() const { return shade; }
+        private:
+            paint::Shade shade;
+        };
+        inline bool is_dark(const paint::Shade& s) {
+            return s == paint::Shade::Dark;
+        }
+        inline uint8_t shade_value(paint::Shade s) {
+            return static_cast<uint8_t>(s);
+        }
+    "};
+    let rs = quote! {
+        let mut brush = ffi::Brush::new().within_unique_ptr();
+        brush.pin_mut().set_shade(ffi::paint::Shade::Dark);
+        assert_eq!(brush.get_shade(), ffi::paint::Shade::Dark);
+        assert!(ffi::is_dark(&ffi::paint::Shade::Dark));
+        assert!(!ffi::is_dark(&ffi::paint::Shade(2)));
+        assert_eq!(ffi::shade_value(ffi::paint::Shade(2)), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(
+            &["Brush", "is_dark", "shade_value"],
+            &[],
+            Some(quote! {
+                enum_representation!(newtype)
+            }),
+        ),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_std_tuple_return() {
+    let hdr = indoc! {"
+        #include <tuple>
+        inline std::tuple<int, double, bool> describe() {
+            return std::make_tuple(42, 1.5, true);
+        }
+    "};
+    let rs = quote! {
+        let (count, ratio, valid) = ffi::describe();
+        assert_eq!(count, autocxx::c_int(42));
+        assert_eq!(ratio, 1.5);
+        assert!(valid);
+    };
+    run_test("", hdr, rs, &["describe"], &[]);
+}
+
+#[test]
+fn test_foreign_fn_hook() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline uint32_t legacy_add(uint32_t a, uint32_t b) { return a + b; }
+        inline void deprecated_reset() {}
+        inline uint32_t answer() { return 42; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::add(2, 3), 5);
+        assert_eq!(ffi::answer(), 42);
+    };
+    fn hook(item: &syn::ForeignItemFn) -> autocxx_engine::FnAction {
+        let name = item.sig.ident.to_string();
+        if name.starts_with("deprecated_") {
+            autocxx_engine::FnAction::Skip
+        } else if let Some(new_name) = name.strip_prefix("legacy_") {
+            autocxx_engine::FnAction::Rename(new_name.to_string())
+        } else {
+            autocxx_engine::FnAction::Keep
+        }
+    }
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate_all!() },
+        Some(Box::new(SetForeignFnHook(hook))),
+        Some(make_string_absence_checker(vec!["deprecated_reset".into()])),
+        None,
+    );
+}
+
+#[test]
+fn test_constructor_detector() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Widget {
+            Widget() : size(1) {}
+            Widget(uint32_t size) : size(size) {}
+            uint32_t get_size() const { return size; }
+        private:
+            uint32_t size;
+        };
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::Widget::new().within_unique_ptr().get_size(), 1);
+        assert_eq!(ffi::Widget::new_overload1(3).within_unique_ptr().get_size(), 3);
+    };
+    fn detector(fn_name: &str, class_name: &str) -> Option<String> {
+        autocxx_engine::is_constructor(fn_name, class_name).map(|suffix| {
+            if suffix.is_empty() {
+                suffix
+            } else {
+                format!("_overload{}", suffix)
+            }
+        })
+    }
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! { generate!("Widget") },
+        Some(Box::new(SetConstructorDetector(detector))),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_prelude() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        struct Point {
+            uint32_t x;
+        };
+        namespace shapes {
+            struct Square {
+                uint32_t side;
+            };
+        }
+        inline std::unique_ptr<shapes::Square> make_square(uint32_t side) {
+            auto s = std::make_unique<shapes::Square>();
+            s->side = side;
+            return s;
+        }
+        inline uint32_t area(const shapes::Square& s) { return s.side * s.side; }
+    "};
+    let rs = quote! {
+        use ffi::prelude::*;
+        let p = Point { x: 2 };
+        let s: cxx::UniquePtr<Square> = make_square(p.x);
+        assert_eq!(area(&s), 4);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Point")
+            generate!("shapes::Square")
+            generate!("make_square")
+            generate!("area")
+            prelude!("Point", "shapes::Square", "make_square", "area")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_slice_param_pod() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstddef>
+        struct Vertex {
+            uint32_t x;
+            uint32_t y;
+        };
+        inline uint32_t draw(const Vertex* verts, size_t count) {
+            uint32_t total = 0;
+            for (size_t i = 0; i < count; i++) {
+                total += verts[i].x * verts[i].y;
+            }
+            return total;
+        }
+        inline void scale(Vertex* verts, size_t count, uint32_t factor) {
+            for (size_t i = 0; i < count; i++) {
+                verts[i].x *= factor;
+            }
+        }
+    "};
+    let rs = quote! {
+        let mut verts = [ffi::Vertex { x: 1, y: 2 }, ffi::Vertex { x: 3, y: 4 }];
+        assert_eq!(ffi::draw(&verts), 14);
+        ffi::scale(&mut verts, 2);
+        assert_eq!(ffi::draw(&verts[1..]), 24);
+        assert_eq!(ffi::draw(&[]), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Vertex")
+            generate!("draw")
+            generate!("scale")
+            slice_param!("draw", "verts", "count")
+            slice_param!("scale", "verts", "count")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_slice_param_c_strings() {
+    let hdr = indoc! {"
+        #include <cstring>
+        #include <cstddef>
+        inline int total_len(int argc, char** argv) {
+            if (argv[argc] != nullptr) {
+                return -1;
+            }
+            int total = 0;
+            for (int i = 0; i < argc; i++) {
+                total += strlen(argv[i]);
+            }
+            return total;
+        }
+        inline char first_char_of_last(const char* const* items, size_t count) {
+            return items[count - 1][0];
+        }
+    "};
+    let rs = quote! {
+        use std::ffi::CStr;
+        let args: Vec<&CStr> = vec![
+            CStr::from_bytes_with_nul(b"prog\0").unwrap(),
+            CStr::from_bytes_with_nul(b"--verbose\0").unwrap(),
+        ];
+        assert_eq!(unsafe { ffi::total_len(&args) }, autocxx::c_int(13));
+        assert_eq!(ffi::first_char_of_last(&args), b'-' as std::os::raw::c_char);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("total_len")
+            generate!("first_char_of_last")
+            slice_param!("total_len", "argv", "argc")
+            slice_param!("first_char_of_last", "items", "count")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_slice_return() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstddef>
+        class Buffer {
+        public:
+            const uint8_t* data() const { return bytes; }
+            int size() const { return 3; }
+        private:
+            uint8_t bytes[3] = { 1, 2, 3 };
+        };
+        inline const uint8_t* get_data() {
+            static const uint8_t bytes[] = { 4, 5 };
+            return bytes;
+        }
+        inline size_t get_len() { return 2; }
+    "};
+    let rs = quote! {
+        let buffer = ffi::Buffer::new().within_unique_ptr();
+        let data: &[u8] = buffer.data();
+        assert_eq!(data, &[1, 2, 3]);
+        assert_eq!(ffi::get_data(), vec![4, 5]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Buffer")
+            generate!("get_data")
+            slice_return!("Buffer::data", "size")
+            slice_return!("get_data", "get_len")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_container_param() {
+    let hdr = indoc! {"
+        #include <cstddef>
+        #include <cstdint>
+        #include <string>
+        #include <vector>
+        inline size_t write(const std::vector<uint8_t>& data) {
+            size_t total = 0;
+            for (auto b : data) {
+                total += b;
+            }
+            return total;
+        }
+        inline size_t write_owned(std::vector<uint8_t> data) {
+            data.push_back(1);
+            return data.size();
+        }
+        inline size_t greet(const std::string& name) {
+            return std::string(\"Hello, \").append(name).size();
+        }
+        class Sink {
+        public:
+            size_t write(const std::vector<uint8_t>& data) const {
+                return data.size();
+            }
+        };
+    "};
+    let rs = quote! {
+        let rust_byte_slice: Vec<u8> = vec![1, 2, 3];
+        assert_eq!(ffi::write(&rust_byte_slice), 6);
+        assert_eq!(ffi::write(&[]), 0);
+        assert_eq!(ffi::write_owned(&rust_byte_slice), 4);
+        assert_eq!(ffi::greet("world"), 12);
+        let sink = ffi::Sink::new().within_unique_ptr();
+        assert_eq!(sink.write(&rust_byte_slice), 3);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("write")
+            generate!("write_owned")
+            generate!("greet")
+            generate!("Sink")
+            container_param!("write", "data")
+            container_param!("Sink::write", "data")
+            container_param!("write_owned", "data")
+            container_param!("greet", "name")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_reference_param() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Counter {
+        public:
+            Counter() : count(1) {}
+            void absorb(Counter* other) {
+                count += other->count;
+                other->count = 0;
+            }
+            uint32_t count;
+        };
+        inline uint32_t total(const Counter* c) { return c->count; }
+    "};
+    let rs = quote! {
+        let mut a = ffi::Counter::new().within_unique_ptr();
+        let mut b = ffi::Counter::new().within_unique_ptr();
+        a.pin_mut().absorb(b.pin_mut());
+        assert_eq!(ffi::total(&a), 2);
+        assert_eq!(ffi::total(&b), 0);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            generate!("total")
+            reference_param!("Counter::absorb", "other")
+            reference_param!("total", "c")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_negative_reference_param_primitive() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        inline void bump(uint32_t* n) { (*n)++; }
+    "};
+    let rs = quote! {
+        let mut n = 0u32;
+        ffi::bump(&mut n);
+    };
+    run_test_expect_fail_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("bump")
+            reference_param!("bump", "n")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_type_link_name() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        inline uint32_t& handles_destroyed() {
+            static uint32_t count = 0;
+            return count;
+        }
+        struct Handle {
+            ~Handle() { handles_destroyed()++; }
+            uint32_t a;
+        };
+        namespace detail {
+            using HandleImpl = ::Handle;
+        }
+        inline std::unique_ptr<Handle> make_handle() {
+            return std::make_unique<Handle>();
+        }
+        inline uint32_t get_handles_destroyed() { return handles_destroyed(); }
+    "};
+    let rs = quote! {
+        let h = ffi::make_handle();
+        assert_eq!(ffi::get_handles_destroyed(), 0);
+        drop(h);
+        assert_eq!(ffi::get_handles_destroyed(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Handle")
+            generate!("make_handle")
+            generate!("get_handles_destroyed")
+            type_link_name!("Handle", "detail::HandleImpl")
+        },
+        None,
+        Some(make_rust_code_finder(vec![
+            quote! {
+                #[namespace = "detail"]
+                #[cxx_name = "HandleImpl"]
+            },
+            quote! {
+                type Id = cxx::type_id!("detail::HandleImpl");
+            },
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_status_result() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        enum Status {
+            OK,
+            FAIL,
+        };
+        inline Status check(uint32_t a) {
+            return a > 3 ? Status::OK : Status::FAIL;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::check(4), Ok(()));
+        assert_eq!(ffi::check(2), Err(ffi::Status::FAIL));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Status")
+            generate!("check")
+            status_result!("Status", "OK")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_virtual_trait() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        class Shape {
+        public:
+            Shape() {}
+            virtual ~Shape() {}
+            virtual uint32_t area() const { return 0; }
+            virtual void grow(uint32_t) {}
+        };
+        class Square : public Shape {
+        public:
+            Square(uint32_t side) : side(side) {}
+            uint32_t area() const override { return side * side; }
+            void grow(uint32_t by) override { side += by; }
+        private:
+            uint32_t side;
+        };
+        inline std::unique_ptr<Shape> make_square(uint32_t side) {
+            return std::make_unique<Square>(side);
+        }
+    "};
+    let rs = quote! {
+        fn area_of(shape: &dyn ffi::ShapeTrait) -> u32 {
+            shape.area()
+        }
+        let mut square = ffi::make_square(3);
+        assert_eq!(area_of(square.as_ref().unwrap()), 9);
+        let shape: std::pin::Pin<&mut dyn ffi::ShapeTrait> = square.pin_mut();
+        shape.grow(1);
+        assert_eq!(area_of(square.as_ref().unwrap()), 16);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Shape")
+            generate!("make_square")
+            virtual_trait!("Shape")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_void_unique_ptr() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <memory>
+        inline uint32_t& live_plugins() {
+            static uint32_t count = 0;
+            return count;
+        }
+        // Move-only, so the deleter mustn't be copied.
+        struct PluginDeleter {
+            PluginDeleter() = default;
+            PluginDeleter(PluginDeleter&&) = default;
+            PluginDeleter(const PluginDeleter&) = delete;
+            void operator()(void* plugin) const {
+                delete static_cast<uint32_t*>(plugin);
+                live_plugins()--;
+            }
+        };
+        inline std::unique_ptr<void, PluginDeleter> load_plugin(uint32_t id) {
+            live_plugins()++;
+            return std::unique_ptr<void, PluginDeleter>(new uint32_t(id));
+        }
+        inline uint32_t get_plugin_id(void* plugin) {
+            return *static_cast<uint32_t*>(plugin);
+        }
+        inline uint32_t count_live_plugins() { return live_plugins(); }
+    "};
+    let rs = quote! {
+        let plugin = ffi::load_plugin(42);
+        assert_eq!(ffi::count_live_plugins(), 1);
+        assert_eq!(unsafe { ffi::get_plugin_id(plugin.as_ptr()) }, 42);
+        drop(plugin);
+        assert_eq!(ffi::count_live_plugins(), 0);
+    };
+    run_test(
+        "",
+        hdr,
+        rs,
+        &["load_plugin", "get_plugin_id", "count_live_plugins"],
+        &[],
+    );
+}
+
+#[test]
+fn test_constexpr_const() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        constexpr int BUFSIZE = 4096;
+        constexpr uint32_t kib(uint32_t n) { return n * 1024; }
+    "};
+    let rs = quote! {
+        // Usable in a const context, so it can't be a runtime call.
+        const SIZE: i32 = ffi::BUFSIZE;
+        assert_eq!(SIZE, 4096);
+        assert_eq!(ffi::kib(4), 4096);
+    };
+    run_test("", hdr, rs, &["BUFSIZE", "kib"], &[]);
+}
+
+#[test]
+fn test_cpp_std_rejects_newer_type() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <span>
+        inline uint32_t sum(std::span<const uint32_t> s) {
+            uint32_t total = 0;
+            for (auto x : s) {
+                total += x;
+            }
+            return total;
+        }
+        inline uint32_t get_one() { return 1; }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::get_one(), 1);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("sum")
+            generate!("get_one")
+            cpp_std!("c++14")
+        },
+        make_cpp20_adder(),
+        Some(make_string_finder(vec![
+            "std::span requires c++20 or later, but cpp_std! specifies c++14".into(),
+        ])),
+        None,
+    );
+}
+
+#[test]
+fn test_indexed() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <cstddef>
+        struct IntList {
+            uint32_t elems[4];
+            uint32_t& operator[](size_t idx) { return elems[idx]; }
+            const uint32_t& operator[](size_t idx) const { return elems[idx]; }
+        };
+    "};
+    let rs = quote! {
+        let mut list = ffi::IntList { elems: [1, 2, 3, 4] };
+        assert_eq!(list[2], 3);
+        list[2] = 7;
+        assert_eq!(list.elems, [1, 2, 7, 4]);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("IntList")
+            indexed!("IntList", "uint32_t")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_bool_conversion() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <string>
+        namespace io {
+            class Handle {
+            public:
+                Handle(int32_t fd) : fd(fd), name(\"handle\") {}
+                explicit operator bool() const { return fd >= 0; }
+            private:
+                int32_t fd;
+                std::string name;
+            };
+        }
+    "};
+    let rs = quote! {
+        let open = ffi::io::Handle::new(3).within_unique_ptr();
+        let closed = ffi::io::Handle::new(-1).within_unique_ptr();
+        assert!(open.as_bool());
+        assert!(!closed.as_bool());
+        assert!(bool::from(open.as_ref().unwrap()));
+        let closed: bool = closed.as_ref().unwrap().into();
+        assert!(!closed);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("io::Handle")
+            bool_conversion!("io::Handle")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_inout_param_reference() {
+    let cxx = indoc! {"
+        void increment(int& x) {
+            x++;
+        }
+        void get_answer(uint32_t& out) {
+            out = 42;
+        }
+    "};
+    let hdr = indoc! {"
+        #include <cstdint>
+        void increment(int& x);
+        void get_answer(uint32_t& out);
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::increment(autocxx::c_int(3)), autocxx::c_int(4));
+        assert_eq!(ffi::get_answer(), 42);
+    };
+    run_test_ex(
+        cxx,
+        hdr,
+        rs,
+        quote! {
+            generate!("increment")
+            generate!("get_answer")
+            inout_param!("increment", "x")
+            out_param!("get_answer", "out")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_trace_calls() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        class Counter {
+        public:
+            Counter() : count(0) {}
+            void add(uint32_t n) { count += n; }
+            uint32_t get() const { return count; }
+        private:
+            uint32_t count;
+        };
+        inline uint32_t double_it(uint32_t n) { return n * 2; }
+    "};
+    let rs = quote! {
+        use autocxx::tracing::{span, Event, Metadata, Subscriber};
+        use std::sync::{Arc, Mutex};
+
+        struct RecordingSubscriber(Arc<Mutex<Vec<String>>>);
+
+        impl Subscriber for RecordingSubscriber {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+                let mut spans = self.0.lock().unwrap();
+                let fields: Vec<_> = attrs.fields().iter().map(|f| f.name()).collect();
+                spans.push(format!("{}({})", attrs.metadata().name(), fields.join(",")));
+                span::Id::from_u64(spans.len() as u64)
+            }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        autocxx::tracing::subscriber::with_default(RecordingSubscriber(spans.clone()), || {
+            let mut c = ffi::Counter::new().within_box();
+            c.as_mut().add(3);
+            assert_eq!(c.get(), 3);
+            assert_eq!(ffi::double_it(4), 8);
+        });
+        let spans = spans.lock().unwrap();
+        assert!(spans.contains(&"add(n)".to_string()));
+        assert!(spans.contains(&"get()".to_string()));
+        assert!(spans.contains(&"double_it(n)".to_string()));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Counter")
+            generate!("double_it")
+            trace_calls!()
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_std_variant_return() {
+    let hdr = indoc! {"
+        #include <variant>
+        inline std::variant<int, double> measure(bool precise) {
+            if (precise) {
+                return 2.5;
+            }
+            return 3;
+        }
+    "};
+    let rs = quote! {
+        match ffi::measure(false) {
+            ffi::StdVariant_c_int_f64::V0(count) => assert_eq!(count, autocxx::c_int(3)),
+            ffi::StdVariant_c_int_f64::V1(_) => panic!("expected an int"),
+        }
+        assert_eq!(ffi::measure(true), ffi::StdVariant_c_int_f64::V1(2.5));
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["measure"], &[], None),
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_std_optional_return() {
+    let hdr = indoc! {"
+        #include <optional>
+        #include <cstdint>
+        inline std::optional<uint32_t> half(uint32_t n) {
+            if (n % 2) {
+                return std::nullopt;
+            }
+            return n / 2;
+        }
+    "};
+    let rs = quote! {
+        assert_eq!(ffi::half(6), Some(3));
+        assert_eq!(ffi::half(7), None);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        directives_from_lists(&["half"], &[], None),
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_generator() {
+    let hdr = indoc! {"
+        #include <optional>
+        #include <cstdint>
+        class Countdown {
+        public:
+            Countdown(uint32_t from) : remaining(from) {}
+            std::optional<uint32_t> next() {
+                if (remaining == 0) {
+                    return std::nullopt;
+                }
+                return remaining--;
+            }
+        private:
+            uint32_t remaining;
+        };
+    "};
+    let rs = quote! {
+        use autocxx::CppGenerator;
+        let mut countdown = ffi::Countdown::new(3).within_unique_ptr();
+        let items: Vec<u32> = countdown.pin_mut().iter().collect();
+        assert_eq!(items, vec![3, 2, 1]);
+        assert_eq!(countdown.pin_mut().next(), None);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Countdown")
+            generator!("Countdown")
+        },
+        make_cpp17_adder(),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_function_table_builder() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Ops {
+            void* context;
+            int32_t (*read)(void* context, int32_t n);
+            void (*close)(void* context);
+        };
+        inline int32_t drive(const Ops& ops) {
+            int32_t total = ops.read(ops.context, 20) + ops.read(ops.context, 1);
+            ops.close(ops.context);
+            return total;
+        }
+    "};
+    let rs = quote! {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        extern "C" fn close(_: *mut std::ffi::c_void) {}
+        let reads = Arc::new(AtomicU32::new(0));
+        let reads_seen = reads.clone();
+        let ops = unsafe {
+            ffi::OpsBuilder::new().bind_read(move |n| {
+                reads.fetch_add(1, Ordering::SeqCst);
+                n * 2
+            })
+        }
+        .set_close(close)
+        .build();
+        assert_eq!(ffi::drive(&ops), 42);
+        assert_eq!(reads_seen.load(Ordering::SeqCst), 2);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod!("Ops")
+            generate!("drive")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_generate_pod_if() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        struct Here {
+            uint32_t a;
+        };
+        struct Elsewhere {
+            uint32_t a;
+        };
+        inline Here make_here() { return Here { 3 }; }
+        inline uint32_t get_elsewhere(const Elsewhere& e) { return e.a; }
+        inline Elsewhere make_elsewhere() { return Elsewhere { 4 }; }
+    "};
+    let rs = quote! {
+        let here = ffi::make_here();
+        assert_eq!(here.a, 3);
+        let elsewhere = ffi::make_elsewhere().within_unique_ptr();
+        assert_eq!(ffi::get_elsewhere(&elsewhere), 4);
+    };
+    let os = std::env::consts::OS;
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate_pod_if!("Here", target_os = #os)
+            generate_pod_if!("Elsewhere", target_pointer_width = "8")
+            generate!("make_here")
+            generate!("make_elsewhere")
+            generate!("get_elsewhere")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_factory() {
+    let hdr = indoc! {"
+        #include <cstdint>
+        #include <new>
+        class Widget {
+        public:
+            uint32_t get_size() const { return size; }
+        private:
+            explicit Widget(uint32_t size) : size(size) {}
+            friend Widget* create_widget(uint32_t size);
+            uint32_t size;
+        };
+        inline Widget* create_widget(uint32_t size) {
+            void* storage = ::operator new(sizeof(Widget));
+            return new (storage) Widget(size);
+        }
+        class Gizmo {
+        public:
+            static Gizmo* create(uint32_t size) { return new Gizmo(size); }
+            uint32_t get_size() const { return size; }
+        private:
+            explicit Gizmo(uint32_t size) : size(size) {}
+            uint32_t size;
+        };
+    "};
+    let rs = quote! {
+        let widget = ffi::Widget::make_unique(42);
+        assert_eq!(widget.get_size(), 42);
+        let gizmo = ffi::Gizmo::make_unique(7);
+        assert_eq!(gizmo.get_size(), 7);
+    };
+    run_test_ex(
+        "",
+        hdr,
+        rs,
+        quote! {
+            generate!("Widget")
+            generate!("create_widget")
+            factory!("Widget", "create_widget")
+            generate!("Gizmo")
+            factory!("Gizmo", "Gizmo::create")
+        },
+        None,
+        None,
+        None,
+    );
+}
+
 // Yet to test:
 // - Ifdef
-// - Out param pointers
 // - ExcludeUtilities
 // - Struct fields which are typedefs
 // Negative tests:
@@ -90,6 +90,103 @@ impl UnsafePolicy {
     }
 }
 
+/// How C++ enums are represented in Rust.
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Default)]
+pub enum EnumRepresentation {
+    /// A Rust `enum`. Only the listed values may be held.
+    #[default]
+    RustEnum,
+    /// A newtype wrapping the underlying integer, with an associated
+    /// constant for each listed value. Any value may be held.
+    Newtype,
+}
+
+impl Parse for EnumRepresentation {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let id: syn::Ident = input.parse()?;
+        let r = if id == "rust_enum" {
+            Self::RustEnum
+        } else if id == "newtype" {
+            Self::Newtype
+        } else {
+            return Err(syn::Error::new(id.span(), "expected rust_enum or newtype"));
+        };
+        if !input.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "unexpected tokens within enum_representation directive",
+            ));
+        }
+        Ok(r)
+    }
+}
+
+/// The C++ standard which the bindings may assume, which governs which
+/// standard library types we're prepared to map.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash, Default)]
+pub enum CppStd {
+    Cpp14,
+    Cpp17,
+    #[default]
+    Cpp20,
+}
+
+impl CppStd {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cpp14 => "c++14",
+            Self::Cpp17 => "c++17",
+            Self::Cpp20 => "c++20",
+        }
+    }
+}
+
+impl std::fmt::Display for CppStd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Parse for CppStd {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let lit: syn::LitStr = input.parse()?;
+        let r = match lit.value().as_str() {
+            "c++14" => Self::Cpp14,
+            "c++17" => Self::Cpp17,
+            "c++20" => Self::Cpp20,
+            _ => {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    "expected \"c++14\", \"c++17\" or \"c++20\"",
+                ))
+            }
+        };
+        if !input.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "unexpected tokens within cpp_std directive",
+            ));
+        }
+        Ok(r)
+    }
+}
+
+impl ToTokens for CppStd {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let s = self.as_str();
+        tokens.extend(quote! { #s })
+    }
+}
+
+impl ToTokens for EnumRepresentation {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Self::RustEnum => tokens.extend(quote! { rust_enum }),
+            Self::Newtype => tokens.extend(quote! { newtype }),
+        }
+    }
+}
+
 /// An entry in the allowlist.
 #[derive(Hash, Debug)]
 pub enum AllowlistEntry {
@@ -183,6 +280,150 @@ pub struct ExternCppType {
     pub opaque: bool,
 }
 
+/// A pointer or reference parameter which C++ uses to pass back an extra
+/// result, and which we should turn into part of the Rust return value.
+/// Used both for pure out-parameters and for in-out parameters.
+#[derive(Debug, Clone, Hash)]
+pub struct OutParam {
+    pub function: String,
+    pub param: String,
+}
+
+/// A `void*` parameter through which C++ is handed an opaque pointer to
+/// some Rust context, which it later hands back.
+#[derive(Debug, Clone, Hash)]
+pub struct ContextParam {
+    pub function: String,
+    pub param: String,
+}
+
+/// A pointer parameter which, together with the length parameter
+/// immediately next to it, describes an array which Rust passes as a slice.
+#[derive(Debug, Clone, Hash)]
+pub struct SliceParam {
+    pub function: String,
+    pub param: String,
+    pub len_param: String,
+}
+
+/// A function returning a pointer to an array, together with the
+/// function which returns its length, such that Rust receives a slice.
+#[derive(Debug, Clone, Hash)]
+pub struct SliceReturn {
+    pub function: String,
+    pub len_function: String,
+}
+
+/// A type whose handle should call the given method before the
+/// object it owns is destroyed. If `unsafe_hook`, the user has
+/// promised that it's fine to call even if it's an `unsafe` function.
+#[derive(Debug, Clone, Hash)]
+pub struct PreDrop {
+    pub ty: String,
+    pub hook: String,
+    pub unsafe_hook: bool,
+}
+
+/// A `std::vector<T>` or `std::string` parameter which Rust passes as a
+/// slice or `&str`, from which C++ builds a temporary container.
+#[derive(Debug, Clone, Hash)]
+pub struct ContainerParam {
+    pub function: String,
+    pub param: String,
+}
+
+/// A pointer parameter which is never null, and which Rust therefore
+/// passes as a reference.
+#[derive(Debug, Clone, Hash)]
+pub struct ReferenceParam {
+    pub function: String,
+    pub param: String,
+}
+
+/// A `std::span` or `rust::Slice` parameter whose elements are `const`,
+/// which Rust therefore passes as a shared slice.
+#[derive(Debug, Clone, Hash)]
+pub struct ConstSliceParam {
+    pub function: String,
+    pub param: String,
+}
+
+/// The name, including any namespace, by which cxx should refer to a C++
+/// type, where that differs from the name autocxx found it under.
+#[derive(Debug, Clone, Hash)]
+pub struct TypeLinkName {
+    pub cpp_name: String,
+    pub link_name: String,
+}
+
+/// An enum returned by C++ functions to indicate success or failure,
+/// along with the value which indicates success.
+#[derive(Debug, Clone, Hash)]
+pub struct StatusResult {
+    pub enum_name: String,
+    pub success_value: String,
+}
+
+/// A type with an `operator[]` taking a `size_t`, along with the type of
+/// the elements it gives access to.
+#[derive(Debug, Clone, Hash)]
+pub struct IndexedType {
+    pub cpp_name: String,
+    pub element: String,
+}
+
+/// A user-specified replacement for a C++ type, supplementing (and taking
+/// precedence over) the types which autocxx already knows how to map.
+#[derive(Debug, Clone, Hash)]
+pub struct TypeMapping {
+    pub cpp_name: String,
+    pub rust_path: TypePath,
+}
+
+/// A C++ smart pointer template which keeps its reference count within
+/// the pointee, along with the functions used to adjust that count.
+#[derive(Debug, Clone, Hash)]
+pub struct IntrusivePtr {
+    pub cpp_name: String,
+    pub add_ref: String,
+    pub release: String,
+}
+
+/// A C++ type whose objects must be destroyed by calling the given function
+/// rather than with `delete`, typically because they come from a custom
+/// allocator.
+#[derive(Debug, Clone, Hash)]
+pub struct CustomDeleter {
+    pub cpp_name: String,
+    pub deleter: String,
+}
+
+/// A type which should be POD on targets matching the given `cfg`
+/// predicate, and opaque on all others.
+#[derive(Debug, Clone, Hash)]
+pub struct ConditionalPod {
+    pub ty: String,
+    pub condition: syn::Meta,
+    /// Whether the condition holds for the target we're generating for.
+    pub pod: bool,
+}
+
+/// A C++ type which is constructed by calling the given factory function,
+/// which returns a pointer to a new object, rather than by a constructor.
+#[derive(Debug, Clone, Hash)]
+pub struct Factory {
+    pub cpp_name: String,
+    pub function: String,
+}
+
+/// A specialization of a templated C++ function which we should make
+/// available under the given name.
+#[derive(Debug, Clone, Hash)]
+pub struct FnInstantiation {
+    pub template: String,
+    pub name: Ident,
+}
+
 /// Newtype wrapper so we can implement Hash.
 #[derive(Debug, Default)]
 pub struct ExternCppTypeMap(pub HashMap<String, ExternCppType>);
@@ -212,10 +453,15 @@ impl std::hash::Hash for ConcretesMap {
 #[derive(Debug, Default, Hash)]
 pub struct IncludeCppConfig {
     pub inclusions: Vec<String>,
+    pub(crate) preamble: Vec<String>,
     pub unsafe_policy: UnsafePolicy,
+    pub(crate) enum_representation: EnumRepresentation,
+    pub(crate) non_exhaustive_enums: bool,
+    pub(crate) cpp_std: CppStd,
     pub parse_only: bool,
     pub exclude_impls: bool,
     pub(crate) pod_requests: Vec<String>,
+    pub(crate) conditional_pods: Vec<ConditionalPod>,
     pub allowlist: Allowlist,
     pub(crate) blocklist: Vec<String>,
     pub(crate) constructor_blocklist: Vec<String>,
@@ -227,6 +473,55 @@ pub struct IncludeCppConfig {
     pub extern_rust_funs: Vec<RustFun>,
     pub concretes: ConcretesMap,
     pub externs: ExternCppTypeMap,
+    pub(crate) out_params: Vec<OutParam>,
+    pub(crate) inout_params: Vec<OutParam>,
+    pub(crate) owning_out_params: Vec<OutParam>,
+    pub(crate) context_params: Vec<ContextParam>,
+    pub(crate) slice_params: Vec<SliceParam>,
+    pub(crate) slice_returns: Vec<SliceReturn>,
+    pub(crate) container_params: Vec<ContainerParam>,
+    pub(crate) reference_params: Vec<ReferenceParam>,
+    pub(crate) const_slice_params: Vec<ConstSliceParam>,
+    pub(crate) type_link_names: Vec<TypeLinkName>,
+    pub(crate) status_results: Vec<StatusResult>,
+    pub(crate) bridge_items: Vec<syn::Item>,
+    pub(crate) shared_structs: Vec<syn::ItemStruct>,
+    pub(crate) flags_enums: Vec<String>,
+    pub(crate) ordered_types: Vec<String>,
+    pub(crate) indexed_types: Vec<IndexedType>,
+    pub(crate) bool_conversion_types: Vec<String>,
+    pub(crate) copy_returns: Vec<String>,
+    pub(crate) error_code_results: Vec<String>,
+    pub(crate) nullable_returns: Vec<String>,
+    pub(crate) owning_returns: Vec<String>,
+    pub(crate) force_mut_selfs: Vec<String>,
+    pub(crate) array_param_functions: Vec<String>,
+    pub(crate) handle_types: Vec<String>,
+    pub(crate) pre_drops: Vec<PreDrop>,
+    pub(crate) thread_safe_types: Vec<String>,
+    pub(crate) thread_unsafe_functions: Vec<String>,
+    pub(crate) clonable_types: Vec<String>,
+    pub(crate) virtual_trait_types: Vec<String>,
+    pub(crate) field_accessor_types: Vec<String>,
+    pub(crate) serde_pods: Vec<String>,
+    pub(crate) identity_eq_types: Vec<String>,
+    pub(crate) generator_types: Vec<String>,
+    pub(crate) prelude_items: Vec<String>,
+    pub(crate) instantiations: Vec<FnInstantiation>,
+    pub(crate) exclude_bridge_wrapper: bool,
+    pub(crate) split_by_namespace: bool,
+    pub(crate) shared_type_declarations: bool,
+    pub(crate) exclude_inline_wrappers: bool,
+    pub(crate) safe_getters: bool,
+    pub(crate) pod_by_default: bool,
+    pub(crate) trace_calls: bool,
+    pub(crate) explicit_cxx_names: bool,
+    pub(crate) check_signatures: bool,
+    pub(crate) extra_type_mappings: Vec<TypeMapping>,
+    pub(crate) intrusive_ptrs: Vec<IntrusivePtr>,
+    pub(crate) custom_deleters: Vec<CustomDeleter>,
+    pub(crate) factories: Vec<Factory>,
+    pub(crate) transparent_types: Vec<String>,
 }
 
 impl Parse for IncludeCppConfig {
@@ -265,6 +560,19 @@ impl Parse for IncludeCppConfig {
                 break;
             }
         }
+        if let Some(both) = config
+            .identity_eq_types
+            .iter()
+            .find(|t| config.ordered_types.contains(t))
+        {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!(
+                    "{} is marked both identity_eq! and ordered!, but only one of them may implement PartialEq",
+                    both
+                ),
+            ));
+        }
         Ok(config)
     }
 }
@@ -274,6 +582,18 @@ impl IncludeCppConfig {
         &self.pod_requests
     }
 
+    /// The type's entry from `generate_pod_if!`, if any, recording whether
+    /// it's POD on the target we're generating bindings for.
+    pub fn get_conditional_pod(&self, cpp_name: &str) -> Option<&ConditionalPod> {
+        self.conditional_pods.iter().find(|cp| cp.ty == cpp_name)
+    }
+
+    /// Lines of C++ which should appear before any of the `#include`s,
+    /// for example forward declarations or macro definitions.
+    pub fn get_preamble(&self) -> &[String] {
+        &self.preamble
+    }
+
     pub fn get_mod_name(&self) -> Ident {
         self.mod_name
             .as_ref()
@@ -316,6 +636,7 @@ impl IncludeCppConfig {
                     .map(AllowlistEntry::to_bindgen_item)
                     .chain(self.pod_requests.iter().cloned())
                     .chain(self.active_utilities())
+                    .chain(self.instantiations.iter().map(|inst| inst.name.to_string()))
                     .chain(self.subclasses.iter().flat_map(|sc| {
                         [
                             format!("{}Cpp", sc.subclass),
@@ -363,6 +684,8 @@ impl IncludeCppConfig {
             || self.is_subclass_cpp(cpp_name)
             || self.is_rust_fun(cpp_name)
             || self.is_concrete_type(cpp_name)
+            || self.is_shared_struct(cpp_name)
+            || self.instantiations.iter().any(|inst| inst.name == cpp_name)
             || match &self.allowlist {
                 Allowlist::Unspecified(_) => panic!("Eek no allowlist yet"),
                 Allowlist::All => true,
@@ -385,6 +708,400 @@ impl IncludeCppConfig {
         self.blocklist.iter()
     }
 
+    /// Whether the given parameter of the given function has been
+    /// marked as an out-parameter using `out_param!`. Here and in the
+    /// other per-function lookups, methods are named `Class::method`.
+    pub fn is_out_param(&self, function: &str, param: &str) -> bool {
+        self.out_params
+            .iter()
+            .any(|op| op.function == function && op.param == param)
+    }
+
+    /// Whether the given parameter of the given function has been marked
+    /// using `owning_out_param!`, meaning C++ stores into it a pointer to a
+    /// new object which the caller then owns.
+    pub fn is_owning_out_param(&self, function: &str, param: &str) -> bool {
+        self.owning_out_params
+            .iter()
+            .any(|op| op.function == function && op.param == param)
+    }
+
+    /// Whether the given parameter of the given function has been
+    /// marked as an in-out parameter using `inout_param!`.
+    pub fn is_inout_param(&self, function: &str, param: &str) -> bool {
+        self.inout_params
+            .iter()
+            .any(|op| op.function == function && op.param == param)
+    }
+
+    /// Whether the given parameter of the given function has been
+    /// marked as a context pointer using `context_param!`.
+    pub fn is_context_param(&self, function: &str, param: &str) -> bool {
+        self.context_params
+            .iter()
+            .any(|cp| cp.function == function && cp.param == param)
+    }
+
+    /// Pointer and length parameters of the given function which have been
+    /// marked using `slice_param!` to be passed from Rust as a slice.
+    pub fn get_slice_params<'a>(
+        &'a self,
+        function: &'a str,
+    ) -> impl Iterator<Item = &'a SliceParam> + 'a {
+        self.slice_params
+            .iter()
+            .filter(move |sp| sp.function == function)
+    }
+
+    /// If the given function has been marked using `slice_return!`, the
+    /// function which returns the length of the array it returns.
+    pub fn get_slice_return_len_function(&self, function: &str) -> Option<&str> {
+        self.slice_returns
+            .iter()
+            .find(|sr| sr.function == function)
+            .map(|sr| sr.len_function.as_str())
+    }
+
+    /// Whether the given parameter of the given function has been
+    /// marked using `container_param!` to be passed from Rust as a slice
+    /// or `&str`.
+    pub fn is_container_param(&self, function: &str, param: &str) -> bool {
+        self.container_params
+            .iter()
+            .any(|cp| cp.function == function && cp.param == param)
+    }
+
+    /// Whether the given pointer parameter of the given function has been
+    /// marked using `reference_param!` to be passed from Rust as a
+    /// reference.
+    pub fn is_reference_param(&self, function: &str, param: &str) -> bool {
+        self.reference_params
+            .iter()
+            .any(|rp| rp.function == function && rp.param == param)
+    }
+
+    /// Whether the given `std::span` or `rust::Slice` parameter of the
+    /// given function has been marked using `const_slice_param!` as
+    /// having `const` elements.
+    pub fn is_const_slice_param(&self, function: &str, param: &str) -> bool {
+        self.const_slice_params
+            .iter()
+            .any(|cp| cp.function == function && cp.param == param)
+    }
+
+    /// The name given using `type_link_name!` by which cxx should refer
+    /// to the given type, if any.
+    pub fn get_type_link_name(&self, cpp_name: &str) -> Option<&str> {
+        self.type_link_names
+            .iter()
+            .find(|tln| tln.cpp_name == cpp_name)
+            .map(|tln| tln.link_name.as_str())
+    }
+
+    /// If the given enum has been marked using `status_result!`, the
+    /// value which indicates success.
+    pub fn get_status_result_success_value(&self, enum_name: &str) -> Option<&str> {
+        self.status_results
+            .iter()
+            .find(|sr| sr.enum_name == enum_name)
+            .map(|sr| sr.success_value.as_str())
+    }
+
+    /// C++ shim functions which call the templated functions requested
+    /// using `instantiate!`. These need to be seen by both bindgen and
+    /// the C++ compiler, after the user's headers.
+    pub fn get_instantiation_shims(&self) -> impl Iterator<Item = String> + '_ {
+        self.instantiations.iter().map(|inst| {
+            format!(
+                "inline decltype({template}()) {name}() {{ return {template}(); }}",
+                template = inst.template,
+                name = inst.name
+            )
+        })
+    }
+
+    /// Whether our generated header has content beyond the user's headers
+    /// (a preamble or instantiation shims) and thus must always be emitted
+    /// and included ahead of them.
+    pub fn has_extra_header_content(&self) -> bool {
+        !self.preamble.is_empty() || !self.instantiations.is_empty()
+    }
+
+    /// The Rust type which the user has asked us to use in place of the
+    /// given C++ type using `type_mapping!`, if any. Later mappings
+    /// override earlier ones.
+    pub fn get_extra_type_mapping(&self, cpp_name: &str) -> Option<&TypePath> {
+        self.extra_type_mappings
+            .iter()
+            .rev()
+            .find(|tm| tm.cpp_name == cpp_name)
+            .map(|tm| &tm.rust_path)
+    }
+
+    /// Types registered using `custom_deleter!` along with the functions
+    /// which should destroy them.
+    pub fn get_custom_deleters(&self) -> &[CustomDeleter] {
+        &self.custom_deleters
+    }
+
+    /// The type constructed by the given function, if it's been registered
+    /// as that type's factory using `factory!`.
+    pub fn get_factory_type(&self, function: &str) -> Option<&str> {
+        self.factories
+            .iter()
+            .find(|f| f.function == function)
+            .map(|f| f.cpp_name.as_str())
+    }
+
+    /// The reference counting functions for the given C++ smart pointer
+    /// template, if it's been registered using `intrusive_ptr!`.
+    pub fn get_intrusive_ptr(&self, cpp_name: &str) -> Option<&IntrusivePtr> {
+        self.intrusive_ptrs
+            .iter()
+            .rev()
+            .find(|ip| ip.cpp_name == cpp_name)
+    }
+
+    /// Whether the given type has been marked using `transparent!` as
+    /// having exactly the same layout as its single field.
+    pub fn is_transparent_type(&self, cpp_name: &str) -> bool {
+        self.transparent_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given function has been marked using `copy_return!`
+    /// such that a returned reference to a POD type is copied into an
+    /// owned value.
+    pub fn is_copy_return(&self, function: &str) -> bool {
+        self.copy_returns.iter().any(|f| f == function)
+    }
+
+    /// Whether the given function has been marked using `error_code_result!`
+    /// such that a returned `std::error_code` becomes a Rust `Result`.
+    pub fn is_error_code_result(&self, function: &str) -> bool {
+        self.error_code_results.iter().any(|f| f == function)
+    }
+
+    /// Whether the given function has been marked using `nullable_return!`
+    /// such that a returned reference may be null, and so becomes an
+    /// `Option`.
+    pub fn is_nullable_return(&self, function: &str) -> bool {
+        self.nullable_returns.iter().any(|f| f == function)
+    }
+
+    /// Whether the given function has been marked using `owning_return!`
+    /// such that the caller owns the object at the pointer it returns, and
+    /// so it becomes a `UniquePtr`.
+    pub fn is_owning_return(&self, function: &str) -> bool {
+        self.owning_returns.iter().any(|f| f == function)
+    }
+
+    /// Whether the given method has been marked using `force_mut_self!`
+    /// such that it takes `self` mutably even though it's `const` in C++.
+    pub fn is_force_mut_self(&self, function: &str) -> bool {
+        self.force_mut_selfs.iter().any(|f| f == function)
+    }
+
+    /// Whether the given function has been marked using `array_params!`
+    /// such that its sized array parameters become references to arrays.
+    pub fn is_array_params(&self, function: &str) -> bool {
+        self.array_param_functions.iter().any(|f| f == function)
+    }
+
+    /// Whether any function has been marked using `array_params!`, in
+    /// which case bindgen must tell us the sizes of array parameters.
+    pub fn has_array_params(&self) -> bool {
+        !self.array_param_functions.is_empty()
+    }
+
+    /// Whether the given type has been marked using `handle!` such that
+    /// we should generate a Rust wrapper around a `UniquePtr` to it.
+    /// Types given a hook using `pre_drop!` also get a handle.
+    pub fn is_handle_type(&self, cpp_name: &str) -> bool {
+        self.handle_types.iter().any(|t| t == cpp_name)
+            || self.get_pre_drop_hook(cpp_name).is_some()
+    }
+
+    /// If the given type has been marked using `pre_drop!`, the method
+    /// which its handle should call before destroying the object.
+    pub fn get_pre_drop_hook(&self, cpp_name: &str) -> Option<&str> {
+        self.pre_drops
+            .iter()
+            .find(|pd| pd.ty == cpp_name)
+            .map(|pd| pd.hook.as_str())
+    }
+
+    /// Whether the `pre_drop!` hook for the given type was marked `unsafe`,
+    /// such that the handle may call it even if it's an `unsafe` function.
+    pub fn is_pre_drop_hook_unsafe(&self, cpp_name: &str) -> bool {
+        self.pre_drops
+            .iter()
+            .any(|pd| pd.ty == cpp_name && pd.unsafe_hook)
+    }
+
+    /// Whether the given type has been marked using `thread_safe!` such
+    /// that we should assert it's `Send` and `Sync`.
+    pub fn is_thread_safe(&self, cpp_name: &str) -> bool {
+        self.thread_safe_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given function has been marked using `thread_unsafe!`
+    /// such that the type it belongs to must never be `Send` or `Sync`.
+    pub fn is_thread_unsafe(&self, function: &str) -> bool {
+        self.thread_unsafe_functions.iter().any(|f| f == function)
+    }
+
+    /// Whether the given type has been marked using `clonable!` such that
+    /// it should have a method to copy it into a new `UniquePtr`.
+    pub fn is_clonable(&self, cpp_name: &str) -> bool {
+        self.clonable_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given type has been marked using `virtual_trait!` such
+    /// that a trait should be generated mirroring its virtual methods.
+    pub fn has_virtual_trait(&self, cpp_name: &str) -> bool {
+        self.virtual_trait_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given type has been marked using `field_accessors!`
+    /// such that its fields should be reached via getters and setters.
+    pub fn has_field_accessors(&self, cpp_name: &str) -> bool {
+        self.field_accessor_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given POD type has been marked using `serde_pod!` such
+    /// that it should derive `Serialize` and `Deserialize`.
+    pub fn is_serde_pod(&self, cpp_name: &str) -> bool {
+        self.serde_pods.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given type has been marked using `generator!` such
+    /// that its `next()` method should drive an iterator.
+    pub fn is_generator(&self, cpp_name: &str) -> bool {
+        self.generator_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// Whether the given type has been marked using `identity_eq!` such
+    /// that `PartialEq` should compare object addresses.
+    pub fn has_identity_eq(&self, cpp_name: &str) -> bool {
+        self.identity_eq_types.iter().any(|t| t == cpp_name)
+    }
+
+    /// How C++ enums should be represented in Rust.
+    pub fn enum_representation(&self) -> &EnumRepresentation {
+        &self.enum_representation
+    }
+
+    /// Whether generated Rust enums should be marked `#[non_exhaustive]`.
+    pub fn non_exhaustive_enums(&self) -> bool {
+        self.non_exhaustive_enums
+    }
+
+    /// The C++ standard which the bindings may assume.
+    pub fn cpp_std(&self) -> CppStd {
+        self.cpp_std
+    }
+
+    /// Whether the given enum has been marked using `flags!` as
+    /// a set of bitmask values which may be combined.
+    pub fn is_flags_enum(&self, cpp_name: &str) -> bool {
+        self.flags_enums.iter().any(|e| e == cpp_name)
+    }
+
+    /// Types marked using `ordered!` as having C++ comparison operators
+    /// which define a total order.
+    pub fn get_ordered_types(&self) -> &[String] {
+        &self.ordered_types
+    }
+
+    /// Types whose `operator[]` should be exposed using Rust's indexing
+    /// traits, as requested using `indexed!`.
+    pub fn get_indexed_types(&self) -> &[IndexedType] {
+        &self.indexed_types
+    }
+
+    /// Types marked using `bool_conversion!` as having a C++
+    /// `operator bool`.
+    pub fn get_bool_conversion_types(&self) -> &[String] {
+        &self.bool_conversion_types
+    }
+
+    /// Items listed using `prelude!` which should be re-exported from
+    /// a `prelude` mod.
+    pub fn get_prelude_items(&self) -> &[String] {
+        &self.prelude_items
+    }
+
+    /// Whether to wrap the items destined for cxx in a `#[cxx::bridge]`
+    /// mod. If not, they're made available separately so that they can
+    /// be added to a bridge mod managed elsewhere.
+    pub fn emit_bridge_wrapper(&self) -> bool {
+        !self.exclude_bridge_wrapper
+    }
+
+    /// Whether to group the items destined for cxx by C++ namespace,
+    /// such that each namespace can be given its own `#[cxx::bridge]` mod.
+    pub fn split_by_namespace(&self) -> bool {
+        self.split_by_namespace
+    }
+
+    /// Whether, when splitting by namespace, to declare each type just
+    /// once in a common group which the other groups refer to.
+    pub fn shared_type_declarations(&self) -> bool {
+        self.split_by_namespace && self.shared_type_declarations
+    }
+
+    /// Whether to mark the Rust wrapper functions we generate as `#[inline]`.
+    pub fn inline_wrappers(&self) -> bool {
+        !self.exclude_inline_wrappers
+    }
+
+    /// Whether to treat parameterless `const` methods returning POD or
+    /// primitive types as safe, even where the safety policy would
+    /// otherwise make them `unsafe`.
+    pub fn safe_getters(&self) -> bool {
+        self.safe_getters
+    }
+
+    /// Whether to treat every struct which could safely be POD as POD,
+    /// rather than only those requested using `generate_pod!`.
+    pub fn pod_by_default(&self) -> bool {
+        self.pod_by_default
+    }
+
+    /// Whether every function and type in the `cxx::bridge` should carry
+    /// a `cxx_name` attribute, even where it matches the Rust name.
+    pub fn explicit_cxx_names(&self) -> bool {
+        self.explicit_cxx_names
+    }
+
+    /// Whether the generated C++ should assert that each function we wrap
+    /// still has the signature we generated bindings for.
+    pub fn check_signatures(&self) -> bool {
+        self.check_signatures
+    }
+
+    /// Whether to wrap each generated function in a `tracing` span
+    /// recording its arguments.
+    pub fn trace_calls(&self) -> bool {
+        self.trace_calls
+    }
+
+    /// Hand-written items to be added to the `#[cxx::bridge]` mod
+    /// alongside those which autocxx generates.
+    pub fn get_bridge_items(&self) -> &[syn::Item] {
+        &self.bridge_items
+    }
+
+    /// Structs defined in Rust using `shared_struct!`, which C++ may
+    /// use by value.
+    pub fn get_shared_structs(&self) -> &[syn::ItemStruct] {
+        &self.shared_structs
+    }
+
+    fn is_shared_struct(&self, name: &str) -> bool {
+        self.shared_structs.iter().any(|s| s.ident == name)
+    }
+
     fn is_concrete_type(&self, cpp_name: &str) -> bool {
         self.concretes.0.values().any(|val| *val == cpp_name)
     }
@@ -411,6 +1128,7 @@ impl IncludeCppConfig {
         self.rust_types
             .iter()
             .any(|rt| rt.get_final_ident() == &id.to_string())
+            || self.is_shared_struct(&id.to_string())
             || self.is_subclass_holder(&id.to_string())
     }
 
@@ -459,6 +1177,8 @@ impl IncludeCppConfig {
     /// Used in reduction to substitute all included headers with a single
     /// preprocessed replacement.
     pub fn replace_included_headers(&mut self, replacement: &str) {
+        // The preprocessed replacement already contains any preamble.
+        self.preamble.clear();
         self.inclusions.clear();
         self.inclusions.push(replacement.to_string());
     }
@@ -490,7 +1210,7 @@ impl ToTokens for IncludeCppConfig {
 
 #[cfg(test)]
 mod parse_tests {
-    use crate::config::UnsafePolicy;
+    use crate::config::{IncludeCppConfig, UnsafePolicy};
     use syn::parse_quote;
     #[test]
     fn test_safety_unsafe() {
@@ -513,4 +1233,25 @@ mod parse_tests {
         let us: UnsafePolicy = parse_quote! {};
         assert_eq!(us, UnsafePolicy::AllFunctionsUnsafe)
     }
+
+    #[test]
+    fn test_identity_eq_conflicts_with_ordered() {
+        let result: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            identity_eq!("A")
+            ordered!("A")
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_thread_safe_needs_unsafe() {
+        let result: syn::Result<IncludeCppConfig> = syn::parse2(quote::quote! {
+            thread_safe!("A")
+        });
+        assert!(result.is_err());
+        let config: IncludeCppConfig = parse_quote! {
+            thread_safe!(unsafe "A")
+        };
+        assert!(config.is_thread_safe("A"));
+    }
 }
@@ -16,6 +16,7 @@ use syn::parse::ParseStream;
 
 use crate::config::{Allowlist, AllowlistErr};
 use crate::directive_names::{EXTERN_RUST_FUN, EXTERN_RUST_TYPE, SUBCLASS};
+use crate::target_cfg::target_cfg_holds;
 use crate::{AllowlistEntry, IncludeCppConfig};
 use crate::{ParseResult, RustFun, RustPath};
 
@@ -31,11 +32,21 @@ pub(crate) fn get_directives() -> &'static DirectivesMap {
         let mut need_hexathorpe: HashMap<String, Box<dyn Directive>> = HashMap::new();
         need_hexathorpe.insert("include".into(), Box::new(Inclusion));
         let mut need_exclamation: HashMap<String, Box<dyn Directive>> = HashMap::new();
+        need_exclamation.insert(
+            "preamble".into(),
+            Box::new(StringList(
+                |config| &mut config.preamble,
+                |config| &config.preamble,
+            )),
+        );
         need_exclamation.insert("generate".into(), Box::new(Generate(false)));
         need_exclamation.insert("generate_pod".into(), Box::new(Generate(true)));
+        need_exclamation.insert("generate_pod_if".into(), Box::new(GeneratePodIf));
         need_exclamation.insert("generate_ns".into(), Box::new(GenerateNs));
         need_exclamation.insert("generate_all".into(), Box::new(GenerateAll));
         need_exclamation.insert("safety".into(), Box::new(Safety));
+        need_exclamation.insert("enum_representation".into(), Box::new(EnumRepresentation));
+        need_exclamation.insert("cpp_std".into(), Box::new(CppStd));
         need_exclamation.insert(
             "pod".into(),
             Box::new(StringList(
@@ -85,6 +96,76 @@ pub(crate) fn get_directives() -> &'static DirectivesMap {
                 |config| &config.exclude_utilities,
             )),
         );
+        need_exclamation.insert(
+            "exclude_bridge_wrapper".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.exclude_bridge_wrapper,
+                |config| &config.exclude_bridge_wrapper,
+            )),
+        );
+        need_exclamation.insert(
+            "exclude_inline_wrappers".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.exclude_inline_wrappers,
+                |config| &config.exclude_inline_wrappers,
+            )),
+        );
+        need_exclamation.insert(
+            "safe_getters".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.safe_getters,
+                |config| &config.safe_getters,
+            )),
+        );
+        need_exclamation.insert(
+            "pod_by_default".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.pod_by_default,
+                |config| &config.pod_by_default,
+            )),
+        );
+        need_exclamation.insert(
+            "explicit_cxx_names".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.explicit_cxx_names,
+                |config| &config.explicit_cxx_names,
+            )),
+        );
+        need_exclamation.insert(
+            "non_exhaustive_enums".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.non_exhaustive_enums,
+                |config| &config.non_exhaustive_enums,
+            )),
+        );
+        need_exclamation.insert(
+            "check_signatures".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.check_signatures,
+                |config| &config.check_signatures,
+            )),
+        );
+        need_exclamation.insert(
+            "trace_calls".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.trace_calls,
+                |config| &config.trace_calls,
+            )),
+        );
+        need_exclamation.insert(
+            "split_by_namespace".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.split_by_namespace,
+                |config| &config.split_by_namespace,
+            )),
+        );
+        need_exclamation.insert(
+            "shared_type_declarations".into(),
+            Box::new(BoolFlag(
+                |config| &mut config.shared_type_declarations,
+                |config| &config.shared_type_declarations,
+            )),
+        );
         need_exclamation.insert("name".into(), Box::new(ModName));
         need_exclamation.insert("concrete".into(), Box::new(Concrete));
         need_exclamation.insert("rust_type".into(), Box::new(RustType { output: false }));
@@ -99,6 +180,160 @@ pub(crate) fn get_directives() -> &'static DirectivesMap {
             "extern_cpp_opaque_type".into(),
             Box::new(ExternCppType { opaque: true }),
         );
+        need_exclamation.insert("out_param".into(), Box::new(OutParam));
+        need_exclamation.insert("inout_param".into(), Box::new(InOutParam));
+        need_exclamation.insert("owning_out_param".into(), Box::new(OwningOutParam));
+        need_exclamation.insert("context_param".into(), Box::new(ContextParam));
+        need_exclamation.insert("slice_param".into(), Box::new(SliceParam));
+        need_exclamation.insert("slice_return".into(), Box::new(SliceReturn));
+        need_exclamation.insert("pre_drop".into(), Box::new(PreDrop));
+        need_exclamation.insert("container_param".into(), Box::new(ContainerParam));
+        need_exclamation.insert("reference_param".into(), Box::new(ReferenceParam));
+        need_exclamation.insert("const_slice_param".into(), Box::new(ConstSliceParam));
+        need_exclamation.insert("type_link_name".into(), Box::new(TypeLinkName));
+        need_exclamation.insert("status_result".into(), Box::new(StatusResult));
+        need_exclamation.insert("indexed".into(), Box::new(Indexed));
+        need_exclamation.insert("bridge_item".into(), Box::new(BridgeItem));
+        need_exclamation.insert("shared_struct".into(), Box::new(SharedStruct));
+        need_exclamation.insert("instantiate".into(), Box::new(Instantiate));
+        need_exclamation.insert("type_mapping".into(), Box::new(TypeMapping));
+        need_exclamation.insert("intrusive_ptr".into(), Box::new(IntrusivePtr));
+        need_exclamation.insert("custom_deleter".into(), Box::new(CustomDeleter));
+        need_exclamation.insert("factory".into(), Box::new(Factory));
+        need_exclamation.insert(
+            "transparent".into(),
+            Box::new(StringList(
+                |config| &mut config.transparent_types,
+                |config| &config.transparent_types,
+            )),
+        );
+        need_exclamation.insert(
+            "copy_return".into(),
+            Box::new(StringList(
+                |config| &mut config.copy_returns,
+                |config| &config.copy_returns,
+            )),
+        );
+        need_exclamation.insert(
+            "error_code_result".into(),
+            Box::new(StringList(
+                |config| &mut config.error_code_results,
+                |config| &config.error_code_results,
+            )),
+        );
+        need_exclamation.insert(
+            "nullable_return".into(),
+            Box::new(StringList(
+                |config| &mut config.nullable_returns,
+                |config| &config.nullable_returns,
+            )),
+        );
+        need_exclamation.insert(
+            "owning_return".into(),
+            Box::new(StringList(
+                |config| &mut config.owning_returns,
+                |config| &config.owning_returns,
+            )),
+        );
+        need_exclamation.insert(
+            "force_mut_self".into(),
+            Box::new(StringList(
+                |config| &mut config.force_mut_selfs,
+                |config| &config.force_mut_selfs,
+            )),
+        );
+        need_exclamation.insert(
+            "array_params".into(),
+            Box::new(StringList(
+                |config| &mut config.array_param_functions,
+                |config| &config.array_param_functions,
+            )),
+        );
+        need_exclamation.insert(
+            "handle".into(),
+            Box::new(StringList(
+                |config| &mut config.handle_types,
+                |config| &config.handle_types,
+            )),
+        );
+        need_exclamation.insert("thread_safe".into(), Box::new(ThreadSafe));
+        need_exclamation.insert(
+            "thread_unsafe".into(),
+            Box::new(StringList(
+                |config| &mut config.thread_unsafe_functions,
+                |config| &config.thread_unsafe_functions,
+            )),
+        );
+        need_exclamation.insert(
+            "clonable".into(),
+            Box::new(StringList(
+                |config| &mut config.clonable_types,
+                |config| &config.clonable_types,
+            )),
+        );
+        need_exclamation.insert(
+            "virtual_trait".into(),
+            Box::new(StringList(
+                |config| &mut config.virtual_trait_types,
+                |config| &config.virtual_trait_types,
+            )),
+        );
+        need_exclamation.insert(
+            "field_accessors".into(),
+            Box::new(StringList(
+                |config| &mut config.field_accessor_types,
+                |config| &config.field_accessor_types,
+            )),
+        );
+        need_exclamation.insert(
+            "prelude".into(),
+            Box::new(StringList(
+                |config| &mut config.prelude_items,
+                |config| &config.prelude_items,
+            )),
+        );
+        need_exclamation.insert(
+            "serde_pod".into(),
+            Box::new(StringList(
+                |config| &mut config.serde_pods,
+                |config| &config.serde_pods,
+            )),
+        );
+        need_exclamation.insert(
+            "identity_eq".into(),
+            Box::new(StringList(
+                |config| &mut config.identity_eq_types,
+                |config| &config.identity_eq_types,
+            )),
+        );
+        need_exclamation.insert(
+            "generator".into(),
+            Box::new(StringList(
+                |config| &mut config.generator_types,
+                |config| &config.generator_types,
+            )),
+        );
+        need_exclamation.insert(
+            "ordered".into(),
+            Box::new(StringList(
+                |config| &mut config.ordered_types,
+                |config| &config.ordered_types,
+            )),
+        );
+        need_exclamation.insert(
+            "bool_conversion".into(),
+            Box::new(StringList(
+                |config| &mut config.bool_conversion_types,
+                |config| &config.bool_conversion_types,
+            )),
+        );
+        need_exclamation.insert(
+            "flags".into(),
+            Box::new(StringList(
+                |config| &mut config.flags_enums,
+                |config| &config.flags_enums,
+            )),
+        );
 
         DirectivesMap {
             need_hexathorpe,
@@ -184,6 +419,50 @@ impl Directive for Generate {
     }
 }
 
+/// Directive for `generate_pod_if!`, which generates a type as POD only if
+/// the given `cfg` predicate holds for the target.
+struct GeneratePodIf;
+
+impl Directive for GeneratePodIf {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        span: &Span,
+    ) -> ParseResult<()> {
+        let ty: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let condition: syn::Meta = args.parse()?;
+        let pod = target_cfg_holds(&condition)?;
+        config
+            .allowlist
+            .push(AllowlistEntry::Item(ty.value()))
+            .map_err(|e| allowlist_err_to_syn_err(e, span))?;
+        if pod {
+            config.pod_requests.push(ty.value());
+        }
+        config.conditional_pods.push(crate::config::ConditionalPod {
+            ty: ty.value(),
+            condition,
+            pod,
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.conditional_pods.iter().map(|cp| {
+            let ty = &cp.ty;
+            let condition = &cp.condition;
+            quote! {
+                #ty,#condition
+            }
+        }))
+    }
+}
+
 struct GenerateNs;
 
 impl Directive for GenerateNs {
@@ -274,6 +553,56 @@ impl Directive for Safety {
     }
 }
 
+struct EnumRepresentation;
+
+impl Directive for EnumRepresentation {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        config.enum_representation = args.parse()?;
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        let representation = &config.enum_representation;
+        match representation {
+            crate::EnumRepresentation::RustEnum => Box::new(std::iter::empty()),
+            _ => Box::new(std::iter::once(representation.to_token_stream())),
+        }
+    }
+}
+
+struct CppStd;
+
+impl Directive for CppStd {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        config.cpp_std = args.parse()?;
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        let cpp_std = &config.cpp_std;
+        match cpp_std {
+            crate::CppStd::Cpp20 => Box::new(std::iter::empty()),
+            _ => Box::new(std::iter::once(cpp_std.to_token_stream())),
+        }
+    }
+}
+
 fn allowlist_err_to_syn_err(err: AllowlistErr, span: &Span) -> syn::Error {
     syn::Error::new(*span, format!("{}", err))
 }
@@ -536,3 +865,695 @@ impl Directive for ExternCppType {
         )
     }
 }
+
+struct OutParam;
+
+impl Directive for OutParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config.out_params.push(crate::config::OutParam {
+            function: function.value(),
+            param: param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.out_params.iter().map(|op| {
+            let function = &op.function;
+            let param = &op.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct InOutParam;
+
+impl Directive for InOutParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config.inout_params.push(crate::config::OutParam {
+            function: function.value(),
+            param: param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.inout_params.iter().map(|op| {
+            let function = &op.function;
+            let param = &op.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct OwningOutParam;
+
+impl Directive for OwningOutParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config.owning_out_params.push(crate::config::OutParam {
+            function: function.value(),
+            param: param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.owning_out_params.iter().map(|op| {
+            let function = &op.function;
+            let param = &op.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct ContextParam;
+
+impl Directive for ContextParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config.context_params.push(crate::config::ContextParam {
+            function: function.value(),
+            param: param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.context_params.iter().map(|cp| {
+            let function = &cp.function;
+            let param = &cp.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct SliceParam;
+
+impl Directive for SliceParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let len_param: syn::LitStr = args.parse()?;
+        config.slice_params.push(crate::config::SliceParam {
+            function: function.value(),
+            param: param.value(),
+            len_param: len_param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.slice_params.iter().map(|sp| {
+            let function = &sp.function;
+            let param = &sp.param;
+            let len_param = &sp.len_param;
+            quote! {
+                #function,#param,#len_param
+            }
+        }))
+    }
+}
+
+struct SliceReturn;
+
+impl Directive for SliceReturn {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let len_function: syn::LitStr = args.parse()?;
+        config.slice_returns.push(crate::config::SliceReturn {
+            function: function.value(),
+            len_function: len_function.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.slice_returns.iter().map(|sr| {
+            let function = &sr.function;
+            let len_function = &sr.len_function;
+            quote! {
+                #function,#len_function
+            }
+        }))
+    }
+}
+
+struct ThreadSafe;
+
+impl Directive for ThreadSafe {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        // We'll implement Send and Sync on the user's say-so, so make them
+        // say it in a way which shows up when auditing for unsafety.
+        if args.parse::<Option<syn::Token![unsafe]>>()?.is_none() {
+            return Err(args.error(
+                "thread_safe! makes a promise which autocxx can't check, so must be written thread_safe!(unsafe \"Type\")",
+            ));
+        }
+        let ty: syn::LitStr = args.parse()?;
+        config.thread_safe_types.push(ty.value());
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.thread_safe_types.iter().map(|ty| {
+            quote! {
+                unsafe #ty
+            }
+        }))
+    }
+}
+
+struct PreDrop;
+
+impl Directive for PreDrop {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let unsafe_hook = args.parse::<Option<syn::Token![unsafe]>>()?.is_some();
+        let ty: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let hook: syn::LitStr = args.parse()?;
+        config.pre_drops.push(crate::config::PreDrop {
+            ty: ty.value(),
+            hook: hook.value(),
+            unsafe_hook,
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.pre_drops.iter().map(|pd| {
+            let unsafety = pd.unsafe_hook.then(|| quote! { unsafe });
+            let ty = &pd.ty;
+            let hook = &pd.hook;
+            quote! {
+                #unsafety #ty,#hook
+            }
+        }))
+    }
+}
+
+struct ContainerParam;
+
+impl Directive for ContainerParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config.container_params.push(crate::config::ContainerParam {
+            function: function.value(),
+            param: param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.container_params.iter().map(|cp| {
+            let function = &cp.function;
+            let param = &cp.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct ReferenceParam;
+
+impl Directive for ReferenceParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config.reference_params.push(crate::config::ReferenceParam {
+            function: function.value(),
+            param: param.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.reference_params.iter().map(|rp| {
+            let function = &rp.function;
+            let param = &rp.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct ConstSliceParam;
+
+impl Directive for ConstSliceParam {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let function: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let param: syn::LitStr = args.parse()?;
+        config
+            .const_slice_params
+            .push(crate::config::ConstSliceParam {
+                function: function.value(),
+                param: param.value(),
+            });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.const_slice_params.iter().map(|cp| {
+            let function = &cp.function;
+            let param = &cp.param;
+            quote! {
+                #function,#param
+            }
+        }))
+    }
+}
+
+struct TypeLinkName;
+
+impl Directive for TypeLinkName {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let cpp_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let link_name: syn::LitStr = args.parse()?;
+        config.type_link_names.push(crate::config::TypeLinkName {
+            cpp_name: cpp_name.value(),
+            link_name: link_name.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.type_link_names.iter().map(|tln| {
+            let cpp_name = &tln.cpp_name;
+            let link_name = &tln.link_name;
+            quote! {
+                #cpp_name,#link_name
+            }
+        }))
+    }
+}
+
+struct StatusResult;
+
+impl Directive for StatusResult {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let enum_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let success_value: syn::LitStr = args.parse()?;
+        config.status_results.push(crate::config::StatusResult {
+            enum_name: enum_name.value(),
+            success_value: success_value.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.status_results.iter().map(|sr| {
+            let enum_name = &sr.enum_name;
+            let success_value = &sr.success_value;
+            quote! {
+                #enum_name,#success_value
+            }
+        }))
+    }
+}
+
+struct Indexed;
+
+impl Directive for Indexed {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let cpp_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let element: syn::LitStr = args.parse()?;
+        config.indexed_types.push(crate::config::IndexedType {
+            cpp_name: cpp_name.value(),
+            element: element.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.indexed_types.iter().map(|it| {
+            let cpp_name = &it.cpp_name;
+            let element = &it.element;
+            quote! {
+                #cpp_name,#element
+            }
+        }))
+    }
+}
+
+struct BridgeItem;
+
+impl Directive for BridgeItem {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        config.bridge_items.push(args.parse()?);
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(
+            config
+                .bridge_items
+                .iter()
+                .map(|item| item.to_token_stream()),
+        )
+    }
+}
+
+struct SharedStruct;
+
+impl Directive for SharedStruct {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        config.shared_structs.push(args.parse()?);
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(
+            config
+                .shared_structs
+                .iter()
+                .map(|item| item.to_token_stream()),
+        )
+    }
+}
+
+struct TypeMapping;
+
+impl Directive for TypeMapping {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let cpp_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let rust_path: syn::TypePath = args.parse()?;
+        config.extra_type_mappings.push(crate::config::TypeMapping {
+            cpp_name: cpp_name.value(),
+            rust_path,
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.extra_type_mappings.iter().map(|tm| {
+            let cpp_name = &tm.cpp_name;
+            let rust_path = &tm.rust_path;
+            quote! {
+                #cpp_name,#rust_path
+            }
+        }))
+    }
+}
+
+struct IntrusivePtr;
+
+impl Directive for IntrusivePtr {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let cpp_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let add_ref: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let release: syn::LitStr = args.parse()?;
+        config.intrusive_ptrs.push(crate::config::IntrusivePtr {
+            cpp_name: cpp_name.value(),
+            add_ref: add_ref.value(),
+            release: release.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.intrusive_ptrs.iter().map(|ip| {
+            let cpp_name = &ip.cpp_name;
+            let add_ref = &ip.add_ref;
+            let release = &ip.release;
+            quote! {
+                #cpp_name,#add_ref,#release
+            }
+        }))
+    }
+}
+
+struct CustomDeleter;
+
+impl Directive for CustomDeleter {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let cpp_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let deleter: syn::LitStr = args.parse()?;
+        config.custom_deleters.push(crate::config::CustomDeleter {
+            cpp_name: cpp_name.value(),
+            deleter: deleter.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.custom_deleters.iter().map(|cd| {
+            let cpp_name = &cd.cpp_name;
+            let deleter = &cd.deleter;
+            quote! {
+                #cpp_name,#deleter
+            }
+        }))
+    }
+}
+
+struct Factory;
+
+impl Directive for Factory {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let cpp_name: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let function: syn::LitStr = args.parse()?;
+        config.factories.push(crate::config::Factory {
+            cpp_name: cpp_name.value(),
+            function: function.value(),
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.factories.iter().map(|f| {
+            let cpp_name = &f.cpp_name;
+            let function = &f.function;
+            quote! {
+                #cpp_name,#function
+            }
+        }))
+    }
+}
+
+struct Instantiate;
+
+impl Directive for Instantiate {
+    fn parse(
+        &self,
+        args: ParseStream,
+        config: &mut IncludeCppConfig,
+        _ident_span: &Span,
+    ) -> ParseResult<()> {
+        let template: syn::LitStr = args.parse()?;
+        args.parse::<syn::token::Comma>()?;
+        let name: syn::Ident = args.parse()?;
+        config.instantiations.push(crate::config::FnInstantiation {
+            template: template.value(),
+            name,
+        });
+        Ok(())
+    }
+
+    fn output<'a>(
+        &self,
+        config: &'a IncludeCppConfig,
+    ) -> Box<dyn Iterator<Item = TokenStream> + 'a> {
+        Box::new(config.instantiations.iter().map(|inst| {
+            let template = &inst.template;
+            let name = &inst.name;
+            quote! {
+                #template,#name
+            }
+        }))
+    }
+}
@@ -14,9 +14,13 @@ pub mod file_locations;
 mod multi_bindings;
 mod path;
 mod subclass_attrs;
+mod target_cfg;
 
 pub use config::{
-    AllowlistEntry, ExternCppType, IncludeCppConfig, RustFun, Subclass, UnsafePolicy,
+    AllowlistEntry, ConditionalPod, ConstSliceParam, ContainerParam, ContextParam, CppStd,
+    CustomDeleter, EnumRepresentation, ExternCppType, Factory, FnInstantiation, IncludeCppConfig,
+    IndexedType, IntrusivePtr, OutParam, PreDrop, ReferenceParam, RustFun, SliceParam, SliceReturn,
+    StatusResult, Subclass, TypeLinkName, TypeMapping, UnsafePolicy,
 };
 use file_locations::FileLocationStrategy;
 pub use multi_bindings::{MultiBindings, MultiBindingsErr};
@@ -0,0 +1,135 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use syn::{Lit, Meta, NestedMeta, Result as ParseResult};
+
+/// Evaluates a `cfg` predicate, such as `target_os = "linux"` or
+/// `any(unix, target_pointer_width = "32")`, for the target we're
+/// generating bindings for.
+///
+/// Within a build script, cargo describes that target using `CARGO_CFG_*`
+/// environment variables. Elsewhere, for instance when running
+/// `autocxx-gen` by hand, we assume the bindings are for the host.
+pub(crate) fn target_cfg_holds(predicate: &Meta) -> ParseResult<bool> {
+    if std::env::var_os("CARGO_CFG_TARGET_OS").is_some() {
+        cfg_holds(predicate, &|key| {
+            std::env::var(format!("CARGO_CFG_{}", key.to_uppercase())).ok()
+        })
+    } else {
+        cfg_holds(predicate, &host_cfg)
+    }
+}
+
+fn host_cfg(key: &str) -> Option<String> {
+    match key {
+        "target_os" => Some(std::env::consts::OS.into()),
+        "target_arch" => Some(std::env::consts::ARCH.into()),
+        "target_family" => Some(std::env::consts::FAMILY.into()),
+        "target_pointer_width" => Some(usize::BITS.to_string()),
+        "target_endian" if cfg!(target_endian = "little") => Some("little".into()),
+        "target_endian" => Some("big".into()),
+        "unix" if cfg!(unix) => Some(String::new()),
+        "windows" if cfg!(windows) => Some(String::new()),
+        _ => None,
+    }
+}
+
+/// Evaluates a `cfg` predicate given a way to look up the value of each
+/// configuration option, which may be a comma-separated list, as cargo
+/// uses for `target_family` and `target_feature`.
+fn cfg_holds(predicate: &Meta, lookup: &dyn Fn(&str) -> Option<String>) -> ParseResult<bool> {
+    let key = |path: &syn::Path| {
+        path.get_ident()
+            .map(|id| id.to_string())
+            .ok_or_else(|| syn::Error::new_spanned(path, "expected a cfg option name"))
+    };
+    match predicate {
+        Meta::Path(path) => Ok(lookup(&key(path)?).is_some()),
+        Meta::NameValue(nv) => match &nv.lit {
+            Lit::Str(value) => Ok(lookup(&key(&nv.path)?)
+                .map(|values| values.split(',').any(|v| v == value.value()))
+                .unwrap_or_default()),
+            _ => Err(syn::Error::new_spanned(
+                &nv.lit,
+                "expected a string literal as the value of a cfg option",
+            )),
+        },
+        Meta::List(list) => {
+            let nested = list
+                .nested
+                .iter()
+                .map(|nested| match nested {
+                    NestedMeta::Meta(meta) => cfg_holds(meta, lookup),
+                    NestedMeta::Lit(lit) => {
+                        Err(syn::Error::new_spanned(lit, "expected a cfg predicate"))
+                    }
+                })
+                .collect::<ParseResult<Vec<bool>>>()?;
+            match key(&list.path)?.as_str() {
+                "all" => Ok(nested.into_iter().all(|b| b)),
+                "any" => Ok(nested.into_iter().any(|b| b)),
+                "not" if nested.len() == 1 => Ok(!nested[0]),
+                "not" => Err(syn::Error::new_spanned(
+                    list,
+                    "not() takes exactly one cfg predicate",
+                )),
+                _ => Err(syn::Error::new_spanned(
+                    &list.path,
+                    "expected all, any or not",
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cfg_holds;
+    use syn::{parse_quote, Meta};
+
+    fn linux(key: &str) -> Option<String> {
+        match key {
+            "target_os" => Some("linux".into()),
+            "target_family" => Some("unix".into()),
+            "target_pointer_width" => Some("64".into()),
+            "unix" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn windows(key: &str) -> Option<String> {
+        match key {
+            "target_os" => Some("windows".into()),
+            "target_family" => Some("windows".into()),
+            "target_pointer_width" => Some("64".into()),
+            "windows" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_target_cfg() {
+        let predicates: Vec<(Meta, bool, bool)> = vec![
+            (parse_quote! { target_os = "linux" }, true, false),
+            (parse_quote! { unix }, true, false),
+            (parse_quote! { not(windows) }, true, false),
+            (parse_quote! { target_family = "windows" }, false, true),
+            (
+                parse_quote! { all(target_pointer_width = "64", any(unix, windows)) },
+                true,
+                true,
+            ),
+        ];
+        for (predicate, on_linux, on_windows) in predicates {
+            assert_eq!(cfg_holds(&predicate, &linux).unwrap(), on_linux);
+            assert_eq!(cfg_holds(&predicate, &windows).unwrap(), on_windows);
+        }
+        let bad: Meta = parse_quote! { maybe(unix) };
+        assert!(cfg_holds(&bad, &linux).is_err());
+    }
+}
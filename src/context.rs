@@ -0,0 +1,59 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{any::Any, fmt::Debug};
+
+use crate::c_void;
+
+/// An arbitrary Rust value which can be handed to C++ as an opaque `void*`
+/// "user data" or "context" pointer, and recovered when C++ hands that
+/// pointer back. Parameters marked with
+/// [`context_param`](crate::context_param) take one of these.
+pub struct Context(Box<dyn Any>);
+
+impl Context {
+    /// Boxes up the given value so it can be passed to C++.
+    pub fn new<T: 'static>(val: T) -> Self {
+        Self(Box::new(val))
+    }
+
+    /// Gives up ownership of this context, returning a pointer suitable
+    /// for passing to C++. The context is leaked unless the pointer is
+    /// later passed to [`Context::from_raw`].
+    pub fn into_raw(self) -> *mut c_void {
+        Box::into_raw(Box::new(self.0)) as *mut c_void
+    }
+
+    /// Takes back ownership of a context which was previously passed to
+    /// C++.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`Context::into_raw`], and must not
+    /// already have been passed to this function.
+    pub unsafe fn from_raw(ptr: *mut c_void) -> Self {
+        Self(*Box::from_raw(ptr as *mut Box<dyn Any>))
+    }
+
+    /// Borrows the value within, if it's of type `T`.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    /// Extracts the value within, if it's of type `T`. Otherwise, returns
+    /// this context unchanged.
+    pub fn downcast<T: 'static>(self) -> Result<Box<T>, Self> {
+        self.0.downcast().map_err(Self)
+    }
+}
+
+impl Debug for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{any::Any, ops::Deref};
+
+use crate::c_void;
+
+/// A table of C function pointers, as built by a generated `TypeBuilder`,
+/// along with any Rust closures which those function pointers call. The
+/// closures are reached through the table's context pointer, so they live
+/// as long as this does. Dereferences to the table itself.
+pub struct FunctionTable<T> {
+    table: T,
+    _closures: Box<FunctionTableClosures>,
+}
+
+impl<T> FunctionTable<T> {
+    #[doc(hidden)]
+    pub fn new(
+        mut table: T,
+        closures: FunctionTableClosures,
+        set_context: impl FnOnce(&mut T, *mut c_void),
+    ) -> Self {
+        let closures = Box::new(closures);
+        set_context(
+            &mut table,
+            closures.as_ref() as *const FunctionTableClosures as *mut c_void,
+        );
+        Self {
+            table,
+            _closures: closures,
+        }
+    }
+}
+
+impl<T> Deref for FunctionTable<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.table
+    }
+}
+
+/// The closures installed in a [`FunctionTable`], indexed by field.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct FunctionTableClosures(Vec<Option<Box<dyn Any + Send + Sync>>>);
+
+impl FunctionTableClosures {
+    pub fn set<F: Any + Send + Sync>(&mut self, idx: usize, f: F) {
+        if self.0.len() <= idx {
+            self.0.resize_with(idx + 1, || None);
+        }
+        self.0[idx] = Some(Box::new(f));
+    }
+
+    /// # Safety
+    ///
+    /// `context` must be the context pointer of a live [`FunctionTable`].
+    pub unsafe fn get<'a, F: Any>(context: *mut c_void, idx: usize) -> &'a F {
+        let closures = &*(context as *const FunctionTableClosures);
+        closures.0[idx]
+            .as_ref()
+            .and_then(|f| f.downcast_ref())
+            .expect("function table closure missing")
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::pin::Pin;
+
+/// A C++ type marked with [`generator!`](crate::generator!), whose `next()`
+/// method returns successive items as a `std::optional` until it's
+/// exhausted. Implemented by the generated bindings.
+pub trait CppGenerator {
+    /// The type of each item.
+    type Item;
+
+    /// Calls the C++ `next()` method, returning `None` once there are no
+    /// more items.
+    fn next_item(self: Pin<&mut Self>) -> Option<Self::Item>;
+
+    /// Returns an iterator which repeatedly calls `next()` until there are
+    /// no more items.
+    fn iter(self: Pin<&mut Self>) -> CppGeneratorIter<'_, Self>
+    where
+        Self: Sized,
+    {
+        CppGeneratorIter(self)
+    }
+}
+
+/// An iterator over the items of a [`CppGenerator`].
+pub struct CppGeneratorIter<'a, G: CppGenerator>(Pin<&'a mut G>);
+
+impl<G: CppGenerator> Iterator for CppGeneratorIter<'_, G> {
+    type Item = G::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut().next_item()
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2022 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::{fmt::Debug, marker::PhantomData, ops::Deref, pin::Pin};
+
+/// A C++ type whose lifetime is governed by a reference count stored
+/// within the object itself. autocxx implements this for each type it
+/// finds within a smart pointer registered using
+/// [`intrusive_ptr`](crate::intrusive_ptr).
+///
+/// # Safety
+///
+/// Implementers must ensure that `add_ref` and `release` respectively
+/// increment and decrement the reference count of a valid object, and that
+/// `release` destroys the object once nothing refers to it.
+pub unsafe trait IntrusivePtrTarget {
+    /// Increment the reference count of the object.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid object.
+    unsafe fn add_ref(ptr: *mut Self);
+
+    /// Decrement the reference count of the object, possibly destroying it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid object, and the caller must own one of
+    /// its references.
+    unsafe fn release(ptr: *mut Self);
+}
+
+/// An owning pointer to a reference-counted C++ object, equivalent to a
+/// C++ intrusive smart pointer such as `RefPtr<T>`. Cloning this adds a
+/// reference; dropping it releases one. Like [`cxx::UniquePtr`], this may
+/// be null.
+pub struct IntrusivePtr<T: IntrusivePtrTarget> {
+    ptr: *mut T,
+    phantom: PhantomData<T>,
+}
+
+impl<T: IntrusivePtrTarget> IntrusivePtr<T> {
+    /// Makes a new null pointer.
+    pub fn null() -> Self {
+        Self {
+            ptr: std::ptr::null_mut(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Takes ownership of one reference to the object at `ptr`, which may
+    /// be null.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or point to a valid object, and the caller must
+    /// own a reference to it which it hands over to the new pointer.
+    pub unsafe fn from_raw(ptr: *mut T) -> Self {
+        Self {
+            ptr,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Relinquishes ownership of this pointer's reference, returning the
+    /// raw pointer. The caller becomes responsible for releasing it.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        std::mem::forget(self);
+        ptr
+    }
+
+    /// Whether this pointer is null.
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// Returns a reference to the object, or `None` if this is null.
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns a pinned mutable reference to the object, or `None` if this
+    /// is null.
+    ///
+    /// # Safety
+    ///
+    /// Every clone of this pointer refers to the same object, so the caller
+    /// must ensure that no other reference to the object, whether obtained
+    /// through this pointer, another `IntrusivePtr` or C++, is used while
+    /// the returned reference is alive.
+    pub unsafe fn as_mut(&mut self) -> Option<Pin<&mut T>> {
+        self.ptr.as_mut().map(|r| Pin::new_unchecked(r))
+    }
+
+    /// Returns a pinned mutable reference to the object.
+    ///
+    /// # Safety
+    ///
+    /// As for [`as_mut`](Self::as_mut): no other reference to the object may
+    /// be used while the returned reference is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this pointer is null.
+    pub unsafe fn pin_mut(&mut self) -> Pin<&mut T> {
+        self.as_mut()
+            .expect("called pin_mut on a null IntrusivePtr")
+    }
+}
+
+impl<T: IntrusivePtrTarget> Clone for IntrusivePtr<T> {
+    fn clone(&self) -> Self {
+        if !self.ptr.is_null() {
+            unsafe { T::add_ref(self.ptr) };
+        }
+        Self {
+            ptr: self.ptr,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: IntrusivePtrTarget> Drop for IntrusivePtr<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { T::release(self.ptr) };
+        }
+    }
+}
+
+impl<T: IntrusivePtrTarget> Deref for IntrusivePtr<T> {
+    type Target = T;
+
+    /// # Panics
+    ///
+    /// Panics if this pointer is null.
+    fn deref(&self) -> &T {
+        self.as_ref().expect("called deref on a null IntrusivePtr")
+    }
+}
+
+impl<T: IntrusivePtrTarget> Debug for IntrusivePtr<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("IntrusivePtr").field(&self.ptr).finish()
+    }
+}
@@ -14,6 +14,10 @@
 // do anything - all the magic is handled entirely by
 // autocxx_macro::include_cpp_impl.
 
+mod context;
+mod function_table;
+mod generator;
+mod intrusive_ptr;
 mod reference_wrapper;
 mod rvalue_param;
 pub mod subclass;
@@ -135,224 +139,1235 @@ macro_rules! generate {
 /// destructor - you'll encounter a compile error otherwise.
 /// If your type doesn't match that description, use [generate]
 /// instead, and own the type using [UniquePtr][cxx::UniquePtr].
+///
+/// If the type is a table of C function pointers, such as a struct of
+/// callbacks which a C library calls, a `TypeBuilder` is also generated
+/// (unless that name is taken), whose `set_x` methods fill each function
+/// pointer `x` from a Rust `extern "C" fn`. If the table has a single
+/// `void*` context field, which C passes back as the first parameter of
+/// its functions, `bind_x` methods also accept closures, and `build`
+/// returns a [`FunctionTable`] which keeps them alive.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_pod {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate as "plain old data", as [generate_pod] does, but only when
+/// building for targets matching the given `cfg` predicate; elsewhere the
+/// type is opaque, as with [generate]. Use this for types whose members
+/// make them POD on some platforms but not others, for example:
+///
+/// ```ignore
+/// generate_pod_if!("Timestamp", target_os = "linux")
+/// ```
+///
+/// The predicate supports `all`, `any`, `not` and the `target_*`, `unix`
+/// and `windows` options. Within a build script it's evaluated for the
+/// target being built; elsewhere, such as when running `autocxx-gen`, for
+/// the host. The bindings refuse to compile for a target which would have
+/// made the other choice.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generate_pod_if {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate Rust bindings for all C++ types and functions
+/// in a given namespace.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+/// See also [generate].
+#[macro_export]
+macro_rules! generate_ns {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate Rust bindings for all C++ types and functions
+/// found. Highly experimental and not recommended.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+/// See also [generate].
+#[macro_export]
+macro_rules! generate_all {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generate as "plain old data". For use with [generate_all]
+/// and similarly experimental.
+#[macro_export]
+macro_rules! pod {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Skip the normal generation of a `make_string` function
+/// and other utilities which we might generate normally.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! exclude_utilities {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Entirely block some type from appearing in the generated
+/// code. This can be useful if there is a type which is not
+/// understood by bindgen or autocxx, and incorrect code is
+/// otherwise generated.
+/// This is 'greedy' in the sense that any functions/methods
+/// which take or return such a type will _also_ be blocked.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! block {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Avoid generating implicit constructors for this type.
+/// The rules for when to generate C++ implicit constructors
+/// are complex, and if autocxx gets it wrong, you can block
+/// such constructors using this.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! block_constructors {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// The name of the mod to be generated with the FFI code.
+/// The default is `ffi`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! name {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// A concrete type to make, for example
+/// `concrete!("Container<Contents>")`.
+/// All types msut already be on the allowlist by having used
+/// `generate!` or similar.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! concrete {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Specifies a global safety policy for functions generated
+/// from these headers. By default (without such a `safety!`
+/// directive) all such functions are marked as `unsafe` and
+/// therefore can only be called within an `unsafe {}` block
+/// or some `unsafe` function which you create.
+///
+/// Alternatively, by specifying a `safety!` block you can
+/// declare that most generated functions are in fact safe.
+/// Specifically, you'd specify:
+/// `safety!(unsafe)`
+/// or
+/// `safety!(unsafe_ffi)`
+/// These two options are functionally identical. If you're
+/// unsure, simply use `unsafe`. The reason for the
+/// latter option is if you have code review policies which
+/// might want to give a different level of scrutiny to
+/// C++ interop as opposed to other types of unsafe Rust code.
+/// Maybe in your organization, C++ interop is less scary than
+/// a low-level Rust data structure using pointer manipulation.
+/// Or maybe it's more scary. Either way, using `unsafe` for
+/// the data structure and using `unsafe_ffi` for the C++
+/// interop allows you to apply different linting tools and
+/// policies to the different options.
+///
+/// Irrespective, C++ code is of course unsafe. It's worth
+/// noting that use of C++ can cause unexpected unsafety at
+/// a distance in faraway Rust code. As with any use of the
+/// `unsafe` keyword in Rust, *you the human* are declaring
+/// that you've analyzed all possible ways that the code
+/// can be used and you are guaranteeing to the compiler that
+/// no badness can occur. Good luck.
+///
+/// Generated C++ APIs which use raw pointers remain `unsafe`
+/// no matter what policy you choose.
+///
+/// There's an additional possible experimental safety
+/// policy available here:
+/// `safety!(unsafe_references_wrapped)`
+/// This policy treats C++ references as scary and requires
+/// them to be wrapped in a `CppRef` type. This `CppRef`
+/// type is implemented within the generated bindings but
+/// follows the contract of [`CppRef`].
+#[macro_export]
+macro_rules! safety {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Whether to avoid generating [`cxx::UniquePtr`] and [`cxx::Vector`]
+/// implementations. This is primarily useful for reducing test cases and
+/// shouldn't be used in normal operation.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! exclude_impls {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type is not to be generated by autocxx in this case,
+/// but instead should refer to some pre-existing Rust type.
+/// Note that the size and alignment of this type *must* be correct.
+/// If you wish for the type to be POD, you can use a `pod!` directive too.
+///
+/// The syntax is:
+/// `extern_cpp_type!("CppNameGoesHere", path::to::rust::type)`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! extern_cpp_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type is not to be generated by autocxx in this case,
+/// but instead should refer to some pre-existing Rust type. Unlike
+/// `extern_cpp_type!`, there's no need for the size and alignment of this
+/// type to be correct.
+///
+/// The syntax is:
+/// `extern_cpp_opaque_type!("CppNameGoesHere", path::to::rust::type)`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! extern_cpp_opaque_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Deprecated - use [`extern_rust_type`] instead.
+#[macro_export]
+#[deprecated]
+macro_rules! rust_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// See [`extern_rust::extern_rust_type`].
+#[macro_export]
+macro_rules! extern_rust_type {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// See [`subclass::subclass`].
+#[macro_export]
+macro_rules! subclass {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type can definitely be instantiated. This has effect
+/// only in a very specific case:
+/// * the type is a typedef to something else
+/// * the 'something else' can't be fully inspected by autocxx, possibly
+///   becaue it relies on dependent qualified types or some other template
+///   arrangement that bindgen cannot fully understand.
+/// In such circumstances, autocxx normally has to err on the side of caution
+/// and assume that some type within the 'something else' is itself a forward
+/// declaration. That means, the opaque typedef won't be storable within
+/// a [`cxx::UniquePtr`]. If you know that no forward declarations are involved,
+/// you can declare the typedef type is instantiable and then you'll be able to
+/// own it within Rust.
+///
+/// The syntax is:
+/// `instantiable!("CppNameGoesHere")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! instantiable {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Lines of C++ to be emitted before any of the `#include`s, for
+/// example forward declarations or macro definitions needed to work
+/// around header ordering problems. The preamble is seen by bindgen
+/// and by the C++ which autocxx and cxx generate.
+///
+/// The syntax is:
+/// `preamble!("#define SOME_MACRO 1")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! preamble {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a pointer parameter of a C++ function is used to pass
+/// back an extra result, rather than as an input. The generated Rust
+/// function won't take this parameter; instead it allocates space for it
+/// and returns it. The parameter must point to a primitive or POD type,
+/// since C++ is handed uninitialized space to write into. If the C++
+/// function returns `bool`, that's assumed to indicate success and the
+/// result is an `Option`; otherwise a tuple of the original return value
+/// and the out-parameter is returned.
+///
+/// Non-const reference parameters, such as `int&`, may also be marked
+/// in this way, so long as they refer to a primitive or POD type.
+///
+/// The syntax is:
+/// `out_param!("function_name", "parameter_name")`
+///
+/// Methods are named along with their class, as in `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! out_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a pointer or non-const reference parameter of a C++
+/// function is used both to pass in a value and to pass back an updated
+/// one, as in `void increment(int& x)`. The generated Rust function takes
+/// this parameter by value, and returns its updated value in the same
+/// way as for [out_param]. The parameter must be a primitive or POD type.
+///
+/// The syntax is:
+/// `inout_param!("function_name", "parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! inout_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ function stores a pointer to a new object into a
+/// non-const reference-to-pointer parameter, as in `void make(Foo*& out)`,
+/// and that the caller then owns that object. The generated Rust function
+/// doesn't take this parameter, but returns a [`cxx::UniquePtr`] owning
+/// the object instead, which is null if C++ stored nothing. The object
+/// must have been allocated using `new`.
+///
+/// Reference-to-pointer parameters which aren't marked this way may be
+/// borrowed, or used to pass a pointer in as well as out, so they're left
+/// as raw pointers to pointers and their functions are `unsafe`.
+///
+/// The syntax is:
+/// `owning_out_param!("function_name", "parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! owning_out_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a `void*` parameter of a C++ function is a "user data"
+/// or "context" pointer, which C++ holds on to and later hands back, for
+/// instance when calling a callback. The generated Rust function takes a
+/// [`Context`] in place of this parameter, boxing up an arbitrary Rust
+/// value. When C++ later hands back the pointer, use
+/// [`Context::from_raw`] to regain ownership of the value.
+///
+/// The syntax is:
+/// `context_param!("function_name", "parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! context_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Tells cxx to refer to a C++ type by a different name, including any
+/// namespace, from that under which autocxx found it. cxx uses this name
+/// in the C++ code it generates, for instance to delete the type when a
+/// `UniquePtr` to it is dropped, and in the names of the symbols which
+/// that code exports. Both names must refer to the same C++ type, for
+/// instance because one is an alias of the other.
+///
+/// The syntax is:
+/// `type_link_name!("Foo", "detail::FooImpl")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! type_link_name {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ enum is returned by functions to indicate success
+/// or failure, and names the value which indicates success. Functions
+/// returning the enum instead return `Result<(), Enum>` in Rust, which
+/// is `Ok(())` for the success value and holds the enum value otherwise.
+/// The enum must also be generated using [generate] or similar.
+///
+/// The syntax is:
+/// `status_result!("Status", "OK")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! status_result {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a pointer parameter of a C++ function, together with the
+/// length parameter immediately before or after it, describes an array. The
+/// generated Rust function takes a single slice in place of both parameters:
+/// `&[T]` for a `const T*` or `&mut [T]` for a `T*`. The elements must be
+/// primitives or POD types (see [generate_pod]).
+///
+/// An array of C strings, such as `char** argv` or `const char* const*
+/// items`, is instead taken as `&[&CStr]`, from which the Rust wrapper
+/// builds a temporary array of pointers. As with `argv`, the array has a
+/// null pointer after its last element. C++ must not modify the strings,
+/// so functions taking a `char**` are `unsafe`.
+///
+/// The syntax is:
+/// `slice_param!("function_name", "pointer_parameter_name", "length_parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! slice_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ function returns a pointer to an array whose
+/// length is returned by another function, taking no parameters, called
+/// in the same way: a free function, a static method of the same class,
+/// or a method of the same object. The elements must be primitives or POD
+/// types (see [generate_pod]).
+///
+/// A method then returns `&[T]`, borrowed from the object. Any other
+/// function returns a `Vec<T>` copied from the array, because there's
+/// nothing for the slice to borrow from.
+///
+/// The syntax is:
+/// `slice_return!("function_name", "length_function_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`. The length
+/// function is named without its class, since it belongs to the same one.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! slice_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a `std::vector<T>` or `std::string` parameter of a C++
+/// function, whether passed by value or by const reference, should be
+/// provided from Rust-owned data. The generated Rust function takes `&[T]`
+/// or `&str` respectively, and the C++ wrapper copies it into a temporary
+/// container for the duration of the call. The vector elements must be
+/// primitives or POD types (see [generate_pod]).
+///
+/// The syntax is:
+/// `container_param!("function_name", "parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! container_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a pointer parameter of a C++ function, pointing to an
+/// opaque (non-POD) type, is never null, so it can be passed from Rust as
+/// a reference rather than a raw pointer. The generated Rust function
+/// takes `Pin<&mut T>` for a `T*` parameter, or `&T` for a `const T*`,
+/// and the C++ wrapper passes on the address of the referenced object.
+///
+/// The syntax is:
+/// `reference_param!("function_name", "parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! reference_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a `std::span` or `rust::Slice` parameter of a C++
+/// function has `const` elements, as in `std::span<const uint32_t>`, so
+/// that it can be passed from Rust as `&[T]`. autocxx can't otherwise tell
+/// whether the elements are `const`, so such parameters are taken as
+/// `&mut [T]` unless marked this way. A `rust::Slice<const T>` marked this
+/// way is bound directly, without a C++ wrapper function.
+///
+/// The syntax is:
+/// `const_slice_param!("function_name", "parameter_name")`
+///
+/// As for [out_param], methods are named as `"Class::method"`.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! const_slice_param {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Adds a hand-written item to the `#[cxx::bridge]` mod which autocxx
+/// generates. This is useful for the occasional function which autocxx
+/// can't handle but which cxx can. Any functions or types declared
+/// within an `extern "C++"` block are exposed from the output mod
+/// alongside the generated bindings.
+///
+/// The syntax is:
+/// `bridge_item!(unsafe extern "C++" { fn foo() -> u32; })`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! bridge_item {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type's comparison operators define a total order,
+/// such that `PartialEq`, `Eq`, `PartialOrd` and `Ord` should be implemented
+/// for it in Rust by calling C++ `operator==` and `operator<`. Those may
+/// be defined individually or, in C++20, synthesized from `operator<=>`.
+/// This allows, for instance, a `Vec` of such objects to be sorted.
+///
+/// bindgen doesn't tell us about comparison operators, so autocxx can't
+/// find them itself.
+///
+/// The syntax is:
+/// `ordered!("Version")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! ordered {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Implements `PartialEq` and `Eq` for a non-POD C++ type by comparing
+/// object addresses. Two references are equal only if they refer to the
+/// very same C++ object; distinct objects with identical contents are
+/// not equal. This is useful for opaque types which have no meaningful
+/// value equality, for example to find a particular object in a list.
+///
+/// This is identity, not value, equality. If the type has a C++
+/// `operator==` you probably want [ordered] instead, and a type can't
+/// be given both. A POD type can't be marked in this way, since it's
+/// copied around by value; nor will [pod_by_default] make it POD.
+///
+/// The syntax is:
+/// `identity_eq!("Widget")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! identity_eq {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type is a generator, whose `next()` method returns
+/// each item in turn as a `std::optional<T>`, then an empty one once it's
+/// exhausted. The type then implements [CppGenerator], whose `iter` method
+/// gives a Rust iterator over the items. `T` must be a simple primitive
+/// type, and `std::optional` requires C++17. A type without such a
+/// `next()` method won't be generated at all.
+///
+/// ```ignore
+/// let items: Vec<_> = generator.pin_mut().iter().collect();
+/// ```
+///
+/// The syntax is:
+/// `generator!("Countdown")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! generator {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type has an `operator bool`, perhaps `explicit`,
+/// as is common for types which behave like smart pointers or optionals.
+/// Rust has no notion of truthiness, so instead the type gets an
+/// `as_bool(&self) -> bool` method which calls that operator, and
+/// `From<&T>` is implemented for `bool`.
+///
+/// As with [ordered], bindgen doesn't tell us about conversion operators,
+/// so autocxx can't find them itself.
+///
+/// The syntax is:
+/// `bool_conversion!("MaybeHandle")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! bool_conversion {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ type's `operator[]` takes a `size_t` and returns
+/// a reference to an element of the given type, such that
+/// `std::ops::Index<usize>` should be implemented for it in Rust.
+/// `std::ops::IndexMut<usize>` is also implemented, so long as the element
+/// is a type which Rust may hold by `&mut` - that is, a primitive or a
+/// POD type.
+///
+/// As with [ordered], bindgen doesn't tell us about `operator[]`, so
+/// autocxx can't find it itself.
+///
+/// The syntax is:
+/// `indexed!("IntList", "uint32_t")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! indexed {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a C++ enum is a set of bitmask flags which may be
+/// combined. In addition to the enum itself, a `Flags` newtype
+/// (e.g. `PermissionsFlags` for `Permissions`) will be generated which
+/// can represent any combination, along with the usual bitwise
+/// operators to produce one.
+///
+/// The syntax is:
+/// `flags!("Permissions")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! flags {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generates bindings for a specialization of a templated C++ function,
+/// under the given name. autocxx can't generate bindings for templated
+/// functions in general, so this creates a small C++ shim which calls
+/// the given specialization. The template arguments may be any types,
+/// including classes for which you're also generating bindings.
+/// Templates constrained by C++20 concepts work too, so long as the
+/// template arguments satisfy those concepts; if not, the C++ compiler
+/// will report that when compiling the shim.
+/// At present this only works for functions which take no parameters.
+///
+/// The syntax is:
+/// `instantiate!("make<Widget>", make_widget)`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! instantiate {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Don't wrap the items destined for cxx in a `#[cxx::bridge]` mod.
+/// Instead, they're made available separately by the code generator
+/// so that you can add them to a `#[cxx::bridge]` mod of your own.
+/// This is only useful for advanced users driving code generation
+/// directly.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! exclude_bridge_wrapper {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Use the given Rust type wherever the given C++ type is encountered,
+/// in preference to autocxx's built-in type mappings. This is useful
+/// for C++ types which are compatible with a type that autocxx and cxx
+/// already understand, for instance a custom smart pointer which derives
+/// from `std::unique_ptr`. Any template arguments are carried across
+/// to the Rust type.
+///
+/// The syntax is:
+/// `type_mapping!("MyPtr", cxx::UniquePtr)`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! type_mapping {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Marks a C++ struct with a single field as having exactly the same
+/// layout and ABI as that field, by generating it as a
+/// `#[repr(transparent)]` Rust struct. The type must also be POD
+/// (see [generate_pod]). This allows cheap conversion between the
+/// type and its field at the FFI boundary.
+///
+/// The syntax is:
+/// `transparent!("Meters")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! transparent {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Group the items destined for cxx by the C++ namespace they came from,
+/// and give each group its own `#[cxx::bridge]` mod rather than wrapping
+/// them all in a single one, which helps for very large sets of bindings.
+/// Alongside [exclude_bridge_wrapper], the groups are instead made
+/// available separately by the code generator, so that each can be
+/// written to its own file; this is only useful for advanced users driving
+/// code generation directly.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! split_by_namespace {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Alongside [split_by_namespace], declare each C++ type to cxx just once,
+/// in a group of its own, rather than in the group for its namespace.
+/// The other groups then refer to those declarations using cxx's support
+/// for types defined in another bridge, so a type can be used from any
+/// number of namespaces without conflicting declarations.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! shared_type_declarations {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Don't mark the Rust wrapper functions which autocxx generates as
+/// `#[inline]`. By default they are, since they're thin shims around
+/// the underlying cxx functions.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! exclude_inline_wrappers {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Treat simple getters as safe. With this directive, even without
+/// `safety!(unsafe)`, a `const` method which takes no parameters and
+/// returns a POD or primitive type by value is callable without `unsafe`,
+/// since it can't hand out anything which outlives its call.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! safe_getters {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Treat every struct which could safely be POD as POD, as if each had
+/// been listed using [generate_pod]. A struct can't be POD if it contains
+/// a type which isn't POD, such as a `std::string`, a pointer to itself,
+/// or virtual functions. Structs with a user-declared destructor, copy
+/// constructor or move constructor aren't made POD either, since their
+/// authors evidently care how they're copied. Such structs remain opaque,
+/// unless listed using [generate_pod]. As with [generate_pod], it's your
+/// responsibility to ensure the structs are trivially movable and
+/// destructible.
+///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! generate_pod {
+macro_rules! pod_by_default {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Generate Rust bindings for all C++ types and functions
-/// in a given namespace.
+/// Give every function and type in the generated `cxx::bridge` an
+/// explicit `#[cxx_name]` attribute naming the C++ symbol it binds to,
+/// even where that's the same as its Rust name. This makes the mapping
+/// between Rust and C++ names explicit in the generated code, so it
+/// doesn't depend upon the names happening to match.
+///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
-/// See also [generate].
 #[macro_export]
-macro_rules! generate_ns {
+macro_rules! explicit_cxx_names {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Generate Rust bindings for all C++ types and functions
-/// found. Highly experimental and not recommended.
+/// Check, when the generated C++ is compiled, that each free function or
+/// static method for which autocxx generates a C++ wrapper really has the
+/// parameter and return types for which the bindings were generated. The
+/// wrappers call these functions in the ordinary way, so if a header has
+/// been edited such that the C++ compiler sees a different signature than
+/// autocxx did, implicit conversions may otherwise let the wrapper compile
+/// and the mismatch surface only at link time, or not at all. With this
+/// directive, autocxx emits a cast of each such function to the exact
+/// function pointer type into the additional C++ file which it generates,
+/// which then fails to compile.
+/// Parameters and return values for which autocxx can't be sure of the
+/// exact original C++ type are not checked.
+///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
-/// See also [generate].
 #[macro_export]
-macro_rules! generate_all {
+macro_rules! check_signatures {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Generate as "plain old data". For use with [generate_all]
-/// and similarly experimental.
+/// Wrap every generated function in a [`tracing`](https://docs.rs/tracing)
+/// span at `TRACE` level, named after the function. Parameters of
+/// primitive types are recorded as fields of the span. The span is entered
+/// for the duration of the call into C++, so any events logged by Rust
+/// callbacks during that call appear within it. For constructors, and
+/// other functions which return an `impl New`, that call only happens
+/// once the `New` is emplaced, so that's when the span is entered, rather
+/// than when the `New` is created.
+///
+/// This requires the `tracing` feature of autocxx to be enabled.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! pod {
+macro_rules! trace_calls {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Skip the normal generation of a `make_string` function
-/// and other utilities which we might generate normally.
+/// Registers a C++ smart pointer template which keeps its reference count
+/// within the pointee, such as `RefPtr<T>`, along with the C++ functions
+/// which increment and decrement that count. Parameters and return values
+/// of that type are then represented in Rust as [`IntrusivePtr`], which
+/// releases its reference when dropped.
+///
+/// The syntax is:
+/// `intrusive_ptr!("RefPtr", "add_ref", "release")`
+///
+/// The functions are called with a raw pointer to the object. The smart
+/// pointer must have a `get()` method, and constructing one from a raw
+/// pointer must take a new reference.
+///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! exclude_utilities {
+macro_rules! intrusive_ptr {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Entirely block some type from appearing in the generated
-/// code. This can be useful if there is a type which is not
-/// understood by bindgen or autocxx, and incorrect code is
-/// otherwise generated.
-/// This is 'greedy' in the sense that any functions/methods
-/// which take or return such a type will _also_ be blocked.
+/// Defines a struct in Rust which C++ may also use, including by value,
+/// in the manner of a `cxx` shared struct. The struct is emitted into the
+/// `#[cxx::bridge]` mod, so its C++ definition will be found in the
+/// header which `cxx` generates; C++ headers using the struct need only
+/// forward declare it. Its fields may be of any type which `cxx` permits
+/// within a shared struct, including POD types from C++.
+///
+/// The syntax is:
+/// `shared_struct!(pub struct Point { pub x: i32, pub y: i32 })`
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! block {
+macro_rules! shared_struct {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Avoid generating implicit constructors for this type.
-/// The rules for when to generate C++ implicit constructors
-/// are complex, and if autocxx gets it wrong, you can block
-/// such constructors using this.
+/// Indicates that a C++ function or method returning a `const` reference
+/// to a POD type should instead return an owned copy of it, avoiding
+/// the need to tie the result's lifetime to the receiver.
+///
+/// The syntax is:
+/// `copy_return!("Space::origin")`
+///
+/// Methods must be qualified with their class name, so that other
+/// classes' methods of the same name are unaffected; free functions
+/// are named alone.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! block_constructors {
+macro_rules! copy_return {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// The name of the mod to be generated with the FFI code.
-/// The default is `ffi`.
+/// Indicates that a C++ function returns a `std::error_code` to report
+/// failure, and that it should instead return a
+/// `Result<(), std::io::Error>` in Rust. A zero error code becomes `Ok(())`.
+/// Codes in `std::system_category` are operating system errors, so their
+/// values are passed to [`std::io::Error::from_raw_os_error`]. So are codes
+/// in `std::generic_category`, which are `errno` values, on Unix platforms,
+/// where those are operating system errors too. Codes in any other
+/// category become an error of kind [`std::io::ErrorKind::Other`] whose
+/// message includes the code's value.
+///
+/// The syntax is:
+/// `error_code_result!("open_file")`
+///
+/// For a method, give its class too, as in `"File::open"`.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! name {
+macro_rules! error_code_result {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// A concrete type to make, for example
-/// `concrete!("Container<Contents>")`.
-/// All types msut already be on the allowlist by having used
-/// `generate!` or similar.
+/// Indicates that a C++ function returning a reference may, in practice,
+/// return a null reference, as some APIs do despite this being undefined
+/// behavior in C++. The generated Rust function returns `Option<&T>` (or
+/// `Option<Pin<&mut T>>` for a non-`const` reference), which is `None`
+/// if the reference was null. A function marked in this way which doesn't
+/// return a reference won't be generated at all.
+///
+/// The syntax is:
+/// `nullable_return!("find_child")`
+///
+/// For a method, give its class too, as in `"Tree::current"`.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! concrete {
+macro_rules! nullable_return {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Specifies a global safety policy for functions generated
-/// from these headers. By default (without such a `safety!`
-/// directive) all such functions are marked as `unsafe` and
-/// therefore can only be called within an `unsafe {}` block
-/// or some `unsafe` function which you create.
+/// Indicates that a C++ function returning a pointer hands ownership of
+/// the object to the caller, who would be expected to `delete` it. The
+/// generated Rust function returns a [`cxx::UniquePtr`] which deletes the
+/// object when dropped, instead of a raw pointer. A null pointer becomes
+/// a null `UniquePtr`. The object must have been allocated using `new`.
 ///
-/// Alternatively, by specifying a `safety!` block you can
-/// declare that most generated functions are in fact safe.
-/// Specifically, you'd specify:
-/// `safety!(unsafe)`
-/// or
-/// `safety!(unsafe_ffi)`
-/// These two options are functionally identical. If you're
-/// unsure, simply use `unsafe`. The reason for the
-/// latter option is if you have code review policies which
-/// might want to give a different level of scrutiny to
-/// C++ interop as opposed to other types of unsafe Rust code.
-/// Maybe in your organization, C++ interop is less scary than
-/// a low-level Rust data structure using pointer manipulation.
-/// Or maybe it's more scary. Either way, using `unsafe` for
-/// the data structure and using `unsafe_ffi` for the C++
-/// interop allows you to apply different linting tools and
-/// policies to the different options.
+/// The syntax is:
+/// `owning_return!("create_widget")`
 ///
-/// Irrespective, C++ code is of course unsafe. It's worth
-/// noting that use of C++ can cause unexpected unsafety at
-/// a distance in faraway Rust code. As with any use of the
-/// `unsafe` keyword in Rust, *you the human* are declaring
-/// that you've analyzed all possible ways that the code
-/// can be used and you are guaranteeing to the compiler that
-/// no badness can occur. Good luck.
+/// For a method, give its class too, as in `"Factory::create"`.
 ///
-/// Generated C++ APIs which use raw pointers remain `unsafe`
-/// no matter what policy you choose.
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! owning_return {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that a `const` C++ method should nevertheless take `self`
+/// mutably in Rust. This is useful for logically-const methods which
+/// return a mutable reference, for example to a cache held in a
+/// `mutable` field: Rust can't return `Pin<&mut T>` from a method taking
+/// `&self`.
 ///
-/// There's an additional possible experimental safety
-/// policy available here:
-/// `safety!(unsafe_references_wrapped)`
-/// This policy treats C++ references as scary and requires
-/// them to be wrapped in a `CppRef` type. This `CppRef`
-/// type is implemented within the generated bindings but
-/// follows the contract of [`CppRef`].
+/// The syntax is:
+/// `force_mut_self!("Memo::cache")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! safety {
+macro_rules! force_mut_self {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Whether to avoid generating [`cxx::UniquePtr`] and [`cxx::Vector`]
-/// implementations. This is primarily useful for reducing test cases and
-/// shouldn't be used in normal operation.
+/// Indicates that the sized array parameters of a C++ function, such as
+/// `int buf[4]`, should become references to Rust arrays, such as
+/// `&mut [c_int; 4]`, rather than pointers to their first element.
+///
+/// The syntax is:
+/// `array_params!("fill")`
+///
+/// For a method, give its class too, as in `"Buffer::fill"`.
+///
+/// bindgen can only tell us array sizes for all functions at once, so
+/// while this directive is in use, a parameter declared as a pointer to
+/// an array (`int (*buf)[4]`) is treated as a pointer to its first
+/// element, just like an array parameter.
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! exclude_impls {
+macro_rules! array_params {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Indicates that a C++ type is not to be generated by autocxx in this case,
-/// but instead should refer to some pre-existing Rust type.
-/// Note that the size and alignment of this type *must* be correct.
-/// If you wish for the type to be POD, you can use a `pod!` directive too.
+/// Generates a thin Rust wrapper around a `UniquePtr` to the given opaque
+/// C++ type, so that callers needn't manipulate the `UniquePtr` directly.
+/// For a type `Foo`, this generates `FooHandle(pub UniquePtr<Foo>)`, which
+/// can be created from a `UniquePtr<Foo>` using `From`, and which exposes
+/// each method of `Foo` taking `&self` or `Pin<&mut Self>` as a method
+/// taking `&self` or `&mut self` respectively. Calling such a method on a
+/// handle holding a null `UniquePtr` will panic.
+///
+/// The type must also be generated using [generate] or similar.
 ///
 /// The syntax is:
-/// `extern_cpp_type!("CppNameGoesHere", path::to::rust::type)`
+/// `handle!("Foo")`
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! extern_cpp_type {
+macro_rules! handle {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Indicates that a C++ type is not to be generated by autocxx in this case,
-/// but instead should refer to some pre-existing Rust type. Unlike
-/// `extern_cpp_type!`, there's no need for the size and alignment of this
-/// type to be correct.
+/// Arranges for the handle to the given C++ type (see [handle]) to call
+/// the given method before the object is destroyed. This is useful for
+/// types which need some teardown, such as flushing or unregistering,
+/// which their destructor doesn't do. The method must take no parameters
+/// other than `this`. A handle is generated for the type even if it isn't
+/// also listed in [handle].
+///
+/// The hook runs only when the handle itself is dropped; the `UniquePtr`
+/// returned by the handle's `into_inner` won't call it.
+///
+/// Dropping can't be `unsafe`, so if the method is an `unsafe` function
+/// (for example because of the [safety] policy), the handle won't compile
+/// unless you promise that it's always fine to call the method as the
+/// object is dropped, by writing `unsafe` before the type name.
 ///
 /// The syntax is:
-/// `extern_cpp_opaque_type!("CppNameGoesHere", path::to::rust::type)`
+/// `pre_drop!("Connection", "flush")` or
+/// `pre_drop!(unsafe "Connection", "flush")`
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! extern_cpp_opaque_type {
+macro_rules! pre_drop {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Deprecated - use [`extern_rust_type`] instead.
+/// Generates a `clone_unique` method for the given C++ type, which
+/// copies it into a new [`cxx::UniquePtr`] using its C++ copy constructor.
+/// `cxx::UniquePtr` can't itself implement `Clone`, so this is the
+/// equivalent. The type must be non-POD and have a public copy
+/// constructor taking a `const` reference; if not, no method is
+/// generated.
+///
+/// The syntax is:
+/// `clonable!("Foo")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-#[deprecated]
-macro_rules! rust_type {
+macro_rules! clonable {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// See [`extern_rust::extern_rust_type`].
+/// Generates a trait mirroring the virtual methods of the given C++ type,
+/// named after the type with a `Trait` suffix, and implements it for the
+/// type. This allows Rust code to accept, for instance, a `&dyn FooTrait`.
+/// Virtual methods which can't be called through a trait object, such as
+/// those taking strings as `impl ToCppString`, are left out of the trait.
+///
+/// The type must also be generated using [generate] or similar.
+///
+/// The syntax is:
+/// `virtual_trait!("Foo")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! extern_rust_type {
+macro_rules! virtual_trait {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// See [`subclass::subclass`].
+/// Re-exports the given items from a `prelude` mod within the generated
+/// bindings, so that the most commonly used ones can be brought into
+/// scope with a single glob import, e.g. `use ffi::prelude::*;`. Items
+/// are named as they appear in the generated Rust, including any
+/// namespace, e.g. `"ns::Foo"`. Each must also be generated using
+/// [generate] or similar.
+///
+/// The syntax is:
+/// `prelude!("Foo", "ns::Bar", "make_foo")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! subclass {
+macro_rules! prelude {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
-/// Indicates that a C++ type can definitely be instantiated. This has effect
-/// only in a very specific case:
-/// * the type is a typedef to something else
-/// * the 'something else' can't be fully inspected by autocxx, possibly
-///   becaue it relies on dependent qualified types or some other template
-///   arrangement that bindgen cannot fully understand.
-/// In such circumstances, autocxx normally has to err on the side of caution
-/// and assume that some type within the 'something else' is itself a forward
-/// declaration. That means, the opaque typedef won't be storable within
-/// a [`cxx::UniquePtr`]. If you know that no forward declarations are involved,
-/// you can declare the typedef type is instantiable and then you'll be able to
-/// own it within Rust.
+/// Asserts that the given C++ type is thread-safe, such that autocxx
+/// should implement `Send` and `Sync` for it. cxx types are neither
+/// by default; this allows (for instance) a `UniquePtr` to such a type
+/// to be moved to another thread.
+///
+/// This is an `unsafe` assertion on your part: autocxx can't check it,
+/// and if the C++ type is not in fact safe to use from multiple threads,
+/// undefined behavior may result. You must therefore write `unsafe`
+/// before the type name. Types with a method listed in [thread_unsafe]
+/// are never made `Send` or `Sync`.
 ///
 /// The syntax is:
-/// `instantiable!("CppNameGoesHere")`
+/// `thread_safe!(unsafe "Foo")`
 ///
 /// A directive to be included inside
 /// [include_cpp] - see [include_cpp] for general information.
 #[macro_export]
-macro_rules! instantiable {
+macro_rules! thread_safe {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Declares that the given C++ function or method isn't safe to call from
+/// multiple threads, for instance because it mutates shared state without
+/// locking. The type to which a method belongs will then never implement
+/// `Send` or `Sync`, even if it's listed in [thread_safe], and the
+/// generated function's documentation notes the constraint.
+///
+/// As for [force_mut_self], methods are named as `"Class::method"`.
+///
+/// The syntax is:
+/// `thread_unsafe!("Counter::reset")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! thread_unsafe {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Generates `get_x` and `set_x` methods for each public field `x` of
+/// the given C++ type, implemented by C++ code which reads or writes the
+/// field. This is useful if you want field access to go through methods,
+/// for example for the sake of API stability, even though the type
+/// could be POD.
+///
+/// The type should be generated using [generate] rather than
+/// [generate_pod], so that it remains opaque in Rust.
+///
+/// The syntax is:
+/// `field_accessors!("Point")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! field_accessors {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Derives `serde::Serialize` and `serde::Deserialize` for the given
+/// POD struct, so that it can be serialized directly. Every field must
+/// be a primitive type or another struct marked with `serde_pod!`.
+/// This requires the `serde` feature of this crate.
+///
+/// The type must also be generated using [generate_pod].
+///
+/// The syntax is:
+/// `serde_pod!("Point")`
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! serde_pod {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Indicates that objects of a given C++ type must be destroyed by calling
+/// a particular function rather than with `delete`, for example because
+/// they come from a custom allocator. When such an object is owned by a
+/// [`cxx::UniquePtr`], that function will be called as it's dropped.
+///
+/// The syntax is:
+/// `custom_deleter!("Pooled", "pool_free")`
+///
+/// The deleter is used for every `std::unique_ptr` of this type, so any
+/// constructors which allocate with `new` (such as the `new` functions
+/// autocxx generates) should be avoided, e.g. using `block_constructors!`.
+///
+/// This works by specializing `std::default_delete` for the type within
+/// the C++ header which autocxx generates (`autocxxgen_ffi.h` unless
+/// you've named it otherwise). C++ requires every translation unit which
+/// uses `std::unique_ptr` of the type to see the same specialization, so
+/// any of your own C++ files which do so must include that generated
+/// header, straight after the header defining the type and before any
+/// such use. Otherwise the program has undefined behavior, and some of
+/// your objects may be destroyed with `delete` after all.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! custom_deleter {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Registers a C++ function as the factory for a type which is created by
+/// that function rather than by a constructor, for example because it
+/// uses placement new into memory from a custom allocator. The type gains
+/// a `make_unique` associated function, taking the same parameters as the
+/// factory and returning a [`cxx::UniquePtr`] which owns the new object.
+///
+/// The syntax is:
+/// `factory!("Widget", "create_widget")`
+///
+/// The factory must return a non-const pointer to a new object of the
+/// type, which the caller then owns. Unless the type is also registered
+/// with [custom_deleter], that object will be destroyed with `delete`.
+/// Both the type and the factory still need to be generated, e.g. using
+/// [generate]. The factory then appears only as `make_unique`, not as a
+/// free function.
+///
+/// The factory may also be a static method, named as `"Class::method"`,
+/// e.g. `factory!("Widget", "Widget::create")`. Non-static methods can't
+/// be factories.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! factory {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Chooses how C++ enums are represented in Rust. The options are:
+/// * `enum_representation!(rust_enum)` - the default. Each enum becomes a
+///   Rust `enum`. Beware that if C++ gives you a value which isn't one of
+///   those listed in the enum, that's undefined behavior.
+/// * `enum_representation!(newtype)` - each enum becomes a newtype
+///   wrapping its underlying integer type, e.g. `pub struct Color(pub u32)`,
+///   with an associated constant for each listed value, e.g. `Color::Red`.
+///   This can safely hold any value C++ may produce.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! enum_representation {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Marks each C++ enum represented as a Rust `enum` with
+/// `#[non_exhaustive]`, since a future version of the C++ library may add
+/// values. Code in other crates which matches on such an enum must then
+/// include a wildcard arm, so it won't break when that happens. As ever
+/// with `#[non_exhaustive]`, this isn't enforced within the crate which
+/// defines the enum, which here is the crate containing [include_cpp].
+/// It has no effect with `enum_representation!(newtype)`, which can
+/// already hold any value.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! non_exhaustive_enums {
+    ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
+}
+
+/// Specifies the C++ standard which these bindings may assume, so that
+/// standard library types newer than that aren't mapped to Rust. For
+/// example, `std::string_view` needs C++17, and `std::span` needs C++20.
+/// APIs using such types are skipped, with an explanation.
+///
+/// The syntax is:
+/// `cpp_std!("c++14")`
+///
+/// The options are `"c++14"`, `"c++17"` and `"c++20"`, the default. This
+/// doesn't change the flags passed to the C++ compiler.
+///
+/// A directive to be included inside
+/// [include_cpp] - see [include_cpp] for general information.
+#[macro_export]
+macro_rules! cpp_std {
     ($($tt:tt)*) => { $crate::usage!{$($tt)*} };
 }
 
@@ -382,6 +1397,8 @@ macro_rules! ctype_wrapper {
     ($r:ident, $c:expr, $d:expr) => {
         #[doc=$d]
         #[derive(Debug, Eq, Copy, Clone, PartialEq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(transparent))]
         #[allow(non_camel_case_types)]
         #[repr(transparent)]
         pub struct $r(pub ::std::os::raw::$r);
@@ -596,11 +1613,39 @@ where
     }
 }
 
+pub use context::Context;
 use cxx::memory::UniquePtrTarget;
 use cxx::UniquePtr;
+pub use function_table::FunctionTable;
+#[doc(hidden)]
+pub use function_table::FunctionTableClosures;
+pub use generator::CppGenerator;
+pub use generator::CppGeneratorIter;
+pub use intrusive_ptr::IntrusivePtr;
+pub use intrusive_ptr::IntrusivePtrTarget;
 use moveit::New;
 pub use rvalue_param::RValueParam;
 pub use rvalue_param::RValueParamHandler;
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing;
+
+/// Stands in for the `tracing` crate when the `tracing` feature isn't
+/// enabled, so that code generated for [trace_calls] explains what's wrong.
+#[cfg(not(feature = "tracing"))]
+#[doc(hidden)]
+pub mod tracing {
+    pub use crate::tracing_feature_required as trace_span;
+}
+
+#[cfg(not(feature = "tracing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! tracing_feature_required {
+    ($($tt:tt)*) => {
+        compile_error! {"trace_calls! requires the tracing feature of autocxx to be enabled"}
+    };
+}
 pub use value_param::as_copy;
 pub use value_param::as_mov;
 pub use value_param::as_new;
@@ -624,6 +1669,7 @@ pub mod prelude {
     pub use crate::c_void;
     pub use crate::cpp_semantics;
     pub use crate::include_cpp;
+    pub use crate::CppGenerator;
     pub use crate::CppMutRef;
     pub use crate::CppPin;
     pub use crate::CppRef;
@@ -644,6 +1690,10 @@ pub mod prelude {
 /// Re-export moveit for ease of consumers.
 pub use moveit;
 
+/// Re-export serde, which is used by structs marked with [serde_pod].
+#[cfg(feature = "serde")]
+pub use serde;
+
 /// Re-export cxx such that clients can use the same version as
 /// us. This doesn't enable clients to avoid depending on the cxx
 /// crate too, unfortunately, since generated cxx::bridge code